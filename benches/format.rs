@@ -0,0 +1,67 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate criterion;
+extern crate message_format;
+
+use criterion::{black_box, Criterion};
+use message_format::{arg, icu, write_message_generic, Context};
+
+const MESSAGE: &str = "{name} {gender, select, female {shared {count, plural, =0 {nothing} \
+                       one {one file} other {# files}} with her} male {shared {count, plural, =0 {nothing} \
+                       one {one file} other {# files}} with his} other {shared {count, plural, =0 {nothing} \
+                       one {one file} other {# files}} with their}} friends in {folder}.";
+
+fn format(c: &mut Criterion) {
+    let ctx = Context::default();
+    let m = icu::parse(MESSAGE).unwrap();
+    let compiled = m.compile();
+    let args = arg("name", "Ann");
+    let args = args.arg("gender", "other");
+    let args = args.arg("count", 3);
+    let args = args.arg("folder", "Photos");
+
+    let mut slots = compiled.args_slots();
+    slots.set(compiled.slot("name").unwrap(), "Ann");
+    slots.set(compiled.slot("gender").unwrap(), "other");
+    slots.set(compiled.slot("count").unwrap(), 3);
+    slots.set(compiled.slot("folder").unwrap(), "Photos");
+
+    let mut group = c.benchmark_group("format");
+    group.bench_function("message", |b| {
+        let mut s = String::new();
+        b.iter(|| {
+            s.clear();
+            m.write_with(&ctx, &mut s, black_box(&args)).unwrap();
+        })
+    });
+    group.bench_function("compiled", |b| {
+        let mut s = String::new();
+        b.iter(|| {
+            s.clear();
+            compiled.write_with(&ctx, &mut s, black_box(&args)).unwrap();
+        })
+    });
+    group.bench_function("compiled_generic", |b| {
+        let mut s = String::new();
+        b.iter(|| {
+            s.clear();
+            write_message_generic(&ctx, &compiled, &mut s, black_box(&args)).unwrap();
+        })
+    });
+    group.bench_function("compiled_slots", |b| {
+        let mut s = String::new();
+        b.iter(|| {
+            s.clear();
+            compiled.write_slots(&ctx, &mut s, black_box(&slots)).unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, format);
+criterion_main!(benches);
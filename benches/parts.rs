@@ -0,0 +1,74 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Compare with `cargo bench --bench parts --features smallvec`.
+
+#[macro_use]
+extern crate criterion;
+extern crate message_format;
+
+use criterion::Criterion;
+use message_format::{icu, Message};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// Messages typical of an application's catalog.
+const CATALOG: &[&str] = &[
+    "Save",
+    "Cancel",
+    "Welcome back, {name}!",
+    "Signed in as {email}",
+    "{count, plural, one {# new message} other {# new messages}}",
+    "{name} liked your photo.",
+    "Last seen {when, date, medium} at {when, time, short}",
+    "{gender, select, female {She} male {He} other {They}} replied to {thread}.",
+    "{count, plural, =0 {No files} one {One file} other {# files}} in {folder}",
+    "Storage used: {used, number} of {total, number} MB",
+    "Are you sure you want to delete {title}?",
+    "{count, plural, one {# day left} other {# days left}} in your trial",
+    "Copyright {year} {company}",
+    "Page {page} of {pages}",
+    "{name} and {others, plural, one {# other} other {# others}} are typing…",
+    "Download complete",
+];
+
+fn parse_catalog() -> Vec<Message> {
+    CATALOG.iter().map(|source| icu::parse(source).unwrap()).collect()
+}
+
+fn parts(c: &mut Criterion) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let messages = parse_catalog();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "parsing {} messages made {} allocations (smallvec feature: {})",
+        messages.len(),
+        allocations,
+        cfg!(feature = "smallvec")
+    );
+    c.bench_function("parse_catalog", |b| b.iter(parse_catalog));
+}
+
+criterion_group!(benches, parts);
+criterion_main!(benches);
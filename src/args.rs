@@ -5,21 +5,79 @@
 // except according to those terms.
 
 use super::Value;
+use number::NumberOptions;
 
 use std::collections::HashMap;
+use std::fmt;
 
 pub trait Args<'a> {
-    fn get(&self, name: &str) -> Option<&'a Value>;
+    fn get(&self, name: &str) -> Option<&'a Value<'_>>;
+
+    /// Number options given with the argument `name`, which take
+    /// precedence over those of the context and of the format using
+    /// the argument. See [`arg_with`].
+    ///
+    /// [`arg_with`]: fn.arg_with.html
+    fn number_options(&self, _name: &str) -> Option<NumberOptions> {
+        None
+    }
+}
+
+/// Replaces the value of an argument before it is formatted. See
+/// [`Context::map_arg`].
+///
+/// [`Context::map_arg`]: struct.Context.html#method.map_arg
+pub trait ArgTransform: Send + Sync {
+    /// The value to format in place of `value`.
+    fn transform(&self, value: &Value) -> Value<'static>;
+}
+
+impl<F> ArgTransform for F
+where
+    F: Fn(&Value) -> Value<'static> + Send + Sync,
+{
+    fn transform(&self, value: &Value) -> Value<'static> {
+        self(value)
+    }
+}
+
+impl fmt::Debug for dyn ArgTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ArgTransform")
+    }
+}
+
+/// Arguments with some values replaced, looking up the rest in
+/// `inner`, and then names starting with `$` in `attributes`.
+pub(crate) struct MappedArgs<'v, 'f> {
+    pub(crate) values: &'v [(&'v str, Value<'v>)],
+    pub(crate) attributes: &'v [(&'v str, Value<'v>)],
+    pub(crate) inner: &'f dyn Args<'f>,
+}
+
+impl<'v, 'f: 'v> Args<'v> for MappedArgs<'v, 'f> {
+    fn get(&self, name: &str) -> Option<&'v Value<'_>> {
+        let find = |values: &'v [(&'v str, Value<'v>)], name: &str| {
+            values.iter().find(|(mapped, _)| *mapped == name).map(|(_, value)| value)
+        };
+        find(self.values, name)
+            .or_else(|| self.inner.get(name))
+            .or_else(|| name.strip_prefix('$').and_then(|name| find(self.attributes, name)))
+    }
+
+    fn number_options(&self, name: &str) -> Option<NumberOptions> {
+        self.inner.number_options(name)
+    }
 }
 
 pub struct EmptyArgs;
 
 impl<'a> Args<'a> for EmptyArgs {
-    fn get(&self, _name: &str) -> Option<&'a Value> { None }
+    fn get(&self, _name: &str) -> Option<&'a Value<'_>> { None }
 }
 
 impl<'a> Args<'a> for HashMap<&str, Value<'a>> {
-    fn get(&self, name: &str) -> Option<&'a Value> {
+    fn get(&self, name: &str) -> Option<&'a Value<'_>> {
         self.get(name)
     }
 }
@@ -41,6 +99,10 @@ pub struct ListArgs<'a> {
     /// The 'next' argument (which is really the previous since this
     /// is a linked list with the last argument first).
     pub prev: Option<&'a ListArgs<'a>>,
+    /// Number options for the value. See [`arg_with`].
+    ///
+    /// [`arg_with`]: fn.arg_with.html
+    pub options: Option<NumberOptions>,
 }
 
 /// Create an argument holder.
@@ -59,9 +121,39 @@ where
     Value<'a>: From<T>,
 {
     ListArgs {
-        name: name,
+        name,
         value: Value::from(value),
         prev: None,
+        options: None,
+    }
+}
+
+/// Create an argument holder for a number formatted with `options`.
+///
+/// The options take precedence over those of the context and the
+/// style of the message, for an app choosing the precision or the
+/// currency of a value at run time. They apply wherever the argument
+/// is written as a number: as `{price}`, `{price, number, ...}`, a
+/// `unit` or the `#` of a `plural`.
+///
+/// ```
+/// use message_format::number::{Currency, NumberOptions};
+/// use message_format::{arg_with, icu, Context};
+///
+/// let m = icu::parse("Total: {price, number, integer}").unwrap();
+/// let ctx = Context::default();
+/// assert_eq!(ctx.format(&m, &arg_with("price", 12.5, NumberOptions::currency(Currency::new("USD").unwrap()))), "Total: $12.50");
+///
+/// let precise = NumberOptions { maximum_fraction_digits: Some(1), ..Default::default() };
+/// assert_eq!(ctx.format(&m, &arg_with("price", 12.5, precise)), "Total: 12.5");
+/// ```
+pub fn arg_with<'a, T: 'a>(name: &'a str, value: T, options: NumberOptions) -> ListArgs<'a>
+where
+    Value<'a>: From<T>,
+{
+    ListArgs {
+        options: Some(options),
+        ..arg(name, value)
     }
 }
 
@@ -87,9 +179,25 @@ impl<'a> ListArgs<'a> {
         Value<'a>: From<T>,
     {
         ListArgs {
-            name: name,
+            name,
             value: Value::from(value),
             prev: Some(self),
+            options: None,
+        }
+    }
+
+    /// Add an additional argument formatted with `options`, as with
+    /// [`arg_with`].
+    ///
+    /// [`arg_with`]: fn.arg_with.html
+    pub fn arg_with<T: 'a>(&'a self, name: &'a str, value: T, options: NumberOptions) -> ListArgs<'a>
+    where
+        Self: Sized,
+        Value<'a>: From<T>,
+    {
+        ListArgs {
+            options: Some(options),
+            ..self.arg(name, value)
         }
     }
 }
@@ -104,7 +212,7 @@ impl<'a> Args<'a> for ListArgs<'a> {
     /// let args = arg("count", 3);
     /// let arg = args.get("count").unwrap();
     /// ```
-    fn get(&self, name: &str) -> Option<&'a Value> {
+    fn get(&self, name: &str) -> Option<&'a Value<'_>> {
         if self.name == name {
             Some(&self.value)
         } else if let Some(prev) = self.prev {
@@ -114,6 +222,14 @@ impl<'a> Args<'a> for ListArgs<'a> {
         }
     }
 
+    fn number_options(&self, name: &str) -> Option<NumberOptions> {
+        if self.name == name {
+            self.options
+        } else {
+            self.prev.and_then(|prev| prev.number_options(name))
+        }
+    }
+
     // fn value(&'a self) -> &'a Value<'a> {
     //     &self.value
     // }
@@ -142,4 +258,21 @@ mod tests {
         assert_eq!(args.get("count").unwrap(), &Value::Number(3));
         assert_eq!(format!("{}", args.get("count").unwrap()), "3");
     }
+
+    #[test]
+    fn options_override_the_message() {
+        use icu::parse;
+        use number::{Currency, NumberOptions};
+        use Context;
+
+        let m = parse("{total} {n, plural, other {# items}} {total, number, ::.0}").unwrap();
+        let eur = arg_with("total", 1234.5, NumberOptions::currency(Currency::new("eur").unwrap()));
+        let args = eur.arg_with("n", 3, NumberOptions { minimum_fraction_digits: Some(1), ..Default::default() });
+        let ctx = Context::default();
+        assert_eq!(ctx.format(&m, &args), "€1,234.50 3.0 items €1,234.50");
+        assert_eq!(m.compile().format_with(&ctx, &args), "€1,234.50 3.0 items €1,234.50");
+
+        let m = parse("{total}").unwrap();
+        assert_eq!(ctx.format(&m, &arg_with("total", 5, NumberOptions::currency(Currency::new("JPY").unwrap()))), "¥5");
+    }
 }
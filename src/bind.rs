@@ -0,0 +1,145 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Substituting some of the arguments of a message ahead of time.
+
+use icu::ast;
+use {Args, Message, MessagePart, StaticMessage, Value};
+
+pub(crate) fn bind<'f>(message: &Message, args: &'f dyn Args<'f>) -> Message {
+    let mut parts = vec![];
+    bind_parts(message, args, &mut parts);
+    Message::new(parts)
+}
+
+// Add the parts of `message`, with the arguments in `args` bound, to
+// `parts`, joining adjacent text.
+fn bind_parts<'f>(message: &Message, args: &'f dyn Args<'f>, parts: &mut Vec<Box<dyn MessagePart>>) {
+    for part in &message.parts {
+        let any = part.as_any();
+        if let Some(nested) = any.downcast_ref::<Message>() {
+            parts.push(Box::new(bind(nested, args)));
+        } else if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+            push_text(parts, &text.text);
+        } else if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+            match args.get(&fmt.variable_name) {
+                Some(&Value::Str(text)) => push_text(parts, text),
+                Some(Value::String(text)) => push_text(parts, text),
+                _ => parts.push(Box::new(ast::SimpleFormat::new(&fmt.variable_name))),
+            }
+        } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+            match args.get(&fmt.variable_name) {
+                Some(value) => bind_parts(fmt.lookup_message(&value.select_key()), args, parts),
+                None => parts.push(Box::new(bind_select(fmt, args))),
+            }
+        } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+            parts.push(Box::new(bind_plural(fmt, args)));
+        } else {
+            parts.push(copy_part(part.as_ref()));
+        }
+    }
+}
+
+fn push_text(parts: &mut Vec<Box<dyn MessagePart>>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    let previous = parts
+        .last_mut()
+        .and_then(|part| part.as_any_mut())
+        .and_then(|any| any.downcast_mut::<ast::PlainText>());
+    match previous {
+        Some(previous) => previous.text.push_str(text),
+        None => parts.push(Box::new(ast::PlainText::new(text))),
+    }
+}
+
+fn bind_select<'f>(fmt: &ast::SelectFormat, args: &'f dyn Args<'f>) -> ast::SelectFormat {
+    let mut bound = ast::SelectFormat::new(&fmt.variable_name, bind(&fmt.default, args));
+    for mapping in &fmt.mappings {
+        let keys: Vec<&str> = Some(&mapping.value).into_iter().chain(&mapping.aliases).map(|k| k.as_str()).collect();
+        bound.map_all(&keys, bind(&mapping.message, args));
+    }
+    bound.fallbacks = fmt.fallbacks.clone();
+    bound.matching = fmt.matching;
+    bound
+}
+
+// The plural category of a number depends on the language, so a bound
+// plural is kept, with its branches bound.
+fn bind_plural<'f>(fmt: &ast::PluralFormat, args: &'f dyn Args<'f>) -> ast::PluralFormat {
+    let bind_branch = |branch: &Option<Message>| branch.as_ref().map(|message| bind(message, args));
+    let mut bound = ast::PluralFormat::new(&fmt.variable_name, bind(&fmt.other, args));
+    bound.classifier = fmt.classifier;
+    bound.offset = fmt.offset;
+    for mapping in &fmt.literals {
+        bound.decimal_literal(mapping.value, bind(&mapping.message, args));
+    }
+    bound.zero = bind_branch(&fmt.zero);
+    bound.one = bind_branch(&fmt.one);
+    bound.two = bind_branch(&fmt.two);
+    bound.few = bind_branch(&fmt.few);
+    bound.many = bind_branch(&fmt.many);
+    bound
+}
+
+// A copy of a part with no arguments to bind, or whose formatting
+// depends on the language.
+fn copy_part(part: &dyn MessagePart) -> Box<dyn MessagePart> {
+    let any = part.as_any();
+    if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+        Box::new(ast::NumberFormat::new(&fmt.variable_name, fmt.style.clone()))
+    } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+        Box::new(ast::DateTimeFormat::new(&fmt.variable_name, fmt.kind, fmt.style.clone()))
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+        Box::new(ast::UnitFormat::new(&fmt.variable_name, &fmt.unit, fmt.width))
+    } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+        Box::new(ast::TextFormat::new(&fmt.variable_name, fmt.transform))
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+        Box::new(ast::UnknownFormat::new(&fmt.variable_name, &fmt.type_name, fmt.style.as_deref()))
+    } else if any.is::<ast::PlaceholderFormat>() {
+        Box::new(ast::PlaceholderFormat::new())
+    } else if let Some(reference) = any.downcast_ref::<ast::MessageReference>() {
+        Box::new(ast::MessageReference::new(&reference.key))
+    } else if let Some(error) = any.downcast_ref::<ast::ErrorNode>() {
+        Box::new(ast::ErrorNode::new(&error.text))
+    } else if let Some(message) = any.downcast_ref::<StaticMessage>() {
+        Box::new(*message)
+    } else {
+        panic!("can't copy a message part of a type defined outside this crate: {:?}", part)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::{parse_with, serialize, ParseOptions};
+    use {arg, Context};
+
+    #[test]
+    fn binds_text_and_selects() {
+        let options = ParseOptions {
+            lenient: true,
+            extensions: true,
+        };
+        let source = "{brand}: {tier, select, pro {{brand} Pro, {n, plural, one {# seat} other {# seats}}} \
+                      other {Free}} {day, date, short} {@footer} {who, select, a|b {AB} other {{brand}}}";
+        let m = parse_with(source, options).unwrap();
+        let bound = m.bind(&arg("brand", "Acme").arg("tier", "pro").arg("n", 3));
+        assert_eq!(
+            serialize(&bound).unwrap(),
+            "Acme: Acme Pro, {n, plural, one {# seat} other {# seats}} {day, date, short} {@footer} \
+             {who, select, a|b {AB} other {Acme}}"
+        );
+        assert_eq!(bound.parts.len(), 8);
+
+        let unbound = m.bind(&arg("brand", 3));
+        assert_eq!(serialize(&unbound).unwrap(), serialize(&m).unwrap());
+
+        let m = parse_with("{brand}: {tier, select, pro {Pro} other {Free}}", options).unwrap();
+        let ctx = Context::default();
+        assert_eq!(ctx.format(&m.bind(&arg("brand", "Acme")), &arg("tier", "free")), "Acme: Free");
+    }
+}
@@ -0,0 +1,128 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use {Args, Context, Message, PartRole};
+
+/// How [`format_message_bounded`] shortens a message that is too long.
+///
+/// [`format_message_bounded`]: fn.format_message_bounded.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Truncation {
+    /// Cut the text at any character and end it with `…`.
+    Ellipsis,
+    /// Cut the text after the last whole word that fits and end it
+    /// with `…`.
+    WordBoundary,
+}
+
+const ELLIPSIS: char = '\u{2026}';
+
+/// Format a message using `ctx` into at most `max_chars` characters,
+/// shortening it with `truncation` if needed.
+///
+/// Only the text of the message is cut. A formatted argument, such
+/// as a number or a date, is either kept whole or left out, so that a
+/// push notification never shows half a price.
+///
+/// ```
+/// use message_format::{arg, format_message_bounded, icu, Context, Truncation};
+///
+/// let m = icu::parse("Your order of {count, plural, one {# item} other {# items}} shipped today").unwrap();
+/// let ctx = Context::default();
+/// let args = arg("count", 12345);
+/// let bounded = |max, truncation| format_message_bounded(&ctx, &m, &args, max, truncation);
+///
+/// assert_eq!(bounded(50, Truncation::Ellipsis), "Your order of 12,345 items shipped today");
+/// assert_eq!(bounded(30, Truncation::Ellipsis), "Your order of 12,345 items sh…");
+/// assert_eq!(bounded(30, Truncation::WordBoundary), "Your order of 12,345 items…");
+/// assert_eq!(bounded(18, Truncation::Ellipsis), "Your order of…");
+/// ```
+pub fn format_message_bounded<'f>(
+    ctx: &Context,
+    message: &Message,
+    args: &'f dyn Args<'f>,
+    max_chars: usize,
+    truncation: Truncation,
+) -> String {
+    // Formatted arguments, such as numbers and dates, are never cut.
+    let segments = match ctx.format_to_parts(message, args) {
+        Some(parts) => parts,
+        None => return String::new(),
+    };
+    let total: usize = segments.iter().map(|segment| segment.text.chars().count()).sum();
+    let mut output = String::new();
+    if total <= max_chars {
+        for segment in &segments {
+            output.push_str(&segment.text);
+        }
+        return output;
+    }
+    let mut budget = match max_chars.checked_sub(1) {
+        Some(budget) => budget,
+        None => return output,
+    };
+    for segment in &segments {
+        let len = segment.text.chars().count();
+        if len <= budget {
+            output.push_str(&segment.text);
+            budget -= len;
+            continue;
+        }
+        if segment.role == PartRole::Literal {
+            let end = segment.text.char_indices().nth(budget).map_or(segment.text.len(), |(i, _)| i);
+            let fits = &segment.text[..end];
+            match truncation {
+                Truncation::Ellipsis => output.push_str(fits),
+                Truncation::WordBoundary => {
+                    let next_is_space = segment.text[end..].starts_with(char::is_whitespace);
+                    match fits.rfind(char::is_whitespace) {
+                        _ if next_is_space => output.push_str(fits),
+                        Some(space) => output.push_str(&fits[..space]),
+                        None if output.ends_with(char::is_whitespace) => (),
+                        None => output.truncate(last_word_start(&output)),
+                    }
+                }
+            }
+        }
+        break;
+    }
+    let trimmed = output.trim_end().len();
+    output.truncate(trimmed);
+    output.push(ELLIPSIS);
+    output
+}
+
+// The start of the last word of `text`, so that cutting there leaves
+// only whole words.
+fn last_word_start(text: &str) -> usize {
+    text.rfind(char::is_whitespace).map_or(0, |i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_message_bounded, Truncation};
+    use {arg, icu, Context};
+
+    #[test]
+    fn arguments_are_never_cut() {
+        let ctx = Context::default();
+        let m = icu::parse("Hi {name}, welcome back").unwrap();
+        let args = arg("name", "Bartholomew");
+        let bounded = |max, truncation| format_message_bounded(&ctx, &m, &args, max, truncation);
+
+        assert_eq!(bounded(10, Truncation::Ellipsis), "Hi…");
+        assert_eq!(bounded(16, Truncation::Ellipsis), "Hi Bartholomew,…");
+        assert_eq!(bounded(20, Truncation::Ellipsis), "Hi Bartholomew, wel…");
+        assert_eq!(bounded(20, Truncation::WordBoundary), "Hi Bartholomew,…");
+        assert_eq!(bounded(24, Truncation::WordBoundary), "Hi Bartholomew, welcome…");
+        assert_eq!(bounded(1, Truncation::Ellipsis), "…");
+        assert_eq!(bounded(0, Truncation::Ellipsis), "");
+
+        let m = icu::parse("Ünïcödé text").unwrap();
+        assert_eq!(format_message_bounded(&ctx, &m, &arg("x", 1), 5, Truncation::Ellipsis), "Ünïc…");
+        assert_eq!(format_message_bounded(&ctx, &m, &arg("x", 1), 5, Truncation::WordBoundary), "…");
+    }
+}
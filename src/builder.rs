@@ -0,0 +1,402 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use datetime::{DateTimeKind, DateTimeStyle};
+use icu::ast;
+use {Message, MessagePart};
+
+/// Builds a [`Message`] from code rather than by parsing text.
+///
+/// Branches of `plural` and `select` formats accept anything that
+/// converts into a `Message`: another `MessageBuilder`, a `Message`
+/// or a `&str`, which is used as plain text.
+///
+/// ```
+/// # #[macro_use] extern crate message_format;
+/// # fn main() {
+/// use message_format::{Context, Message};
+///
+/// let m = Message::builder()
+///     .text("Hi ")
+///     .arg("name")
+///     .text(", you have ")
+///     .plural("n", |p| {
+///         p.literal(0, "no messages")
+///             .one("one message")
+///             .other(Message::builder().placeholder().text(" messages"))
+///     })
+///     .text(".")
+///     .build();
+///
+/// let ctx = Context::default();
+/// assert_eq!(format_message!(ctx, &m, name => "Ann", n => 3),
+///            "Hi Ann, you have 3 messages.");
+/// # }
+/// ```
+///
+/// [`Message`]: struct.Message.html
+#[derive(Debug, Default)]
+pub struct MessageBuilder {
+    parts: Vec<Box<dyn MessagePart>>,
+}
+
+impl MessageBuilder {
+    /// Construct an empty `MessageBuilder`.
+    pub fn new() -> Self {
+        MessageBuilder::default()
+    }
+
+    /// Append an arbitrary part.
+    pub fn part(mut self, part: Box<dyn MessagePart>) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// Append plain text.
+    pub fn text(self, text: &str) -> Self {
+        self.part(Box::new(ast::PlainText::new(text)))
+    }
+
+    /// Append the value of an argument, like `{name}`.
+    pub fn arg(self, variable_name: &str) -> Self {
+        self.part(Box::new(ast::SimpleFormat::new(variable_name)))
+    }
+
+    /// Append the number being pluralized, like `#`. This is only
+    /// valid within a branch of a `plural` format.
+    pub fn placeholder(self) -> Self {
+        self.part(Box::new(ast::PlaceholderFormat::new()))
+    }
+
+    /// Append a date, like `{name, date, style}`.
+    pub fn date(self, variable_name: &str, style: DateTimeStyle) -> Self {
+        self.part(Box::new(ast::DateTimeFormat::new(variable_name, DateTimeKind::Date, style)))
+    }
+
+    /// Append a time, like `{name, time, style}`.
+    pub fn time(self, variable_name: &str, style: DateTimeStyle) -> Self {
+        self.part(Box::new(ast::DateTimeFormat::new(variable_name, DateTimeKind::Time, style)))
+    }
+
+    /// Append a `plural` format, with branches set up by `f`.
+    pub fn plural<F>(self, variable_name: &str, f: F) -> Self
+    where
+        F: FnOnce(PluralBuilder) -> PluralBuilder,
+    {
+        let builder = f(PluralBuilder::new(variable_name));
+        self.part(Box::new(builder.build()))
+    }
+
+    /// Append a `select` format, with branches set up by `f`.
+    pub fn select<F>(self, variable_name: &str, f: F) -> Self
+    where
+        F: FnOnce(SelectBuilder) -> SelectBuilder,
+    {
+        let builder = f(SelectBuilder::new(variable_name));
+        self.part(Box::new(builder.build()))
+    }
+
+    /// Append a `select` format whose every branch is a `plural`
+    /// format over the same variable, with branches set up by `f`.
+    ///
+    /// This is the common "gender of the subject times count" pattern.
+    /// Building it this way guarantees that every branch pluralizes the
+    /// same variable and has an `other` case.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate message_format;
+    /// # fn main() {
+    /// use message_format::{icu, Context, Message};
+    ///
+    /// let m = Message::builder()
+    ///     .nested_select("gender", "count", |n| {
+    ///         n.case("female", |p| {
+    ///             p.one("She has a cat")
+    ///                 .other(Message::builder().text("She has ").placeholder().text(" cats"))
+    ///         })
+    ///         .other(|p| {
+    ///             p.one("They have a cat")
+    ///                 .other(Message::builder().text("They have ").placeholder().text(" cats"))
+    ///         })
+    ///     })
+    ///     .build();
+    ///
+    /// let parsed = icu::parse(
+    ///     "{gender, select, \
+    ///        female {{count, plural, one {She has a cat} other {She has # cats}}} \
+    ///        other {{count, plural, one {They have a cat} other {They have # cats}}}}",
+    /// ).unwrap();
+    /// assert_eq!(m, parsed);
+    ///
+    /// let ctx = Context::default();
+    /// assert_eq!(format_message!(ctx, &m, gender => "female", count => 3),
+    ///            "She has 3 cats");
+    /// # }
+    /// ```
+    pub fn nested_select<F>(self, select_variable: &str, plural_variable: &str, f: F) -> Self
+    where
+        F: FnOnce(NestedSelectBuilder) -> NestedSelectBuilder,
+    {
+        let builder = f(NestedSelectBuilder::new(select_variable, plural_variable));
+        self.part(Box::new(builder.build()))
+    }
+
+    /// Finish building the `Message`.
+    pub fn build(self) -> Message {
+        Message::new(self.parts)
+    }
+}
+
+impl From<MessageBuilder> for Message {
+    fn from(builder: MessageBuilder) -> Message {
+        builder.build()
+    }
+}
+
+impl<'a> From<&'a str> for Message {
+    /// Construct a message consisting of plain text.
+    fn from(text: &'a str) -> Message {
+        MessageBuilder::new().text(text).build()
+    }
+}
+
+/// Builds an [`ast::PluralFormat`]. See [`MessageBuilder::plural`].
+///
+/// If no `other` branch is given, it is empty.
+///
+/// [`ast::PluralFormat`]: icu/ast/struct.PluralFormat.html
+/// [`MessageBuilder::plural`]: struct.MessageBuilder.html#method.plural
+#[derive(Debug)]
+pub struct PluralBuilder {
+    format: ast::PluralFormat,
+}
+
+impl PluralBuilder {
+    /// Construct a `PluralBuilder` for the named variable.
+    pub fn new(variable_name: &str) -> Self {
+        PluralBuilder {
+            format: ast::PluralFormat::new(variable_name, Message::default()),
+        }
+    }
+
+    /// Set the message for an exact value, like `=0`.
+    pub fn literal<M: Into<Message>>(mut self, value: i64, message: M) -> Self {
+        self.format.literal(value, message.into());
+        self
+    }
+
+    /// Set the message for an exact decimal value, like `=0.5`.
+    pub fn decimal_literal<M: Into<Message>>(mut self, value: f64, message: M) -> Self {
+        self.format.decimal_literal(value, message.into());
+        self
+    }
+
+    /// Set the offset subtracted from the value before choosing
+    /// a plural category.
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.format.offset(offset);
+        self
+    }
+
+    /// Set the message for `PluralCategory::Zero`.
+    pub fn zero<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.zero(message.into());
+        self
+    }
+
+    /// Set the message for `PluralCategory::One`.
+    pub fn one<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.one(message.into());
+        self
+    }
+
+    /// Set the message for `PluralCategory::Two`.
+    pub fn two<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.two(message.into());
+        self
+    }
+
+    /// Set the message for `PluralCategory::Few`.
+    pub fn few<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.few(message.into());
+        self
+    }
+
+    /// Set the message for `PluralCategory::Many`.
+    pub fn many<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.many(message.into());
+        self
+    }
+
+    /// Set the message for `PluralCategory::Other`.
+    pub fn other<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.other = message.into();
+        self
+    }
+
+    /// Finish building the `PluralFormat`.
+    pub fn build(self) -> ast::PluralFormat {
+        self.format
+    }
+}
+
+/// Builds an [`ast::SelectFormat`]. See [`MessageBuilder::select`].
+///
+/// If no `other` branch is given, it is empty.
+///
+/// [`ast::SelectFormat`]: icu/ast/struct.SelectFormat.html
+/// [`MessageBuilder::select`]: struct.MessageBuilder.html#method.select
+#[derive(Debug)]
+pub struct SelectBuilder {
+    format: ast::SelectFormat,
+}
+
+impl SelectBuilder {
+    /// Construct a `SelectBuilder` for the named variable.
+    pub fn new(variable_name: &str) -> Self {
+        SelectBuilder {
+            format: ast::SelectFormat::new(variable_name, Message::default()),
+        }
+    }
+
+    /// Set the message used when the value matches `key`.
+    pub fn case<M: Into<Message>>(mut self, key: &str, message: M) -> Self {
+        self.format.map(key, message.into());
+        self
+    }
+
+    /// Set the message used when no other key matches.
+    pub fn other<M: Into<Message>>(mut self, message: M) -> Self {
+        self.format.default = message.into();
+        self
+    }
+
+    /// Use the message for `fallback` when the value is `key` and
+    /// there is no message for it.
+    pub fn fallback(mut self, key: &str, fallback: &str) -> Self {
+        self.format.fallback(key, fallback);
+        self
+    }
+
+    /// Finish building the `SelectFormat`.
+    pub fn build(self) -> ast::SelectFormat {
+        self.format
+    }
+}
+
+/// Builds an [`ast::SelectFormat`] whose branches are each a single
+/// [`ast::PluralFormat`] over the same variable. See
+/// [`MessageBuilder::nested_select`].
+///
+/// If no `other` branch is given, it is a plural format with empty
+/// branches.
+///
+/// [`ast::SelectFormat`]: icu/ast/struct.SelectFormat.html
+/// [`ast::PluralFormat`]: icu/ast/struct.PluralFormat.html
+/// [`MessageBuilder::nested_select`]: struct.MessageBuilder.html#method.nested_select
+#[derive(Debug)]
+pub struct NestedSelectBuilder {
+    plural_variable: String,
+    select: SelectBuilder,
+    has_other: bool,
+}
+
+impl NestedSelectBuilder {
+    /// Construct a `NestedSelectBuilder` selecting on `select_variable`
+    /// and pluralizing `plural_variable` in every branch.
+    pub fn new(select_variable: &str, plural_variable: &str) -> Self {
+        NestedSelectBuilder {
+            plural_variable: plural_variable.to_string(),
+            select: SelectBuilder::new(select_variable),
+            has_other: false,
+        }
+    }
+
+    fn branch<F>(&self, f: F) -> Message
+    where
+        F: FnOnce(PluralBuilder) -> PluralBuilder,
+    {
+        let plural = f(PluralBuilder::new(&self.plural_variable)).build();
+        Message::new(vec![Box::new(plural)])
+    }
+
+    /// Set the plural branches used when the selected value matches `key`.
+    pub fn case<F>(mut self, key: &str, f: F) -> Self
+    where
+        F: FnOnce(PluralBuilder) -> PluralBuilder,
+    {
+        let message = self.branch(f);
+        self.select = self.select.case(key, message);
+        self
+    }
+
+    /// Set the plural branches used when no other key matches.
+    pub fn other<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(PluralBuilder) -> PluralBuilder,
+    {
+        let message = self.branch(f);
+        self.select = self.select.other(message);
+        self.has_other = true;
+        self
+    }
+
+    /// Finish building the `SelectFormat`.
+    pub fn build(self) -> ast::SelectFormat {
+        if self.has_other {
+            self.select.build()
+        } else {
+            self.other(|p| p).select.build()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::parse;
+    use {Context, Message};
+
+    #[test]
+    fn builds_same_as_parse() {
+        let built = Message::builder()
+            .select("gender", |s| {
+                s.case("male", "He")
+                    .case("female", "She")
+                    .other("They")
+            })
+            .text(" found ")
+            .plural("count", |p| {
+                p.literal(0, "nothing")
+                    .one(Message::builder().placeholder().text(" item"))
+                    .other(Message::builder().placeholder().text(" items"))
+            })
+            .text(" for ")
+            .arg("name")
+            .build();
+
+        let parsed = parse(
+            "{gender, select, male {He} female {She} other {They}} found \
+             {count, plural, =0 {nothing} one {# item} other {# items}} for {name}",
+        ).unwrap();
+        assert_eq!(built, parsed);
+
+        let ctx = Context::default();
+        let output = format_message!(ctx, &built, gender => "female", count => 1, name => "Bo");
+        assert_eq!(output, "She found 1 item for Bo");
+    }
+
+    #[test]
+    fn nested_select_defaults_other_to_plural() {
+        let built = Message::builder()
+            .nested_select("gender", "count", |n| n.case("male", |p| p.other("items")))
+            .build();
+        let parsed = parse(
+            "{gender, select, male {{count, plural, other {items}}} \
+             other {{count, plural, other {}}}}",
+        ).unwrap();
+        assert_eq!(built, parsed);
+    }
+}
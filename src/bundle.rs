@@ -0,0 +1,938 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::collections::btree_map::{self, BTreeMap};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Bound;
+use std::sync::Arc;
+
+use edit::{self, EditError, RenameReport, RenamedMessage};
+use icu::parse::{is_valid_argument_name, parse_with, ParseError, ParseOptions};
+use icu::serialize;
+use intern::Interner;
+use stats;
+use lint::{self, LintConfig, LintDiagnostic, LintLevel};
+use {BundleStats, Context, DateTime, FormatError, InternStats, Message, SharedMessage, Value};
+
+/// Where messages referenced with `{@key}` are looked up. See
+/// [`Context::message_source`].
+///
+/// [`Context::message_source`]: struct.Context.html#structfield.message_source
+pub trait MessageSource: Send + Sync {
+    /// The message for `key`, if any.
+    fn message(&self, key: &str) -> Option<&Message>;
+}
+
+impl MessageSource for MessageBundle {
+    fn message(&self, key: &str) -> Option<&Message> {
+        self.get(key)
+    }
+}
+
+impl fmt::Debug for dyn MessageSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("MessageSource")
+    }
+}
+
+/// A catalog of messages for one language, looked up by key.
+///
+/// Keys are kept in sorted order, so iterating over a bundle is
+/// deterministic regardless of how it was loaded.
+///
+/// ```
+/// use message_format::{arg, Context, MessageBundle};
+///
+/// let bundle = MessageBundle::parse_entries(
+///     "en".parse().unwrap(),
+///     vec![("greeting", "Hello, {name}!"), ("farewell", "Goodbye!")],
+/// ).unwrap();
+///
+/// let ctx = Context::default();
+/// let greeting = bundle.get("greeting").unwrap();
+/// assert_eq!(greeting.format_with(&ctx, &arg("name", "Ann")), "Hello, Ann!");
+/// ```
+#[derive(Debug, Default)]
+pub struct MessageBundle {
+    language_tag: LanguageTag,
+    messages: BTreeMap<String, SharedMessage>,
+    metadata: BTreeMap<String, MessageMetadata>,
+    // The variants of each key, as (dimension, value, message), in
+    // the order they were added.
+    variants: BTreeMap<String, Vec<(String, String, SharedMessage)>>,
+    interner: Interner,
+}
+
+/// Information about a message for translators and tools, rather than
+/// for formatting.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageMetadata {
+    /// A description of where and how the message is used.
+    pub description: Option<String>,
+    /// The meaning of the message, distinguishing messages with the
+    /// same text that may need different translations.
+    pub meaning: Option<String>,
+    /// The maximum length of a translation, in characters.
+    pub max_length: Option<usize>,
+    /// The arguments of the message, with their types and example
+    /// values. See [`MessageBundle::validate_examples`].
+    ///
+    /// [`MessageBundle::validate_examples`]: struct.MessageBundle.html#method.validate_examples
+    pub placeholders: Vec<PlaceholderMetadata>,
+    /// The levels of lint rules for this message, by rule id, which
+    /// take precedence over the [`LintConfig`].
+    ///
+    /// [`LintConfig`]: lint/struct.LintConfig.html
+    pub lint_levels: Vec<(String, LintLevel)>,
+}
+
+/// The type and example value of an argument of a message, as given
+/// in the `placeholders` of ARB catalogs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlaceholderMetadata {
+    /// The name of the argument.
+    pub name: String,
+    /// The type of the argument, such as `String`, `int`, `num` or
+    /// `DateTime`.
+    pub type_name: Option<String>,
+    /// An example value, as text.
+    pub example: Option<String>,
+}
+
+impl PlaceholderMetadata {
+    /// The example as a value of the argument's type, or `None` if
+    /// there is no example. An example that isn't valid for its type,
+    /// such as `many` for an `int`, is kept as text, so that
+    /// formatting it reports the mismatch.
+    ///
+    /// Numbers keep the fraction digits of their example, and dates
+    /// are written as `2024-03-14`, optionally followed by a time as
+    /// in `T15:09:26`.
+    pub fn example_value(&self) -> Option<Value<'_>> {
+        let example = self.example.as_ref()?;
+        let typed = match self.type_name.as_ref().map(|t| t.to_ascii_lowercase()) {
+            Some(ref t) if t == "int" || t == "integer" => example.parse().ok().map(Value::Number),
+            Some(ref t) if t == "num" || t == "double" => example.parse().ok().map(Value::Decimal),
+            Some(ref t) if t == "datetime" => parse_date(example).map(Value::Date),
+            _ => None,
+        };
+        Some(typed.unwrap_or(Value::Str(example)))
+    }
+}
+
+// Parse a date such as `2024-03-14` or `2024-03-14T15:09:26`.
+fn parse_date(s: &str) -> Option<DateTime> {
+    let (date, time) = match s.find('T') {
+        Some(t) => (&s[..t], Some(&s[t + 1..])),
+        None => (s, None),
+    };
+    let mut fields = date.splitn(3, '-');
+    let (year, month, day) = (fields.next()?.parse().ok()?, fields.next()?.parse().ok()?, fields.next()?.parse().ok()?);
+    let date = DateTime::new(year, month, day);
+    let time = match time {
+        Some(time) => time,
+        None => return Some(date),
+    };
+    let mut fields = time.splitn(3, ':').map(|field| field.parse().ok());
+    let hour = fields.next()??;
+    let minute = fields.next().unwrap_or(Some(0))?;
+    let second = fields.next().unwrap_or(Some(0))?;
+    Some(date.with_time(hour, minute, second))
+}
+
+impl MessageMetadata {
+    /// Whether no metadata is given.
+    pub fn is_empty(&self) -> bool {
+        *self == MessageMetadata::default()
+    }
+}
+
+/// The messages in a catalog that failed to parse. See
+/// [`MessageBundle::parse_entries`].
+///
+/// [`MessageBundle::parse_entries`]: struct.MessageBundle.html#method.parse_entries
+#[derive(Clone, Debug, PartialEq)]
+pub struct BundleError {
+    /// The key and parse error of each failing message, in the order
+    /// the entries were given.
+    pub errors: Vec<(String, ParseError)>,
+}
+
+impl Error for BundleError {}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} message(s) failed to parse", self.errors.len())?;
+        for (key, error) in &self.errors {
+            write!(f, "\n  {}: {}", key, error)?;
+        }
+        Ok(())
+    }
+}
+
+/// What loading a catalog does with messages that fail to parse. See
+/// [`MessageBundle::load_entries`].
+///
+/// [`MessageBundle::load_entries`]: struct.MessageBundle.html#method.load_entries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadPolicy {
+    /// Fail to load the catalog, reporting every failing message.
+    FailFast,
+    /// Leave failing messages out of the bundle.
+    SkipBad,
+    /// Use the key of a failing message as its text, so that it still
+    /// shows something recognizable.
+    ReplaceWithKey,
+}
+
+/// A bundle loaded despite some of its messages failing to parse. See
+/// [`MessageBundle::load_entries`].
+///
+/// [`MessageBundle::load_entries`]: struct.MessageBundle.html#method.load_entries
+#[derive(Debug)]
+pub struct LoadReport {
+    /// The bundle of the messages that parsed, and of the replacements
+    /// of the others with [`LoadPolicy::ReplaceWithKey`].
+    ///
+    /// [`LoadPolicy::ReplaceWithKey`]: enum.LoadPolicy.html#variant.ReplaceWithKey
+    pub loaded: MessageBundle,
+    /// The key and parse error of each failing message, in the order
+    /// the entries were given.
+    pub errors: Vec<(String, ParseError)>,
+}
+
+impl LoadReport {
+    /// Whether every message parsed.
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl MessageBundle {
+    /// Construct an empty `MessageBundle` for a language.
+    pub fn new(language_tag: LanguageTag) -> Self {
+        MessageBundle {
+            language_tag,
+            messages: BTreeMap::new(),
+            metadata: BTreeMap::new(),
+            variants: BTreeMap::new(),
+            interner: Interner::default(),
+        }
+    }
+
+    /// Parse a catalog of `(key, source)` entries into a bundle.
+    ///
+    /// All entries are parsed, and every failure is reported rather
+    /// than only the first. When a key is repeated, the last entry
+    /// wins. With the `parallel` feature, large catalogs are parsed
+    /// on the rayon thread pool; the result is the same either way.
+    pub fn parse_entries<I, K, S>(language_tag: LanguageTag, entries: I) -> Result<Self, BundleError>
+    where
+        I: IntoIterator<Item = (K, S)>,
+        K: Into<String>,
+        S: AsRef<str> + Send,
+    {
+        MessageBundle::parse_entries_with(language_tag, entries, ParseOptions::default())
+    }
+
+    /// Like [`parse_entries`], parsing each message using `options`.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use message_format::icu::ParseOptions;
+    /// use message_format::{Context, EmptyArgs, MessageBundle};
+    ///
+    /// let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+    /// let bundle = Arc::new(MessageBundle::parse_entries_with(
+    ///     "en".parse().unwrap(),
+    ///     vec![("product", "Frobnicator"), ("welcome", "Welcome to {@product}!")],
+    ///     options,
+    /// ).unwrap());
+    ///
+    /// let ctx = Context::default().with_message_source(bundle.clone());
+    /// let welcome = bundle.get("welcome").unwrap();
+    /// assert_eq!(ctx.format(welcome, &EmptyArgs), "Welcome to Frobnicator!");
+    /// ```
+    ///
+    /// [`parse_entries`]: #method.parse_entries
+    pub fn parse_entries_with<I, K, S>(
+        language_tag: LanguageTag,
+        entries: I,
+        options: ParseOptions,
+    ) -> Result<Self, BundleError>
+    where
+        I: IntoIterator<Item = (K, S)>,
+        K: Into<String>,
+        S: AsRef<str> + Send,
+    {
+        MessageBundle::load_entries(language_tag, entries, options, LoadPolicy::FailFast).map(|report| report.loaded)
+    }
+
+    /// Like [`parse_entries_with`], handling the messages that fail to
+    /// parse as `policy` says, so that one bad translation doesn't
+    /// take down a whole language.
+    ///
+    /// Only [`LoadPolicy::FailFast`] returns an error; the others
+    /// always load the bundle and list the failures in the report.
+    ///
+    /// ```
+    /// use message_format::icu::ParseOptions;
+    /// use message_format::{LoadPolicy, MessageBundle};
+    ///
+    /// let entries = vec![("ok", "Fine"), ("broken", "{oops")];
+    /// let report = MessageBundle::load_entries(
+    ///     "en".parse().unwrap(),
+    ///     entries,
+    ///     ParseOptions::default(),
+    ///     LoadPolicy::ReplaceWithKey,
+    /// ).unwrap();
+    /// assert_eq!(report.errors.len(), 1);
+    /// assert_eq!(report.errors[0].0, "broken");
+    /// assert_eq!(report.loaded.get("broken"), Some(&"broken".into()));
+    /// assert_eq!(report.loaded.len(), 2);
+    /// ```
+    ///
+    /// [`parse_entries_with`]: #method.parse_entries_with
+    /// [`LoadPolicy::FailFast`]: enum.LoadPolicy.html#variant.FailFast
+    pub fn load_entries<I, K, S>(
+        language_tag: LanguageTag,
+        entries: I,
+        options: ParseOptions,
+        policy: LoadPolicy,
+    ) -> Result<LoadReport, BundleError>
+    where
+        I: IntoIterator<Item = (K, S)>,
+        K: Into<String>,
+        S: AsRef<str> + Send,
+    {
+        let entries: Vec<(String, S)> = entries.into_iter().map(|(k, s)| (k.into(), s)).collect();
+        let mut bundle = MessageBundle::new(language_tag);
+        let mut errors = vec![];
+        for (key, result) in parse_all(entries, options) {
+            match result {
+                Ok(message) => bundle.insert(key, message),
+                Err(error) => {
+                    if policy == LoadPolicy::ReplaceWithKey {
+                        bundle.insert(key.as_str(), Message::from(key.as_str()));
+                    }
+                    errors.push((key, error));
+                }
+            }
+        }
+        if policy == LoadPolicy::FailFast && !errors.is_empty() {
+            Err(BundleError { errors })
+        } else {
+            Ok(LoadReport { loaded: bundle, errors })
+        }
+    }
+
+    /// The language of the messages in this bundle.
+    pub fn language_tag(&self) -> &LanguageTag {
+        &self.language_tag
+    }
+
+    /// Add a message, replacing any existing message with the same key.
+    pub fn insert<K: Into<String>>(&mut self, key: K, message: Message) {
+        self.insert_shared(key, Arc::new(message));
+    }
+
+    /// Add a message shared with other owners, replacing any existing
+    /// message with the same key.
+    pub fn insert_shared<K: Into<String>>(&mut self, key: K, message: SharedMessage) {
+        self.messages.insert(key.into(), message);
+    }
+
+    /// Look up a message by key.
+    pub fn get(&self, key: &str) -> Option<&Message> {
+        self.messages.get(key).map(|message| &**message)
+    }
+
+    /// Look up a message by key, returning a handle to it which can
+    /// outlive the bundle, such as to move it into another thread or
+    /// an async task. Cloning the handle doesn't copy the message.
+    ///
+    /// ```
+    /// use std::thread;
+    /// use message_format::{arg, Context, MessageBundle};
+    ///
+    /// let bundle = MessageBundle::parse_entries("en".parse().unwrap(), vec![("hi", "Hi, {name}!")]).unwrap();
+    /// let hi = bundle.get_shared("hi").unwrap();
+    /// drop(bundle);
+    ///
+    /// let output = thread::spawn(move || hi.format_with(&Context::default(), &arg("name", "Ann")));
+    /// assert_eq!(output.join().unwrap(), "Hi, Ann!");
+    /// ```
+    pub fn get_shared(&self, key: &str) -> Option<SharedMessage> {
+        self.messages.get(key).cloned()
+    }
+
+    /// Add a variant of the message for a key, used instead of it when
+    /// the context's attribute `dimension` is `value`. This replaces
+    /// any variant for the same key, dimension and value.
+    ///
+    /// Variants cover differences beyond the language, such as formal
+    /// and informal German, or shorter text for SMS than for email.
+    ///
+    /// ```
+    /// use message_format::{Context, EmptyArgs, MessageBundle};
+    ///
+    /// let mut bundle = MessageBundle::new("de".parse().unwrap());
+    /// bundle.insert("thanks", "Danke dir!".into());
+    /// bundle.insert_variant("thanks", "formality", "formal", "Vielen Dank Ihnen!".into());
+    ///
+    /// let ctx = Context::default();
+    /// assert_eq!(ctx.format(bundle.get_for("thanks", &ctx).unwrap(), &EmptyArgs), "Danke dir!");
+    /// let formal = ctx.with_attribute("formality", "formal");
+    /// assert_eq!(formal.format(bundle.get_for("thanks", &formal).unwrap(), &EmptyArgs), "Vielen Dank Ihnen!");
+    /// ```
+    pub fn insert_variant<K: Into<String>>(&mut self, key: K, dimension: &str, value: &str, message: Message) {
+        let variants = self.variants.entry(key.into()).or_default();
+        match variants.iter().position(|(d, v, _)| d == dimension && v == value) {
+            Some(i) => variants[i].2 = Arc::new(message),
+            None => variants.push((dimension.to_string(), value.to_string(), Arc::new(message))),
+        }
+    }
+
+    /// The variants of the message for a key, as `(dimension, value,
+    /// message)`, in the order they were added.
+    pub fn variants(&self, key: &str) -> impl Iterator<Item = (&str, &str, &Message)> {
+        self.variants
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|(dimension, value, message)| (dimension.as_str(), value.as_str(), &**message))
+    }
+
+    /// Look up a message by key, choosing the first variant matching
+    /// the attributes of `ctx`, or else the message without variants.
+    pub fn get_for(&self, key: &str, ctx: &Context) -> Option<&Message> {
+        self.variants(key)
+            .find(|&(dimension, value, _)| ctx.attribute(dimension) == Some(value))
+            .map(|(_, _, message)| message)
+            .or_else(|| self.get(key))
+    }
+
+    /// Set the metadata for a key, replacing any existing metadata.
+    /// Empty metadata is removed.
+    pub fn set_metadata<K: Into<String>>(&mut self, key: K, metadata: MessageMetadata) {
+        let key = key.into();
+        if metadata.is_empty() {
+            self.metadata.remove(&key);
+        } else {
+            self.metadata.insert(key, metadata);
+        }
+    }
+
+    /// Look up the metadata for a key, if any was given.
+    ///
+    /// ```
+    /// use message_format::{MessageBundle, MessageMetadata};
+    ///
+    /// let mut bundle = MessageBundle::new("en".parse().unwrap());
+    /// bundle.insert("greeting", "Hello!".into());
+    /// bundle.set_metadata("greeting", MessageMetadata {
+    ///     description: Some("Shown on the front page.".to_string()),
+    ///     ..Default::default()
+    /// });
+    /// assert_eq!(bundle.metadata("greeting").unwrap().max_length, None);
+    /// assert_eq!(bundle.metadata("farewell"), None);
+    /// ```
+    pub fn metadata(&self, key: &str) -> Option<&MessageMetadata> {
+        self.metadata.get(key)
+    }
+
+    /// Format each message whose metadata lists its placeholders with
+    /// their example values, to check before deploying a catalog that
+    /// its messages render. Messages without placeholders listed are
+    /// skipped.
+    ///
+    /// The examples are checked against the formats using them in
+    /// every branch, as by [`Message::check_arg_types`], and then the
+    /// message is formatted with `ctx`. The first error of each message
+    /// is returned with its key.
+    ///
+    /// ```
+    /// use message_format::catalog::json::parse_entries_with_metadata;
+    /// use message_format::{Context, FormatError, MessageBundle};
+    ///
+    /// let source = r#"{
+    ///   "files": "{count, plural, one {# file} other {# files}}",
+    ///   "@files": {"placeholders": {"count": {"type": "int", "example": "3"}}},
+    ///   "due": "Due {when, date, short}",
+    ///   "@due": {"placeholders": {"when": {"type": "String", "example": "tomorrow"}}}
+    /// }"#;
+    /// let mut bundle = MessageBundle::new("en".parse().unwrap());
+    /// for (key, source, metadata) in parse_entries_with_metadata(source).unwrap() {
+    ///     bundle.insert(key.as_str(), message_format::icu::parse(&source).unwrap());
+    ///     bundle.set_metadata(key, metadata);
+    /// }
+    /// let failures = bundle.validate_examples(&Context::default());
+    /// assert_eq!(failures.len(), 1);
+    /// assert_eq!(failures[0].0, "due");
+    /// assert!(matches!(failures[0].1, FormatError::TypeMismatch { .. }));
+    /// ```
+    ///
+    /// [`Message::check_arg_types`]: struct.Message.html#method.check_arg_types
+    pub fn validate_examples(&self, ctx: &Context) -> Vec<(String, FormatError)> {
+        let mut failures = vec![];
+        for (key, metadata) in &self.metadata {
+            let message = match self.get(key) {
+                Some(message) if !metadata.placeholders.is_empty() => message,
+                _ => continue,
+            };
+            let args: HashMap<&str, Value> = metadata
+                .placeholders
+                .iter()
+                .filter_map(|placeholder| Some((placeholder.name.as_str(), placeholder.example_value()?)))
+                .collect();
+            let error = match message.check_arg_types(&args).into_iter().next() {
+                Some(error) => error,
+                None => match ctx.try_format(message, &args) {
+                    Ok(_) => continue,
+                    Err(error) => error,
+                },
+            };
+            failures.push((key.clone(), error));
+        }
+        failures
+    }
+
+    /// Check every message with the [lint] rules, at the levels given
+    /// by `config` and the metadata of the messages. The findings are
+    /// ordered by key.
+    ///
+    /// [lint]: lint/index.html
+    pub fn lint(&self, config: &LintConfig) -> Vec<LintDiagnostic> {
+        let mut found = vec![];
+        for (key, message) in &self.messages {
+            let metadata = self.metadata.get(key);
+            lint::lint_message(key, message, metadata, &self.language_tag, config, &mut found);
+        }
+        found
+    }
+
+    /// Rename the argument `old` to `new` in the messages, and their
+    /// variants, whose keys match `key_glob`, in which `*` matches any
+    /// text. This includes the `plural` formats whose `#` shows it, and
+    /// the placeholders of their metadata.
+    ///
+    /// The report gives each changed message serialized again, to
+    /// write back to its catalog. A message already using `new` is
+    /// left as it was, and reported as a conflict.
+    ///
+    /// ```
+    /// use message_format::MessageBundle;
+    ///
+    /// let mut bundle = MessageBundle::parse_entries("en".parse().unwrap(), vec![
+    ///     ("cart.items", "{n, plural, one {# item} other {# items}}"),
+    ///     ("cart.total", "Total for {n} items"),
+    ///     ("inbox", "{n} new"),
+    /// ]).unwrap();
+    /// let report = bundle.rename_argument("cart.*", "n", "count").unwrap();
+    /// assert_eq!(report.changed[0].source.as_ref().unwrap(), "{count, plural, one {# item} other {# items}}");
+    /// assert_eq!(report.changed[1].source.as_ref().unwrap(), "Total for {count} items");
+    /// assert_eq!(bundle.get("inbox").unwrap().argument_names(), ["n"]);
+    /// ```
+    pub fn rename_argument(&mut self, key_glob: &str, old: &str, new: &str) -> Result<RenameReport, EditError> {
+        if !is_valid_argument_name(new) {
+            return Err(EditError::InvalidArgumentName(new.to_string()));
+        }
+        let mut report = RenameReport::default();
+        let variants = self
+            .variants
+            .iter_mut()
+            .flat_map(|(key, variants)| variants.iter_mut().map(move |variant| (key, variant)));
+        let messages = self.messages.iter_mut().map(|(key, message)| (key, None, message));
+        let variants = variants.map(|(key, (dimension, value, message))| (key, Some((dimension, value)), message));
+        for (key, variant, message) in messages.chain(variants) {
+            if !lint::glob_matches(key_glob, key) {
+                continue;
+            }
+            match edit::rename_shared(message, old, new) {
+                Ok(0) => {}
+                Ok(uses) => report.changed.push(RenamedMessage {
+                    key: key.clone(),
+                    variant: variant.map(|(dimension, value)| (dimension.clone(), value.clone())),
+                    uses,
+                    source: serialize(message).ok(),
+                }),
+                Err(error) => report.conflicts.push((key.clone(), error)),
+            }
+        }
+        report.changed.sort_by(|a, b| a.key.cmp(&b.key));
+        for (key, metadata) in &mut self.metadata {
+            if !lint::glob_matches(key_glob, key) || report.conflicts.iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            for placeholder in metadata.placeholders.iter_mut().filter(|p| p.name == old) {
+                placeholder.name = new.to_string();
+            }
+        }
+        Ok(report)
+    }
+
+    /// The number of messages in the bundle.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether the bundle contains no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Iterate over the keys and messages, in key order.
+    pub fn iter(&self) -> BundleIter<'_> {
+        BundleIter {
+            inner: self.messages.iter(),
+        }
+    }
+
+    /// Count the messages and their parts, formats, arguments and text.
+    ///
+    /// ```
+    /// use message_format::MessageBundle;
+    ///
+    /// let bundle = MessageBundle::parse_entries("en".parse().unwrap(), vec![
+    ///     ("files", "{n, plural, one {# file} other {# files}} in {folder}"),
+    ///     ("hi", "Hi, {name}!"),
+    /// ]).unwrap();
+    /// let stats = bundle.stats();
+    /// assert_eq!((stats.messages, stats.plural_messages), (2, 1));
+    /// assert_eq!(stats.average_parts(), 3.0);
+    /// assert_eq!(stats.argument_names, ["folder", "n", "name"]);
+    /// assert_eq!(stats.longest[0], ("files".to_string(), 15));
+    /// ```
+    pub fn stats(&self) -> BundleStats {
+        stats::stats(self.iter(), self.variants.values().map(Vec::len).sum())
+    }
+
+    /// Share identical parts between messages, such as a repeated
+    /// "Learn more" or a `select` used by several messages, so that
+    /// each is stored once.
+    ///
+    /// Only the top-level parts of messages are shared, which includes
+    /// whole `plural` and `select` formats. Formatting is unaffected.
+    /// Call this again after adding messages to share their parts too.
+    /// Messages with a handle from [`get_shared`] elsewhere are left
+    /// as they are.
+    ///
+    /// ```
+    /// use message_format::MessageBundle;
+    ///
+    /// let mut bundle = MessageBundle::parse_entries(
+    ///     "en".parse().unwrap(),
+    ///     vec![("help.a", "Learn more"), ("help.b", "Learn more"), ("help.c", "Learn more")],
+    /// ).unwrap();
+    /// let stats = bundle.intern();
+    /// assert_eq!((stats.shared, stats.replaced), (1, 2));
+    /// assert!(stats.bytes_saved > 0);
+    /// ```
+    ///
+    /// [`get_shared`]: #method.get_shared
+    pub fn intern(&mut self) -> InternStats {
+        self.interner.prune();
+        let mut stats = InternStats::default();
+        let variants = self.variants.values_mut().flatten().map(|(_, _, message)| message);
+        for message in self.messages.values_mut().chain(variants) {
+            if let Some(message) = Arc::get_mut(message) {
+                self.interner.intern(message, &mut stats);
+            }
+        }
+        stats.shared = self.interner.shared();
+        stats
+    }
+
+    /// A view of the messages whose keys are within a namespace,
+    /// looked up by the rest of their key.
+    ///
+    /// Keys are namespaced with `.`, so the namespace `errors.login`
+    /// contains `errors.login.invalid_password` as `invalid_password`.
+    ///
+    /// ```
+    /// use message_format::MessageBundle;
+    ///
+    /// let bundle = MessageBundle::parse_entries(
+    ///     "en".parse().unwrap(),
+    ///     vec![
+    ///         ("errors.login.invalid_password", "That password is wrong."),
+    ///         ("errors.login.locked", "Your account is locked."),
+    ///         ("errors.signup.taken", "That name is taken."),
+    ///     ],
+    /// ).unwrap();
+    ///
+    /// let login = bundle.namespace("errors").namespace("login");
+    /// assert_eq!(login.get("locked"), bundle.get("errors.login.locked"));
+    /// let keys: Vec<&str> = login.iter().map(|(k, _)| k).collect();
+    /// assert_eq!(keys, ["invalid_password", "locked"]);
+    /// ```
+    pub fn namespace(&self, namespace: &str) -> Namespace<'_> {
+        Namespace {
+            bundle: self,
+            prefix: format!("{}.", namespace),
+        }
+    }
+}
+
+/// The messages in a namespace of a [`MessageBundle`]. See
+/// [`MessageBundle::namespace`].
+///
+/// [`MessageBundle`]: struct.MessageBundle.html
+/// [`MessageBundle::namespace`]: struct.MessageBundle.html#method.namespace
+#[derive(Clone, Debug)]
+pub struct Namespace<'b> {
+    bundle: &'b MessageBundle,
+    // The namespace followed by `.`.
+    prefix: String,
+}
+
+impl<'b> Namespace<'b> {
+    /// The full name of the namespace.
+    pub fn name(&self) -> &str {
+        &self.prefix[..self.prefix.len() - 1]
+    }
+
+    /// Look up a message by its key within the namespace.
+    pub fn get(&self, key: &str) -> Option<&'b Message> {
+        self.bundle.get(&format!("{}{}", self.prefix, key))
+    }
+
+    /// A view of a namespace nested within this one.
+    pub fn namespace(&self, namespace: &str) -> Namespace<'b> {
+        Namespace {
+            bundle: self.bundle,
+            prefix: format!("{}{}.", self.prefix, namespace),
+        }
+    }
+
+    /// Iterate over the keys within the namespace and their messages,
+    /// in key order. This includes the messages of nested namespaces.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &'b Message)> {
+        let prefix = self.prefix.as_str();
+        self.bundle
+            .messages
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(prefix))
+            .map(move |(key, message)| (&key[prefix.len()..], &**message))
+    }
+}
+
+/// An iterator over the keys and messages of a [`MessageBundle`], in
+/// key order. See [`MessageBundle::iter`].
+///
+/// [`MessageBundle`]: struct.MessageBundle.html
+/// [`MessageBundle::iter`]: struct.MessageBundle.html#method.iter
+#[derive(Clone, Debug)]
+pub struct BundleIter<'a> {
+    inner: btree_map::Iter<'a, String, SharedMessage>,
+}
+
+impl<'a> Iterator for BundleIter<'a> {
+    type Item = (&'a String, &'a Message);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, message)| (key, &**message))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a MessageBundle {
+    type Item = (&'a String, &'a Message);
+    type IntoIter = BundleIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+type ParsedEntry = (String, Result<Message, ParseError>);
+
+fn parse_chunk<S: AsRef<str>>(entries: Vec<(String, S)>, options: ParseOptions) -> Vec<ParsedEntry> {
+    entries
+        .into_iter()
+        .map(|(key, source)| {
+            let result = parse_with(source.as_ref(), options);
+            (key, result)
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn parse_all<S: AsRef<str> + Send>(entries: Vec<(String, S)>, options: ParseOptions) -> Vec<ParsedEntry> {
+    parse_chunk(entries, options)
+}
+
+// Below this many entries, splitting the work costs more than it saves.
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 256;
+
+#[cfg(feature = "parallel")]
+fn parse_all<S: AsRef<str> + Send>(entries: Vec<(String, S)>, options: ParseOptions) -> Vec<ParsedEntry> {
+    use rayon::prelude::*;
+
+    if entries.len() < PARALLEL_THRESHOLD {
+        return parse_chunk(entries, options);
+    }
+    // Collecting from a vector keeps the result in input order.
+    entries
+        .into_par_iter()
+        .map(|(key, source)| {
+            let result = parse_with(source.as_ref(), options);
+            (key, result)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadPolicy, MessageBundle, MessageMetadata, PlaceholderMetadata};
+    use icu::parse::{parse, ParseError};
+    use {arg, Context, EditError};
+
+    #[test]
+    fn errors_are_aggregated() {
+        let entries = (0..1000).map(|i| {
+            let source = if i % 250 == 7 { "{bad name}" } else { "Item {n}" };
+            (format!("key{:04}", i), source)
+        });
+        let error = MessageBundle::parse_entries("en".parse().unwrap(), entries).unwrap_err();
+        let keys: Vec<&str> = error.errors.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["key0007", "key0257", "key0507", "key0757"]);
+        assert_eq!(
+            error.errors[0].1,
+            ParseError::InvalidArgumentName("bad name".to_string())
+        );
+    }
+
+    #[test]
+    fn keys_are_ordered() {
+        let entries = vec![("b", "B"), ("a", "A"), ("c", "C"), ("a", "A2")];
+        let bundle = MessageBundle::parse_entries("en".parse().unwrap(), entries).unwrap();
+        let keys: Vec<&str> = bundle.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["a", "b", "c"]);
+        assert_eq!(bundle.len(), 3);
+        assert_eq!(bundle.get("a"), Some(&"A2".into()));
+    }
+
+    #[test]
+    fn variants_fall_back_to_the_base_message() {
+        let mut bundle = MessageBundle::new("de".parse().unwrap());
+        bundle.insert("sent", "Gesendet".into());
+        bundle.insert_variant("sent", "channel", "sms", "OK".into());
+        bundle.insert_variant("sent", "formality", "formal", "Wurde gesendet".into());
+        bundle.insert_variant("sent", "channel", "sms", "Ok".into());
+        bundle.insert_variant("other", "channel", "sms", "Nur SMS".into());
+
+        let ctx = Context::default();
+        assert_eq!(bundle.get_for("sent", &ctx), Some(&"Gesendet".into()));
+        let formal = ctx.with_attribute("formality", "formal");
+        assert_eq!(bundle.get_for("sent", &formal), Some(&"Wurde gesendet".into()));
+        let sms = formal.with_attribute("channel", "sms");
+        assert_eq!(bundle.get_for("sent", &sms), Some(&"Ok".into()));
+        assert_eq!(bundle.variants("sent").count(), 2);
+        assert_eq!(bundle.get_for("other", &Context::default()), None);
+        assert_eq!(bundle.len(), 1);
+    }
+
+    #[test]
+    fn interning_shares_identical_parts() {
+        let entries = vec![
+            ("a", "{n, plural, one {# item} other {# items}} left"),
+            ("b", "{n, plural, one {# item} other {# items}}"),
+            ("c", "{n, plural, one {# item} other {# things}}"),
+        ];
+        let mut bundle = MessageBundle::parse_entries("en".parse().unwrap(), entries).unwrap();
+        let before: Vec<String> = bundle.iter().map(|(_, m)| m.debug_sexpr()).collect();
+        let stats = bundle.intern();
+        assert_eq!((stats.parts, stats.shared, stats.replaced), (4, 1, 1));
+        let after: Vec<String> = bundle.iter().map(|(_, m)| m.debug_sexpr()).collect();
+        assert_eq!(before, after);
+        let plural = |key| bundle.get(key).unwrap().parts[0].as_any() as *const _ as *const u8;
+        assert_eq!(plural("a"), plural("b"));
+        assert_eq!(Context::default().format(bundle.get("b").unwrap(), &arg("n", 1)), "1 item");
+
+        // Interning again finds nothing new, and replaced messages are
+        // forgotten.
+        assert_eq!(bundle.intern().replaced, 0);
+        bundle.insert("a", "Gone".into());
+        assert_eq!(bundle.intern().shared, 0);
+    }
+
+    #[test]
+    fn load_policies() {
+        let entries = vec![("a", "A"), ("b", "{bad name}"), ("c", "{n m}")];
+        let load = |policy| MessageBundle::load_entries("en".parse().unwrap(), entries.clone(), Default::default(), policy);
+
+        let error = load(LoadPolicy::FailFast).unwrap_err();
+        assert_eq!(error.errors.len(), 2);
+
+        let report = load(LoadPolicy::SkipBad).unwrap();
+        let keys: Vec<&str> = report.errors.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["b", "c"]);
+        assert_eq!(report.loaded.len(), 1);
+        assert!(!report.is_clean());
+
+        let report = load(LoadPolicy::ReplaceWithKey).unwrap();
+        assert_eq!(report.loaded.get("c"), Some(&"c".into()));
+        assert_eq!(report.loaded.len(), 3);
+    }
+
+    #[test]
+    fn arguments_are_renamed_across_messages() {
+        let entries = vec![
+            ("cart.a", "{n, plural, one {# item} other {# items}} left"),
+            ("cart.b", "{n, plural, one {# item} other {# items}}"),
+            ("cart.c", "{n} of {count}"),
+            ("other", "{n}"),
+        ];
+        let mut bundle = MessageBundle::parse_entries("en".parse().unwrap(), entries).unwrap();
+        bundle.insert_variant("cart.b", "tone", "casual", parse("{n} things").unwrap());
+        bundle.set_metadata(
+            "cart.a",
+            MessageMetadata {
+                placeholders: vec![PlaceholderMetadata {
+                    name: "n".into(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        bundle.intern();
+        let shared = bundle.get_shared("cart.b").unwrap();
+
+        let report = bundle.rename_argument("cart.*", "n", "count").unwrap();
+        let changed: Vec<(&str, bool, Option<&str>)> = report
+            .changed
+            .iter()
+            .map(|c| (c.key.as_str(), c.variant.is_some(), c.source.as_deref()))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                ("cart.a", false, Some("{count, plural, one {# item} other {# items}} left")),
+                ("cart.b", false, Some("{count, plural, one {# item} other {# items}}")),
+                ("cart.b", true, Some("{count} things")),
+            ]
+        );
+        assert_eq!(report.conflicts, [("cart.c".to_string(), EditError::ArgumentExists("count".into()))]);
+        assert_eq!(
+            report.to_string(),
+            "cart.a: 1 uses\ncart.b: 1 uses\ncart.b [tone=casual]: 1 uses\n\
+             cart.c: the message already uses the argument \"count\"\n"
+        );
+        assert_eq!(bundle.metadata("cart.a").unwrap().placeholders[0].name, "count");
+        assert_eq!(bundle.get("other").unwrap().argument_names(), ["n"]);
+        assert_eq!(shared.argument_names(), ["n"]);
+        assert_eq!(Context::default().format(bundle.get("cart.a").unwrap(), &arg("count", 2)), "2 items left");
+        assert_eq!(bundle.rename_argument("*", "n", "a b"), Err(EditError::InvalidArgumentName("a b".into())));
+    }
+}
@@ -0,0 +1,242 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::sync::Arc;
+
+use {locale, Args, Context, Message, MessageBundle, MessageSource, UsageTracker};
+
+/// The bundles for each language an application supports, with one
+/// of them used as the default.
+#[derive(Debug)]
+pub struct BundleSet {
+    bundles: Vec<MessageBundle>,
+    default: usize,
+}
+
+impl BundleSet {
+    /// Construct a `BundleSet` whose default is `default`.
+    pub fn new(default: MessageBundle) -> Self {
+        BundleSet {
+            bundles: vec![default],
+            default: 0,
+        }
+    }
+
+    /// Add a bundle, replacing any bundle for the same language.
+    pub fn insert(&mut self, bundle: MessageBundle) {
+        match self
+            .bundles
+            .iter()
+            .position(|b| b.language_tag() == bundle.language_tag())
+        {
+            Some(i) => self.bundles[i] = bundle,
+            None => self.bundles.push(bundle),
+        }
+    }
+
+    /// The bundle for exactly `language_tag`, if any.
+    pub fn get(&self, language_tag: &LanguageTag) -> Option<&MessageBundle> {
+        self.bundles.iter().find(|b| b.language_tag() == language_tag)
+    }
+
+    /// The default bundle.
+    pub fn default_bundle(&self) -> &MessageBundle {
+        &self.bundles[self.default]
+    }
+
+    /// The languages of the bundles, in the order they were added.
+    pub fn languages(&self) -> impl Iterator<Item = &LanguageTag> {
+        self.bundles.iter().map(MessageBundle::language_tag)
+    }
+
+    // The indices of the bundles to use for the requested languages,
+    // most preferred first and ending with the default.
+    fn negotiate(&self, requested: &[LanguageTag]) -> Vec<usize> {
+        let available: Vec<LanguageTag> = self.languages().cloned().collect();
+        locale::negotiate(requested, &available, &available[self.default])
+            .iter()
+            .filter_map(|tag| available.iter().position(|t| t == tag))
+            .collect()
+    }
+}
+
+/// Looks up messages in the bundles of a `Localizer`, in order.
+struct ChainSource {
+    bundles: Arc<BundleSet>,
+    order: Vec<usize>,
+    usage: Option<Arc<UsageTracker>>,
+}
+
+impl MessageSource for ChainSource {
+    fn message(&self, key: &str) -> Option<&Message> {
+        let message = self.order.iter().find_map(|&i| self.bundles.bundles[i].get(key));
+        if let (Some(usage), Some(_)) = (&self.usage, message) {
+            usage.record(key);
+        }
+        message
+    }
+}
+
+/// A handle for formatting messages in the best available languages
+/// for a user, such as the client of a web request.
+///
+/// Messages are looked up in the preferred language first and then in
+/// each fallback, ending with the default bundle. Each message is
+/// formatted with the language of the bundle it came from, so plural
+/// rules and date formats match the text.
+///
+/// Cloning a `Localizer` is cheap, as the bundles are shared.
+///
+/// ```
+/// use std::sync::Arc;
+/// use message_format::{arg, BundleSet, Localizer, MessageBundle};
+///
+/// let en = MessageBundle::parse_entries(
+///     "en".parse().unwrap(),
+///     vec![("hello", "Hello, {name}!"), ("bye", "Goodbye!")],
+/// ).unwrap();
+/// let fr = MessageBundle::parse_entries(
+///     "fr".parse().unwrap(),
+///     vec![("hello", "Bonjour, {name} !")],
+/// ).unwrap();
+/// let mut bundles = BundleSet::new(en);
+/// bundles.insert(fr);
+///
+/// let localizer = Localizer::new(Arc::new(bundles), &["fr-CA".parse().unwrap()]);
+/// assert_eq!(localizer.language_tag().to_string(), "fr");
+/// assert_eq!(localizer.format("hello", &arg("name", "Ann")).unwrap(), "Bonjour, Ann !");
+/// assert_eq!(localizer.format("bye", &arg("name", "Ann")).unwrap(), "Goodbye!");
+/// assert_eq!(localizer.format("missing", &arg("name", "Ann")), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Localizer {
+    bundles: Arc<BundleSet>,
+    chain: Vec<(usize, Context)>,
+    key_fallback: bool,
+    usage: Option<Arc<UsageTracker>>,
+}
+
+impl Localizer {
+    /// Construct a `Localizer` for the `requested` languages, most
+    /// preferred first, based on `Context::current`.
+    pub fn new(bundles: Arc<BundleSet>, requested: &[LanguageTag]) -> Self {
+        Localizer::with_context(bundles, requested, &Context::current())
+    }
+
+    /// Like [`new`], but the contexts used for formatting are copies
+    /// of `ctx` with their language replaced.
+    ///
+    /// [`new`]: #method.new
+    ///
+    /// Messages referenced with `{@key}` are looked up along the same
+    /// chain of bundles.
+    pub fn with_context(bundles: Arc<BundleSet>, requested: &[LanguageTag], ctx: &Context) -> Self {
+        let order = bundles.negotiate(requested);
+        let source: Arc<dyn MessageSource> = Arc::new(ChainSource {
+            bundles: bundles.clone(),
+            order: order.clone(),
+            usage: None,
+        });
+        let chain = order
+            .into_iter()
+            .map(|i| {
+                let ctx = Context {
+                    language_tag: bundles.bundles[i].language_tag().clone(),
+                    message_source: Some(source.clone()),
+                    ..ctx.clone()
+                };
+                (i, ctx)
+            })
+            .collect();
+        Localizer {
+            bundles,
+            chain,
+            key_fallback: false,
+            usage: None,
+        }
+    }
+
+    /// Count the keys looked up, including those of the messages
+    /// referenced with `{@key}`, in `tracker`. See [`UsageTracker`].
+    ///
+    /// [`UsageTracker`]: struct.UsageTracker.html
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        let source: Arc<dyn MessageSource> = Arc::new(ChainSource {
+            bundles: self.bundles.clone(),
+            order: self.chain.iter().map(|&(i, _)| i).collect(),
+            usage: Some(tracker.clone()),
+        });
+        for (_, ctx) in &mut self.chain {
+            ctx.message_source = Some(source.clone());
+        }
+        self.usage = Some(tracker);
+        self
+    }
+
+    /// Render missing messages, and messages that fail to format, as
+    /// their key in brackets, such as `⟦errors.login.invalid⟧`, so
+    /// that they stand out when testing a translation.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use message_format::{arg, BundleSet, EmptyArgs, Localizer, MessageBundle};
+    ///
+    /// let en = MessageBundle::parse_entries(
+    ///     "en".parse().unwrap(),
+    ///     vec![("due", "Due {when, date}")],
+    /// ).unwrap();
+    /// let localizer = Localizer::new(Arc::new(BundleSet::new(en)), &[]).with_key_fallback(true);
+    /// assert_eq!(localizer.format("errors.login.invalid", &EmptyArgs).unwrap(), "⟦errors.login.invalid⟧");
+    /// assert_eq!(localizer.format("due", &arg("when", "soon")).unwrap(), "⟦due⟧");
+    /// ```
+    pub fn with_key_fallback(mut self, enabled: bool) -> Self {
+        self.key_fallback = enabled;
+        self
+    }
+
+    /// The language of the most preferred bundle.
+    pub fn language_tag(&self) -> &LanguageTag {
+        &self.chain[0].1.language_tag
+    }
+
+    /// The context used for the most preferred bundle.
+    pub fn context(&self) -> &Context {
+        &self.chain[0].1
+    }
+
+    /// The bundles used, most preferred first.
+    pub fn bundles(&self) -> impl Iterator<Item = &MessageBundle> {
+        self.chain.iter().map(move |&(i, _)| &self.bundles.bundles[i])
+    }
+
+    /// Look up a message along with the context to format it with.
+    /// Variants matching the attributes of the context are preferred.
+    pub fn lookup(&self, key: &str) -> Option<(&Message, &Context)> {
+        let found = self
+            .chain
+            .iter()
+            .filter_map(|(i, ctx)| self.bundles.bundles[*i].get_for(key, ctx).map(|m| (m, ctx)))
+            .next();
+        if let (Some(usage), Some(_)) = (&self.usage, found) {
+            usage.record(key);
+        }
+        found
+    }
+
+    /// Format the message for `key`, or return `None` if no bundle
+    /// contains it. See [`with_key_fallback`] for marking missing
+    /// messages instead.
+    ///
+    /// [`with_key_fallback`]: #method.with_key_fallback
+    pub fn format<'f>(&self, key: &str, args: &'f dyn Args<'f>) -> Option<String> {
+        if !self.key_fallback {
+            return self.lookup(key).map(|(message, ctx)| ctx.format(message, args));
+        }
+        let formatted = self.lookup(key).and_then(|(message, ctx)| ctx.try_format(message, args).ok());
+        Some(formatted.unwrap_or_else(|| format!("⟦{}⟧", key)))
+    }
+}
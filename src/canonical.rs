@@ -0,0 +1,126 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rewriting a message's AST into a canonical form.
+
+use std::mem;
+
+use icu::ast;
+use Message;
+
+/// A key of a `select` or `plural` removed by [`Message::normalize`]
+/// because an earlier branch of the same format already has it.
+///
+/// [`Message::normalize`]: struct.Message.html#method.normalize
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// The argument the format selects on.
+    pub argument: String,
+    /// The key, as written, such as `female` or `=0`.
+    pub key: String,
+}
+
+pub(crate) fn normalize(message: &mut Message, duplicates: &mut Vec<DuplicateKey>) {
+    message.walk_mut(&mut |part| {
+        let any = match part.as_any_mut() {
+            Some(any) => any,
+            None => return,
+        };
+        if let Some(fmt) = any.downcast_mut::<ast::SelectFormat>() {
+            normalize_select(fmt, duplicates);
+        } else if let Some(fmt) = any.downcast_mut::<ast::PluralFormat>() {
+            normalize_plural(fmt, duplicates);
+        } else if let Some(fmt) = any.downcast_mut::<ast::UnknownFormat>() {
+            if let Some(ref mut style) = fmt.style {
+                *style = style.split_whitespace().collect::<Vec<_>>().join(" ");
+            }
+        }
+    });
+}
+
+// Drop the keys matched by an earlier mapping, which can never be
+// selected, and sort the mappings and their keys. The branches are
+// normalized afterwards, by `normalize`.
+fn normalize_select(fmt: &mut ast::SelectFormat, duplicates: &mut Vec<DuplicateKey>) {
+    let mut seen: Vec<String> = vec![];
+    let mut mappings = mem::take(&mut fmt.mappings);
+    mappings.retain_mut(|mapping| {
+        let aliases = mem::take(&mut mapping.aliases);
+        let mut keys = vec![];
+        for key in Some(mem::take(&mut mapping.value)).into_iter().chain(aliases) {
+            if seen.contains(&key) {
+                duplicates.push(DuplicateKey {
+                    argument: fmt.variable_name.clone(),
+                    key,
+                });
+            } else {
+                seen.push(key.clone());
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        let mut keys = keys.into_iter();
+        match keys.next() {
+            Some(value) => {
+                mapping.value = value;
+                mapping.aliases = keys.collect();
+                true
+            }
+            None => false,
+        }
+    });
+    mappings.sort_by(|a, b| a.value.cmp(&b.value));
+    fmt.mappings = mappings;
+}
+
+// Sort the exact values, keeping the first branch of each. The
+// categories are kept in their own fields, so they are already in
+// order.
+fn normalize_plural(fmt: &mut ast::PluralFormat, duplicates: &mut Vec<DuplicateKey>) {
+    let argument = &fmt.variable_name;
+    fmt.literals.sort_by(|a, b| a.value.total_cmp(&b.value));
+    fmt.literals.dedup_by(|later, kept| {
+        let duplicate = later.value == kept.value;
+        if duplicate {
+            duplicates.push(DuplicateKey {
+                argument: argument.clone(),
+                key: format!("={}", later.value),
+            });
+        }
+        duplicate
+    });
+}
+
+// Find the keys `normalize` would remove, without changing the message.
+pub(crate) fn find_duplicates(message: &Message, duplicates: &mut Vec<DuplicateKey>) {
+    message.walk(&mut |part| {
+        let any = part.as_any();
+        if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+            let mut seen: Vec<&str> = vec![];
+            for mapping in &fmt.mappings {
+                for key in Some(&mapping.value).into_iter().chain(&mapping.aliases) {
+                    if seen.contains(&key.as_str()) {
+                        duplicates.push(DuplicateKey {
+                            argument: fmt.variable_name.clone(),
+                            key: key.clone(),
+                        });
+                    } else {
+                        seen.push(key);
+                    }
+                }
+            }
+        } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+            for (i, mapping) in fmt.literals.iter().enumerate() {
+                if fmt.literals[..i].iter().any(|earlier| earlier.value == mapping.value) {
+                    duplicates.push(DuplicateKey {
+                        argument: fmt.variable_name.clone(),
+                        key: format!("={}", mapping.value),
+                    });
+                }
+            }
+        }
+    });
+}
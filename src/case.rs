@@ -0,0 +1,230 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::fmt;
+
+/// A change of case applied to formatted text, either to an argument
+/// with a `text` format, such as `{name, text, capitalize}`, or to a
+/// whole message with [`Context::message_case`].
+///
+/// Case mapping is Unicode-aware, including the final form of the
+/// Greek sigma. It follows the rules of Turkish and Azerbaijani for
+/// the dotted and dotless `i`, of Lithuanian for the dot of `i` under
+/// accents and of Dutch for title casing `ij`.
+///
+/// [`Context::message_case`]: struct.Context.html#structfield.message_case
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseTransform {
+    /// Map all letters to upper case.
+    Upper,
+    /// Map all letters to lower case.
+    Lower,
+    /// Map the first letter to title case, leaving the rest as is, as
+    /// at the start of a sentence.
+    Capitalize,
+    /// Map the first letter of each word to title case and the rest of
+    /// the word to lower case.
+    Title,
+}
+
+impl CaseTransform {
+    /// Parse a transform as it appears in a message, such as `upper`.
+    pub fn parse(transform: &str) -> Option<Self> {
+        match transform {
+            "upper" => Some(CaseTransform::Upper),
+            "lower" => Some(CaseTransform::Lower),
+            "capitalize" => Some(CaseTransform::Capitalize),
+            "title" => Some(CaseTransform::Title),
+            _ => None,
+        }
+    }
+
+    /// Apply the transform to `text` using the case rules of
+    /// `language_tag`.
+    ///
+    /// ```
+    /// use message_format::CaseTransform;
+    ///
+    /// let en = "en".parse().unwrap();
+    /// assert_eq!(CaseTransform::Capitalize.apply("¿qué tal?", &en), "¿Qué tal?");
+    /// assert_eq!(CaseTransform::Title.apply("ǆungla STRAẞE", &en), "ǅungla Straße");
+    /// assert_eq!(CaseTransform::Upper.apply("istanbul", &"tr".parse().unwrap()), "İSTANBUL");
+    /// ```
+    pub fn apply(self, text: &str, language_tag: &LanguageTag) -> String {
+        let rules = CaseRules::of(language_tag);
+        match self {
+            CaseTransform::Upper => upper(text, rules),
+            CaseTransform::Lower => lower(text, rules),
+            CaseTransform::Capitalize | CaseTransform::Title => {
+                let mut output = String::with_capacity(text.len());
+                let mut rest = text;
+                let mut capitalized = false;
+                while !rest.is_empty() {
+                    let start = rest.find(char::is_alphanumeric).unwrap_or(rest.len());
+                    output.push_str(&rest[..start]);
+                    let word_len = rest[start..].find(|c: char| c.is_whitespace() || c == '-');
+                    let end = start + word_len.unwrap_or(rest.len() - start);
+                    let word = &rest[start..end];
+                    rest = &rest[end..];
+                    if word.is_empty() || (self == CaseTransform::Capitalize && capitalized) {
+                        output.push_str(word);
+                        continue;
+                    }
+                    capitalized = true;
+                    let tail = push_title_case(word, rules, &mut output);
+                    if self == CaseTransform::Title {
+                        output.push_str(&lower(tail, rules));
+                    } else {
+                        output.push_str(tail);
+                    }
+                }
+                output
+            }
+        }
+    }
+}
+
+// The languages with their own case mappings.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CaseRules {
+    Default,
+    // Turkish and Azerbaijani, with a dotted and a dotless `i`.
+    Turkic,
+    // Lithuanian, which keeps the dot of `i` under accents.
+    Lithuanian,
+    // Dutch, where `ij` is title cased as one letter.
+    Dutch,
+}
+
+impl CaseRules {
+    fn of(language_tag: &LanguageTag) -> Self {
+        let language = language_tag.language.as_deref().unwrap_or("").to_ascii_lowercase();
+        match &language[..] {
+            "tr" | "az" => CaseRules::Turkic,
+            "lt" => CaseRules::Lithuanian,
+            "nl" => CaseRules::Dutch,
+            _ => CaseRules::Default,
+        }
+    }
+}
+
+// Accents written above a letter, which keep the dot of a Lithuanian
+// `i` when lower casing.
+fn is_accent_above(c: char) -> bool {
+    ('\u{300}'..='\u{314}').contains(&c)
+}
+
+fn upper(text: &str, rules: CaseRules) -> String {
+    match rules {
+        CaseRules::Turkic => text.replace('i', "\u{130}").to_uppercase(),
+        CaseRules::Lithuanian => {
+            // The dot kept above a soft-dotted letter goes away.
+            let mut undotted = String::with_capacity(text.len());
+            let mut previous = None;
+            for c in text.chars() {
+                if !(c == '\u{307}' && matches!(previous, Some('i' | 'j' | '\u{12f}'))) {
+                    undotted.push(c);
+                }
+                previous = Some(c);
+            }
+            undotted.to_uppercase()
+        }
+        _ => text.to_uppercase(),
+    }
+}
+
+fn lower(text: &str, rules: CaseRules) -> String {
+    match rules {
+        CaseRules::Turkic => text
+            .replace("I\u{307}", "i")
+            .replace('I', "\u{131}")
+            .replace('\u{130}', "i")
+            .to_lowercase(),
+        CaseRules::Lithuanian => {
+            let mut dotted = String::with_capacity(text.len());
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                let accented = chars.peek().is_some_and(|&next| is_accent_above(next));
+                match c {
+                    'I' | 'J' | '\u{12e}' if accented => {
+                        dotted.extend(c.to_lowercase());
+                        dotted.push('\u{307}');
+                    }
+                    '\u{cc}' => dotted.push_str("i\u{307}\u{300}"),
+                    '\u{cd}' => dotted.push_str("i\u{307}\u{301}"),
+                    '\u{128}' => dotted.push_str("i\u{307}\u{303}"),
+                    c => dotted.push(c),
+                }
+            }
+            dotted.to_lowercase()
+        }
+        _ => text.to_lowercase(),
+    }
+}
+
+// Write the title case of the start of `word`, returning the rest.
+// Title case differs from upper case for a few digraphs and for `ß`.
+fn push_title_case<'w>(word: &'w str, rules: CaseRules, output: &mut String) -> &'w str {
+    if rules == CaseRules::Dutch && word.get(..2).is_some_and(|start| start.eq_ignore_ascii_case("ij")) {
+        output.push_str("IJ");
+        return &word[2..];
+    }
+    let mut chars = word.chars();
+    match chars.next() {
+        Some('\u{1c4}'..='\u{1c6}') => output.push('\u{1c5}'),
+        Some('\u{1c7}'..='\u{1c9}') => output.push('\u{1c8}'),
+        Some('\u{1ca}'..='\u{1cc}') => output.push('\u{1cb}'),
+        Some('\u{1f1}'..='\u{1f3}') => output.push('\u{1f2}'),
+        Some('\u{df}') => output.push_str("Ss"),
+        Some('i') if rules == CaseRules::Turkic => output.push('\u{130}'),
+        Some(c) => output.extend(c.to_uppercase()),
+        None => (),
+    }
+    chars.as_str()
+}
+
+impl fmt::Display for CaseTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CaseTransform::Upper => f.write_str("upper"),
+            CaseTransform::Lower => f.write_str("lower"),
+            CaseTransform::Capitalize => f.write_str("capitalize"),
+            CaseTransform::Title => f.write_str("title"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CaseTransform;
+
+    fn apply(transform: &str, text: &str, language: &str) -> String {
+        CaseTransform::parse(transform).unwrap().apply(text, &language.parse().unwrap())
+    }
+
+    #[test]
+    fn lower_case_uses_the_final_sigma() {
+        assert_eq!(apply("lower", "ΣΟΦΟΣ ΣΑΣ", "el"), "σοφος σας");
+        assert_eq!(apply("title", "ΣΟΦΟΣ", "el"), "Σοφος");
+    }
+
+    #[test]
+    fn title_case_lowers_the_rest_of_words() {
+        assert_eq!(apply("title", "hello WORLD-wide", "en"), "Hello World-Wide");
+        assert_eq!(apply("capitalize", "hello WORLD", "en"), "Hello WORLD");
+        assert_eq!(apply("title", "ijssel IJMUIDEN", "nl"), "IJssel IJmuiden");
+        assert_eq!(apply("title", "ijssel", "en"), "Ijssel");
+    }
+
+    #[test]
+    fn language_specific_rules() {
+        assert_eq!(apply("lower", "DİYARBAKIR", "tr"), "diyarbakır");
+        assert_eq!(apply("title", "istanbul İZMİR", "tr"), "İstanbul İzmir");
+        assert_eq!(apply("lower", "\u{cc}R I\u{303}", "lt"), "i\u{307}\u{300}r i\u{307}\u{303}");
+        assert_eq!(apply("upper", "i\u{307}\u{300}r", "lt"), "I\u{300}R");
+    }
+}
@@ -0,0 +1,326 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comparing two versions of a bundle.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use datetime::DateTimeKind;
+use icu::{ast, serialize};
+use {Message, MessageBundle};
+
+/// A change to the structure of a message, found by [`diff`].
+///
+/// [`diff`]: fn.diff.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StructuralChange {
+    /// The message uses an argument it didn't use before.
+    ArgumentAdded(String),
+    /// The message no longer uses an argument.
+    ArgumentRemoved(String),
+    /// An argument is formatted differently, such as a `number` that
+    /// became a `plural`. The formats are listed by name, such as
+    /// `number` or `select`, with `simple` for `{name}`.
+    ArgumentFormatChanged {
+        /// The name of the argument.
+        name: String,
+        /// The formats the argument had.
+        old: Vec<String>,
+        /// The formats the argument has.
+        new: Vec<String>,
+    },
+    /// A `plural` or `select` format gained a branch.
+    BranchAdded {
+        /// The name of the argument of the format.
+        name: String,
+        /// The selector of the branch, such as `one` or `=0`.
+        selector: String,
+    },
+    /// A `plural` or `select` format lost a branch.
+    BranchRemoved {
+        /// The name of the argument of the format.
+        name: String,
+        /// The selector of the branch, such as `one` or `=0`.
+        selector: String,
+    },
+    /// Only the text of the message changed.
+    TextChanged,
+}
+
+impl fmt::Display for StructuralChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StructuralChange::ArgumentAdded(ref name) => write!(f, "argument `{}` added", name),
+            StructuralChange::ArgumentRemoved(ref name) => write!(f, "argument `{}` removed", name),
+            StructuralChange::ArgumentFormatChanged { ref name, ref old, ref new } => write!(
+                f,
+                "argument `{}` changed from {} to {}",
+                name,
+                old.join("/"),
+                new.join("/")
+            ),
+            StructuralChange::BranchAdded { ref name, ref selector } => {
+                write!(f, "branch `{}` of `{}` added", selector, name)
+            }
+            StructuralChange::BranchRemoved { ref name, ref selector } => {
+                write!(f, "branch `{}` of `{}` removed", selector, name)
+            }
+            StructuralChange::TextChanged => f.write_str("text changed"),
+        }
+    }
+}
+
+/// A message whose key is in both bundles, but which changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageChange {
+    /// The key of the message.
+    pub key: String,
+    /// The source of the old message.
+    pub old: String,
+    /// The source of the new message.
+    pub new: String,
+    /// How the message changed. This holds only
+    /// [`StructuralChange::TextChanged`] when the structure is the same.
+    ///
+    /// [`StructuralChange::TextChanged`]: enum.StructuralChange.html#variant.TextChanged
+    pub changes: Vec<StructuralChange>,
+}
+
+impl MessageChange {
+    /// Whether the structure of the message changed, rather than only
+    /// its text.
+    pub fn is_structural(&self) -> bool {
+        self.changes.iter().any(|change| *change != StructuralChange::TextChanged)
+    }
+}
+
+/// The differences between two versions of a bundle, found by
+/// [`diff`].
+///
+/// Displaying a diff writes one line per added or removed key, and
+/// one line per change to a message.
+///
+/// [`diff`]: fn.diff.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CatalogDiff {
+    /// The keys only in the new bundle, in order.
+    pub added: Vec<String>,
+    /// The keys only in the old bundle, in order.
+    pub removed: Vec<String>,
+    /// The messages that changed, in order of their keys.
+    pub changed: Vec<MessageChange>,
+}
+
+impl CatalogDiff {
+    /// Whether the bundles hold the same messages.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether a key was removed or a message changed structurally,
+    /// which may break code or translations relying on it.
+    pub fn has_breaking_changes(&self) -> bool {
+        !self.removed.is_empty() || self.changed.iter().any(MessageChange::is_structural)
+    }
+}
+
+impl fmt::Display for CatalogDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for key in &self.added {
+            writeln!(f, "+ {}", key)?;
+        }
+        for key in &self.removed {
+            writeln!(f, "- {}", key)?;
+        }
+        for change in &self.changed {
+            for detail in &change.changes {
+                writeln!(f, "~ {}: {}", change.key, detail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The arguments of a message with their formats, and the branches of
+/// its `plural` and `select` formats.
+#[derive(Default)]
+struct Structure {
+    arguments: BTreeMap<String, BTreeSet<String>>,
+    branches: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Structure {
+    fn of(message: &Message) -> Self {
+        let mut structure = Structure::default();
+        structure.collect(message);
+        structure
+    }
+
+    fn argument(&mut self, name: &str, format: &str) {
+        self.arguments.entry(name.to_string()).or_default().insert(format.to_string());
+    }
+
+    fn branch(&mut self, name: &str, selector: String, message: &Message) {
+        self.branches.entry(name.to_string()).or_default().insert(selector);
+        self.collect(message);
+    }
+
+    fn collect(&mut self, message: &Message) {
+        for part in &message.parts {
+            let any = part.as_any();
+            if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+                self.argument(&fmt.variable_name, "simple");
+            } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+                self.argument(&fmt.variable_name, "number");
+            } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+                let kind = match fmt.kind {
+                    DateTimeKind::Date => "date",
+                    DateTimeKind::Time => "time",
+                };
+                self.argument(&fmt.variable_name, kind);
+            } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+                self.argument(&fmt.variable_name, "unit");
+            } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+                self.argument(&fmt.variable_name, "text");
+            } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+                self.argument(&fmt.variable_name, &fmt.type_name);
+            } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+                self.argument(&fmt.variable_name, "select");
+                for mapping in &fmt.mappings {
+                    self.branch(&fmt.variable_name, mapping.value.clone(), &mapping.message);
+                }
+                self.branch(&fmt.variable_name, "other".to_string(), &fmt.default);
+            } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+                self.argument(&fmt.variable_name, "plural");
+                for mapping in &fmt.literals {
+                    self.branch(&fmt.variable_name, format!("={}", mapping.value), &mapping.message);
+                }
+                let categories = [
+                    ("zero", &fmt.zero),
+                    ("one", &fmt.one),
+                    ("two", &fmt.two),
+                    ("few", &fmt.few),
+                    ("many", &fmt.many),
+                ];
+                for &(keyword, message) in &categories {
+                    if let Some(ref message) = *message {
+                        self.branch(&fmt.variable_name, keyword.to_string(), message);
+                    }
+                }
+                self.branch(&fmt.variable_name, "other".to_string(), &fmt.other);
+            } else {
+                for nested in part.children() {
+                    self.collect(nested);
+                }
+            }
+        }
+    }
+}
+
+fn structural_changes(old: &Message, new: &Message) -> Vec<StructuralChange> {
+    let old = Structure::of(old);
+    let new = Structure::of(new);
+    let mut changes = vec![];
+    for (name, formats) in &new.arguments {
+        match old.arguments.get(name) {
+            None => changes.push(StructuralChange::ArgumentAdded(name.clone())),
+            Some(old_formats) if old_formats != formats => changes.push(StructuralChange::ArgumentFormatChanged {
+                name: name.clone(),
+                old: old_formats.iter().cloned().collect(),
+                new: formats.iter().cloned().collect(),
+            }),
+            Some(_) => (),
+        }
+    }
+    for name in old.arguments.keys().filter(|name| !new.arguments.contains_key(*name)) {
+        changes.push(StructuralChange::ArgumentRemoved(name.clone()));
+    }
+    let empty = BTreeSet::new();
+    for (name, selectors) in &new.branches {
+        let old_selectors = old.branches.get(name).unwrap_or(&empty);
+        for selector in selectors.difference(old_selectors) {
+            changes.push(StructuralChange::BranchAdded {
+                name: name.clone(),
+                selector: selector.clone(),
+            });
+        }
+    }
+    for (name, old_selectors) in &old.branches {
+        let selectors = new.branches.get(name).unwrap_or(&empty);
+        for selector in old_selectors.difference(selectors) {
+            changes.push(StructuralChange::BranchRemoved {
+                name: name.clone(),
+                selector: selector.clone(),
+            });
+        }
+    }
+    if changes.is_empty() {
+        changes.push(StructuralChange::TextChanged);
+    }
+    changes
+}
+
+/// Compare two versions of a bundle, reporting the keys added and
+/// removed and how the messages in both changed.
+///
+/// Release tooling can use this to require review when a message
+/// gains an argument or loses a branch, which translations must
+/// follow.
+///
+/// ```
+/// use message_format::catalog::{diff, StructuralChange};
+/// use message_format::MessageBundle;
+///
+/// let en = || "en".parse().unwrap();
+/// let old = MessageBundle::parse_entries(en(), vec![
+///     ("files", "{n, plural, one {# file} other {# files}}"),
+///     ("bye", "Bye"),
+///     ("hi", "Hi"),
+/// ]).unwrap();
+/// let new = MessageBundle::parse_entries(en(), vec![
+///     ("files", "{n, plural, other {# files in {folder}}}"),
+///     ("hi", "Hello"),
+///     ("welcome", "Welcome"),
+/// ]).unwrap();
+///
+/// let diff = diff(&old, &new);
+/// assert_eq!(diff.added, ["welcome"]);
+/// assert_eq!(diff.removed, ["bye"]);
+/// assert_eq!(diff.changed[0].changes, [
+///     StructuralChange::ArgumentAdded("folder".into()),
+///     StructuralChange::BranchRemoved { name: "n".into(), selector: "one".into() },
+/// ]);
+/// assert_eq!(diff.changed[1].changes, [StructuralChange::TextChanged]);
+/// assert!(diff.has_breaking_changes());
+/// ```
+pub fn diff(old: &MessageBundle, new: &MessageBundle) -> CatalogDiff {
+    let mut result = CatalogDiff::default();
+    for (key, message) in new.iter() {
+        let old_message = match old.get(key) {
+            Some(old_message) => old_message,
+            None => {
+                result.added.push(key.clone());
+                continue;
+            }
+        };
+        if old_message == message {
+            continue;
+        }
+        result.changed.push(MessageChange {
+            key: key.clone(),
+            old: serialize(old_message).unwrap_or_default(),
+            new: serialize(message).unwrap_or_default(),
+            changes: structural_changes(old_message, message),
+        });
+    }
+    for (key, _) in old.iter() {
+        if new.get(key).is_none() {
+            result.removed.push(key.clone());
+        }
+    }
+    result
+}
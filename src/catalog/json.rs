@@ -0,0 +1,429 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading JSON catalogs, where nested objects are namespaces.
+//!
+//! ```text
+//! {
+//!   "errors": {
+//!     "login": { "invalid_password": "That password is wrong." }
+//!   }
+//! }
+//! ```
+//!
+//! An object with a `defaultMessage` or `message` string is a message
+//! with [metadata], as written by FormatJS and Chrome extensions:
+//!
+//! ```text
+//! {
+//!   "greeting": {
+//!     "defaultMessage": "Hello!",
+//!     "description": "Shown on the front page.",
+//!     "meaning": "salutation",
+//!     "maxLength": 20,
+//!     "lint": { "too-long": "deny" }
+//!   }
+//! }
+//! ```
+//!
+//! The `lint` member sets the levels of [lint rules] for the message.
+//!
+//! Metadata can also be given in a member named after the message with
+//! an `@` before it, as in ARB catalogs, which also list the arguments
+//! of the message with their types and example values:
+//!
+//! ```text
+//! {
+//!   "files": "{count, plural, one {# file} other {# files}}",
+//!   "@files": {
+//!     "description": "The number of files selected.",
+//!     "placeholders": { "count": { "type": "int", "example": "3" } }
+//!   }
+//! }
+//! ```
+//!
+//! Other members starting with `@`, such as `@@locale`, are skipped.
+//! Only objects, arrays, strings and non-negative integers are read,
+//! and catalogs don't use arrays.
+//!
+//! [metadata]: ../../struct.MessageMetadata.html
+//! [lint rules]: ../../lint/index.html
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use lint::LintLevel;
+use {MessageMetadata, PlaceholderMetadata};
+
+/// Parse a JSON catalog into `(key, source)` entries, joining the keys
+/// of nested objects with `.`.
+///
+/// On failure, the number of the line with the error is returned.
+///
+/// ```
+/// use message_format::catalog::json::parse_entries;
+///
+/// let entries = parse_entries(r#"{"a": {"b": "B", "c": {"d": "D"}}, "e": "E"}"#).unwrap();
+/// assert_eq!(entries, [
+///     ("a.b".to_string(), "B".to_string()),
+///     ("a.c.d".to_string(), "D".to_string()),
+///     ("e".to_string(), "E".to_string()),
+/// ]);
+/// ```
+pub fn parse_entries(source: &str) -> Result<Vec<(String, String)>, usize> {
+    let entries = parse_entries_with_metadata(source)?;
+    Ok(entries.into_iter().map(|(key, source, _)| (key, source)).collect())
+}
+
+/// Parse a JSON catalog into `(key, source, metadata)` entries.
+///
+/// On failure, the number of the line with the error is returned.
+pub fn parse_entries_with_metadata(source: &str) -> Result<Vec<(String, String, MessageMetadata)>, usize> {
+    let root = parse_object(source)?;
+    let mut entries = vec![];
+    flatten("", root, &mut entries)?;
+    Ok(entries)
+}
+
+// Parse a document whose root is an object into its members.
+pub(crate) fn parse_object(source: &str) -> Result<Vec<(String, usize, Value)>, usize> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let mut reader = Reader {
+        source,
+        chars: source.char_indices().peekable(),
+    };
+    let root = reader.object()?;
+    reader.skip_white_space();
+    if reader.chars.peek().is_some() {
+        return Err(reader.line());
+    }
+    Ok(root)
+}
+
+pub(crate) enum Value {
+    String(String),
+    Number(usize),
+    // The members of an object and the line each starts on.
+    Object(Vec<(String, usize, Value)>),
+    Array(Vec<Value>),
+}
+
+fn flatten(
+    prefix: &str,
+    members: Vec<(String, usize, Value)>,
+    entries: &mut Vec<(String, String, MessageMetadata)>,
+) -> Result<(), usize> {
+    let join = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    };
+    let mut annotations = vec![];
+    for (name, line, value) in members {
+        if let Some(annotated) = name.strip_prefix('@') {
+            match value {
+                Value::Object(members) if !annotated.starts_with('@') => {
+                    annotations.push((join(annotated), members));
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let key = join(&name);
+        match value {
+            Value::String(source) => entries.push((key, source, MessageMetadata::default())),
+            Value::Object(members) => match message(&members, line)? {
+                Some((source, metadata)) => entries.push((key, source, metadata)),
+                None => flatten(&key, members, entries)?,
+            },
+            Value::Number(_) | Value::Array(_) => return Err(line),
+        }
+    }
+    // Metadata of messages which aren't in the catalog is skipped.
+    for (key, members) in annotations {
+        let metadata = match entries.iter_mut().find(|entry| entry.0 == key) {
+            Some(entry) => &mut entry.2,
+            None => continue,
+        };
+        for (name, line, value) in &members {
+            read_metadata(metadata, name, *line, value)?;
+        }
+    }
+    Ok(())
+}
+
+// The message and metadata of an object, if it is a message.
+fn message(
+    members: &[(String, usize, Value)],
+    line: usize,
+) -> Result<Option<(String, MessageMetadata)>, usize> {
+    let mut source = None;
+    let mut metadata = MessageMetadata::default();
+    for (name, line, value) in members {
+        match (name.as_str(), value) {
+            ("defaultMessage", Value::String(s)) | ("message", Value::String(s)) => {
+                source = Some(s.clone())
+            }
+            ("defaultMessage", _) | ("message", _) => return Err(*line),
+            _ => read_metadata(&mut metadata, name, *line, value)?,
+        }
+    }
+    match source {
+        Some(source) => Ok(Some((source, metadata))),
+        None if metadata.is_empty() => Ok(None),
+        None => Err(line),
+    }
+}
+
+// Read a member of the metadata of a message, skipping unknown ones.
+fn read_metadata(metadata: &mut MessageMetadata, name: &str, line: usize, value: &Value) -> Result<(), usize> {
+    match (name, value) {
+        ("description", Value::String(s)) => metadata.description = Some(s.clone()),
+        ("meaning", Value::String(s)) => metadata.meaning = Some(s.clone()),
+        ("maxLength", Value::Number(n)) => metadata.max_length = Some(*n),
+        ("placeholders", Value::Object(placeholders)) => {
+            for (name, line, value) in placeholders {
+                metadata.placeholders.push(placeholder(name, *line, value)?);
+            }
+        }
+        ("lint", Value::Object(levels)) => {
+            for (rule, line, level) in levels {
+                match level {
+                    Value::String(level) => match LintLevel::from_name(level) {
+                        Some(level) => metadata.lint_levels.push((rule.clone(), level)),
+                        None => return Err(*line),
+                    },
+                    _ => return Err(*line),
+                }
+            }
+        }
+        ("maxLength", _) | ("placeholders", _) | ("lint", _) => return Err(line),
+        _ => {}
+    }
+    Ok(())
+}
+
+// The type and example of an ARB placeholder.
+fn placeholder(name: &str, line: usize, value: &Value) -> Result<PlaceholderMetadata, usize> {
+    let members = match value {
+        Value::Object(members) => members,
+        _ => return Err(line),
+    };
+    let mut placeholder = PlaceholderMetadata {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    for (name, line, value) in members {
+        match (name.as_str(), value) {
+            ("type", Value::String(s)) => placeholder.type_name = Some(s.clone()),
+            ("example", Value::String(s)) => placeholder.example = Some(s.clone()),
+            ("example", Value::Number(n)) => placeholder.example = Some(n.to_string()),
+            ("type", _) | ("example", _) => return Err(*line),
+            _ => {}
+        }
+    }
+    Ok(placeholder)
+}
+
+struct Reader<'s> {
+    source: &'s str,
+    chars: Peekable<CharIndices<'s>>,
+}
+
+impl<'s> Reader<'s> {
+    fn line(&mut self) -> usize {
+        let offset = self.chars.peek().map_or(self.source.len(), |&(i, _)| i);
+        self.source[..offset].matches('\n').count() + 1
+    }
+
+    fn skip_white_space(&mut self) {
+        while self.chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), usize> {
+        self.skip_white_space();
+        match self.chars.next_if(|&(_, c)| c == expected) {
+            Some(_) => Ok(()),
+            None => Err(self.line()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Vec<(String, usize, Value)>, usize> {
+        let mut members = vec![];
+        self.expect('{')?;
+        self.skip_white_space();
+        if self.chars.next_if(|&(_, c)| c == '}').is_some() {
+            return Ok(members);
+        }
+        loop {
+            self.skip_white_space();
+            let line = self.line();
+            let name = self.string()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            members.push((name, line, value));
+            self.skip_white_space();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, '}')) => return Ok(members),
+                _ => return Err(self.line()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, usize> {
+        self.skip_white_space();
+        match self.chars.peek() {
+            Some(&(_, '{')) => Ok(Value::Object(self.object()?)),
+            Some(&(_, '[')) => Ok(Value::Array(self.array()?)),
+            Some(&(_, c)) if c.is_ascii_digit() => Ok(Value::Number(self.number()?)),
+            _ => Ok(Value::String(self.string()?)),
+        }
+    }
+
+    fn array(&mut self) -> Result<Vec<Value>, usize> {
+        let mut values = vec![];
+        self.expect('[')?;
+        self.skip_white_space();
+        if self.chars.next_if(|&(_, c)| c == ']').is_some() {
+            return Ok(values);
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_white_space();
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, ']')) => return Ok(values),
+                _ => return Err(self.line()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, usize> {
+        let line = self.line();
+        let mut number: usize = 0;
+        while let Some((_, c)) = self.chars.next_if(|&(_, c)| c.is_ascii_digit()) {
+            let digit = c.to_digit(10).unwrap() as usize;
+            number = number.checked_mul(10).and_then(|n| n.checked_add(digit)).ok_or(line)?;
+        }
+        Ok(number)
+    }
+
+    fn string(&mut self) -> Result<String, usize> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let line = self.line();
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(value),
+                Some((_, '\\')) => {
+                    let c = match self.chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'u')) => self.unicode_escape().ok_or(line)?,
+                        Some((_, c @ ('"' | '\\' | '/'))) => c,
+                        _ => return Err(line),
+                    };
+                    value.push(c);
+                }
+                Some((_, c)) if c >= ' ' => value.push(c),
+                _ => return Err(line),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            code = code * 16 + self.chars.next()?.1.to_digit(16)?;
+        }
+        Some(code)
+    }
+
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        // A high surrogate must be followed by an escaped low one.
+        self.chars.next_if(|&(_, c)| c == '\\')?;
+        self.chars.next_if(|&(_, c)| c == 'u')?;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_entries, parse_entries_with_metadata};
+    use lint::LintLevel;
+
+    #[test]
+    fn escapes_are_decoded() {
+        let entries = parse_entries(r#"{"k": "a\"b\\c\n\u00e9\ud83d\ude00"}"#).unwrap();
+        assert_eq!(entries[0].1, "a\"b\\c\né\u{1F600}");
+    }
+
+    #[test]
+    fn messages_have_metadata() {
+        let source = r#"{
+            "a": {"defaultMessage": "A", "description": "The letter", "maxLength": 3},
+            "b": {"message": "B", "meaning": "bee", "lint": {"too-long": "allow"}},
+            "c": {"d": "D"}
+        }"#;
+        let entries = parse_entries_with_metadata(source).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!((entries[0].0.as_str(), entries[0].1.as_str()), ("a", "A"));
+        assert_eq!(entries[0].2.description.as_deref(), Some("The letter"));
+        assert_eq!(entries[0].2.max_length, Some(3));
+        assert_eq!(entries[1].2.meaning.as_deref(), Some("bee"));
+        assert_eq!(entries[1].2.lint_levels, [("too-long".to_string(), LintLevel::Allow)]);
+        assert_eq!((entries[2].0.as_str(), entries[2].2.is_empty()), ("c.d", true));
+        assert_eq!(parse_entries_with_metadata("{\n\"a\": {\"description\": \"x\"}}"), Err(2));
+        let source = "{\"a\": {\"message\": \"A\", \"lint\": {\n\"too-long\": \"forbid\"}}}";
+        assert_eq!(parse_entries_with_metadata(source), Err(2));
+    }
+
+    #[test]
+    fn errors_report_the_line() {
+        assert_eq!(parse_entries("{\n  \"a\": \"A\",\n  \"b\": 1\n}"), Err(3));
+        assert_eq!(parse_entries("{\"a\": \"A\"}\n{"), Err(2));
+        assert_eq!(parse_entries("{}"), Ok(vec![]));
+        assert_eq!(parse_entries("\u{feff}{\"a\": \"A\"}"), Ok(vec![("a".to_string(), "A".to_string())]));
+        assert_eq!(parse_entries("{\n  \"a\": [\"A\"]\n}"), Err(2));
+    }
+
+    #[test]
+    fn arb_metadata() {
+        let source = r#"{
+            "@@locale": "en",
+            "files": "{count, plural, one {# file} other {# files}}",
+            "@files": {
+                "description": "Selected files",
+                "placeholders": {"count": {"type": "int", "example": 3}, "who": {}}
+            }
+        }"#;
+        let entries = parse_entries_with_metadata(source).unwrap();
+        assert_eq!(entries.len(), 1);
+        let metadata = &entries[0].2;
+        assert_eq!(metadata.description.as_deref(), Some("Selected files"));
+        let placeholders: Vec<_> = metadata
+            .placeholders
+            .iter()
+            .map(|p| (p.name.as_str(), p.type_name.as_deref(), p.example.as_deref()))
+            .collect();
+        assert_eq!(placeholders, [("count", Some("int"), Some("3")), ("who", None, None)]);
+        assert_eq!(parse_entries_with_metadata(r#"{"@a": {"maxLength": "3"}}"#), Ok(vec![]));
+    }
+}
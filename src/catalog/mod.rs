@@ -0,0 +1,396 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Loading bundles from catalog files.
+//!
+//! A catalog file holds the messages for one language and is named
+//! after its language tag, such as `en-US.messages`. Each line is a
+//! key and a message pattern separated by `=`, with whitespace around
+//! both trimmed. Blank lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! # Shown on the front page.
+//! # @meaning salutation
+//! # @max-length 40
+//! greeting = Hello, {name}!
+//!
+//! farewell = Goodbye!
+//! ```
+//!
+//! The comment lines directly above an entry are its [metadata]: the
+//! `@meaning` and `@max-length` lines set those fields, lines such as
+//! `@deny missing-plural-category` set the level of a [lint] rule, and
+//! the other lines are joined to form its description.
+//!
+//! Files named like `en-US.json` are read as [JSON catalogs] instead,
+//! where nested objects become dotted keys such as
+//! `errors.login.invalid_password`.
+//!
+//! Files named like `en-US.yml` are read as Rails style [YAML catalogs],
+//! using the entries under the top level key for their language. A
+//! top level key for any other language is an error.
+//!
+//! [metadata]: ../struct.MessageMetadata.html
+//! [lint]: ../lint/index.html
+//! [JSON catalogs]: json/index.html
+//! [YAML catalogs]: yaml/index.html
+
+use language_tags::LanguageTag;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+mod diff;
+pub mod json;
+mod store;
+pub mod yaml;
+
+pub use self::diff::{diff, CatalogDiff, MessageChange, StructuralChange};
+pub use self::store::{load_store, CatalogFuture, CatalogStore};
+
+use icu::ParseOptions;
+use lint::LintLevel;
+use {BundleError, BundleSet, LoadPolicy, LoadReport, MessageBundle, MessageMetadata};
+
+/// The extension of catalog files.
+pub const EXTENSION: &str = "messages";
+
+/// The extension of JSON catalog files.
+pub const JSON_EXTENSION: &str = "json";
+
+/// The extensions of YAML catalog files.
+pub const YAML_EXTENSIONS: &[&str] = &["yml", "yaml"];
+
+/// An error from loading a catalog.
+#[derive(Debug)]
+pub enum CatalogError {
+    /// A file or directory couldn't be read.
+    Io(PathBuf, io::Error),
+    /// A file name isn't a valid language tag.
+    InvalidLanguageTag(PathBuf),
+    /// A file is malformed at a line, numbered from 1.
+    Syntax(PathBuf, usize),
+    /// Messages in a file failed to parse.
+    Bundle(PathBuf, BundleError),
+    /// There is no catalog for the default language, given as a
+    /// string.
+    MissingDefault(String),
+    /// A YAML file has a top level key, given as a string, which isn't
+    /// the language in its name.
+    LanguageMismatch(PathBuf, String),
+}
+
+impl Error for CatalogError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CatalogError::Io(_, error) => Some(error),
+            CatalogError::Bundle(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            CatalogError::InvalidLanguageTag(path) => {
+                write!(f, "{}: file name is not a language tag", path.display())
+            }
+            CatalogError::Syntax(path, line) => {
+                write!(f, "{}:{}: malformed catalog", path.display(), line)
+            }
+            CatalogError::Bundle(path, error) => write!(f, "{}: {}", path.display(), error),
+            CatalogError::MissingDefault(tag) => write!(f, "no catalog for default language {}", tag),
+            CatalogError::LanguageMismatch(path, key) => {
+                write!(f, "{}: top level key {} is not the language of the file", path.display(), key)
+            }
+        }
+    }
+}
+
+/// Parse the contents of a catalog file into `(key, source)` entries.
+///
+/// On failure, the number of the first malformed line is returned.
+pub fn parse_entries(source: &str) -> Result<Vec<(&str, &str)>, usize> {
+    let entries = parse_entries_with_metadata(source)?;
+    Ok(entries.into_iter().map(|(key, source, _)| (key, source)).collect())
+}
+
+/// Parse the contents of a catalog file into `(key, source, metadata)`
+/// entries.
+///
+/// On failure, the number of the first malformed line is returned.
+pub fn parse_entries_with_metadata(source: &str) -> Result<Vec<(&str, &str, MessageMetadata)>, usize> {
+    let mut entries = vec![];
+    let mut metadata = MessageMetadata::default();
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            metadata = MessageMetadata::default();
+        } else if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            if let Some(meaning) = comment.strip_prefix("@meaning ") {
+                metadata.meaning = Some(meaning.trim().to_string());
+            } else if let Some(max_length) = comment.strip_prefix("@max-length ") {
+                metadata.max_length = Some(max_length.trim().parse().map_err(|_| i + 1)?);
+            } else if let Some((level, rule)) = lint_level(comment) {
+                metadata.lint_levels.push((rule.to_string(), level));
+            } else if !comment.is_empty() {
+                let description = metadata.description.get_or_insert_with(String::new);
+                if !description.is_empty() {
+                    description.push(' ');
+                }
+                description.push_str(comment);
+            }
+        } else {
+            match line.find('=') {
+                Some(eq) if eq > 0 => {
+                    let (key, source) = (line[..eq].trim_end(), line[eq + 1..].trim_start());
+                    entries.push((key, source, metadata));
+                    metadata = MessageMetadata::default();
+                }
+                _ => return Err(i + 1),
+            }
+        }
+    }
+    Ok(entries)
+}
+
+// The level and rule of a comment such as `@deny too-long`.
+fn lint_level(comment: &str) -> Option<(LintLevel, &str)> {
+    let (level, rule) = comment.strip_prefix('@')?.split_once(' ')?;
+    Some((LintLevel::from_name(level)?, rule.trim()))
+}
+
+/// Load a bundle from a catalog file of any supported format, taking
+/// its language from the file name.
+pub fn load_file(path: &Path) -> Result<MessageBundle, CatalogError> {
+    load_file_with(path, LoadPolicy::FailFast).map(|report| report.loaded)
+}
+
+/// Like [`load_file`], handling the messages that fail to parse as
+/// `policy` says.
+///
+/// [`load_file`]: fn.load_file.html
+pub fn load_file_with(path: &Path, policy: LoadPolicy) -> Result<LoadReport, CatalogError> {
+    let source = fs::read_to_string(path).map_err(|e| CatalogError::Io(path.to_path_buf(), e))?;
+    parse_file_with(path, &source, policy)
+}
+
+/// Parse the contents of a catalog file into a bundle, taking the
+/// format and language from the file name as [`load_file`] does.
+///
+/// [`load_file`]: fn.load_file.html
+pub fn parse_file(path: &Path, source: &str) -> Result<MessageBundle, CatalogError> {
+    parse_file_with(path, source, LoadPolicy::FailFast).map(|report| report.loaded)
+}
+
+/// Like [`parse_file`], handling the messages that fail to parse as
+/// `policy` says. A malformed file is an error whatever the policy.
+///
+/// ```
+/// use std::path::Path;
+/// use message_format::catalog::parse_file_with;
+/// use message_format::LoadPolicy;
+///
+/// let source = "greeting = Hello, {name}!\nfarewell = Bye, {bad name}\n";
+/// let report = parse_file_with(Path::new("en.messages"), source, LoadPolicy::SkipBad).unwrap();
+/// assert_eq!(report.loaded.len(), 1);
+/// assert_eq!(report.errors[0].0, "farewell");
+/// ```
+///
+/// [`parse_file`]: fn.parse_file.html
+pub fn parse_file_with(path: &Path, source: &str, policy: LoadPolicy) -> Result<LoadReport, CatalogError> {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+    let language_tag: LanguageTag = stem
+        .parse()
+        .map_err(|_| CatalogError::InvalidLanguageTag(path.to_path_buf()))?;
+    let syntax = |line| CatalogError::Syntax(path.to_path_buf(), line);
+    let mut entries: Vec<(String, String, MessageMetadata)> = match Format::of(path) {
+        Some(Format::Json) => json::parse_entries_with_metadata(source).map_err(syntax)?,
+        Some(Format::Yaml) => {
+            let prefix = format!("{}.", stem);
+            let mut entries = vec![];
+            for (key, source) in yaml::parse_entries(source).map_err(syntax)? {
+                let key = match key.strip_prefix(&prefix) {
+                    Some(key) => key.to_string(),
+                    None => {
+                        let top = key.split('.').next().unwrap_or_default().to_string();
+                        return Err(CatalogError::LanguageMismatch(path.to_path_buf(), top));
+                    }
+                };
+                entries.push((key, source, MessageMetadata::default()));
+            }
+            entries
+        }
+        _ => parse_entries_with_metadata(source)
+            .map_err(syntax)?
+            .into_iter()
+            .map(|(key, source, metadata)| (key.to_string(), source.to_string(), metadata))
+            .collect(),
+    };
+    let metadata: Vec<(String, MessageMetadata)> = entries
+        .iter_mut()
+        .map(|(key, _, metadata)| (key.clone(), mem::take(metadata)))
+        .collect();
+    let sources = entries.into_iter().map(|(key, source, _)| (key, source));
+    let mut report = MessageBundle::load_entries(language_tag, sources, ParseOptions::default(), policy)
+        .map_err(|e| CatalogError::Bundle(path.to_path_buf(), e))?;
+    for (key, metadata) in metadata {
+        report.loaded.set_metadata(key, metadata);
+    }
+    Ok(report)
+}
+
+enum Format {
+    Messages,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?;
+        if extension == EXTENSION {
+            Some(Format::Messages)
+        } else if extension == JSON_EXTENSION {
+            Some(Format::Json)
+        } else if YAML_EXTENSIONS.contains(&extension) {
+            Some(Format::Yaml)
+        } else {
+            None
+        }
+    }
+}
+
+// Whether a file is named like a catalog.
+pub(crate) fn is_catalog_file(path: &Path) -> bool {
+    Format::of(path).is_some()
+}
+
+/// The catalog files in a directory, sorted by name.
+pub fn catalog_files(dir: &Path) -> Result<Vec<PathBuf>, CatalogError> {
+    let io_error = |e| CatalogError::Io(dir.to_path_buf(), e);
+    let mut files = vec![];
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_file() && is_catalog_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Load every catalog file in a directory into a [`BundleSet`].
+///
+/// [`BundleSet`]: ../struct.BundleSet.html
+pub fn load_dir(dir: &Path, default: &LanguageTag) -> Result<BundleSet, CatalogError> {
+    let mut bundles = vec![];
+    for path in catalog_files(dir)? {
+        bundles.push(load_file(&path)?);
+    }
+    bundle_set(bundles, default)
+}
+
+/// Like [`load_dir`], handling the messages that fail to parse as
+/// `policy` says. The failures of each file are returned as a
+/// [`CatalogError::Bundle`] alongside the set.
+///
+/// [`load_dir`]: fn.load_dir.html
+/// [`CatalogError::Bundle`]: enum.CatalogError.html#variant.Bundle
+pub fn load_dir_with(
+    dir: &Path,
+    default: &LanguageTag,
+    policy: LoadPolicy,
+) -> Result<(BundleSet, Vec<CatalogError>), CatalogError> {
+    let mut bundles = vec![];
+    let mut failures = vec![];
+    for path in catalog_files(dir)? {
+        let report = load_file_with(&path, policy)?;
+        if !report.is_clean() {
+            failures.push(CatalogError::Bundle(path, BundleError { errors: report.errors }));
+        }
+        bundles.push(report.loaded);
+    }
+    Ok((bundle_set(bundles, default)?, failures))
+}
+
+// Gather bundles into a set, with the bundle for `default` as its
+// default.
+fn bundle_set(mut bundles: Vec<MessageBundle>, default: &LanguageTag) -> Result<BundleSet, CatalogError> {
+    let position = bundles
+        .iter()
+        .position(|b| b.language_tag() == default)
+        .ok_or_else(|| CatalogError::MissingDefault(default.to_string()))?;
+    let mut set = BundleSet::new(bundles.remove(position));
+    for bundle in bundles {
+        set.insert(bundle);
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{parse_entries, parse_entries_with_metadata, parse_file, CatalogError};
+    use lint::LintLevel;
+
+    #[test]
+    fn entries_are_parsed() {
+        let source = "# comment\n\ngreeting = Hello, {name}!\n  a=b = c  \n";
+        assert_eq!(
+            parse_entries(source).unwrap(),
+            [("greeting", "Hello, {name}!"), ("a", "b = c")]
+        );
+        assert_eq!(parse_entries("a = b\nno equals sign\n"), Err(2));
+        assert_eq!(parse_entries("= value"), Err(1));
+        assert_eq!(parse_entries("\u{feff}a = b"), Ok(vec![("a", "b")]));
+    }
+
+    #[test]
+    fn yaml_files_hold_their_own_language() {
+        let bundle = parse_file(Path::new("de.yml"), "\u{feff}de:\n  hi: Hallo\n").unwrap();
+        assert_eq!(bundle.len(), 1);
+        match parse_file(Path::new("de.yml"), "de-DE:\n  hi: Hallo\n") {
+            Err(CatalogError::LanguageMismatch(path, key)) => {
+                assert_eq!((path.as_path(), key.as_str()), (Path::new("de.yml"), "de-DE"))
+            }
+            other => panic!("{:?}", other.map(|bundle| bundle.len())),
+        }
+        assert!(parse_file(Path::new("de.json"), "\u{feff}{\"hi\": \"Hallo\"}").is_ok());
+    }
+
+    #[test]
+    fn comments_are_metadata() {
+        let source = "\
+# Unattached.
+
+# Shown on the
+# front page.
+# @max-length 20
+greeting = Hello!
+# @meaning goodbye
+# @deny too-long
+farewell = Bye
+plain = Plain
+";
+        let entries = parse_entries_with_metadata(source).unwrap();
+        let greeting = &entries[0].2;
+        assert_eq!(greeting.description.as_deref(), Some("Shown on the front page."));
+        assert_eq!(greeting.max_length, Some(20));
+        assert_eq!(entries[1].2.meaning.as_deref(), Some("goodbye"));
+        assert_eq!(entries[1].2.lint_levels, [("too-long".to_string(), LintLevel::Deny)]);
+        assert!(entries[2].2.is_empty());
+        assert_eq!(parse_entries_with_metadata("# @max-length many\na = b"), Err(1));
+    }
+}
@@ -0,0 +1,266 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Loading catalogs from asynchronous storage.
+
+use language_tags::LanguageTag;
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use super::{bundle_set, parse_file, CatalogError, Format};
+use BundleSet;
+
+/// A future returned by a [`CatalogStore`]. It is `Send`, so loading
+/// can run as a task of a multithreaded runtime such as tokio.
+///
+/// [`CatalogStore`]: trait.CatalogStore.html
+pub type CatalogFuture<'a, T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send + 'a>>;
+
+/// Storage holding catalog files which is read asynchronously, such
+/// as an object storage bucket or a configuration service.
+///
+/// Files are named as in a catalog directory, such as `en-US.json`,
+/// and their names select their format and language. See
+/// [`load_store`].
+///
+/// [`load_store`]: fn.load_store.html
+pub trait CatalogStore: Send + Sync {
+    /// The names of the files in the store. Names without a catalog
+    /// extension are skipped.
+    fn list(&self) -> CatalogFuture<'_, Vec<PathBuf>>;
+    /// The contents of the file `name`.
+    fn read(&self, name: &Path) -> CatalogFuture<'_, String>;
+}
+
+/// Load every catalog file in `store` into a [`BundleSet`], as
+/// [`load_dir`] does for a directory.
+///
+/// The files are read concurrently. Formatting with the bundles
+/// afterwards doesn't block, and [`Message`], [`Context`] and
+/// [`Localizer`] are `Send` and `Sync`, so they can be used by any
+/// task:
+///
+/// ```edition2018
+/// use std::collections::HashMap;
+/// use std::future::Future;
+/// use std::io;
+/// use std::path::{Path, PathBuf};
+/// use std::sync::Arc;
+/// use message_format::catalog::{load_store, CatalogError, CatalogFuture, CatalogStore};
+/// use message_format::{arg, Localizer};
+///
+/// // Files kept in memory, standing in for an object storage client.
+/// struct Bucket(HashMap<String, String>);
+///
+/// impl CatalogStore for Bucket {
+///     fn list(&self) -> CatalogFuture<'_, Vec<PathBuf>> {
+///         Box::pin(async move { Ok(self.0.keys().map(PathBuf::from).collect()) })
+///     }
+///
+///     fn read(&self, name: &Path) -> CatalogFuture<'_, String> {
+///         let name = name.to_string_lossy().into_owned();
+///         Box::pin(async move { self.0.get(&name).cloned().ok_or_else(|| io::ErrorKind::NotFound.into()) })
+///     }
+/// }
+///
+/// async fn start(bucket: Bucket) -> Result<Arc<message_format::BundleSet>, CatalogError> {
+///     Ok(Arc::new(load_store(&bucket, &"en".parse().unwrap()).await?))
+/// }
+///
+/// // A request handler, formatting in the language the client asked for.
+/// async fn greet(bundles: Arc<message_format::BundleSet>, accept_language: String, name: String) -> String {
+///     let requested = [accept_language.parse().unwrap()];
+///     let localizer = Localizer::new(bundles, &requested);
+///     localizer.format("greeting", &arg("name", name.as_str())).unwrap_or_default()
+/// }
+///
+/// // Both can be spawned on a multithreaded runtime.
+/// fn spawn<F: Future + Send + 'static>(_task: F) {}
+/// let bucket = Bucket(vec![("en.messages".to_string(), "greeting = Hi, {name}!".to_string())].into_iter().collect());
+/// spawn(start(bucket));
+/// # let bundles = Arc::new(message_format::BundleSet::new(message_format::MessageBundle::new("en".parse().unwrap())));
+/// spawn(greet(bundles, "en-GB".to_string(), "Ann".to_string()));
+/// ```
+///
+/// [`BundleSet`]: ../struct.BundleSet.html
+/// [`load_dir`]: fn.load_dir.html
+/// [`Message`]: ../struct.Message.html
+/// [`Context`]: ../struct.Context.html
+/// [`Localizer`]: ../struct.Localizer.html
+pub fn load_store<'a, S>(
+    store: &'a S,
+    default: &'a LanguageTag,
+) -> impl Future<Output = Result<BundleSet, CatalogError>> + Send + 'a
+where
+    S: CatalogStore + ?Sized,
+{
+    LoadStore {
+        store,
+        default,
+        state: State::Listing(store.list()),
+    }
+}
+
+// A file being read, and then its contents.
+struct Read<'a> {
+    name: PathBuf,
+    future: Option<CatalogFuture<'a, String>>,
+    source: String,
+}
+
+enum State<'a> {
+    Listing(CatalogFuture<'a, Vec<PathBuf>>),
+    Reading(Vec<Read<'a>>),
+    Done,
+}
+
+struct LoadStore<'a, S: ?Sized> {
+    store: &'a S,
+    default: &'a LanguageTag,
+    state: State<'a>,
+}
+
+impl<'a, S: CatalogStore + ?Sized> Future for LoadStore<'a, S> {
+    type Output = Result<BundleSet, CatalogError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let State::Listing(ref mut future) = this.state {
+            let mut names = match future.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(names)) => names,
+                Poll::Ready(Err(e)) => {
+                    this.state = State::Done;
+                    return Poll::Ready(Err(CatalogError::Io(PathBuf::new(), e)));
+                }
+            };
+            names.retain(|name| Format::of(name).is_some());
+            names.sort();
+            let store = this.store;
+            let reads = names
+                .into_iter()
+                .map(|name| Read {
+                    future: Some(store.read(&name)),
+                    name,
+                    source: String::new(),
+                })
+                .collect();
+            this.state = State::Reading(reads);
+        }
+        let reads = match this.state {
+            State::Reading(ref mut reads) => reads,
+            _ => panic!("`LoadStore` polled after completion"),
+        };
+        let mut pending = false;
+        for read in reads.iter_mut() {
+            let result = match read.future {
+                Some(ref mut future) => future.as_mut().poll(cx),
+                None => continue,
+            };
+            match result {
+                Poll::Pending => pending = true,
+                Poll::Ready(Ok(source)) => {
+                    read.future = None;
+                    read.source = source;
+                }
+                Poll::Ready(Err(e)) => {
+                    let name = mem::take(&mut read.name);
+                    this.state = State::Done;
+                    return Poll::Ready(Err(CatalogError::Io(name, e)));
+                }
+            }
+        }
+        if pending {
+            return Poll::Pending;
+        }
+        let reads = match mem::replace(&mut this.state, State::Done) {
+            State::Reading(reads) => reads,
+            _ => unreachable!(),
+        };
+        let mut bundles = vec![];
+        for read in reads {
+            match parse_file(&read.name, &read.source) {
+                Ok(bundle) => bundles.push(bundle),
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+        Poll::Ready(bundle_set(bundles, this.default))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::future::{self, Future};
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+
+    use super::{load_store, CatalogFuture, CatalogStore};
+    use catalog::CatalogError;
+    use {arg, Localizer};
+
+    // A future which is ready the second time it is polled.
+    struct Later<T>(Option<T>, bool);
+
+    impl<T: Unpin> Future for Later<T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+            if self.1 {
+                Poll::Ready(self.0.take().unwrap())
+            } else {
+                self.1 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    struct Memory(HashMap<PathBuf, String>);
+
+    impl CatalogStore for Memory {
+        fn list(&self) -> CatalogFuture<'_, Vec<PathBuf>> {
+            Box::pin(future::ready(Ok(self.0.keys().cloned().collect())))
+        }
+
+        fn read(&self, name: &Path) -> CatalogFuture<'_, String> {
+            let result = self.0.get(name).cloned().ok_or_else(|| io::ErrorKind::NotFound.into());
+            Box::pin(Later(Some(result), false))
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn catalogs_are_loaded_from_a_store() {
+        let files = vec![
+            ("en.messages", "hi = Hi, {name}!"),
+            ("fr.json", r#"{"hi": "Salut, {name} !"}"#),
+            ("README.md", "Not a catalog"),
+        ];
+        let store = Memory(files.into_iter().map(|(k, v)| (PathBuf::from(k), v.to_string())).collect());
+        let bundles = block_on(load_store(&store, &"en".parse().unwrap())).unwrap();
+        let localizer = Localizer::new(bundles.into(), &["fr-CA".parse().unwrap()]);
+        assert_eq!(localizer.format("hi", &arg("name", "Ann")).unwrap(), "Salut, Ann !");
+
+        let error = block_on(load_store(&store, &"de".parse().unwrap())).unwrap_err();
+        assert!(matches!(error, CatalogError::MissingDefault(_)));
+    }
+}
@@ -0,0 +1,615 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading Rails i18n style YAML catalogs.
+//!
+//! These have the language as the top level key, with nested mappings
+//! as namespaces:
+//!
+//! ```text
+//! en:
+//!   users:
+//!     greeting: "Hello, %{name}!"
+//!     inbox:
+//!       one: "1 message"
+//!       other: "%{count} messages"
+//! ```
+//!
+//! Rails `%{name}` interpolations are converted to `{name}` arguments,
+//! and the text around them is quoted where ICU would read it as
+//! syntax. A mapping with an `other` key and only the keys of plural
+//! categories becomes a `plural` format on the Rails `count` argument,
+//! with `zero` as the `=0` case. Block mappings, flow mappings such as
+//! `{ greeting: Hello }`, block scalars with `|` and `>` and plain,
+//! single quoted and double quoted scalars are supported; sequences,
+//! anchors and tags aren't.
+
+// A parsed YAML value.
+#[derive(Debug)]
+enum Node {
+    Scalar(String),
+    Mapping(Vec<(String, Node)>),
+}
+
+// The Rails keys of plural categories, in the order they're written
+// in the `plural` format, and their ICU selectors.
+const PLURAL_KEYS: &[(&str, &str)] = &[
+    ("zero", "=0"),
+    ("one", "one"),
+    ("two", "two"),
+    ("few", "few"),
+    ("many", "many"),
+    ("other", "other"),
+];
+
+/// Parse a YAML catalog into `(key, source)` entries, joining the keys
+/// of nested mappings with `.` and converting interpolations and
+/// plurals.
+///
+/// The top level keys are included, so the entries of the example
+/// above would be `en.users.greeting` and `en.users.inbox`. On
+/// failure, the number of the line with the error is returned.
+///
+/// ```
+/// use message_format::catalog::yaml::parse_entries;
+///
+/// let source = "\
+/// en:
+///   users: { greeting: \"Hello, %{name}!\", bye: 'Bye' }  # short
+///   inbox:
+///     one: 1 message
+///     other: \"%{count} messages\"
+/// ";
+/// assert_eq!(parse_entries(source).unwrap(), [
+///     ("en.users.greeting".to_string(), "Hello, {name}!".to_string()),
+///     ("en.users.bye".to_string(), "Bye".to_string()),
+///     (
+///         "en.inbox".to_string(),
+///         "{count, plural, one {1 message} other {{count} messages}}".to_string(),
+///     ),
+/// ]);
+/// ```
+pub fn parse_entries(source: &str) -> Result<Vec<(String, String)>, usize> {
+    let mut entries = vec![];
+    for (key, node) in parse_mapping(source)? {
+        flatten(key, node, &mut entries);
+    }
+    Ok(entries)
+}
+
+/// Convert Rails `%{name}` interpolations to ICU `{name}` arguments,
+/// quoting apostrophes and braces so that the rest is kept as text.
+/// As in Rails, `%%` is a `%`.
+///
+/// ```
+/// use message_format::catalog::yaml::convert_interpolations;
+///
+/// assert_eq!(convert_interpolations("%{count} of %{total}, 100%"), "{count} of {total}, 100%");
+/// assert_eq!(convert_interpolations("it's {raw}, %%{name}"), "it''s '{'raw'}', %'{'name'}'");
+/// ```
+pub fn convert_interpolations(source: &str) -> String {
+    convert(source, false)
+}
+
+// Convert a scalar, also quoting `#` inside the branches of a plural.
+fn convert(source: &str, in_plural: bool) -> String {
+    let special = |c: char| c == '{' || c == '}' || (in_plural && c == '#');
+    let mut converted = String::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("%%") {
+            converted.push('%');
+            rest = after;
+        } else if let Some((name, after)) = interpolation(rest) {
+            converted.push('{');
+            converted.push_str(name);
+            converted.push('}');
+            rest = after;
+        } else if special(c) {
+            let end = rest.find(|c| !special(c)).unwrap_or(rest.len());
+            converted.push('\'');
+            converted.push_str(&rest[..end]);
+            converted.push('\'');
+            rest = &rest[end..];
+        } else {
+            converted.push_str(if c == '\'' { "''" } else { &rest[..c.len_utf8()] });
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    converted
+}
+
+// The name of a `%{name}` interpolation at the start of `s`, and the
+// text after it.
+fn interpolation(s: &str) -> Option<(&str, &str)> {
+    let after = s.strip_prefix("%{")?;
+    let end = after.find('}')?;
+    let name = &after[..end];
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name, &after[end + 1..]))
+}
+
+// Add the entries for a node, with plural mappings as one entry.
+fn flatten(key: String, node: Node, entries: &mut Vec<(String, String)>) {
+    match node {
+        Node::Scalar(value) => entries.push((key, convert_interpolations(&value))),
+        Node::Mapping(members) => match plural(&members) {
+            Some(source) => entries.push((key, source)),
+            None => {
+                for (name, node) in members {
+                    flatten(format!("{}.{}", key, name), node, entries);
+                }
+            }
+        },
+    }
+}
+
+// The `plural` format for a Rails plural mapping, if it is one.
+fn plural(members: &[(String, Node)]) -> Option<String> {
+    let mut branches = Vec::with_capacity(members.len());
+    for (name, node) in members {
+        let index = PLURAL_KEYS.iter().position(|&(key, _)| key == name)?;
+        match node {
+            Node::Scalar(value) => branches.push((index, value)),
+            Node::Mapping(_) => return None,
+        }
+    }
+    if !branches.iter().any(|&(index, _)| index == PLURAL_KEYS.len() - 1) {
+        return None;
+    }
+    branches.sort_by_key(|&(index, _)| index);
+    let mut source = String::from("{count, plural,");
+    for (index, value) in branches {
+        source.push_str(&format!(" {} {{{}}}", PLURAL_KEYS[index].1, convert(value, true)));
+    }
+    source.push('}');
+    Some(source)
+}
+
+// An entry of a block mapping which is still being read.
+struct Open {
+    indent: usize,
+    key: String,
+    members: Vec<(String, Node)>,
+}
+
+// Parse the block mapping at the top of a document.
+fn parse_mapping(source: &str) -> Result<Vec<(String, Node)>, usize> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let lines: Vec<&str> = source.lines().collect();
+    let mut root = vec![];
+    let mut open: Vec<Open> = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        let number = i;
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') || content == "---" {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        if line[..indent].contains('\t') {
+            return Err(number);
+        }
+        while open.last().is_some_and(|entry| entry.indent >= indent) {
+            close(&mut open, &mut root);
+        }
+        let (key, rest) = split_key(content).ok_or(number)?;
+        let rest = strip_comment(rest).trim();
+        let node = if rest.is_empty() {
+            // A key without a value or members is left out.
+            open.push(Open {
+                indent,
+                key,
+                members: vec![],
+            });
+            continue;
+        } else if rest.starts_with(['|', '>']) {
+            let (value, read) = block_scalar(rest, indent, &lines[i..]).ok_or(number)?;
+            i += read;
+            Node::Scalar(value)
+        } else if rest.starts_with('{') {
+            // A flow mapping may continue on the following lines.
+            let mut text = rest.to_string();
+            while flow_depth(&text) > 0 && i < lines.len() {
+                text.push(' ');
+                text.push_str(strip_comment(lines[i]).trim());
+                i += 1;
+            }
+            match flow_mapping(&text) {
+                Some((members, "")) => Node::Mapping(members),
+                _ => return Err(number),
+            }
+        } else {
+            Node::Scalar(scalar(rest).ok_or(number)?)
+        };
+        match open.last_mut() {
+            Some(parent) => parent.members.push((key, node)),
+            None => root.push((key, node)),
+        }
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut root);
+    }
+    Ok(root)
+}
+
+// Finish the last open entry, adding it to its parent unless it is
+// empty.
+fn close(open: &mut Vec<Open>, root: &mut Vec<(String, Node)>) {
+    if let Some(entry) = open.pop().filter(|entry| !entry.members.is_empty()) {
+        let node = (entry.key, Node::Mapping(entry.members));
+        match open.last_mut() {
+            Some(parent) => parent.members.push(node),
+            None => root.push(node),
+        }
+    }
+}
+
+// Read a `|` or `>` block scalar with the given header, from the lines
+// after its key, returning its value and the number of lines read.
+fn block_scalar(header: &str, indent: usize, lines: &[&str]) -> Option<(String, usize)> {
+    let folded = header.starts_with('>');
+    let chomping = match &header[1..] {
+        "" => None,
+        "-" => Some(false),
+        "+" => Some(true),
+        _ => return None,
+    };
+    let read = lines
+        .iter()
+        .position(|line| {
+            let content = line.trim_start();
+            !content.is_empty() && line.len() - content.len() <= indent
+        })
+        .unwrap_or(lines.len());
+    let block_indent = lines[..read]
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len());
+    let mut body = vec![];
+    for line in &lines[..read] {
+        if line.trim().is_empty() {
+            body.push("");
+        } else {
+            let block_indent = block_indent?;
+            if line.len() - line.trim_start().len() < block_indent || line[..block_indent].contains('\t') {
+                return None;
+            }
+            body.push(&line[block_indent..]);
+        }
+    }
+    let trailing = body.iter().rev().take_while(|line| line.is_empty()).count();
+    body.truncate(body.len() - trailing);
+
+    let mut value = String::new();
+    let mut breaks = 0;
+    let mut previous: Option<&str> = None;
+    for line in body {
+        if line.is_empty() {
+            breaks += 1;
+            continue;
+        }
+        match previous {
+            // Folding joins lines with a space, except around lines
+            // which are indented further.
+            Some(previous) if folded && !previous.starts_with(' ') && !line.starts_with(' ') => {
+                if breaks == 0 {
+                    value.push(' ');
+                }
+                value.push_str(&"\n".repeat(breaks));
+            }
+            Some(_) => value.push_str(&"\n".repeat(breaks + 1)),
+            None => value.push_str(&"\n".repeat(breaks)),
+        }
+        value.push_str(line);
+        breaks = 0;
+        previous = Some(line);
+    }
+    match chomping {
+        None if previous.is_some() => value.push('\n'),
+        Some(true) => value.push_str(&"\n".repeat(trailing + previous.is_some() as usize)),
+        _ => (),
+    }
+    Some((value, read))
+}
+
+// The nesting of flow mappings open at the end of `s`.
+fn flow_depth(s: &str) -> isize {
+    let mut depth = 0;
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        if c == '"' || c == '\'' {
+            match quoted_end(&s[i..]) {
+                Some(end) => i += end,
+                None => return depth,
+            }
+            continue;
+        }
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => (),
+        }
+        i += c.len_utf8();
+    }
+    depth
+}
+
+// Parse a flow mapping at the start of `s`, returning its members and
+// the rest of `s`.
+fn flow_mapping(s: &str) -> Option<(Vec<(String, Node)>, &str)> {
+    let mut rest = s.strip_prefix('{')?.trim_start();
+    let mut members = vec![];
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            return Some((members, after.trim_start()));
+        }
+        let (key, after) = if rest.starts_with(['"', '\'']) {
+            let end = quoted_end(rest)?;
+            (scalar(&rest[..end])?, &rest[end..])
+        } else {
+            let end = rest.find(':')?;
+            if rest[..end].contains([',', '{', '}']) {
+                return None;
+            }
+            (rest[..end].trim_end().to_string(), &rest[end..])
+        };
+        if key.is_empty() {
+            return None;
+        }
+        rest = after.trim_start().strip_prefix(':')?.trim_start();
+        let node = if rest.starts_with('{') {
+            let (nested, after) = flow_mapping(rest)?;
+            rest = after;
+            Node::Mapping(nested)
+        } else if rest.starts_with(['"', '\'']) {
+            let end = quoted_end(rest)?;
+            let value = scalar(&rest[..end])?;
+            rest = rest[end..].trim_start();
+            Node::Scalar(value)
+        } else {
+            let end = rest.find([',', '}'])?;
+            let value = rest[..end].trim_end();
+            if value.is_empty() {
+                return None;
+            }
+            rest = &rest[end..];
+            Node::Scalar(scalar(value)?)
+        };
+        members.push((key, node));
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after.trim_start(),
+            None if rest.starts_with('}') => (),
+            None => return None,
+        }
+    }
+}
+
+// Split `key: rest` or `key:` into the key and the rest.
+fn split_key(content: &str) -> Option<(String, &str)> {
+    let (key, rest) = if content.starts_with(['"', '\'']) {
+        let end = quoted_end(content)?;
+        (scalar(&content[..end])?, &content[end..])
+    } else {
+        let colon = content
+            .match_indices(':')
+            .map(|(i, _)| i)
+            .find(|&i| content[i + 1..].is_empty() || content[i + 1..].starts_with(' '))?;
+        (content[..colon].trim_end().to_string(), &content[colon..])
+    };
+    let rest = rest.trim_start().strip_prefix(':')?;
+    if key.is_empty() || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((key, rest))
+}
+
+// The end of a quoted scalar at the start of `s`.
+fn quoted_end(s: &str) -> Option<usize> {
+    let quote = s.chars().next()?;
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            // Doubled single quotes are an escaped quote.
+            if quote == '\'' && s[i + 1..].starts_with('\'') {
+                chars.next();
+            } else {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+// Remove a comment after a value. A `#` only starts a comment after a
+// space, and not inside quoted scalars, which start at the beginning
+// of the value or of an entry of a flow mapping.
+fn strip_comment(rest: &str) -> &str {
+    let mut previous = ' ';
+    let mut i = 0;
+    while let Some(c) = rest[i..].chars().next() {
+        if (c == '"' || c == '\'') && [' ', '{', ','].contains(&previous) {
+            match quoted_end(&rest[i..]) {
+                Some(end) => {
+                    i += end;
+                    previous = c;
+                    continue;
+                }
+                None => return rest,
+            }
+        }
+        if c == '#' && previous == ' ' {
+            return &rest[..i];
+        }
+        previous = c;
+        i += c.len_utf8();
+    }
+    rest
+}
+
+fn scalar(s: &str) -> Option<String> {
+    if let Some(inner) = s.strip_prefix('\'') {
+        let inner = inner.strip_suffix('\'')?;
+        return Some(inner.replace("''", "'"));
+    }
+    if let Some(inner) = s.strip_prefix('"') {
+        let inner = inner.strip_suffix('"')?;
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            value.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '0' => '\0',
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                c @ ('"' | '\\' | '/' | ' ') => c,
+                _ => return None,
+            });
+        }
+        return Some(value);
+    }
+    // Sequences, anchors, aliases and tags aren't supported.
+    if s.starts_with(['|', '>', '[', '{', '&', '*', '!']) {
+        return None;
+    }
+    Some(s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_entries;
+    use icu::parse;
+    use {arg, Context};
+
+    fn entries(source: &str) -> Vec<(String, String)> {
+        parse_entries(source).expect("valid YAML")
+    }
+
+    #[test]
+    fn nesting_follows_indentation() {
+        let source = "\
+---
+# Greetings
+en:
+  users:
+    greeting: Hello, %{name}!
+  'quoted: key': \"say \\\"hi\\\"\"
+  time: 'it''s #1'
+fr:
+  users:
+    greeting: Bonjour
+";
+        let entries = parse_entries(source).unwrap();
+        let entries: Vec<(&str, &str)> = entries.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(
+            entries,
+            [
+                ("en.users.greeting", "Hello, {name}!"),
+                ("en.quoted: key", "say \"hi\""),
+                ("en.time", "it''s #1"),
+                ("fr.users.greeting", "Bonjour"),
+            ]
+        );
+    }
+
+    #[test]
+    fn flow_mappings_and_block_scalars() {
+        let source = "\
+en: { users: { greeting: \"Hello, %{name}\" },
+      'a, b': 'x # y', plain: one two }  # done
+notes:
+  literal: |
+    line one
+      indented
+
+    line three
+  folded: >-
+    joined
+    lines
+
+    new paragraph
+  kept: |+
+    text
+
+last: plain
+";
+        let expected = [
+            ("en.users.greeting", "Hello, {name}"),
+            ("en.a, b", "x # y"),
+            ("en.plain", "one two"),
+            ("notes.literal", "line one\n  indented\n\nline three\n"),
+            ("notes.folded", "joined lines\nnew paragraph"),
+            ("notes.kept", "text\n\n"),
+            ("last", "plain"),
+        ];
+        let entries = entries(source);
+        let entries: Vec<(&str, &str)> = entries.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn text_is_quoted() {
+        let source = "en:\n  braces: \"it's {not} an argument, %{n}'s {} #1\"\n";
+        let source = &entries(source)[0].1;
+        let message = parse(source).unwrap();
+        let ctx = Context::default();
+        assert_eq!(ctx.format(&message, &arg("n", "Ann")), "it's {not} an argument, Ann's {} #1");
+    }
+
+    #[test]
+    fn plural_subtrees_become_plurals() {
+        let source = "\
+en:
+  inbox:
+    zero: No messages
+    one: '%{count} message'
+    other: \"%{count} messages, it's #%{count}\"
+  not_plural:
+    one: One
+    two: Two
+";
+        let entries = entries(source);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].0, "en.not_plural.one");
+        let message = parse(&entries[0].1).unwrap();
+        let ctx = Context::default();
+        assert_eq!(ctx.format(&message, &arg("count", 0)), "No messages");
+        assert_eq!(ctx.format(&message, &arg("count", 1)), "1 message");
+        assert_eq!(ctx.format(&message, &arg("count", 3)), "3 messages, it's #3");
+    }
+
+    #[test]
+    fn errors_report_the_line() {
+        assert_eq!(parse_entries("en:\n  a: A\n  b: [1]\n"), Err(3));
+        assert_eq!(parse_entries("en:\n  a: { b: c\n"), Err(2));
+        assert_eq!(parse_entries("en:\n  a: |x\n    text\n"), Err(2));
+        assert_eq!(parse_entries("en:\n  no colon\n"), Err(2));
+    }
+
+    #[test]
+    fn empty_keys_have_no_entries() {
+        assert_eq!(entries("en:\n  a:\n  b: B\n"), [("en.b".to_string(), "B".to_string())]);
+        assert_eq!(entries("en:\n  a:\nfr:\n  a: A\n"), [("fr.a".to_string(), "A".to_string())]);
+        assert!(entries("en:\n").is_empty());
+        let inbox = "en:\n  inbox:\n    one:\n    other: \"%{count} messages\"\n";
+        assert_eq!(entries(inbox), [("en.inbox".to_string(), "{count, plural, other {{count} messages}}".to_string())]);
+        assert_eq!(entries("\u{feff}en:\n  a: A\n"), [("en.a".to_string(), "A".to_string())]);
+    }
+}
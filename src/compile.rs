@@ -0,0 +1,586 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::ops::Range;
+use std::ptr;
+
+use icu::ast;
+use number::{Number, NumberOptions};
+use {Args, Context, Message, MessagePart, Value};
+
+/// A [`Message`] compiled to a flat list of instructions, for messages
+/// formatted in a hot loop.
+///
+/// The plain text of the message is interned in one string, each
+/// argument is given a numbered slot which is looked up once per call,
+/// and the branches of `select` and `plural` formats are compiled into
+/// tables of instruction ranges. Simple arguments, text and branches
+/// are then written without calling through `MessagePart`. Other
+/// formats, such as `number`, are still called through their part.
+///
+/// The output is always the same as formatting the `Message` itself.
+///
+/// ```
+/// use message_format::{arg, icu, Context};
+///
+/// let m = icu::parse("{name} has {n, plural, one {# file} other {# files}}.").unwrap();
+/// let compiled = m.compile();
+/// let args = arg("name", "Ann");
+/// let args = args.arg("n", 3);
+/// assert_eq!(compiled.format_with(&Context::default(), &args), "Ann has 3 files.");
+/// ```
+///
+/// [`Message`]: struct.Message.html
+#[derive(Debug)]
+pub struct CompiledMessage<'m> {
+    ops: Vec<Op<'m>>,
+    // The range of `ops` for the message itself.
+    main: Range<usize>,
+    text: String,
+    // The argument name of each slot. The slots after those used by
+    // the instructions are for arguments of formats called through
+    // their part, so that `ArgsSlots` can hold them.
+    slots: Vec<&'m str>,
+    used_slots: usize,
+    selects: Vec<BranchTable<'m, ast::SelectFormat>>,
+    plurals: Vec<BranchTable<'m, ast::PluralFormat>>,
+}
+
+// The number of argument slots looked up without allocating.
+const INLINE_SLOTS: usize = 8;
+
+#[derive(Debug)]
+enum Op<'m> {
+    // A range of the interned text.
+    Text(Range<usize>),
+    // An argument written as a `SimpleFormat` does.
+    Argument(usize),
+    // A `select` format, by its index in the select tables.
+    Select(usize),
+    // A `plural` format, by its index in the plural tables.
+    Plural(usize),
+    // The `#` of the innermost `plural` format.
+    Placeholder,
+    Part(&'m dyn MessagePart),
+}
+
+// The value of `#`, its visible fraction digits and the number options
+// given with its argument.
+type Placeholder = (Number, Option<usize>, Option<NumberOptions>);
+
+// The instructions for each branch of a format.
+#[derive(Debug)]
+struct BranchTable<'m, F: 'm> {
+    format: &'m F,
+    slot: usize,
+    branches: Vec<(&'m Message, Range<usize>)>,
+    // Whether the branches call through parts, which need a context
+    // holding the value of `#`.
+    needs_context: bool,
+}
+
+impl<'m, F> BranchTable<'m, F> {
+    fn branch(&self, message: &Message) -> Option<Range<usize>> {
+        self.branches
+            .iter()
+            .find(|&&(branch, _)| ptr::eq(branch, message))
+            .map(|(_, range)| range.clone())
+    }
+}
+
+struct Compiler<'m> {
+    compiled: CompiledMessage<'m>,
+    part_count: usize,
+}
+
+impl<'m> Compiler<'m> {
+    fn slot(&mut self, name: &'m str) -> usize {
+        match self.compiled.slots.iter().position(|&slot| slot == name) {
+            Some(slot) => slot,
+            None => {
+                self.compiled.slots.push(name);
+                self.compiled.slots.len() - 1
+            }
+        }
+    }
+
+    // Compile the parts of a message, after any branches they have, so
+    // that the instructions for the message are contiguous.
+    fn message(&mut self, message: &'m Message) -> Range<usize> {
+        let mut body = vec![];
+        self.parts(message, &mut body);
+        let start = self.compiled.ops.len();
+        self.compiled.ops.extend(body);
+        start..self.compiled.ops.len()
+    }
+
+    fn parts(&mut self, message: &'m Message, body: &mut Vec<Op<'m>>) {
+        for part in &message.parts {
+            let any = part.as_any();
+            let op = if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+                let start = self.compiled.text.len();
+                self.compiled.text.push_str(&text.text);
+                let end = self.compiled.text.len();
+                if let Some(Op::Text(ref mut last)) = body.last_mut() {
+                    if last.end == start {
+                        last.end = end;
+                        continue;
+                    }
+                }
+                Op::Text(start..end)
+            } else if let Some(nested) = any.downcast_ref::<Message>() {
+                self.parts(nested, body);
+                continue;
+            } else if let Some(format) = any.downcast_ref::<ast::SimpleFormat>() {
+                Op::Argument(self.slot(&format.variable_name))
+            } else if let Some(format) = any.downcast_ref::<ast::SelectFormat>() {
+                let slot = self.slot(&format.variable_name);
+                let messages = format.mappings.iter().map(|mapping| &mapping.message);
+                let branches = messages
+                    .chain(Some(&format.default))
+                    .map(|branch| (branch, self.message(branch)))
+                    .collect();
+                self.compiled.selects.push(BranchTable {
+                    format,
+                    slot,
+                    branches,
+                    needs_context: false,
+                });
+                Op::Select(self.compiled.selects.len() - 1)
+            } else if let Some(format) = any.downcast_ref::<ast::PluralFormat>() {
+                let slot = self.slot(&format.variable_name);
+                let part_count = self.part_count;
+                let literals = format.literals.iter().map(|literal| &literal.message);
+                let categories = [&format.zero, &format.one, &format.two, &format.few, &format.many];
+                let branches = literals
+                    .chain(categories.iter().filter_map(|branch| branch.as_ref()))
+                    .chain(Some(&format.other))
+                    .map(|branch| (branch, self.message(branch)))
+                    .collect();
+                self.compiled.plurals.push(BranchTable {
+                    format,
+                    slot,
+                    branches,
+                    needs_context: self.part_count > part_count,
+                });
+                Op::Plural(self.compiled.plurals.len() - 1)
+            } else if any.is::<ast::PlaceholderFormat>() {
+                Op::Placeholder
+            } else {
+                self.part_count += 1;
+                Op::Part(part.as_ref())
+            };
+            body.push(op);
+        }
+    }
+}
+
+impl<'m> CompiledMessage<'m> {
+    pub(crate) fn new(message: &'m Message) -> Self {
+        let mut compiler = Compiler {
+            compiled: CompiledMessage {
+                ops: vec![],
+                main: 0..0,
+                text: String::with_capacity(message.literal_len()),
+                slots: vec![],
+                used_slots: 0,
+                selects: vec![],
+                plurals: vec![],
+            },
+            part_count: 0,
+        };
+        compiler.compiled.main = compiler.message(message);
+        compiler.compiled.used_slots = compiler.compiled.slots.len();
+        for name in message.argument_names() {
+            compiler.slot(name);
+        }
+        compiler.compiled
+    }
+
+    /// The names of the arguments used by the message, by slot.
+    pub fn argument_names(&self) -> &[&'m str] {
+        &self.slots
+    }
+
+    /// The slot of an argument, for setting it in an [`ArgsSlots`].
+    ///
+    /// [`ArgsSlots`]: struct.ArgsSlots.html
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|&slot| slot == name)
+    }
+
+    /// Create an empty [`ArgsSlots`] for the arguments of this message.
+    ///
+    /// [`ArgsSlots`]: struct.ArgsSlots.html
+    pub fn args_slots<'a>(&self) -> ArgsSlots<'_, 'a> {
+        ArgsSlots {
+            names: &self.slots,
+            values: self.slots.iter().map(|_| None).collect(),
+        }
+    }
+
+    /// Format the message with arguments set by slot, returning a
+    /// string.
+    ///
+    /// Slots belonging to another message are looked up by name.
+    pub fn format_slots(&self, ctx: &Context, args: &ArgsSlots) -> String {
+        let mut output = String::with_capacity(self.text.len());
+        let _ = self.write_slots(ctx, &mut output, args);
+        output
+    }
+
+    /// Write the message with arguments set by slot to a stream.
+    pub fn write_slots(&self, ctx: &Context, stream: &mut dyn fmt::Write, args: &ArgsSlots) -> fmt::Result {
+        if !ptr::eq(args.names, &self.slots[..]) || ctx.maps_args() {
+            return self.write_with(ctx, stream, args);
+        }
+        let placeholder = ctx
+            .placeholder_number()
+            .map(|value| (value, ctx.placeholder_fraction_digits, ctx.placeholder_options));
+        self.run(self.main.clone(), ctx, stream, args, &args.values[..], placeholder)
+    }
+
+    /// Format the message using `ctx`, returning a string.
+    pub fn format_with<'f>(&self, ctx: &Context, args: &'f dyn Args<'f>) -> String {
+        let mut output = String::with_capacity(self.text.len());
+        let _ = self.write_with(ctx, &mut output, args);
+        output
+    }
+
+    /// Write the message to a stream using `ctx`.
+    pub fn write_with<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        if ctx.maps_args() {
+            return ctx.with_mapped_args(args, |args| self.write_values(ctx, stream, args, |name| args.get(name)));
+        }
+        self.write_values(ctx, stream, args, |name| args.get(name))
+    }
+
+    /// Like [`format_with`], for arguments of a known type, so that
+    /// looking them up can be inlined.
+    ///
+    /// [`format_with`]: #method.format_with
+    pub fn format_generic<'f, A: Args<'f>>(&self, ctx: &Context, args: &'f A) -> String {
+        let mut output = String::with_capacity(self.text.len());
+        let _ = self.write_generic(ctx, &mut output, args);
+        output
+    }
+
+    /// Like [`write_with`], for arguments of a known type.
+    ///
+    /// [`write_with`]: #method.write_with
+    pub fn write_generic<'f, A: Args<'f>>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f A,
+    ) -> fmt::Result {
+        if ctx.maps_args() {
+            return self.write_with(ctx, stream, args);
+        }
+        self.write_values(ctx, stream, args, |name| args.get(name))
+    }
+
+    // Look up the argument of each slot with `get`, then write the
+    // message. Formats called through their part use `args`.
+    fn write_values<'f, G>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+        get: G,
+    ) -> fmt::Result
+    where
+        G: Fn(&str) -> Option<&'f Value<'f>>,
+    {
+        let placeholder = ctx
+            .placeholder_number()
+            .map(|value| (value, ctx.placeholder_fraction_digits, ctx.placeholder_options));
+        // Most messages have few arguments, so their slots are kept on
+        // the stack.
+        if self.used_slots <= INLINE_SLOTS {
+            let mut values = [None; INLINE_SLOTS];
+            for (value, name) in values.iter_mut().zip(&self.slots[..self.used_slots]) {
+                *value = get(name);
+            }
+            self.run(self.main.clone(), ctx, stream, args, &values[..], placeholder)
+        } else {
+            let names = &self.slots[..self.used_slots];
+            let values: Vec<Option<&Value>> = names.iter().map(|name| get(name)).collect();
+            self.run(self.main.clone(), ctx, stream, args, &values[..], placeholder)
+        }
+    }
+
+    fn run<'f, S: Slots + ?Sized>(
+        &self,
+        range: Range<usize>,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+        values: &S,
+        placeholder: Option<Placeholder>,
+    ) -> fmt::Result {
+        for op in &self.ops[range] {
+            match *op {
+                Op::Text(ref text) => stream.write_str(&self.text[text.clone()])?,
+                Op::Argument(slot) => {
+                    let value = values.value(slot).ok_or(fmt::Error {})?;
+                    ctx.write_isolated(stream, |stream| match *value {
+                        Value::Str(s) => stream.write_str(s),
+                        Value::String(ref s) => stream.write_str(s),
+                        ref value => {
+                            let overrides = args.number_options(self.slots[slot]);
+                            value.format_with(ctx, overrides.as_ref(), stream)
+                        }
+                    })?
+                }
+                Op::Select(table) => {
+                    let table = &self.selects[table];
+                    let value = values.value(table.slot).ok_or(fmt::Error {})?;
+                    let message = table.format.select_message(ctx, &value.select_key());
+                    self.branch(table, message, ctx, stream, args, values, placeholder)?;
+                }
+                Op::Plural(table) => {
+                    let table = &self.plurals[table];
+                    let value = values.value(table.slot).ok_or(fmt::Error {})?;
+                    let number = table.format.placeholder_number(value).ok_or(fmt::Error {})?;
+                    let overrides = args.number_options(&table.format.variable_name);
+                    let placeholder = Some((number, value.fraction_digits(), overrides));
+                    if table.needs_context {
+                        let (message, mut ctx) = table.format.resolve(ctx, value).ok_or(fmt::Error {})?;
+                        ctx.placeholder_options = overrides;
+                        self.branch(table, message, &ctx, stream, args, values, placeholder)?;
+                    } else {
+                        let message = table.format.resolve_message(ctx, value).ok_or(fmt::Error {})?;
+                        self.branch(table, message, ctx, stream, args, values, placeholder)?;
+                    }
+                }
+                Op::Placeholder => {
+                    let (value, fraction_digits, overrides) = placeholder.ok_or(fmt::Error {})?;
+                    ast::write_placeholder(ctx, value, fraction_digits, overrides.as_ref(), stream)?
+                }
+                Op::Part(part) => part.apply_format(ctx, stream, args)?,
+            }
+        }
+        Ok(())
+    }
+
+    // Write the branch of a format chosen for the arguments.
+    #[allow(clippy::too_many_arguments)]
+    fn branch<'f, F, S: Slots + ?Sized>(
+        &self,
+        table: &BranchTable<F>,
+        message: &Message,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+        values: &S,
+        placeholder: Option<Placeholder>,
+    ) -> fmt::Result {
+        match table.branch(message) {
+            Some(range) => self.run(range, ctx, stream, args, values, placeholder),
+            None => message.write_message(ctx, stream, args),
+        }
+    }
+}
+
+/// Arguments stored by the slot numbers of a [`CompiledMessage`], so
+/// that formatting indexes them rather than comparing names.
+///
+/// The arguments can be changed between calls, keeping the slots
+/// looked up once:
+///
+/// ```
+/// use message_format::{icu, Context};
+///
+/// let m = icu::parse("{name} has {n, plural, one {# file} other {# files}}.").unwrap();
+/// let compiled = m.compile();
+/// let (name, n) = (compiled.slot("name").unwrap(), compiled.slot("n").unwrap());
+///
+/// let ctx = Context::default();
+/// let mut args = compiled.args_slots();
+/// args.set(name, "Ann");
+/// for count in 1..3 {
+///     args.set(n, count);
+///     println!("{}", compiled.format_slots(&ctx, &args));
+/// }
+/// assert_eq!(compiled.format_slots(&ctx, &args), "Ann has 2 files.");
+/// ```
+///
+/// It also implements [`Args`] by name, for use with other messages.
+///
+/// [`CompiledMessage`]: struct.CompiledMessage.html
+/// [`Args`]: trait.Args.html
+#[derive(Debug)]
+pub struct ArgsSlots<'n, 'a> {
+    names: &'n [&'n str],
+    values: Vec<Option<Value<'a>>>,
+}
+
+impl<'n, 'a> ArgsSlots<'n, 'a> {
+    /// Set the argument in a slot. Slots past the end are ignored.
+    pub fn set<T>(&mut self, slot: usize, value: T)
+    where
+        Value<'a>: From<T>,
+    {
+        if let Some(stored) = self.values.get_mut(slot) {
+            *stored = Some(Value::from(value));
+        }
+    }
+
+    /// Remove all of the arguments.
+    pub fn clear(&mut self) {
+        for value in &mut self.values {
+            *value = None;
+        }
+    }
+}
+
+impl<'n, 'a> Args<'a> for ArgsSlots<'n, 'a> {
+    fn get(&self, name: &str) -> Option<&'a Value<'_>> {
+        let slot = self.names.iter().position(|&slot| slot == name)?;
+        self.values[slot].as_ref()
+    }
+}
+
+// Argument values by slot number.
+trait Slots {
+    fn value(&self, slot: usize) -> Option<&Value<'_>>;
+}
+
+impl<'v> Slots for [Option<&'v Value<'v>>] {
+    fn value(&self, slot: usize) -> Option<&Value<'_>> {
+        self[slot]
+    }
+}
+
+impl<'v> Slots for [Option<Value<'v>>] {
+    fn value(&self, slot: usize) -> Option<&Value<'_>> {
+        self[slot].as_ref()
+    }
+}
+
+/// Format a compiled message with arguments of a known type,
+/// returning a string.
+///
+/// Unlike [`Context::format`], nothing here goes through `dyn Args` or
+/// `dyn MessagePart` except formats such as `number`, which are still
+/// called through their part, so the argument lookups are
+/// monomorphized and can be inlined.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use message_format::{format_message_generic, icu, Context, Value};
+///
+/// let m = icu::parse("Hi {name}, {count, plural, one {# message} other {# messages}}.").unwrap();
+/// let compiled = m.compile();
+/// let mut args = HashMap::new();
+/// args.insert("name", Value::from("Ann"));
+/// args.insert("count", Value::from(2));
+/// assert_eq!(format_message_generic(&Context::default(), &compiled, &args), "Hi Ann, 2 messages.");
+/// ```
+///
+/// [`Context::format`]: struct.Context.html#method.format
+pub fn format_message_generic<'f, A: Args<'f>>(ctx: &Context, message: &CompiledMessage, args: &'f A) -> String {
+    message.format_generic(ctx, args)
+}
+
+/// Write a compiled message with arguments of a known type to a
+/// stream. See [`format_message_generic`].
+///
+/// [`format_message_generic`]: fn.format_message_generic.html
+pub fn write_message_generic<'f, A: Args<'f>>(
+    ctx: &Context,
+    message: &CompiledMessage,
+    stream: &mut dyn fmt::Write,
+    args: &'f A,
+) -> fmt::Result {
+    message.write_generic(ctx, stream, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_message_generic;
+    use std::collections::HashMap;
+    use {arg, icu, Context, Value};
+
+    #[test]
+    fn matches_message_output() {
+        let m = icu::parse(
+            "{who} {gender, select, female {shared {n, plural, =0 {nothing} \
+             one {{who}''s file} other {# files}}} other {shared {n, number} {n, plural, \
+             one {file} other {files}}}} at {when, date, short}, {who}.",
+        ).unwrap();
+        let compiled = m.compile();
+        assert_eq!(compiled.argument_names(), ["who", "gender", "n", "when"]);
+        assert_eq!(compiled.used_slots, 3);
+        let ctx = Context::default();
+        let when = ::DateTime::new(2024, 5, 3);
+        for &gender in &["female", "male"] {
+            for &n in &[0.0, 1.0, 2.0, 1500.5] {
+                let args = arg("who", "Ann");
+                let args = args.arg("gender", gender);
+                let args = args.arg("n", n);
+                let args = args.arg("when", when);
+                assert_eq!(compiled.format_with(&ctx, &args), m.format_with(&ctx, &args));
+            }
+        }
+        let args = arg("who", "Ann");
+        let args = args.arg("gender", "female");
+        let args = args.arg("n", 2);
+        let args = args.arg("when", when);
+        assert_eq!(compiled.format_with(&ctx, &args), "Ann shared 2 files at 5/3/24, Ann.");
+    }
+
+    #[test]
+    fn generic_arguments() {
+        let m = icu::parse("{gender, select, female {{name} has {n, plural, one {# file} other {# files}}} \
+             other {{name} has {n, number} files}}.").unwrap();
+        let compiled = m.compile();
+        let ctx = Context::default();
+        for &gender in &["female", "male"] {
+            for &n in &[1, 1500] {
+                let mut args = HashMap::new();
+                args.insert("gender", Value::from(gender));
+                args.insert("name", Value::from("Ann"));
+                args.insert("n", Value::from(n));
+                assert_eq!(format_message_generic(&ctx, &compiled, &args), m.format_with(&ctx, &args));
+            }
+        }
+    }
+
+    #[test]
+    fn slot_arguments() {
+        let m = icu::parse("{n, number} {who} {n, plural, one {file} other {files}}").unwrap();
+        let compiled = m.compile();
+        let ctx = Context::default();
+        let mut args = compiled.args_slots();
+        args.set(compiled.slot("who").unwrap(), "Ann");
+        args.set(compiled.slot("n").unwrap(), 1500);
+        assert_eq!(compiled.format_slots(&ctx, &args), "1,500 Ann files");
+
+        // Slots of another message are looked up by name.
+        let other = icu::parse("{who}: {n, plural, one {# file} other {# files}}").unwrap();
+        assert_eq!(other.compile().format_slots(&ctx, &args), "Ann: 1,500 files");
+
+        args.clear();
+        assert_eq!(compiled.format_slots(&ctx, &args), "");
+    }
+
+    #[test]
+    fn missing_arguments_fail() {
+        let m = icu::parse("a {b} c").unwrap();
+        let compiled = m.compile();
+        let mut s = String::new();
+        assert!(compiled.write_with(&Context::default(), &mut s, &arg("x", 1)).is_err());
+        assert_eq!(s, "a ");
+    }
+}
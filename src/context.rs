@@ -5,51 +5,1055 @@
 // except according to those terms.
 
 use language_tags::LanguageTag;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
 use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
-use {Args, Message};
+use datetime::{
+    calendar_for_language_tag, format_datetime_with_data, Calendar, DateTime, DateTimeData, DateTimeKind,
+    DateTimeStyle,
+};
+use number::{write_digits_in, Number, NumberLocale, NumberOptions, NumberSymbols, NumberingSystem, UnitPatterns};
+use plural::{PluralRuleSet, PluralRuleType, PluralRules};
+use profiler::Profiler;
+use provider::{DataProvider, DataRef, LocaleData};
+#[cfg(feature = "icu4x")]
+use provider::Icu4xDataProvider;
+use args::MappedArgs;
+use error::FormatError;
+use parts::{self, FormattedPart};
+use {ArgTransform, Args, CaseTransform, Message, MessageDisplay, MessagePart, MessageSource, Transliterator, Value};
+
+/// How formatted arguments are isolated from the surrounding text.
+///
+/// Without isolation, a right-to-left argument inserted into a
+/// left-to-right sentence (or vice versa) can reorder the text around
+/// it when displayed. Isolation wraps each formatted argument in Unicode
+/// directional isolate characters, closed by POP DIRECTIONAL ISOLATE.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BidiStrategy {
+    /// Arguments are inserted without isolation.
+    #[default]
+    None,
+    /// Arguments are wrapped in FIRST STRONG ISOLATE, so their
+    /// direction is taken from their own content.
+    FirstStrong,
+    /// Arguments are wrapped in LEFT-TO-RIGHT ISOLATE.
+    LeftToRight,
+    /// Arguments are wrapped in RIGHT-TO-LEFT ISOLATE.
+    RightToLeft,
+}
+
+impl BidiStrategy {
+    /// The character opening an isolate, if any.
+    fn opening(self) -> Option<char> {
+        match self {
+            BidiStrategy::None => None,
+            BidiStrategy::FirstStrong => Some('\u{2068}'),
+            BidiStrategy::LeftToRight => Some('\u{2066}'),
+            BidiStrategy::RightToLeft => Some('\u{2067}'),
+        }
+    }
+}
+
+/// How formatted arguments are written into the surrounding text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Arguments are written as they are formatted.
+    #[default]
+    Text,
+    /// Arguments are HTML-escaped, while the text of the message is
+    /// written as is. This keeps argument values from injecting markup
+    /// into a web page, while letting translators use markup.
+    Html,
+}
+
+// Escapes the characters significant in HTML text and attributes.
+struct HtmlEscaper<'a>(&'a mut dyn fmt::Write);
+
+impl<'a> fmt::Write for HtmlEscaper<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            let escaped = match c {
+                '&' => "&amp;",
+                '<' => "&lt;",
+                '>' => "&gt;",
+                '"' => "&quot;",
+                '\'' => "&#39;",
+                _ => continue,
+            };
+            self.0.write_str(&s[start..i])?;
+            self.0.write_str(escaped)?;
+            start = i + 1;
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+/// Whether a message is rendered to be read or to be spoken.
+///
+/// A spoken rendering, such as the `aria-label` of an element, spells
+/// out whole numbers where it can and writes units in full, so `5 km`
+/// becomes `five kilometers`. A message can also have a variant for
+/// each rendering in a [`MessageBundle`], with the dimension
+/// `rendering`.
+///
+/// [`MessageBundle`]: struct.MessageBundle.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rendering {
+    /// Text to be read.
+    #[default]
+    Visual,
+    /// Text to be spoken, such as by a screen reader.
+    Spoken,
+}
+
+impl Rendering {
+    /// The name of the rendering, as used by message variants.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rendering::Visual => "visual",
+            Rendering::Spoken => "spoken",
+        }
+    }
+}
+
+/// Whether styled output, such as that of `term::format_styled` with
+/// the `term` feature, is written with ANSI escape sequences.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Style the output unless the `NO_COLOR` environment variable is
+    /// set to a non-empty value.
+    #[default]
+    Auto,
+    /// Always style the output.
+    Always,
+    /// Never style the output, removing the tags.
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether output should be styled.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// How `select` formats compare the value of their argument with the
+/// keys of their branches. By default, they must be equal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SelectMatching {
+    /// Ignore the case of ASCII letters, so that `"Male"` selects the
+    /// `male` branch.
+    pub ignore_ascii_case: bool,
+    /// Normalize both the value and the keys, so that `"e\u{301}"`
+    /// selects the `é` branch. With the `unicode-normalization`
+    /// feature, this is Unicode Normalization Form C. Otherwise only
+    /// Latin letters are composed with the marks right after them.
+    pub normalize: bool,
+}
 
 /// Contextual configuration data.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Context {
     /// The language being localized for.
     pub language_tag: LanguageTag,
     /// The value to use in a `PlaceholderFormat`.
-    pub placeholder_value: Option<i64>,
+    pub placeholder_value: Option<f64>,
+    /// The value to use in a `PlaceholderFormat` when it is an integer,
+    /// with all of its digits, which `placeholder_value` may not hold
+    /// above 2^53. This takes precedence over `placeholder_value`.
+    pub placeholder_integer: Option<i128>,
+    /// The number of fraction digits to show in a `PlaceholderFormat`,
+    /// when the value came from a [`Decimal`]. When this is `None`,
+    /// the value is written as briefly as possible.
+    ///
+    /// [`Decimal`]: struct.Decimal.html
+    pub placeholder_fraction_digits: Option<usize>,
+    /// Number options given with the argument of a `PlaceholderFormat`,
+    /// which take precedence over `number_options`. See [`arg_with`].
+    ///
+    /// [`arg_with`]: fn.arg_with.html
+    pub placeholder_options: Option<NumberOptions>,
+    /// The calendar to use when formatting dates. When this is `None`,
+    /// the calendar is chosen from the `language_tag`.
+    pub calendar: Option<Arc<dyn Calendar>>,
+    /// How formatted arguments are isolated from the surrounding text.
+    pub bidi_isolation: BidiStrategy,
+    /// How `select` formats match their argument against their keys,
+    /// unless the format says otherwise.
+    pub select_matching: SelectMatching,
+    /// The digits and rounding used by `number` formats, unless their
+    /// skeleton says otherwise.
+    pub number_options: NumberOptions,
+    /// The number styles defined by the app, by name. See
+    /// [`define_number_style`].
+    ///
+    /// [`define_number_style`]: #method.define_number_style
+    pub number_styles: Vec<(String, NumberOptions)>,
+    /// Plural rules replacing the built in rules for a language. See
+    /// [`set_plural_rules`].
+    ///
+    /// [`set_plural_rules`]: #method.set_plural_rules
+    pub plural_rules: Vec<(LanguageTag, Arc<dyn PluralRuleSet>)>,
+    /// A source of locale data to use before the data compiled into
+    /// this crate. With the `icu4x` feature, this is an
+    /// [`Icu4xDataProvider`] by default.
+    ///
+    /// [`Icu4xDataProvider`]: provider/struct.Icu4xDataProvider.html
+    pub data_provider: Option<Arc<dyn DataProvider>>,
+    /// Records statistics about the messages formatted with this
+    /// context. See [`with_profiler`].
+    ///
+    /// [`with_profiler`]: #method.with_profiler
+    pub profiler: Option<Arc<Profiler>>,
+    /// Transforms applied to arguments before they are formatted. See
+    /// [`map_arg`].
+    ///
+    /// [`map_arg`]: #method.map_arg
+    pub arg_transforms: Vec<(String, Arc<dyn ArgTransform>)>,
+    /// Where messages referenced with `{@key}` are looked up.
+    pub message_source: Option<Arc<dyn MessageSource>>,
+    /// Attributes of the context beyond its language, such as the
+    /// formality or the channel messages are sent through. These
+    /// choose between the variants of a message in a bundle.
+    ///
+    /// Messages parsed with [extensions] can also use an attribute as
+    /// an argument, by prefixing its name with `$`, as in
+    /// `{$viewer_gender, select, ...}`, unless an argument with that
+    /// name is given.
+    ///
+    /// [extensions]: icu/parse/struct.ParseOptions.html#structfield.extensions
+    pub attributes: BTreeMap<String, String>,
+    /// A change of case applied to each whole message written by
+    /// [`format`] or [`write`], such as capitalizing the first letter
+    /// of a message that starts with an argument.
+    ///
+    /// [`format`]: #method.format
+    /// [`write`]: #method.write
+    pub message_case: Option<CaseTransform>,
+    /// Transliterates each whole message written by [`format`] or
+    /// [`write`], before any `message_case` is applied. See
+    /// [`with_transliterator`].
+    ///
+    /// [`format`]: #method.format
+    /// [`write`]: #method.write
+    /// [`with_transliterator`]: #method.with_transliterator
+    pub transliterator: Option<Arc<dyn Transliterator>>,
+    /// How formatted arguments are written into the message.
+    pub output_mode: OutputMode,
+    /// Whether messages are rendered to be read or to be spoken.
+    pub rendering: Rendering,
+    /// Whether styled output, such as that of `term::format_styled`
+    /// with the `term` feature, uses ANSI escape sequences.
+    pub color: ColorChoice,
+}
+
+static GLOBAL_DEFAULT: RwLock<Option<Context>> = RwLock::new(None);
+
+thread_local! {
+    static SCOPED_DEFAULTS: RefCell<Vec<Context>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes a scoped default when dropped, even if the scope panics.
+struct ScopedDefaultGuard;
+
+impl Drop for ScopedDefaultGuard {
+    fn drop(&mut self) {
+        SCOPED_DEFAULTS.with(|defaults| defaults.borrow_mut().pop());
+    }
+}
+
+#[cfg(feature = "icu4x")]
+fn default_data_provider() -> Option<Arc<dyn DataProvider>> {
+    Some(Arc::new(Icu4xDataProvider::new()))
+}
+
+#[cfg(not(feature = "icu4x"))]
+fn default_data_provider() -> Option<Arc<dyn DataProvider>> {
+    None
 }
 
 impl Context {
     /// Create a new instance of `Context`.
-    pub fn new(language: LanguageTag, placeholder_value: Option<i64>) -> Self {
+    pub fn new(language: LanguageTag, placeholder_value: Option<f64>) -> Self {
         Context {
             language_tag: language,
-            placeholder_value: placeholder_value,
+            placeholder_value,
+            placeholder_integer: None,
+            placeholder_fraction_digits: None,
+            placeholder_options: None,
+            calendar: None,
+            bidi_isolation: BidiStrategy::None,
+            select_matching: SelectMatching::default(),
+            number_options: NumberOptions::default(),
+            number_styles: vec![],
+            plural_rules: vec![],
+            data_provider: default_data_provider(),
+            profiler: None,
+            arg_transforms: vec![],
+            message_source: None,
+            attributes: BTreeMap::new(),
+            message_case: None,
+            transliterator: None,
+            output_mode: OutputMode::Text,
+            rendering: Rendering::Visual,
+            color: ColorChoice::Auto,
+        }
+    }
+
+    // The value of a `PlaceholderFormat`.
+    pub(crate) fn placeholder_number(&self) -> Option<Number> {
+        match self.placeholder_integer {
+            Some(value) => Some(Number::integer(value)),
+            None => self.placeholder_value.map(Number::Float),
+        }
+    }
+
+    /// Record the count, duration and missing arguments of each
+    /// message formatted with this context in `profiler`.
+    pub fn with_profiler(mut self, profiler: Arc<Profiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Transliterate each message formatted with this context with
+    /// `transliterator`, after it is formatted and before it is written
+    /// to the stream.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context};
+    /// use std::sync::Arc;
+    ///
+    /// let latin = |text: &str| {
+    ///     text.chars()
+    ///         .map(|c| match c {
+    ///             'Ж' => 'Ž',
+    ///             'ж' => 'ž',
+    ///             'и' => 'i',
+    ///             'а' => 'a',
+    ///             c => c,
+    ///         })
+    ///         .collect::<String>()
+    /// };
+    /// let ctx = Context::default().with_transliterator(Arc::new(latin));
+    /// let m = icu::parse("{name} ({n})").unwrap();
+    /// assert_eq!(ctx.format(&m, &arg("name", "Жижа").arg("n", 3)), "Žiža (3)");
+    /// ```
+    pub fn with_transliterator(mut self, transliterator: Arc<dyn Transliterator>) -> Self {
+        self.transliterator = Some(transliterator);
+        self
+    }
+
+    /// Write formatted arguments according to `mode`.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context, OutputMode};
+    ///
+    /// let ctx = Context::default().output_mode(OutputMode::Html);
+    /// let m = icu::parse("<b>{name}</b> commented").unwrap();
+    /// assert_eq!(
+    ///     ctx.format(&m, &arg("name", "<script>alert('hi')</script>")),
+    ///     "<b>&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt;</b> commented",
+    /// );
+    /// ```
+    pub fn output_mode(mut self, mode: OutputMode) -> Self {
+        self.output_mode = mode;
+        self
+    }
+
+    /// Set the attribute `name` to `value`.
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// The value of the attribute `name`, if it is set. Unless it is
+    /// set explicitly, the `rendering` attribute is the name of the
+    /// [`rendering`].
+    ///
+    /// [`rendering`]: #structfield.rendering
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        match self.attributes.get(name) {
+            Some(value) => Some(value),
+            None if name == "rendering" => Some(self.rendering.as_str()),
+            None => None,
+        }
+    }
+
+    /// Look up messages referenced with `{@key}` in `source`, such as
+    /// a [`MessageBundle`].
+    ///
+    /// [`MessageBundle`]: struct.MessageBundle.html
+    pub fn with_message_source(mut self, source: Arc<dyn MessageSource>) -> Self {
+        self.message_source = Some(source);
+        self
+    }
+
+    /// Set the context used when formatting without an explicit
+    /// context, such as `format_message!(&msg)`, for the whole process.
+    pub fn set_global_default(ctx: Context) {
+        let mut global = GLOBAL_DEFAULT.write().unwrap_or_else(|e| e.into_inner());
+        *global = Some(ctx);
+    }
+
+    /// Use `ctx` as the default context on the current thread while
+    /// running `f`. This takes precedence over the global default.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate message_format;
+    /// # fn main() {
+    /// use message_format::{icu, Context, DateTime};
+    ///
+    /// let m = icu::parse("{d, date, long}").unwrap();
+    /// let d = DateTime::new(2024, 10, 3);
+    /// let ctx = Context::new("fr".parse().unwrap(), None);
+    /// let s = Context::with_default(ctx, || format_message!(&m, d => d));
+    /// # if cfg!(feature = "locale-data") {
+    /// assert_eq!(s, "3 octobre 2024");
+    /// # }
+    /// # }
+    /// ```
+    pub fn with_default<R, F: FnOnce() -> R>(ctx: Context, f: F) -> R {
+        SCOPED_DEFAULTS.with(|defaults| defaults.borrow_mut().push(ctx));
+        let _guard = ScopedDefaultGuard;
+        f()
+    }
+
+    /// The context to use when none is given explicitly: the innermost
+    /// [`with_default`] context, then the [`set_global_default`] context,
+    /// and finally `Context::default()`.
+    ///
+    /// [`with_default`]: #method.with_default
+    /// [`set_global_default`]: #method.set_global_default
+    pub fn current() -> Context {
+        let scoped = SCOPED_DEFAULTS.with(|defaults| defaults.borrow().last().cloned());
+        scoped.unwrap_or_else(|| {
+            let global = GLOBAL_DEFAULT.read().unwrap_or_else(|e| e.into_inner());
+            global.clone().unwrap_or_default()
+        })
+    }
+
+    /// Use `rules` to choose plural categories for `language_tag`,
+    /// replacing any rules registered for it before.
+    ///
+    /// Rules registered for a language without a region or other
+    /// subtags, such as `pt`, also apply to its regional variants,
+    /// such as `pt-BR`, unless those have rules of their own.
+    pub fn set_plural_rules<R>(&mut self, language_tag: LanguageTag, rules: R)
+    where
+        R: PluralRuleSet + 'static,
+    {
+        self.plural_rules.retain(|(tag, _)| *tag != language_tag);
+        self.plural_rules.push((language_tag, Arc::new(rules)));
+    }
+
+    /// Replace the value of the argument `name` with the result of `f`
+    /// before any format sees it, such as to mask personal data.
+    /// Transforms registered for the same argument are applied in
+    /// turn.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context};
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.map_arg("email", |v| {
+    ///     let email = v.to_string();
+    ///     match email.find('@') {
+    ///         Some(at) => format!("{}***{}", &email[..1], &email[at..]),
+    ///         None => email,
+    ///     }
+    /// });
+    /// let m = icu::parse("Sent to {email}.").unwrap();
+    /// assert_eq!(ctx.format(&m, &arg("email", "ann@example.com")), "Sent to a***@example.com.");
+    /// ```
+    pub fn map_arg<F, V>(&mut self, name: &str, f: F)
+    where
+        F: Fn(&Value) -> V + Send + Sync + 'static,
+        Value<'static>: From<V>,
+    {
+        let transform = move |value: &Value| Value::from(f(value));
+        self.arg_transforms.push((name.to_string(), Arc::new(transform)));
+    }
+
+    /// Transliterate the text of the argument `name` before any format
+    /// sees it, leaving the rest of the message as written. Numbers,
+    /// booleans and dates are left alone, but other values are
+    /// replaced by their transliterated text, which `select` formats
+    /// then match on.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context};
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.transliterate_arg("city", |text: &str| text.replace("Ниш", "Niš"));
+    /// let m = icu::parse("Ниш → {city}").unwrap();
+    /// assert_eq!(ctx.format(&m, &arg("city", "Ниш")), "Ниш → Niš");
+    /// ```
+    pub fn transliterate_arg<T>(&mut self, name: &str, transliterator: T)
+    where
+        T: Transliterator + 'static,
+    {
+        self.map_arg(name, move |value| match *value {
+            Value::Number(n) => Value::Number(n),
+            Value::Float(n) => Value::Float(n),
+            Value::Bool(b) => Value::Bool(b),
+            Value::Decimal(ref d) => Value::Decimal(d.clone()),
+            Value::Date(d) => Value::Date(d),
+            Value::Str(s) => Value::String(transliterator.transliterate(s)),
+            Value::String(ref s) => Value::String(transliterator.transliterate(s)),
+            Value::Dynamic(_) => Value::String(transliterator.transliterate(&value.to_string())),
+        });
+    }
+
+    /// Define the number style `name`, so that messages can use it as
+    /// in `{amount, number, price}` rather than each spelling out its
+    /// precision. Redefining a style replaces it.
+    ///
+    /// The style writes numbers as the decimal style does, with
+    /// `options` taking precedence over the `number_options` of the
+    /// context. Formatting fails for a style that isn't defined, so
+    /// that a misspelt name is noticed.
+    ///
+    /// ```
+    /// use message_format::number::{Currency, NumberOptions};
+    /// use message_format::{arg, icu, Context};
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.define_number_style("price", NumberOptions::currency(Currency::new("EUR").unwrap()));
+    /// ctx.define_number_style("rating", NumberOptions { maximum_fraction_digits: Some(1), ..Default::default() });
+    /// let m = icu::parse("{amount, number, price} ({stars, number, rating} stars)").unwrap();
+    /// assert_eq!(ctx.format(&m, &arg("amount", 19).arg("stars", 4.25)), "€19.00 (4.2 stars)");
+    /// ```
+    pub fn define_number_style(&mut self, name: &str, options: NumberOptions) {
+        self.number_styles.retain(|(defined, _)| defined != name);
+        self.number_styles.push((name.to_string(), options));
+    }
+
+    /// The options of the number style `name`, if it is defined.
+    pub fn number_style(&self, name: &str) -> Option<&NumberOptions> {
+        self.number_styles
+            .iter()
+            .find(|(defined, _)| defined == name)
+            .map(|(_, options)| options)
+    }
+
+    /// Call `f` with `args`, after applying the `arg_transforms`.
+    pub(crate) fn with_mapped_args<'f, R, F>(&self, args: &'f dyn Args<'f>, f: F) -> R
+    where
+        F: for<'a> FnOnce(&'a dyn Args<'a>) -> R,
+    {
+        if !self.maps_args() {
+            return f(args);
+        }
+        let mut values: Vec<(&str, Value)> = vec![];
+        for (name, transform) in &self.arg_transforms {
+            let mapped = match values.iter().position(|(mapped, _)| mapped == name) {
+                Some(i) => transform.transform(&values.remove(i).1),
+                None => match args.get(name) {
+                    Some(value) => transform.transform(value),
+                    None => continue,
+                },
+            };
+            values.push((name, mapped));
+        }
+        let attributes: Vec<(&str, Value)> = self
+            .attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), Value::Str(value)))
+            .collect();
+        f(&MappedArgs {
+            values: &values,
+            attributes: &attributes,
+            inner: args,
+        })
+    }
+
+    /// Whether arguments are transformed or come from attributes, so
+    /// that `with_mapped_args` has work to do.
+    pub(crate) fn maps_args(&self) -> bool {
+        !self.arg_transforms.is_empty() || !self.attributes.is_empty()
+    }
+
+    /// The plural rules registered for the language being localized
+    /// for, or else those from the `data_provider`, if any, or else the
+    /// built-in CLDR rules for the language, English when it has none.
+    pub fn registered_plural_rules(&self) -> Arc<dyn PluralRuleSet> {
+        let exact = self.plural_rules.iter().find(|(tag, _)| *tag == self.language_tag);
+        let language = || {
+            self.plural_rules.iter().find(|(tag, _)| {
+                let language = match tag.language {
+                    Some(ref language) => language,
+                    None => return false,
+                };
+                let language_only = LanguageTag {
+                    language: Some(language.clone()),
+                    ..Default::default()
+                };
+                *tag == language_only
+                    && self.language_tag.language.as_ref().is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+        };
+        match exact.or_else(language) {
+            Some((_, rules)) => rules.clone(),
+            None => self
+                .data_provider
+                .as_ref()
+                .and_then(|provider| provider.plural_rules(&self.language_tag))
+                .unwrap_or_else(|| {
+                    // A language without built-in rules, or none at all,
+                    // formats as English.
+                    let rules = PluralRules::find(&self.language_tag, PluralRuleType::Cardinal)
+                        .unwrap_or_else(|| PluralRules::cardinal(&"en".parse().expect("valid language tag")));
+                    Arc::new(rules)
+                }),
+        }
+    }
+
+    // Write a number with the locale data of this context.
+    pub(crate) fn with_number_locale<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&NumberLocale) -> R,
+    {
+        let rules = self.registered_plural_rules();
+        let symbols = self.number_symbols();
+        f(&NumberLocale {
+            language_tag: &self.language_tag,
+            symbols: &symbols,
+            plural_rules: rules.as_ref(),
+            provider: self.data_provider.as_deref(),
+            numbering_system: self.numbering_system(),
+        })
+    }
+
+    // Write a date or time, letting the `data_provider` format it
+    // before using the `datetime_data`.
+    pub(crate) fn write_datetime(
+        &self,
+        value: &DateTime,
+        kind: DateTimeKind,
+        style: &DateTimeStyle,
+        stream: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        let calendar = self.calendar();
+        write_digits_in(self.numbering_system(), stream, |stream| {
+            if let Some(ref provider) = self.data_provider {
+                if let Some(result) = provider.format_datetime(&self.language_tag, calendar, value, kind, style, stream) {
+                    return result;
+                }
+            }
+            format_datetime_with_data(&self.datetime_data(), calendar, value, kind, style, stream)
+        })
+    }
+
+    /// The data for formatting dates, from the `data_provider` if it
+    /// has any for the language. See [`missing_locale_data`] for when
+    /// English data is used.
+    ///
+    /// [`missing_locale_data`]: #method.missing_locale_data
+    pub fn datetime_data(&self) -> DataRef<DateTimeData> {
+        self.data_provider
+            .as_ref()
+            .and_then(|provider| provider.datetime_data(&self.language_tag))
+            .unwrap_or_else(|| DataRef::Static(DateTimeData::for_language_tag(&self.language_tag)))
+    }
+
+    /// The symbols for formatting numbers, from the `data_provider` if
+    /// it has any for the language. See [`missing_locale_data`] for
+    /// when English symbols are used.
+    ///
+    /// [`missing_locale_data`]: #method.missing_locale_data
+    pub fn number_symbols(&self) -> DataRef<NumberSymbols> {
+        self.data_provider
+            .as_ref()
+            .and_then(|provider| provider.number_symbols(&self.language_tag))
+            .unwrap_or_else(|| DataRef::Static(NumberSymbols::for_language_tag(&self.language_tag)))
+    }
+
+    /// The locale data missing for the language of this context, from
+    /// both the `data_provider` and the bundled data, for which English
+    /// data is used instead.
+    ///
+    /// Check this for each language an application supports, so that
+    /// missing data is noticed rather than formatted as English.
+    ///
+    /// ```
+    /// use message_format::provider::LocaleData;
+    /// use message_format::Context;
+    ///
+    /// let ctx = Context::new("tlh".parse().unwrap(), None);
+    /// assert_eq!(
+    ///     ctx.missing_locale_data(),
+    ///     [LocaleData::NumberSymbols, LocaleData::DateTime, LocaleData::Units],
+    /// );
+    /// assert!(Context::default().missing_locale_data().is_empty());
+    /// ```
+    pub fn missing_locale_data(&self) -> Vec<LocaleData> {
+        // Without a language, as in the default context, the English
+        // data is the data asked for.
+        if self.language_tag.language.is_none() {
+            return vec![];
+        }
+        let provider = self.data_provider.as_ref();
+        let mut missing = vec![];
+        if provider.and_then(|provider| provider.number_symbols(&self.language_tag)).is_none()
+            && NumberSymbols::find(&self.language_tag).is_none()
+        {
+            missing.push(LocaleData::NumberSymbols);
+        }
+        if provider.and_then(|provider| provider.datetime_data(&self.language_tag)).is_none()
+            && DateTimeData::find(&self.language_tag).is_none()
+        {
+            missing.push(LocaleData::DateTime);
+        }
+        if !UnitPatterns::has_language(&self.language_tag) {
+            missing.push(LocaleData::Units);
+        }
+        missing
+    }
+
+    /// The digits numbers, dates and `#` are written with, from the
+    /// `nu` keyword of the `language_tag`, as in `ar-EG-u-nu-arab`.
+    /// The keyword takes precedence over the digits of the language.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context, DateTime};
+    ///
+    /// let m = icu::parse("{n, plural, other {# items}} on {d, date, short}").unwrap();
+    /// let args = arg("n", 12);
+    /// let args = args.arg("d", DateTime::new(2024, 3, 5));
+    /// let ctx = Context::new("en-u-nu-arab".parse().unwrap(), None);
+    /// assert_eq!(ctx.format(&m, &args), "١٢ items on ٣/٥/٢٤");
+    /// ```
+    pub fn numbering_system(&self) -> NumberingSystem {
+        NumberingSystem::for_language_tag(&self.language_tag)
+    }
+
+    /// The calendar to use when formatting dates.
+    pub fn calendar(&self) -> &dyn Calendar {
+        match self.calendar {
+            Some(ref calendar) => calendar.as_ref(),
+            None => calendar_for_language_tag(&self.language_tag),
+        }
+    }
+
+    /// Write a formatted argument to `stream` using `f`, wrapped in
+    /// directional isolates according to `bidi_isolation`, and escaped
+    /// according to `output_mode`.
+    pub fn write_isolated<F>(&self, stream: &mut dyn fmt::Write, f: F) -> fmt::Result
+    where
+        F: FnOnce(&mut dyn fmt::Write) -> fmt::Result,
+    {
+        let mut escaper;
+        let stream = match self.output_mode {
+            OutputMode::Text => stream,
+            OutputMode::Html => {
+                escaper = HtmlEscaper(stream);
+                &mut escaper as &mut dyn fmt::Write
+            }
+        };
+        match self.bidi_isolation.opening() {
+            Some(opening) => {
+                stream.write_char(opening)?;
+                f(stream)?;
+                stream.write_char('\u{2069}')
+            }
+            None => f(stream),
         }
     }
 
     /// Format a message, returning a string.
-    pub fn format<'f>(&self, message: &Message, args: &'f dyn Args<'f>) -> String {
-        let mut output = String::new();
-        let _ = message.write_message(self, &mut output, args);
+    ///
+    /// The message is usually a [`Message`], but may be any
+    /// [`MessagePart`], such as a [`StaticMessage`].
+    ///
+    /// [`Message`]: struct.Message.html
+    /// [`MessagePart`]: trait.MessagePart.html
+    /// [`StaticMessage`]: struct.StaticMessage.html
+    pub fn format<'f>(&self, message: &dyn MessagePart, args: &'f dyn Args<'f>) -> String {
+        let capacity = match message.as_any().downcast_ref::<Message>() {
+            Some(message) => message.estimated_len(args),
+            None => 0,
+        };
+        let mut output = String::with_capacity(capacity);
+        let _ = self.write(message, &mut output, args);
         output
     }
 
+    /// Format a message into its parts, telling the text of the
+    /// message apart from the formatted arguments, so that a UI can
+    /// wrap arguments in spans or links.
+    ///
+    /// The branch chosen by a `plural` or `select` format is formatted
+    /// in place, and adjacent literal text is merged into one part.
+    /// Formatting fails, returning `None`, when an argument is missing.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context, FormattedPart, PartRole};
+    ///
+    /// let ctx = Context::default();
+    /// let m = icu::parse("{name} has {n, plural, one {# message} other {# messages}}").unwrap();
+    /// let args = arg("name", "Ann");
+    /// let args = args.arg("n", 3);
+    ///
+    /// let parts = ctx.format_to_parts(&m, &args).unwrap();
+    /// assert_eq!(parts, vec![
+    ///     FormattedPart { role: PartRole::Argument { name: "name".into() }, text: "Ann".into() },
+    ///     FormattedPart { role: PartRole::Literal, text: " has ".into() },
+    ///     FormattedPart { role: PartRole::PluralNumber, text: "3".into() },
+    ///     FormattedPart { role: PartRole::Literal, text: " messages".into() },
+    /// ]);
+    /// ```
+    pub fn format_to_parts<'f>(&self, message: &Message, args: &'f dyn Args<'f>) -> Option<Vec<FormattedPart>> {
+        let mut parts = vec![];
+        self.with_mapped_args(args, |args| parts::collect_parts(message, self, args, &mut parts))
+            .ok()?;
+        Some(parts)
+    }
+
+    /// Format a message, returning why it failed if it did.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use message_format::icu::ParseOptions;
+    /// use message_format::{Context, EmptyArgs, FormatError, MessageBundle};
+    ///
+    /// let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+    /// let bundle = Arc::new(MessageBundle::parse_entries_with(
+    ///     "en".parse().unwrap(),
+    ///     vec![("a", "{@b}"), ("b", "{@a}")],
+    ///     options,
+    /// ).unwrap());
+    ///
+    /// let ctx = Context::default().with_message_source(bundle.clone());
+    /// assert_eq!(
+    ///     ctx.try_format(bundle.get("a").unwrap(), &EmptyArgs),
+    ///     Err(FormatError::ReferenceCycle(vec!["b".into(), "a".into(), "b".into()])),
+    /// );
+    /// ```
+    pub fn try_format<'f>(&self, message: &dyn MessagePart, args: &'f dyn Args<'f>) -> Result<String, FormatError> {
+        let mut output = String::new();
+        self.try_write(message, &mut output, args)?;
+        Ok(output)
+    }
+
+    /// Pair a message with its arguments in a value implementing
+    /// `fmt::Display`, formatted using this context.
+    pub fn display<'a>(
+        &'a self,
+        message: &'a dyn MessagePart,
+        args: &'a dyn Args<'a>,
+    ) -> MessageDisplay<'a> {
+        MessageDisplay::new(Some(self), message, args)
+    }
+
     /// Write a message to a stream.
     pub fn write<'f>(
         &self,
-        message: &Message,
+        message: &dyn MessagePart,
         stream: &mut dyn fmt::Write,
         args: &'f dyn Args<'f>,
     ) -> fmt::Result {
-        message.write_message(self, stream, args)
+        self.try_write(message, stream, args).map_err(|_| fmt::Error {})
+    }
+
+    // Like `write`, returning why it failed if it did.
+    fn try_write<'f>(
+        &self,
+        message: &dyn MessagePart,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        if self.message_case.is_none() && self.transliterator.is_none() {
+            return self.write_profiled(message, stream, args);
+        }
+        let mut output = String::new();
+        self.write_profiled(message, &mut output, args)?;
+        if let Some(ref transliterator) = self.transliterator {
+            output = transliterator.transliterate(&output);
+        }
+        if let Some(case) = self.message_case {
+            output = case.apply(&output, &self.language_tag);
+        }
+        Ok(stream.write_str(&output)?)
+    }
+
+    fn write_profiled<'f>(
+        &self,
+        message: &dyn MessagePart,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        let profiler = match self.profiler {
+            Some(ref profiler) => profiler,
+            None => return self.with_mapped_args(args, |args| message.try_apply_format(self, stream, args)),
+        };
+        let start = Instant::now();
+        let result = self.with_mapped_args(args, |args| message.try_apply_format(self, stream, args));
+        let elapsed = start.elapsed();
+        if let Some(message) = message.as_any().downcast_ref::<Message>() {
+            profiler.record(message, args, elapsed, result.is_err());
+        }
+        result
     }
 }
 
-impl Default for Context {
-    fn default() -> Self {
-        Context {
-            language_tag: Default::default(),
-            placeholder_value: None,
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{BidiStrategy, Context, OutputMode, Rendering};
+    use CaseTransform;
+    use icu::{parse_with, ParseOptions};
+    #[cfg(feature = "locale-data")]
+    use provider::LocaleData;
+    use {arg, icu, EmptyArgs, Value};
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn missing_locale_data() {
+        let ctx = Context::new("fr-CA".parse().unwrap(), None);
+        assert!(ctx.missing_locale_data().is_empty());
+        let ctx = Context::new("ja".parse().unwrap(), None);
+        assert_eq!(
+            ctx.missing_locale_data(),
+            [LocaleData::NumberSymbols, LocaleData::DateTime, LocaleData::Units]
+        );
+        // Swedish has number symbols and dates, but no unit patterns.
+        let ctx = Context::new("sv".parse().unwrap(), None);
+        assert_eq!(ctx.missing_locale_data(), [LocaleData::Units]);
+    }
+
+    #[test]
+    fn plural_rules_fall_back_to_english() {
+        let m = icu::parse("{n, plural, one {# item} other {# items}}").unwrap();
+        let fil = Context::new("fil".parse().unwrap(), None);
+        assert_eq!(fil.format(&m, &arg("n", 1)), "1 item");
+        assert_eq!(Context::default().format(&m, &arg("n", 1)), "1 item");
+        let ja = Context::new("ja".parse().unwrap(), None);
+        assert_eq!(ja.format(&m, &arg("n", 1)), "1 items");
+    }
+
+    #[test]
+    fn scoped_defaults_nest() {
+        let sv = Context::new("sv".parse().unwrap(), None);
+        let de = Context::new("de".parse().unwrap(), None);
+        Context::with_default(sv, || {
+            assert_eq!(Context::current().language_tag.to_string(), "sv");
+            Context::with_default(de, || {
+                assert_eq!(Context::current().language_tag.to_string(), "de");
+            });
+            assert_eq!(Context::current().language_tag.to_string(), "sv");
+        });
+    }
+
+    #[test]
+    fn arguments_are_isolated() {
+        let m = icu::parse("{name} wrote {count, plural, one {# post} other {# posts}}.").unwrap();
+        let mut ctx = Context {
+            bidi_isolation: BidiStrategy::FirstStrong,
+            ..Context::default()
+        };
+        assert_eq!(
+            ctx.format(&m, &arg("name", "\u{5e9}\u{5e8}\u{5d4}").arg("count", 3)),
+            "\u{2068}\u{5e9}\u{5e8}\u{5d4}\u{2069} wrote 3 posts."
+        );
+        ctx.bidi_isolation = BidiStrategy::None;
+        assert_eq!(ctx.format(&m, &arg("name", "Kim").arg("count", 1)), "Kim wrote 1 post.");
+    }
+
+    #[test]
+    fn transliteration_precedes_case() {
+        let m = icu::parse("{who} has {n, plural, one {# cat} other {# cats}}").unwrap();
+        let romaji = |text: &str| text.replace("ねこ", "neko");
+        let mut ctx = Context {
+            message_case: Some(CaseTransform::Capitalize),
+            ..Context::default()
         }
+        .with_transliterator(Arc::new(romaji));
+        assert_eq!(ctx.format(&m, &arg("who", "ねこ").arg("n", 1)), "Neko has 1 cat");
+
+        ctx.transliterator = None;
+        ctx.transliterate_arg("who", romaji);
+        ctx.transliterate_arg("n", |_: &str| "many".to_string());
+        assert_eq!(ctx.format(&m, &arg("who", "ねこ").arg("n", 2)), "Neko has 2 cats");
+    }
+
+    #[test]
+    fn html_mode_escapes_only_arguments() {
+        let m = icu::parse("<a href=\"/u/{id}\">{name}</a> & {n, plural, one {# friend} other {# friends}}").unwrap();
+        let ctx = Context::default().output_mode(OutputMode::Html);
+        let args = arg("id", "1\"onclick=\"x");
+        let args = args.arg("name", "Tom & Jerry");
+        let args = args.arg("n", 2);
+        let expected = "<a href=\"/u/1&quot;onclick=&quot;x\">Tom &amp; Jerry</a> & 2 friends";
+        assert_eq!(ctx.format(&m, &args), expected);
+        assert_eq!(m.compile().format_with(&ctx, &args), expected);
+    }
+
+    #[test]
+    fn spoken_rendering() {
+        let m = icu::parse("{n, plural, one {# stop} other {# stops}}, {d, unit, kilometer} ({p, number, percent})").unwrap();
+        let ctx = Context {
+            rendering: Rendering::Spoken,
+            ..Context::default()
+        };
+        let args = arg("n", 21);
+        let args = args.arg("d", 5);
+        let args = args.arg("p", 0.5);
+        assert_eq!(ctx.format(&m, &args), "twenty-one stops, five kilometers (50%)");
+        assert_eq!(m.compile().format_with(&ctx, &args), "twenty-one stops, five kilometers (50%)");
+        assert_eq!(Context::default().format(&m, &args), "21 stops, 5 km (50%)");
+    }
+
+    #[test]
+    fn message_case_applies_to_whole_message() {
+        let m = icu::parse("{name} joined {group}").unwrap();
+        let ctx = Context {
+            message_case: Some(CaseTransform::Capitalize),
+            ..Context::default()
+        };
+        assert_eq!(ctx.format(&m, &arg("name", "ñandú").arg("group", "the team")), "Ñandú joined the team");
+    }
+
+    #[test]
+    fn transforms_apply_before_every_format() {
+        let m = icu::parse("{name}: {count, plural, one {# item} other {# items}}").unwrap();
+        let mut ctx = Context::default();
+        ctx.map_arg("name", |v| v.to_string().to_uppercase());
+        ctx.map_arg("name", |v| format!("<{}>", v));
+        ctx.map_arg("count", |v| v.as_number().map_or(0, |n| n.min(1)));
+        ctx.map_arg("missing", |_| Value::Bool(true));
+
+        let args = arg("name", "kim");
+        let args = args.arg("count", 7);
+        assert_eq!(ctx.format(&m, &args), "<KIM>: 1 item");
+        assert_eq!(m.compile().format_with(&ctx, &args), "<KIM>: 1 item");
+        assert_eq!(ctx.display(&m, &args).to_string(), "<KIM>: 1 item");
+    }
+
+    #[test]
+    fn attributes_are_arguments_with_extensions() {
+        let source = "{$viewer_gender, select, female {Willkommen, liebe {name}} other {Willkommen, {name}}}";
+        assert!(icu::parse(source).is_err());
+        let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+        let m = parse_with(source, options).unwrap();
+
+        let ctx = Context::default().with_attribute("viewer_gender", "female");
+        let args = arg("name", "Ann");
+        assert_eq!(ctx.format(&m, &args), "Willkommen, liebe Ann");
+        assert_eq!(m.compile().format_with(&ctx, &args), "Willkommen, liebe Ann");
+        let args = args.arg("$viewer_gender", "male");
+        assert_eq!(ctx.format(&m, &args), "Willkommen, Ann");
+        assert_eq!(ctx.try_format(&m, &EmptyArgs).map_err(|_| ()), Err(()));
+        assert_eq!(Context::default().try_format(&m, &arg("name", "Ann")).map_err(|_| ()), Err(()));
     }
 }
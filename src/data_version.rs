@@ -0,0 +1,86 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+// The CLDR release the bundled locale data is derived from.
+const CLDR_VERSION: u32 = 44;
+
+// Incremented whenever the bundled data changes, including fixes that
+// don't move to a new CLDR release.
+const DATA_REVISION: u32 = 3;
+
+// With a `cldr-*` feature enabled, upgrading to a release of this crate
+// with data from another CLDR release fails to compile rather than
+// silently changing formatted output.
+#[cfg(feature = "cldr-44")]
+const _: () = assert!(
+    CLDR_VERSION == 44,
+    "the `cldr-44` feature is enabled, but the bundled data is from another CLDR release"
+);
+
+/// The version of the locale data bundled with this crate, which
+/// determines formatted output such as month names and number symbols.
+///
+/// Tests that compare formatted output can assert on this, so that a
+/// change of data is noticed when upgrading. To prevent upgrading to
+/// data from another CLDR release altogether, enable the `cldr-44`
+/// feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DataVersion {
+    /// The CLDR release the data is derived from, such as `44`.
+    pub cldr: u32,
+    /// The revision of the data, which changes with any change to it.
+    pub revision: u32,
+}
+
+impl fmt::Display for DataVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cldr-{}.r{}", self.cldr, self.revision)
+    }
+}
+
+/// The version of the bundled locale data.
+///
+/// ```
+/// let version = message_format::data_version();
+/// assert_eq!(version.to_string(), format!("cldr-{}.r{}", version.cldr, version.revision));
+/// ```
+pub fn data_version() -> DataVersion {
+    DataVersion {
+        cldr: CLDR_VERSION,
+        revision: DATA_REVISION,
+    }
+}
+
+#[cfg(all(test, feature = "locale-data"))]
+mod tests {
+    use super::DATA_REVISION;
+    use id::sha512;
+    use {datetime, locale, number, plural};
+
+    // The hash of the bundled tables at each data revision. When the
+    // tables change, bump `DATA_REVISION` and add an entry for it,
+    // rather than replacing the last one.
+    const REVISIONS: &[(u32, &str)] = &[
+        (2, "e4dbf55989591a173cc9cb77a5e41c9f"),
+        (3, "dfdfc890a2bce86d5844d483e5b969dd"),
+    ];
+
+    #[test]
+    fn bundled_tables_match_the_revision() {
+        let tables = [
+            datetime::bundled_tables(),
+            number::bundled_tables(),
+            plural::bundled_tables(),
+            locale::bundled_tables(),
+        ]
+        .concat();
+        let hash: String = sha512(tables.as_bytes())[..16].iter().map(|b| format!("{:02x}", b)).collect();
+        assert!(REVISIONS.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(REVISIONS.last(), Some(&(DATA_REVISION, hash.as_str())));
+    }
+}
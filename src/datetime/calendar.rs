@@ -0,0 +1,280 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::sync::Arc;
+
+use language_tags::LanguageTag;
+
+use super::DateTime;
+
+/// A date expressed in a particular [`Calendar`].
+///
+/// [`Calendar`]: trait.Calendar.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CalendarDate {
+    /// The name of the era, such as `AD` or `Reiwa`.
+    pub era: &'static str,
+    /// The year within the era.
+    pub year: i32,
+    /// The month, starting from `1`.
+    pub month: u8,
+    /// The day of the month, starting from `1`.
+    pub day: u8,
+}
+
+/// A calendar system used when formatting dates.
+///
+/// The calendar is chosen from the `ca` keyword of the Unicode locale
+/// extension of the [`Context`] language tag, such as
+/// `ja-JP-u-ca-japanese`, unless a calendar is set on the `Context`
+/// directly. Implement this trait to use other calendars.
+///
+/// Calendars other than the Gregorian calendar use English month
+/// and era names regardless of the locale.
+///
+/// [`Context`]: ../struct.Context.html
+pub trait Calendar: fmt::Debug + Send + Sync {
+    /// The identifier of the calendar as used by the `ca` keyword.
+    fn identifier(&self) -> &'static str;
+
+    /// Convert a Gregorian date to this calendar.
+    fn convert(&self, value: &DateTime) -> CalendarDate;
+
+    /// The name of a month, if it differs from the Gregorian name
+    /// in the locale data.
+    fn month_name(&self, _month: u8, _abbreviated: bool) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the era should be displayed along with the year even
+    /// when a pattern doesn't ask for it.
+    fn shows_era(&self) -> bool {
+        true
+    }
+}
+
+/// The proleptic Gregorian calendar.
+#[derive(Debug)]
+pub struct Gregorian;
+
+impl Calendar for Gregorian {
+    fn identifier(&self) -> &'static str {
+        "gregory"
+    }
+
+    fn convert(&self, value: &DateTime) -> CalendarDate {
+        if value.year > 0 {
+            CalendarDate {
+                era: "AD",
+                year: value.year,
+                month: value.month,
+                day: value.day,
+            }
+        } else {
+            CalendarDate {
+                era: "BC",
+                year: 1 - value.year,
+                month: value.month,
+                day: value.day,
+            }
+        }
+    }
+
+    fn shows_era(&self) -> bool {
+        false
+    }
+}
+
+/// The Thai Buddhist calendar, which counts years from 543 BC.
+#[derive(Debug)]
+pub struct Buddhist;
+
+impl Calendar for Buddhist {
+    fn identifier(&self) -> &'static str {
+        "buddhist"
+    }
+
+    fn convert(&self, value: &DateTime) -> CalendarDate {
+        CalendarDate {
+            era: "BE",
+            year: value.year + 543,
+            month: value.month,
+            day: value.day,
+        }
+    }
+}
+
+/// The Japanese imperial calendar, which numbers years from the
+/// start of each era. Dates before the Meiji era use the Gregorian
+/// calendar.
+#[derive(Debug)]
+pub struct Japanese;
+
+/// The Japanese eras, with the Gregorian date on which they started.
+pub(super) const JAPANESE_ERAS: &[(&str, i32, u8, u8)] = &[
+    ("Reiwa", 2019, 5, 1),
+    ("Heisei", 1989, 1, 8),
+    ("Shōwa", 1926, 12, 25),
+    ("Taishō", 1912, 7, 30),
+    ("Meiji", 1868, 9, 8),
+];
+
+impl Calendar for Japanese {
+    fn identifier(&self) -> &'static str {
+        "japanese"
+    }
+
+    fn convert(&self, value: &DateTime) -> CalendarDate {
+        let date = (value.year, value.month, value.day);
+        JAPANESE_ERAS
+            .iter()
+            .find(|&&(_, y, m, d)| date >= (y, m, d))
+            .map_or_else(
+                || Gregorian.convert(value),
+                |&(era, start, _, _)| CalendarDate {
+                    era,
+                    year: value.year - start + 1,
+                    month: value.month,
+                    day: value.day,
+                },
+            )
+    }
+}
+
+/// The tabular Islamic civil calendar.
+#[derive(Debug)]
+pub struct IslamicCivil;
+
+/// The day 1 Muharram 1 AH, relative to the Unix epoch.
+const ISLAMIC_EPOCH: i64 = -492_148;
+
+const ISLAMIC_MONTHS: [&str; 12] = [
+    "Muharram", "Safar", "Rabiʻ I", "Rabiʻ II", "Jumada I", "Jumada II", "Rajab",
+    "Shaʻban", "Ramadan", "Shawwal", "Dhuʻl-Qiʻdah", "Dhuʻl-Hijjah",
+];
+
+const ISLAMIC_MONTHS_ABBR: [&str; 12] = [
+    "Muh.", "Saf.", "Rab. I", "Rab. II", "Jum. I", "Jum. II", "Raj.", "Sha.", "Ram.",
+    "Shaw.", "Dhuʻl-Q.", "Dhuʻl-H.",
+];
+
+impl IslamicCivil {
+    fn days_from_date(year: i64, month: i64, day: i64) -> i64 {
+        day + (59 * (month - 1) + 1) / 2 + (year - 1) * 354 + (3 + 11 * year).div_euclid(30)
+            + ISLAMIC_EPOCH
+            - 1
+    }
+}
+
+impl Calendar for IslamicCivil {
+    fn identifier(&self) -> &'static str {
+        "islamic-civil"
+    }
+
+    fn convert(&self, value: &DateTime) -> CalendarDate {
+        let days = value.days_since_epoch();
+        let year = (30 * (days - ISLAMIC_EPOCH) + 10646).div_euclid(10631);
+        let mut month = 1;
+        while month < 12 && days >= IslamicCivil::days_from_date(year, month + 1, 1) {
+            month += 1;
+        }
+        let day = days - IslamicCivil::days_from_date(year, month, 1) + 1;
+        CalendarDate {
+            era: "AH",
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+
+    fn month_name(&self, month: u8, abbreviated: bool) -> Option<&'static str> {
+        let names = if abbreviated {
+            &ISLAMIC_MONTHS_ABBR
+        } else {
+            &ISLAMIC_MONTHS
+        };
+        names.get(usize::from(month) - 1).cloned()
+    }
+}
+
+/// Find the calendar requested by the `ca` keyword of the Unicode
+/// locale extension of `language_tag`, defaulting to the Gregorian
+/// calendar.
+///
+/// ```
+/// use message_format::datetime::calendar_for_language_tag;
+///
+/// let tag = "ja-JP-u-ca-japanese".parse().unwrap();
+/// assert_eq!(calendar_for_language_tag(&tag).identifier(), "japanese");
+/// ```
+pub fn calendar_for_language_tag(language_tag: &LanguageTag) -> &'static dyn Calendar {
+    let keywords = language_tag.extensions.get(&b'u').map_or(&[][..], |k| &k[..]);
+    // A value may have several subtags, as `islamic-civil` does, up to
+    // the next two letter key.
+    let requested = keywords.iter().position(|k| k.eq_ignore_ascii_case("ca")).map(|i| {
+        let value: Vec<&str> = keywords[i + 1..]
+            .iter()
+            .take_while(|subtag| subtag.len() > 2)
+            .map(|subtag| subtag.as_str())
+            .collect();
+        value.join("-")
+    });
+    match requested.map(|c| c.to_ascii_lowercase()) {
+        Some(ref c) if c == "buddhist" => &Buddhist,
+        Some(ref c) if c == "japanese" => &Japanese,
+        Some(ref c) if c == "islamic-civil" || c == "islamicc" => &IslamicCivil,
+        _ => &Gregorian,
+    }
+}
+
+// Like `calendar_for_language_tag`, but returns a calendar that
+// can be stored in a `Context`.
+pub(crate) fn shared_calendar_for_language_tag(language_tag: &LanguageTag) -> Arc<dyn Calendar> {
+    match calendar_for_language_tag(language_tag).identifier() {
+        "buddhist" => Arc::new(Buddhist),
+        "japanese" => Arc::new(Japanese),
+        "islamic-civil" => Arc::new(IslamicCivil),
+        _ => Arc::new(Gregorian),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_convert() {
+        let d = DateTime::new(2024, 10, 3);
+        assert_eq!(Buddhist.convert(&d).year, 2567);
+
+        let reiwa = Japanese.convert(&d);
+        assert_eq!((reiwa.era, reiwa.year), ("Reiwa", 6));
+        let heisei = Japanese.convert(&DateTime::new(2019, 4, 30));
+        assert_eq!((heisei.era, heisei.year), ("Heisei", 31));
+
+        let islamic = IslamicCivil.convert(&d);
+        assert_eq!((islamic.year, islamic.month, islamic.day), (1446, 3, 29));
+        let new_year = IslamicCivil.convert(&DateTime::new(2024, 7, 8));
+        assert_eq!((new_year.year, new_year.month, new_year.day), (1446, 1, 1));
+    }
+
+    #[test]
+    fn calendar_is_chosen_from_extension() {
+        let tag = "th-TH-u-ca-buddhist".parse().unwrap();
+        assert_eq!(calendar_for_language_tag(&tag).identifier(), "buddhist");
+        let tag = "en-US".parse().unwrap();
+        assert_eq!(calendar_for_language_tag(&tag).identifier(), "gregory");
+        let tag = "ar-u-ca-islamic-civil".parse().unwrap();
+        assert_eq!(calendar_for_language_tag(&tag).identifier(), "islamic-civil");
+        let tag = "ar-u-ca-islamic-civil-nu-arab".parse().unwrap();
+        assert_eq!(calendar_for_language_tag(&tag).identifier(), "islamic-civil");
+        let tag = "ar-u-ca-islamicc".parse().unwrap();
+        assert_eq!(calendar_for_language_tag(&tag).identifier(), "islamic-civil");
+        let tag = "ar-u-ca-islamic-umalqura".parse().unwrap();
+        assert_eq!(calendar_for_language_tag(&tag).identifier(), "gregory");
+    }
+}
@@ -0,0 +1,254 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::borrow::Cow;
+
+/// Locale specific data used when formatting dates and times.
+///
+/// This data is derived from the [CLDR].
+///
+/// [CLDR]: http://cldr.unicode.org/
+#[derive(Clone, Debug)]
+pub struct DateTimeData {
+    /// The language this data is for.
+    pub language: Cow<'static, str>,
+    /// The wide month names, starting with January.
+    pub months: [Cow<'static, str>; 12],
+    /// The abbreviated month names, starting with January.
+    pub months_abbr: [Cow<'static, str>; 12],
+    /// The wide weekday names, starting with Sunday.
+    pub weekdays: [Cow<'static, str>; 7],
+    /// The abbreviated weekday names, starting with Sunday.
+    pub weekdays_abbr: [Cow<'static, str>; 7],
+    /// The names for AM and PM.
+    pub am_pm: [Cow<'static, str>; 2],
+    /// The short, medium, long and full date patterns.
+    pub date_patterns: [Cow<'static, str>; 4],
+    /// The short, medium, long and full time patterns.
+    pub time_patterns: [Cow<'static, str>; 4],
+    /// The localized GMT format, where `{0}` is replaced by the offset.
+    pub gmt_format: Cow<'static, str>,
+    /// The localized GMT format for a zero offset.
+    pub gmt_zero: Cow<'static, str>,
+    /// The patterns for the skeletons that this locale provides.
+    pub skeletons: Cow<'static, [(Cow<'static, str>, Cow<'static, str>)]>,
+}
+
+impl DateTimeData {
+    /// Find the data for a language, falling back to English if
+    /// there is no data available. [`Context::missing_locale_data`]
+    /// reports when that happens.
+    ///
+    /// [`Context::missing_locale_data`]: ../struct.Context.html#method.missing_locale_data
+    pub fn for_language_tag(language_tag: &LanguageTag) -> &'static DateTimeData {
+        DateTimeData::find(language_tag).unwrap_or(&EN)
+    }
+
+    // The bundled data for a language, without falling back.
+    pub(crate) fn find(language_tag: &LanguageTag) -> Option<&'static DateTimeData> {
+        let language = language_tag.language.as_ref()?;
+        ALL.iter().find(|data| data.language == **language).cloned()
+    }
+}
+
+// An array of names, borrowed from the bundled data.
+macro_rules! names {
+    ($($name:expr),* $(,)?) => {
+        [$(Cow::Borrowed($name)),*]
+    };
+}
+
+// A table of skeletons and their patterns, borrowed from the bundled data.
+macro_rules! skeletons {
+    ($(($skeleton:expr, $pattern:expr)),* $(,)?) => {
+        Cow::Borrowed(&[$((Cow::Borrowed($skeleton), Cow::Borrowed($pattern))),*])
+    };
+}
+
+#[cfg(feature = "locale-data")]
+pub(super) static ALL: &[&DateTimeData] = &[&EN, &DE, &FR, &SV];
+
+#[cfg(not(feature = "locale-data"))]
+pub(super) static ALL: &[&DateTimeData] = &[&EN];
+
+static EN: DateTimeData = DateTimeData {
+    language: Cow::Borrowed("en"),
+    months: names![
+        "January", "February", "March", "April", "May", "June", "July", "August",
+        "September", "October", "November", "December",
+    ],
+    months_abbr: names![
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+    weekdays: names![
+        "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+    ],
+    weekdays_abbr: names!["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+    am_pm: names!["AM", "PM"],
+    date_patterns: names!["M/d/yy", "MMM d, y", "MMMM d, y", "EEEE, MMMM d, y"],
+    time_patterns: names!["h:mm a", "h:mm:ss a", "h:mm:ss a z", "h:mm:ss a zzzz"],
+    gmt_format: Cow::Borrowed("GMT{0}"),
+    gmt_zero: Cow::Borrowed("GMT"),
+    skeletons: skeletons![
+        ("d", "d"),
+        ("Ed", "d E"),
+        ("hm", "h:mm a"),
+        ("Hm", "HH:mm"),
+        ("hms", "h:mm:ss a"),
+        ("Hms", "HH:mm:ss"),
+        ("Md", "M/d"),
+        ("MEd", "E, M/d"),
+        ("MMM", "LLL"),
+        ("MMMd", "MMM d"),
+        ("MMMEd", "E, MMM d"),
+        ("MMMMd", "MMMM d"),
+        ("y", "y"),
+        ("yM", "M/y"),
+        ("yMd", "M/d/y"),
+        ("yMEd", "E, M/d/y"),
+        ("yMMM", "MMM y"),
+        ("yMMMd", "MMM d, y"),
+        ("yMMMEd", "E, MMM d, y"),
+        ("yMMMM", "MMMM y"),
+        ("yMMMMd", "MMMM d, y"),
+    ],
+};
+
+#[cfg(feature = "locale-data")]
+static DE: DateTimeData = DateTimeData {
+    language: Cow::Borrowed("de"),
+    months: names![
+        "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August",
+        "September", "Oktober", "November", "Dezember",
+    ],
+    months_abbr: names![
+        "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.",
+        "Nov.", "Dez.",
+    ],
+    weekdays: names![
+        "Sonntag", "Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag",
+    ],
+    weekdays_abbr: names!["So.", "Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa."],
+    am_pm: names!["AM", "PM"],
+    date_patterns: names!["dd.MM.yy", "dd.MM.y", "d. MMMM y", "EEEE, d. MMMM y"],
+    time_patterns: names!["HH:mm", "HH:mm:ss", "HH:mm:ss z", "HH:mm:ss zzzz"],
+    gmt_format: Cow::Borrowed("GMT{0}"),
+    gmt_zero: Cow::Borrowed("GMT"),
+    skeletons: skeletons![
+        ("d", "d"),
+        ("Ed", "E, d."),
+        ("hm", "h:mm a"),
+        ("Hm", "HH:mm"),
+        ("hms", "h:mm:ss a"),
+        ("Hms", "HH:mm:ss"),
+        ("Md", "d.M."),
+        ("MEd", "E, d.M."),
+        ("MMM", "LLL"),
+        ("MMMd", "d. MMM"),
+        ("MMMEd", "E, d. MMM"),
+        ("MMMMd", "d. MMMM"),
+        ("y", "y"),
+        ("yM", "M/y"),
+        ("yMd", "d.M.y"),
+        ("yMEd", "E, d.M.y"),
+        ("yMMM", "MMM y"),
+        ("yMMMd", "d. MMM y"),
+        ("yMMMEd", "E, d. MMM y"),
+        ("yMMMM", "MMMM y"),
+        ("yMMMMd", "d. MMMM y"),
+    ],
+};
+
+#[cfg(feature = "locale-data")]
+static FR: DateTimeData = DateTimeData {
+    language: Cow::Borrowed("fr"),
+    months: names![
+        "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août",
+        "septembre", "octobre", "novembre", "décembre",
+    ],
+    months_abbr: names![
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+        "nov.", "déc.",
+    ],
+    weekdays: names![
+        "dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi",
+    ],
+    weekdays_abbr: names!["dim.", "lun.", "mar.", "mer.", "jeu.", "ven.", "sam."],
+    am_pm: names!["AM", "PM"],
+    date_patterns: names!["dd/MM/y", "d MMM y", "d MMMM y", "EEEE d MMMM y"],
+    time_patterns: names!["HH:mm", "HH:mm:ss", "HH:mm:ss z", "HH:mm:ss zzzz"],
+    gmt_format: Cow::Borrowed("UTC{0}"),
+    gmt_zero: Cow::Borrowed("UTC"),
+    skeletons: skeletons![
+        ("d", "d"),
+        ("Ed", "E d"),
+        ("hm", "h:mm a"),
+        ("Hm", "HH:mm"),
+        ("hms", "h:mm:ss a"),
+        ("Hms", "HH:mm:ss"),
+        ("Md", "dd/MM"),
+        ("MEd", "E dd/MM"),
+        ("MMM", "LLL"),
+        ("MMMd", "d MMM"),
+        ("MMMEd", "E d MMM"),
+        ("MMMMd", "d MMMM"),
+        ("y", "y"),
+        ("yM", "MM/y"),
+        ("yMd", "dd/MM/y"),
+        ("yMEd", "E dd/MM/y"),
+        ("yMMM", "MMM y"),
+        ("yMMMd", "d MMM y"),
+        ("yMMMEd", "E d MMM y"),
+        ("yMMMM", "MMMM y"),
+        ("yMMMMd", "d MMMM y"),
+    ],
+};
+
+#[cfg(feature = "locale-data")]
+static SV: DateTimeData = DateTimeData {
+    language: Cow::Borrowed("sv"),
+    months: names![
+        "januari", "februari", "mars", "april", "maj", "juni", "juli", "augusti",
+        "september", "oktober", "november", "december",
+    ],
+    months_abbr: names![
+        "jan.", "feb.", "mars", "apr.", "maj", "juni", "juli", "aug.", "sep.", "okt.",
+        "nov.", "dec.",
+    ],
+    weekdays: names![
+        "söndag", "måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag",
+    ],
+    weekdays_abbr: names!["sön", "mån", "tis", "ons", "tors", "fre", "lör"],
+    am_pm: names!["fm", "em"],
+    date_patterns: names!["y-MM-dd", "d MMM y", "d MMMM y", "EEEE d MMMM y"],
+    time_patterns: names!["HH:mm", "HH:mm:ss", "HH:mm:ss z", "HH:mm:ss zzzz"],
+    gmt_format: Cow::Borrowed("GMT{0}"),
+    gmt_zero: Cow::Borrowed("GMT"),
+    skeletons: skeletons![
+        ("d", "d"),
+        ("Ed", "E d"),
+        ("hm", "h:mm a"),
+        ("Hm", "HH:mm"),
+        ("hms", "h:mm:ss a"),
+        ("Hms", "HH:mm:ss"),
+        ("Md", "d/M"),
+        ("MEd", "E d/M"),
+        ("MMM", "LLL"),
+        ("MMMd", "d MMM"),
+        ("MMMEd", "E d MMM"),
+        ("MMMMd", "d MMMM"),
+        ("y", "y"),
+        ("yM", "y-MM"),
+        ("yMd", "y-MM-dd"),
+        ("yMEd", "E, y-MM-dd"),
+        ("yMMM", "MMM y"),
+        ("yMMMd", "d MMM y"),
+        ("yMMMEd", "E d MMM y"),
+        ("yMMMM", "MMMM y"),
+        ("yMMMMd", "d MMMM y"),
+    ],
+};
@@ -0,0 +1,540 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Date and Time Support
+//!
+//! This module provides the [`DateTime`] value used with the ICU
+//! `date` and `time` formats along with the locale aware formatting
+//! of those values.
+//!
+//! Dates are formatted using [CLDR date format patterns], either
+//! selected by a fixed style (`short`, `medium`, `long` or `full`)
+//! or derived from a [skeleton] such as `::yMMMd`.
+//!
+//! Dates may be displayed in calendars other than the Gregorian
+//! calendar by implementing the [`Calendar`] trait.
+//!
+//! Locale data for English is always available. Data for additional
+//! locales is included when the `locale-data` feature is enabled
+//! (which it is by default).
+//!
+//! [`DateTime`]: struct.DateTime.html
+//! [`Calendar`]: trait.Calendar.html
+//! [CLDR date format patterns]: http://unicode.org/reports/tr35/tr35-dates.html#Date_Format_Patterns
+//! [skeleton]: http://unicode.org/reports/tr35/tr35-dates.html#availableFormats_appendItems
+
+use std::fmt;
+
+use language_tags::LanguageTag;
+
+mod calendar;
+mod data;
+mod skeleton;
+mod zone;
+
+pub use self::calendar::{
+    calendar_for_language_tag, Buddhist, Calendar, CalendarDate, Gregorian, IslamicCivil, Japanese,
+};
+pub(crate) use self::calendar::shared_calendar_for_language_tag;
+pub use self::data::DateTimeData;
+pub use self::skeleton::Skeleton;
+pub use self::zone::TimeZone;
+
+/// A civil date and time, optionally in a [`TimeZone`].
+///
+/// ```
+/// use message_format::DateTime;
+///
+/// let dt = DateTime::new(2024, 10, 3).with_time(14, 5, 0);
+/// assert_eq!(dt, DateTime::from_timestamp(1727964300));
+/// ```
+///
+/// [`TimeZone`]: datetime/struct.TimeZone.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    /// The year, using astronomical year numbering.
+    pub year: i32,
+    /// The month, from `1` to `12`.
+    pub month: u8,
+    /// The day of the month, starting from `1`.
+    pub day: u8,
+    /// The hour, from `0` to `23`.
+    pub hour: u8,
+    /// The minute, from `0` to `59`.
+    pub minute: u8,
+    /// The second, from `0` to `59`.
+    pub second: u8,
+    /// The zone that this is the local time of. This is `None`
+    /// for a floating time, which is formatted as though it were UTC.
+    pub zone: Option<TimeZone>,
+}
+
+impl DateTime {
+    /// Construct a `DateTime` at midnight of the given date.
+    pub fn new(year: i32, month: u8, day: u8) -> Self {
+        DateTime {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            zone: None,
+        }
+    }
+
+    /// Set the time of day.
+    pub fn with_time(self, hour: u8, minute: u8, second: u8) -> Self {
+        DateTime {
+            hour,
+            minute,
+            second,
+            ..self
+        }
+    }
+
+    /// Construct a `DateTime` from the number of seconds since the
+    /// Unix epoch, in UTC.
+    pub fn from_timestamp(timestamp: i64) -> Self {
+        let days = timestamp.div_euclid(86_400);
+        let seconds = timestamp.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        DateTime::new(year, month, day).with_time(
+            (seconds / 3600) as u8,
+            (seconds / 60 % 60) as u8,
+            (seconds % 60) as u8,
+        )
+    }
+
+    /// Construct the local `DateTime` in `zone` of an instant given
+    /// as the number of seconds since the Unix epoch.
+    ///
+    /// ```
+    /// use message_format::DateTime;
+    /// use message_format::datetime::TimeZone;
+    ///
+    /// let dt = DateTime::from_instant(1727964300, TimeZone::fixed(7200));
+    /// assert_eq!((dt.hour, dt.minute), (16, 5));
+    /// ```
+    pub fn from_instant(timestamp: i64, zone: TimeZone) -> Self {
+        DateTime {
+            zone: Some(zone),
+            ..DateTime::from_timestamp(timestamp + i64::from(zone.offset_seconds))
+        }
+    }
+
+    /// Set the zone without changing the local time.
+    pub fn with_zone(self, zone: TimeZone) -> Self {
+        DateTime {
+            zone: Some(zone),
+            ..self
+        }
+    }
+
+    /// The number of seconds since the Unix epoch of this time.
+    /// A floating time is treated as UTC.
+    pub fn timestamp(&self) -> i64 {
+        let offset = self.zone.map_or(0, |zone| i64::from(zone.offset_seconds));
+        self.days_since_epoch() * 86_400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second)
+            - offset
+    }
+
+    /// The number of days since the Unix epoch.
+    pub fn days_since_epoch(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day)
+    }
+
+    /// The day of the week, with `0` being Sunday.
+    pub fn weekday(&self) -> u8 {
+        (self.days_since_epoch() + 4).rem_euclid(7) as u8
+    }
+
+    // Whether every field is within its range, and the day exists in
+    // the month.
+    fn is_valid(&self) -> bool {
+        let date = (self.year, self.month, self.day);
+        (1..=12).contains(&self.month)
+            && civil_from_days(self.days_since_epoch()) == date
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// Format as an ISO 8601 date and time.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        match self.zone {
+            Some(zone) if zone.offset_seconds != 0 => {
+                let minutes = zone.offset_seconds.abs() / 60;
+                let sign = if zone.offset_seconds < 0 { '-' } else { '+' };
+                write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+            }
+            Some(_) => f.write_str("Z"),
+            None => Ok(()),
+        }
+    }
+}
+
+// These conversions are from Howard Hinnant's `chrono`-compatible
+// date algorithms: http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    let y = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as i32, month, day)
+}
+
+/// Whether a date, a time or both are being formatted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateTimeKind {
+    /// Format the date portion of the value.
+    Date,
+    /// Format the time portion of the value.
+    Time,
+}
+
+/// How a date or time should be formatted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DateTimeStyle {
+    /// A short, typically numeric format.
+    Short,
+    /// A medium length format. This is the default.
+    Medium,
+    /// A long format.
+    Long,
+    /// The most complete format.
+    Full,
+    /// A [`Skeleton`] which is mapped to the best pattern for the locale.
+    ///
+    /// [`Skeleton`]: struct.Skeleton.html
+    Skeleton(Skeleton),
+    /// A CLDR date format pattern, such as `EEEE dd MMM`, used as is
+    /// whatever the locale.
+    Pattern(String),
+}
+
+// The letters of the fields `format_field` can write, out of those ICU
+// allows in a date format pattern.
+const PATTERN_FIELDS: &str = "GyMLdEcahHkKmszvOZXx";
+
+/// Whether `pattern` is a valid date format pattern, using only the
+/// field letters that can be formatted outside of quoted text.
+pub(crate) fn is_valid_pattern(pattern: &str) -> bool {
+    let mut quoted = false;
+    pattern.chars().all(|c| {
+        if c == '\'' {
+            quoted = !quoted;
+        }
+        quoted || !c.is_ascii_alphabetic() || PATTERN_FIELDS.contains(c)
+    })
+}
+
+impl DateTimeStyle {
+    /// Parse a style as it appears in a message, such as `short`
+    /// or `::yMMMd`.
+    pub fn parse(style: &str) -> Option<Self> {
+        match style {
+            "short" => Some(DateTimeStyle::Short),
+            "medium" => Some(DateTimeStyle::Medium),
+            "long" => Some(DateTimeStyle::Long),
+            "full" => Some(DateTimeStyle::Full),
+            _ if style.starts_with("::") => Skeleton::parse(&style[2..]).map(DateTimeStyle::Skeleton),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DateTimeStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DateTimeStyle::Short => f.write_str("short"),
+            DateTimeStyle::Medium => f.write_str("medium"),
+            DateTimeStyle::Long => f.write_str("long"),
+            DateTimeStyle::Full => f.write_str("full"),
+            DateTimeStyle::Skeleton(ref skeleton) => write!(f, "::{}", skeleton),
+            DateTimeStyle::Pattern(ref pattern) => f.write_str(pattern),
+        }
+    }
+}
+
+/// Format `value` for the locale `language_tag` using `calendar`.
+pub fn format_datetime(
+    language_tag: &LanguageTag,
+    calendar: &dyn Calendar,
+    value: &DateTime,
+    kind: DateTimeKind,
+    style: &DateTimeStyle,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let data = DateTimeData::for_language_tag(language_tag);
+    format_datetime_with_data(data, calendar, value, kind, style, stream)
+}
+
+/// Like [`format_datetime`], using the locale `data` given, such as
+/// data from a [`DataProvider`].
+///
+/// [`format_datetime`]: fn.format_datetime.html
+/// [`DataProvider`]: ../provider/trait.DataProvider.html
+pub fn format_datetime_with_data(
+    data: &DateTimeData,
+    calendar: &dyn Calendar,
+    value: &DateTime,
+    kind: DateTimeKind,
+    style: &DateTimeStyle,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let index = match *style {
+        DateTimeStyle::Short => 0,
+        DateTimeStyle::Medium => 1,
+        DateTimeStyle::Long => 2,
+        DateTimeStyle::Full => 3,
+        DateTimeStyle::Skeleton(ref skeleton) => {
+            let pattern = skeleton.best_pattern(data);
+            return format_pattern(data, calendar, value, &pattern, stream);
+        }
+        DateTimeStyle::Pattern(ref pattern) => return format_pattern(data, calendar, value, pattern, stream),
+    };
+    let pattern = match kind {
+        DateTimeKind::Date => &data.date_patterns[index],
+        DateTimeKind::Time => &data.time_patterns[index],
+    };
+    format_pattern(data, calendar, value, pattern, stream)
+}
+
+/// Format `value` using a CLDR date format pattern.
+///
+/// If the calendar [shows eras](trait.Calendar.html#method.shows_era)
+/// and the pattern has a year but no era, the era follows the year.
+/// A `value` with a field out of range, such as month `0` or
+/// February 30, fails with `fmt::Error`.
+///
+/// ```
+/// use message_format::DateTime;
+/// use message_format::datetime::{format_pattern, DateTimeData, Gregorian, Japanese};
+///
+/// let data = DateTimeData::for_language_tag(&Default::default());
+/// let date = DateTime::new(2024, 10, 3);
+/// let mut s = String::new();
+/// format_pattern(data, &Gregorian, &date, "EEEE, MMMM d, y", &mut s).unwrap();
+/// assert_eq!(s, "Thursday, October 3, 2024");
+///
+/// let mut s = String::new();
+/// format_pattern(data, &Japanese, &date, "MMM d, y", &mut s).unwrap();
+/// assert_eq!(s, "Oct 3, 6 Reiwa");
+/// ```
+pub fn format_pattern(
+    data: &DateTimeData,
+    calendar: &dyn Calendar,
+    value: &DateTime,
+    pattern: &str,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    if !value.is_valid() {
+        return Err(fmt::Error {});
+    }
+    let date = calendar.convert(value);
+    let add_era = calendar.shows_era() && pattern.contains('y') && !pattern.contains('G');
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            if chars.peek() == Some(&'\'') {
+                chars.next();
+                stream.write_char('\'')?;
+                continue;
+            }
+            // Within quoted text, two apostrophes stand for one.
+            while let Some(c) = chars.next() {
+                if c == '\'' {
+                    if chars.peek() != Some(&'\'') {
+                        break;
+                    }
+                    chars.next();
+                }
+                stream.write_char(c)?;
+            }
+        } else if c.is_ascii_alphabetic() {
+            let mut count = 1;
+            while chars.peek() == Some(&c) {
+                chars.next();
+                count += 1;
+            }
+            format_field(data, calendar, &date, value, c, count, stream)?;
+            if c == 'y' && add_era {
+                write!(stream, " {}", date.era)?;
+            }
+        } else {
+            stream.write_char(c)?;
+        }
+    }
+    Ok(())
+}
+
+fn format_field(
+    data: &DateTimeData,
+    calendar: &dyn Calendar,
+    date: &CalendarDate,
+    value: &DateTime,
+    field: char,
+    count: usize,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    match field {
+        'G' => stream.write_str(date.era),
+        'y' if count == 2 => write!(stream, "{:02}", date.year.rem_euclid(100)),
+        'y' => write!(stream, "{:01$}", date.year, count),
+        'M' | 'L' => match count {
+            1 | 2 => write!(stream, "{:01$}", date.month, count),
+            _ => {
+                let names = if count == 3 { &data.months_abbr } else { &data.months };
+                let fallback = usize::from(date.month).checked_sub(1).and_then(|index| names.get(index));
+                match calendar.month_name(date.month, count == 3) {
+                    Some(name) => stream.write_str(name),
+                    None => stream.write_str(fallback.ok_or(fmt::Error {})?),
+                }
+            }
+        },
+        'd' => write!(stream, "{:01$}", date.day, count),
+        'E' | 'c' if count >= 4 => stream.write_str(&data.weekdays[usize::from(value.weekday())]),
+        'E' | 'c' => stream.write_str(&data.weekdays_abbr[usize::from(value.weekday())]),
+        'a' => stream.write_str(&data.am_pm[usize::from(value.hour >= 12)]),
+        'h' => {
+            let hour = match value.hour % 12 {
+                0 => 12,
+                h => h,
+            };
+            write!(stream, "{:01$}", hour, count)
+        }
+        'H' => write!(stream, "{:01$}", value.hour, count),
+        'k' => {
+            let hour = match value.hour {
+                0 => 24,
+                h => h,
+            };
+            write!(stream, "{:01$}", hour, count)
+        }
+        'K' => write!(stream, "{:01$}", value.hour % 12, count),
+        'm' => write!(stream, "{:01$}", value.minute, count),
+        's' => write!(stream, "{:01$}", value.second, count),
+        'z' | 'v' | 'O' | 'Z' | 'X' | 'x' => value
+            .zone
+            .unwrap_or(TimeZone::UTC)
+            .format_field(data, field, count, stream),
+        _ => Err(fmt::Error {}),
+    }
+}
+
+// The bundled tables, written out so that `data_version` can check
+// that a change to them comes with a new data revision.
+#[cfg(all(test, feature = "locale-data"))]
+pub(crate) fn bundled_tables() -> String {
+    format!("{:?}{:?}", data::ALL, calendar::JAPANESE_ERAS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_convert() {
+        assert_eq!(DateTime::from_timestamp(0), DateTime::new(1970, 1, 1));
+        assert_eq!(
+            DateTime::from_timestamp(951_782_400 - 1),
+            DateTime::new(2000, 2, 28).with_time(23, 59, 59)
+        );
+        assert_eq!(DateTime::from_timestamp(-86_400), DateTime::new(1969, 12, 31));
+        assert_eq!(DateTime::new(2024, 10, 3).weekday(), 4);
+    }
+
+    #[test]
+    fn styles_format() {
+        let en = Default::default();
+        let dt = DateTime::new(2024, 10, 3).with_time(14, 5, 9);
+        let format = |kind, style| {
+            let mut s = String::new();
+            format_datetime(&en, &Gregorian, &dt, kind, &style, &mut s).unwrap();
+            s
+        };
+        assert_eq!(format(DateTimeKind::Date, DateTimeStyle::Short), "10/3/24");
+        assert_eq!(format(DateTimeKind::Date, DateTimeStyle::Medium), "Oct 3, 2024");
+        assert_eq!(format(DateTimeKind::Date, DateTimeStyle::Full), "Thursday, October 3, 2024");
+        assert_eq!(format(DateTimeKind::Time, DateTimeStyle::Short), "2:05 PM");
+        assert_eq!(format(DateTimeKind::Time, DateTimeStyle::Medium), "2:05:09 PM");
+        assert_eq!(format(DateTimeKind::Time, DateTimeStyle::Long), "2:05:09 PM GMT");
+    }
+
+    #[test]
+    fn calendars_format() {
+        let en = Default::default();
+        let dt = DateTime::new(2024, 10, 3);
+        let format = |calendar: &dyn Calendar| {
+            let mut s = String::new();
+            format_datetime(&en, calendar, &dt, DateTimeKind::Date, &DateTimeStyle::Long, &mut s)
+                .unwrap();
+            s
+        };
+        assert_eq!(format(&Buddhist), "October 3, 2567 BE");
+        assert_eq!(format(&IslamicCivil), "Rabiʻ I 29, 1446 AH");
+    }
+
+    #[test]
+    fn pattern_fields_format() {
+        let en = Default::default();
+        let format = |dt: &DateTime, pattern: &str| {
+            let mut s = String::new();
+            let style = DateTimeStyle::Pattern(pattern.to_string());
+            format_datetime(&en, &Gregorian, dt, DateTimeKind::Time, &style, &mut s).unwrap();
+            s
+        };
+        let midnight = DateTime::new(2024, 10, 3);
+        assert_eq!(format(&midnight, "H h k K"), "0 12 24 0");
+        assert_eq!(format(&midnight.with_time(13, 0, 0), "HH hh kk KK"), "13 01 13 01");
+        for pattern in &["GGGG yy MMMM LLL d EEEE ccc a", "m:ss zzzz vvvv O Z X x"] {
+            assert!(is_valid_pattern(pattern));
+            format(&midnight, pattern);
+        }
+        assert!(!is_valid_pattern("qqq ww"));
+        assert!(is_valid_pattern("'qqq ww'"));
+    }
+
+    #[test]
+    fn out_of_range_fields_fail() {
+        let en = Default::default();
+        let format = |dt: DateTime| {
+            let mut s = String::new();
+            format_datetime(&en, &Gregorian, &dt, DateTimeKind::Date, &DateTimeStyle::Full, &mut s).map(|_| s)
+        };
+        assert!(format(DateTime::new(2024, 0, 3)).is_err());
+        assert!(format(DateTime::new(2024, 13, 3)).is_err());
+        assert!(format(DateTime::new(2023, 2, 29)).is_err());
+        assert!(format(DateTime::new(2024, 2, 29).with_time(24, 0, 0)).is_err());
+        assert_eq!(format(DateTime::new(2024, 2, 29)).unwrap(), "Thursday, February 29, 2024");
+    }
+}
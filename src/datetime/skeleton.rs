@@ -0,0 +1,158 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use super::DateTimeData;
+
+/// The fields that may appear in a skeleton, in canonical order.
+const FIELDS: &str = "GyMLdEcahHmsz";
+
+/// A date time skeleton, such as `yMMMd`.
+///
+/// A skeleton describes which fields should be displayed and how
+/// wide they should be, but not their order or the punctuation
+/// between them. That is determined by the locale.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skeleton {
+    fields: Vec<(char, usize)>,
+}
+
+impl Skeleton {
+    /// Parse a skeleton. Returns `None` if it contains unsupported
+    /// or repeated fields.
+    ///
+    /// ```
+    /// use message_format::datetime::Skeleton;
+    ///
+    /// assert!(Skeleton::parse("yMMMd").is_some());
+    /// assert!(Skeleton::parse("yMMMdM").is_none());
+    /// ```
+    pub fn parse(skeleton: &str) -> Option<Self> {
+        let mut fields: Vec<(char, usize)> = vec![];
+        for c in skeleton.chars() {
+            if !FIELDS.contains(c) {
+                return None;
+            }
+            if let Some(&mut (last, ref mut count)) = fields.last_mut() {
+                if last == c {
+                    *count += 1;
+                    continue;
+                }
+            }
+            if fields.iter().any(|&(f, _)| f == c) {
+                return None;
+            }
+            fields.push((c, 1));
+        }
+        if fields.is_empty() {
+            None
+        } else {
+            Some(Skeleton { fields })
+        }
+    }
+
+    /// Find the best pattern in the locale `data` for this skeleton.
+    ///
+    /// An exact match is preferred. Otherwise, a pattern for the same
+    /// set of fields is used, with the field widths adjusted to those
+    /// requested. If the locale has no such pattern, the fields are
+    /// output in canonical order separated by spaces.
+    pub fn best_pattern(&self, data: &DateTimeData) -> String {
+        let requested = self.to_string();
+        if let Some((_, pattern)) = data.skeletons.iter().find(|(s, _)| *s == requested) {
+            return pattern.to_string();
+        }
+        let candidate = data.skeletons.iter().find(|(s, _)| {
+            Skeleton::parse(s).is_some_and(|s| s.same_fields(self))
+        });
+        if let Some((_, pattern)) = candidate {
+            self.adjust_widths(pattern)
+        } else {
+            let mut fields = self.fields.clone();
+            fields.sort_by_key(|&(c, _)| FIELDS.find(c));
+            fields
+                .iter()
+                .map(|&(c, count)| c.to_string().repeat(count))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+
+    fn same_fields(&self, other: &Skeleton) -> bool {
+        self.fields.len() == other.fields.len()
+            && self.fields.iter().all(|&(c, count)| {
+                other
+                    .fields
+                    .iter()
+                    .any(|&(o, other_count)| o == c && is_text(c, count) == is_text(o, other_count))
+            })
+    }
+
+    /// Replace the widths of the fields in `pattern` with the widths
+    /// requested by this skeleton. Numeric fields are only ever widened.
+    fn adjust_widths(&self, pattern: &str) -> String {
+        let mut output = String::new();
+        let mut chars = pattern.chars().peekable();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            if c == '\'' {
+                quoted = !quoted;
+            }
+            let field = self.fields.iter().find(|&&(f, _)| f == c);
+            match field {
+                Some(&(f, count)) if !quoted => {
+                    let mut existing = 1;
+                    while chars.peek() == Some(&f) {
+                        chars.next();
+                        existing += 1;
+                    }
+                    let width = if is_text(f, count) { count } else { count.max(existing) };
+                    output.push_str(&f.to_string().repeat(width));
+                }
+                _ => output.push(c),
+            }
+        }
+        output
+    }
+}
+
+/// Whether a field with this width is displayed as text rather
+/// than as a number.
+fn is_text(field: char, count: usize) -> bool {
+    match field {
+        'M' | 'L' => count >= 3,
+        'G' | 'E' | 'c' | 'a' | 'z' => true,
+        _ => false,
+    }
+}
+
+impl fmt::Display for Skeleton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(c, count) in &self.fields {
+            for _ in 0..count {
+                fmt::Write::write_char(f, c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Skeleton;
+    use datetime::DateTimeData;
+
+    #[test]
+    fn patterns_are_found() {
+        let en = DateTimeData::for_language_tag(&Default::default());
+        let pattern = |s| Skeleton::parse(s).unwrap().best_pattern(en);
+        assert_eq!(pattern("yMMMd"), "MMM d, y");
+        assert_eq!(pattern("yMMMMEEEEd"), "EEEE, MMMM d, y");
+        assert_eq!(pattern("dMMMy"), "MMM d, y");
+        assert_eq!(pattern("Hmmss"), "HH:mm:ss");
+    }
+}
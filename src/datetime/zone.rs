@@ -0,0 +1,143 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use super::DateTimeData;
+
+/// A time zone with a fixed offset from UTC.
+///
+/// Names are optional. When a zone has no name, it is displayed
+/// using the localized GMT format, such as `GMT+2`.
+///
+/// ```
+/// use message_format::datetime::TimeZone;
+///
+/// let pst = TimeZone::named(-8 * 3600, "PST", "Pacific Standard Time");
+/// assert_eq!(pst.offset_seconds, -28800);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeZone {
+    /// The offset from UTC, in seconds.
+    pub offset_seconds: i32,
+    /// The abbreviated name, such as `PST`.
+    pub short_name: Option<&'static str>,
+    /// The full name, such as `Pacific Standard Time`.
+    pub long_name: Option<&'static str>,
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time.
+    pub const UTC: TimeZone = TimeZone {
+        offset_seconds: 0,
+        short_name: None,
+        long_name: None,
+    };
+
+    /// Construct an unnamed zone with the given offset from UTC.
+    pub fn fixed(offset_seconds: i32) -> Self {
+        TimeZone {
+            offset_seconds,
+            short_name: None,
+            long_name: None,
+        }
+    }
+
+    /// Construct a named zone with the given offset from UTC.
+    pub fn named(offset_seconds: i32, short_name: &'static str, long_name: &'static str) -> Self {
+        TimeZone {
+            offset_seconds,
+            short_name: Some(short_name),
+            long_name: Some(long_name),
+        }
+    }
+
+    /// Write a zone field as described by CLDR, such as `z` or `OOOO`.
+    pub(crate) fn format_field(
+        &self,
+        data: &DateTimeData,
+        field: char,
+        count: usize,
+        stream: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        match field {
+            'z' if count < 4 && self.short_name.is_some() => {
+                stream.write_str(self.short_name.unwrap_or_default())
+            }
+            'z' | 'v' if count >= 4 && self.long_name.is_some() => {
+                stream.write_str(self.long_name.unwrap_or_default())
+            }
+            'z' | 'v' | 'O' => self.format_gmt(data, count >= 4, stream),
+            'Z' if count >= 4 => self.format_gmt(data, true, stream),
+            'Z' => self.format_iso(false, false, stream),
+            'X' | 'x' => self.format_iso(field == 'X', count >= 3, stream),
+            _ => Err(fmt::Error {}),
+        }
+    }
+
+    /// The localized GMT format, such as `GMT-8` or `GMT-08:00`.
+    fn format_gmt(&self, data: &DateTimeData, long: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+        if self.offset_seconds == 0 {
+            return stream.write_str(&data.gmt_zero);
+        }
+        let (sign, hours, minutes) = self.split();
+        let offset = if long {
+            format!("{}{:02}:{:02}", sign, hours, minutes)
+        } else if minutes == 0 {
+            format!("{}{}", sign, hours)
+        } else {
+            format!("{}{}:{:02}", sign, hours, minutes)
+        };
+        stream.write_str(&data.gmt_format.replace("{0}", &offset))
+    }
+
+    /// The ISO 8601 format, such as `-0800`, `-08:00` or `Z`.
+    fn format_iso(&self, use_z: bool, colon: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+        if use_z && self.offset_seconds == 0 {
+            return stream.write_str("Z");
+        }
+        let (sign, hours, minutes) = self.split();
+        let separator = if colon { ":" } else { "" };
+        write!(stream, "{}{:02}{}{:02}", sign, hours, separator, minutes)
+    }
+
+    fn split(&self) -> (char, i32, i32) {
+        let sign = if self.offset_seconds < 0 { '-' } else { '+' };
+        let minutes = self.offset_seconds.abs() / 60;
+        (sign, minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimeZone;
+    use datetime::{format_pattern, DateTime, DateTimeData, Gregorian};
+
+    fn format(dt: &DateTime, pattern: &str) -> String {
+        let data = DateTimeData::for_language_tag(&Default::default());
+        let mut s = String::new();
+        format_pattern(data, &Gregorian, dt, pattern, &mut s).unwrap();
+        s
+    }
+
+    #[test]
+    fn zones_format() {
+        let pst = TimeZone::named(-8 * 3600, "PST", "Pacific Standard Time");
+        let dt = DateTime::from_instant(1_727_964_300, pst);
+        assert_eq!(format(&dt, "h:mm a z"), "6:05 AM PST");
+        assert_eq!(format(&dt, "h:mm a zzzz"), "6:05 AM Pacific Standard Time");
+        assert_eq!(format(&dt, "O"), "GMT-8");
+        assert_eq!(format(&dt, "OOOO"), "GMT-08:00");
+        assert_eq!(format(&dt, "Z"), "-0800");
+        assert_eq!(format(&dt, "XXX"), "-08:00");
+
+        let ist = DateTime::from_instant(0, TimeZone::fixed(5 * 3600 + 1800));
+        assert_eq!(format(&ist, "HH:mm z"), "05:30 GMT+5:30");
+
+        let utc = DateTime::from_timestamp(0);
+        assert_eq!(format(&utc, "z X"), "GMT Z");
+    }
+}
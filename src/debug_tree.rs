@@ -0,0 +1,205 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readable renderings of a message's AST, for snapshot tests.
+
+use std::fmt::Write;
+
+use datetime::DateTimeKind;
+use icu::ast;
+use {Message, MessagePart};
+
+/// A node of the AST, with a one line label.
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn leaf(label: String) -> Self {
+        Node { label, children: vec![] }
+    }
+
+    fn branch(selector: &str, message: &Message) -> Self {
+        Node {
+            label: selector.to_string(),
+            children: message.parts.iter().map(|part| Node::of(part.as_ref())).collect(),
+        }
+    }
+
+    fn of(part: &dyn MessagePart) -> Self {
+        let any = part.as_any();
+        if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+            Node::leaf(format!("literal {:?}", text.text))
+        } else if let Some(nested) = any.downcast_ref::<Message>() {
+            Node::branch("message", nested)
+        } else if any.is::<ast::PlaceholderFormat>() {
+            Node::leaf("placeholder".to_string())
+        } else if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+            Node::leaf(format!("argument {}", fmt.variable_name))
+        } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+            Node::leaf(format!("number {} {}", fmt.variable_name, fmt.style))
+        } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+            let kind = match fmt.kind {
+                DateTimeKind::Date => "date",
+                DateTimeKind::Time => "time",
+            };
+            Node::leaf(format!("{} {} {}", kind, fmt.variable_name, fmt.style))
+        } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+            Node::leaf(format!("unit {} {} {}", fmt.variable_name, fmt.unit, fmt.width))
+        } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+            Node::leaf(format!("text {} {}", fmt.variable_name, fmt.transform))
+        } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+            match fmt.style {
+                Some(ref style) => Node::leaf(format!("unknown {} {} {:?}", fmt.variable_name, fmt.type_name, style)),
+                None => Node::leaf(format!("unknown {} {}", fmt.variable_name, fmt.type_name)),
+            }
+        } else if let Some(reference) = any.downcast_ref::<ast::MessageReference>() {
+            Node::leaf(format!("reference {}", reference.key))
+        } else if let Some(error) = any.downcast_ref::<ast::ErrorNode>() {
+            Node::leaf(format!("error {:?}", error.text))
+        } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+            let mut children: Vec<Node> = fmt
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    let mut keys = mapping.value.clone();
+                    for alias in &mapping.aliases {
+                        keys.push('|');
+                        keys.push_str(alias);
+                    }
+                    Node::branch(&keys, &mapping.message)
+                })
+                .collect();
+            children.push(Node::branch("other", &fmt.default));
+            Node {
+                label: format!("select {}", fmt.variable_name),
+                children,
+            }
+        } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+            let mut label = format!("plural {}", fmt.variable_name);
+            if fmt.offset != 0 {
+                let _ = write!(label, " offset {}", fmt.offset);
+            }
+            let mut children: Vec<Node> = fmt
+                .literals
+                .iter()
+                .map(|mapping| Node::branch(&format!("={}", mapping.value), &mapping.message))
+                .collect();
+            let categories = [
+                ("zero", &fmt.zero),
+                ("one", &fmt.one),
+                ("two", &fmt.two),
+                ("few", &fmt.few),
+                ("many", &fmt.many),
+            ];
+            for &(keyword, message) in &categories {
+                if let Some(ref message) = *message {
+                    children.push(Node::branch(keyword, message));
+                }
+            }
+            children.push(Node::branch("other", &fmt.other));
+            Node { label, children }
+        } else {
+            let children = part.children();
+            if children.is_empty() {
+                Node::leaf(format!("{:?}", part))
+            } else {
+                Node {
+                    label: "part".to_string(),
+                    children: children.into_iter().map(|message| Node::branch("message", message)).collect(),
+                }
+            }
+        }
+    }
+
+    // Sort the keyed branches of every select, whose order doesn't
+    // affect formatting.
+    fn sort_selects(&mut self) {
+        if self.label.starts_with("select ") {
+            let other = self.children.len() - 1;
+            self.children[..other].sort_by(|a, b| a.label.cmp(&b.label));
+        }
+        for child in &mut self.children {
+            child.sort_selects();
+        }
+    }
+
+    fn write_tree(&self, depth: usize, output: &mut String) {
+        for _ in 0..depth {
+            output.push_str("  ");
+        }
+        output.push_str(&self.label);
+        output.push('\n');
+        for child in &self.children {
+            child.write_tree(depth + 1, output);
+        }
+    }
+
+    fn write_sexpr(&self, output: &mut String) {
+        output.push('(');
+        output.push_str(&self.label);
+        for child in &self.children {
+            output.push(' ');
+            child.write_sexpr(output);
+        }
+        output.push(')');
+    }
+}
+
+pub(crate) fn tree(message: &Message) -> String {
+    let mut output = String::new();
+    Node::branch("message", message).write_tree(0, &mut output);
+    output
+}
+
+pub(crate) fn sexpr(message: &Message) -> String {
+    let mut output = String::new();
+    Node::branch("message", message).write_sexpr(&mut output);
+    output
+}
+
+/// The s-expression of `message` with the branches of each select in
+/// a fixed order, so that equivalent messages render the same.
+pub(crate) fn canonical(message: &Message) -> String {
+    let mut node = Node::branch("message", message);
+    node.sort_selects();
+    let mut output = String::new();
+    node.write_sexpr(&mut output);
+    output
+}
+
+/// The canonical rendering of one part, as in [`canonical`].
+pub(crate) fn part_canonical(part: &dyn MessagePart) -> String {
+    let mut node = Node::of(part);
+    node.sort_selects();
+    let mut output = String::new();
+    node.write_sexpr(&mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::{self, parse_with, ParseOptions};
+
+    #[test]
+    fn every_kind_of_part() {
+        let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+        let m = parse_with(
+            "{d, date, short}{t, time}{k, unit, meter}{s, text, upper}{@common.ok}{x, select, a|b {} other {}}",
+            options,
+        )
+        .unwrap();
+        assert_eq!(
+            m.debug_tree(),
+            "message\n  date d short\n  time t medium\n  unit k meter short\n  text s upper\n  \
+             reference common.ok\n  select x\n    a|b\n    other\n"
+        );
+
+        let (m, _) = icu::parse_recovering("{n, plural, =1 {#} other {}} {oops");
+        assert_eq!(m.debug_sexpr(), r#"(message (plural n (=1 (placeholder)) (other)) (literal " ") (error "{oops"))"#);
+    }
+}
@@ -0,0 +1,119 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::str::FromStr;
+
+use plural::PluralOperands;
+
+/// A decimal number that keeps its visible fraction digits, including
+/// trailing zeros.
+///
+/// Plural rules treat `1.0` differently from `1` in many languages,
+/// so a number formatted with a fixed number of fraction digits should
+/// be passed as a `Decimal` rather than an `f64`. It is written exactly
+/// as given.
+///
+/// ```
+/// # #[macro_use] extern crate message_format;
+/// # fn main() {
+/// use message_format::{icu, Context, Decimal};
+///
+/// let ctx = Context::default();
+/// let m = icu::parse("{n, plural, one {# star} other {# stars}}").unwrap();
+/// assert_eq!(format_message!(ctx, &m, n => 1), "1 star");
+///
+/// let rating: Decimal = "1.0".parse().unwrap();
+/// assert_eq!(format_message!(ctx, &m, n => rating), "1.0 stars");
+/// assert_eq!(format_message!(ctx, &m, n => Decimal::from_f64(4.25, 1)), "4.2 stars");
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decimal {
+    text: String,
+    value: f64,
+    operands: PluralOperands,
+}
+
+/// The error when parsing a [`Decimal`] from a string that isn't a
+/// plain decimal number.
+///
+/// [`Decimal`]: struct.Decimal.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid decimal number")
+    }
+}
+
+impl ::std::error::Error for ParseDecimalError {}
+
+impl Decimal {
+    /// Round `value` to `fraction_digits` digits after the decimal
+    /// point, keeping any trailing zeros.
+    pub fn from_f64(value: f64, fraction_digits: usize) -> Self {
+        let text = format!("{:.*}", fraction_digits, value);
+        text.parse().unwrap_or_else(|_| Decimal {
+            // Infinite and NaN values have no digits to keep.
+            text,
+            value,
+            operands: PluralOperands::from(value),
+        })
+    }
+
+    /// The numeric value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// The number of visible fraction digits, including trailing zeros.
+    pub fn fraction_digits(&self) -> usize {
+        self.operands.v
+    }
+
+    /// The operands used by plural rules.
+    pub fn operands(&self) -> PluralOperands {
+        self.operands
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    /// Parse a number such as `-12.50`, with an optional sign and
+    /// fraction but no exponent or grouping.
+    fn from_str(s: &str) -> Result<Self, ParseDecimalError> {
+        let operands: PluralOperands = s.parse().map_err(|_| ParseDecimalError)?;
+        Ok(Decimal {
+            text: s.to_string(),
+            value: s.parse().map_err(|_| ParseDecimalError)?,
+            operands,
+        })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.text.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decimal;
+
+    #[test]
+    fn digits_are_kept() {
+        let d: Decimal = "-2.50".parse().unwrap();
+        assert_eq!((d.value(), d.fraction_digits(), d.to_string().as_str()), (-2.5, 2, "-2.50"));
+        assert_eq!(Decimal::from_f64(3.0, 2).to_string(), "3.00");
+        assert_eq!(Decimal::from_f64(3.0, 0).fraction_digits(), 0);
+        assert!("1e5".parse::<Decimal>().is_err());
+        assert!("+1".parse::<Decimal>().is_err());
+    }
+}
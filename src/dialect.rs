@@ -0,0 +1,408 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Syntaxes other than the ICU Message Format.
+//!
+//! Each [`Dialect`] parses its syntax into the same [`Message`] as
+//! [`icu::parse`], so strings written for another runtime can be
+//! formatted by this one while they are migrated:
+//!
+//! ```
+//! use message_format::dialect::{Dialect, JavaMessageFormat, Printf};
+//! use message_format::{arg, Context};
+//!
+//! let ctx = Context::default();
+//! let java = JavaMessageFormat.parse("{0} has {1,number,#.##} GB left").unwrap();
+//! let printf = Printf.parse("%s has %.1f GB left").unwrap();
+//!
+//! let args = arg("0", "Disk");
+//! let args = args.arg("1", 12.346);
+//! assert_eq!(ctx.format(&java, &args), "Disk has 12.35 GB left");
+//! assert_eq!(ctx.format(&printf, &args), "Disk has 12.3 GB left");
+//! ```
+//!
+//! Arguments are named by their position, from `0`, as in Java.
+//!
+//! [`Dialect`]: trait.Dialect.html
+//! [`Message`]: ../struct.Message.html
+//! [`icu::parse`]: ../icu/fn.parse.html
+
+use icu::ast::{PlainText, SimpleFormat};
+use icu::parse::{is_valid_argument_name, ParseError};
+use icu::{parse_with, parser, ParseOptions};
+use {Message, MessagePart};
+
+/// A syntax for messages.
+pub trait Dialect {
+    /// Parse `source` into a [`Message`].
+    ///
+    /// [`Message`]: ../struct.Message.html
+    fn parse(&self, source: &str) -> Result<Message, ParseError>;
+}
+
+/// The ICU Message Format, as parsed by [`icu::parse_with`].
+///
+/// [`icu::parse_with`]: ../icu/fn.parse_with.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Icu {
+    /// The options to parse with.
+    pub options: ParseOptions,
+}
+
+impl Dialect for Icu {
+    fn parse(&self, source: &str) -> Result<Message, ParseError> {
+        parse_with(source, self.options)
+    }
+}
+
+/// The syntax of `java.text.MessageFormat`, as in
+/// `{0,number,#.##}`.
+///
+/// Arguments may be formatted as a `number`, with the `integer` or
+/// `percent` style or a decimal pattern such as `#,##0.00`, or as a
+/// `date` or `time`, with a named style or a date pattern. The
+/// `choice` format and the `currency` style are not supported.
+///
+/// As in Java, an apostrophe always starts quoted text, and `''` is
+/// an apostrophe.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JavaMessageFormat;
+
+impl Dialect for JavaMessageFormat {
+    fn parse(&self, source: &str) -> Result<Message, ParseError> {
+        let mut parts: Vec<Box<dyn MessagePart>> = vec![];
+        let mut text = String::new();
+        let mut rest = source;
+        while let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+            match c {
+                '\'' if rest.starts_with('\'') => {
+                    text.push('\'');
+                    rest = &rest[1..];
+                }
+                '\'' => {
+                    // Quoted text runs to the next lone apostrophe.
+                    loop {
+                        match rest.find('\'') {
+                            None => {
+                                text.push_str(rest);
+                                rest = "";
+                                break;
+                            }
+                            Some(end) => {
+                                text.push_str(&rest[..end]);
+                                rest = &rest[end + 1..];
+                                if !rest.starts_with('\'') {
+                                    break;
+                                }
+                                text.push('\'');
+                                rest = &rest[1..];
+                            }
+                        }
+                    }
+                }
+                '{' => {
+                    let len = java_argument_len(rest).ok_or(ParseError::NotImplemented)?;
+                    parts.push(Box::new(PlainText::new(&text)));
+                    text.clear();
+                    parts.push(parse_argument(&java_argument(&rest[..len])?)?);
+                    rest = &rest[len + 1..];
+                }
+                _ => text.push(c),
+            }
+        }
+        parts.push(Box::new(PlainText::new(&text)));
+        Ok(Message::new(parts))
+    }
+}
+
+// The length of the argument at the start of `s`, after its opening
+// brace, up to its closing brace.
+fn java_argument_len(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            _ if quoted => (),
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+// Translate the inside of a Java argument to an ICU argument.
+fn java_argument(argument: &str) -> Result<String, ParseError> {
+    let mut fields = argument.splitn(3, ',').map(str::trim);
+    let index = fields.next().unwrap_or("");
+    if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ParseError::InvalidArgumentName(index.to_string()));
+    }
+    let argument = match (fields.next(), fields.next()) {
+        (None, _) => format!("{{{}}}", index),
+        (Some("number"), None) => format!("{{{}, number}}", index),
+        (Some("number"), Some(style)) if style == "integer" || style == "percent" => {
+            format!("{{{}, number, {}}}", index, style)
+        }
+        (Some("number"), Some(pattern)) => {
+            let skeleton = decimal_pattern_skeleton(pattern).ok_or(ParseError::NotImplemented)?;
+            format!("{{{}, number, ::{}}}", index, skeleton)
+        }
+        (Some(kind), None) if kind == "date" || kind == "time" => format!("{{{}, {}}}", index, kind),
+        (Some(kind), Some(style)) if kind == "date" || kind == "time" => {
+            format!("{{{}, {}, {}}}", index, kind, style)
+        }
+        _ => return Err(ParseError::NotImplemented),
+    };
+    Ok(argument)
+}
+
+// The number skeleton equivalent to a `java.text.DecimalFormat`
+// pattern, such as `.00` for `#,##0.00`. Grouping and the minimum
+// number of integer digits are left to the locale.
+fn decimal_pattern_skeleton(pattern: &str) -> Option<String> {
+    let (number, unit) = match pattern.chars().last() {
+        Some('%') => (&pattern[..pattern.len() - 1], Some("percent")),
+        Some('\u{2030}') => (&pattern[..pattern.len() - '\u{2030}'.len_utf8()], Some("permille")),
+        _ => (pattern, None),
+    };
+    let (integer, fraction) = match number.find('.') {
+        Some(dot) => (&number[..dot], Some(&number[dot + 1..])),
+        None => (number, None),
+    };
+    if integer.is_empty() || !integer.chars().all(|c| c == '#' || c == '0' || c == ',') {
+        return None;
+    }
+    let precision = match fraction {
+        Some(fraction) if !fraction.is_empty() => format!(".{}", fraction),
+        _ => "precision-integer".to_string(),
+    };
+    Some(match unit {
+        Some(unit) => format!("{} {}", unit, precision),
+        None => precision,
+    })
+}
+
+/// The `printf` syntax, as in `%s` or `%1$.2f`.
+///
+/// Conversions without a position take the next argument. `%s` and
+/// `%@` are formatted as is, `%d`, `%i` and `%u` as integers, and
+/// `%f` as a decimal with the precision given, or 6 fraction digits.
+/// Numbers are formatted for the context's locale, as the ICU formats
+/// would. Flags, widths and other conversions are not supported.
+///
+/// ```
+/// use message_format::dialect::{Dialect, Printf};
+/// use message_format::icu;
+///
+/// let m = Printf.parse("%2$s sent %1$d files (100%%)").unwrap();
+/// assert_eq!(icu::serialize(&m).unwrap(), "{1} sent {0, number, integer} files (100%)");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Printf;
+
+impl Dialect for Printf {
+    fn parse(&self, source: &str) -> Result<Message, ParseError> {
+        let mut parts: Vec<Box<dyn MessagePart>> = vec![];
+        let mut next = 0;
+        let mut pieces = source.split('%');
+        let mut text = pieces.next().unwrap_or("").to_string();
+        let mut escaped = false;
+        for piece in pieces {
+            if escaped {
+                escaped = false;
+                text.push_str(piece);
+                continue;
+            }
+            if piece.is_empty() {
+                // `%%`, or a `%` at the end.
+                text.push('%');
+                escaped = true;
+                continue;
+            }
+            let (argument, len) = printf_conversion(piece, &mut next).ok_or(ParseError::NotImplemented)?;
+            parts.push(Box::new(PlainText::new(&text)));
+            parts.push(parse_argument(&argument)?);
+            text = piece[len..].to_string();
+        }
+        if escaped {
+            // A lone `%` at the end.
+            return Err(ParseError::NotImplemented);
+        }
+        parts.push(Box::new(PlainText::new(&text)));
+        Ok(Message::new(parts))
+    }
+}
+
+// Translate the conversion at the start of `spec`, which follows a
+// `%`, to an ICU argument, returning it with the length of the
+// conversion.
+fn printf_conversion(spec: &str, next: &mut usize) -> Option<(String, usize)> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let mut len = digits(spec);
+    let index = if len > 0 && spec[len..].starts_with('$') {
+        let position: usize = spec[..len].parse().ok()?;
+        len += 1;
+        position.checked_sub(1)?
+    } else {
+        len = 0;
+        *next += 1;
+        *next - 1
+    };
+    let precision = if spec[len..].starts_with('.') {
+        let count = digits(&spec[len + 1..]);
+        let precision = spec[len + 1..len + 1 + count].parse().ok();
+        len += 1 + count;
+        Some(precision.unwrap_or(0))
+    } else {
+        None
+    };
+    let conversion = spec[len..].chars().next()?;
+    let argument = match (conversion, precision) {
+        ('s', None) | ('@', None) => format!("{{{}}}", index),
+        ('d', None) | ('i', None) | ('u', None) => format!("{{{}, number, integer}}", index),
+        ('f', Some(0)) => format!("{{{}, number, ::precision-integer}}", index),
+        ('f', precision) => {
+            let zeros = "0".repeat(precision.unwrap_or(6));
+            format!("{{{}, number, ::.{}}}", index, zeros)
+        }
+        _ => return None,
+    };
+    Some((argument, len + conversion.len_utf8()))
+}
+
+/// Messages whose arguments are written `{{name}}`, as in Mustache and
+/// Handlebars templates, or in the ICU Message Format.
+///
+/// A message using `{{name}}` is taken as a template, whose text is
+/// kept as is, including apostrophes. Any other message is parsed as
+/// an ICU message with `options`, so a catalog may hold both kinds.
+/// Mixing the two in one message is a [`ParseError::MixedSyntax`].
+/// `{{{name}}}`, which Handlebars doesn't escape, is the same as
+/// `{{name}}`.
+///
+/// ```
+/// use message_format::dialect::{Dialect, Mustache};
+/// use message_format::icu::parse::ParseError;
+/// use message_format::icu;
+///
+/// let m = Mustache::default().parse("'{{ name }}' has {{count}} files").unwrap();
+/// assert_eq!(icu::serialize(&m).unwrap(), "''{name}' has {count} files");
+///
+/// let m = Mustache::default().parse("{n, plural, other {{n} files}}").unwrap();
+/// assert_eq!(m.argument_names(), ["n"]);
+///
+/// let mixed = Mustache::default().parse("{{name}} has {n, number} files");
+/// assert_eq!(mixed.unwrap_err(), ParseError::MixedSyntax);
+/// ```
+///
+/// [`ParseError::MixedSyntax`]: ../icu/parse/enum.ParseError.html#variant.MixedSyntax
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Mustache {
+    /// The options to parse ICU messages with.
+    pub options: ParseOptions,
+}
+
+impl Dialect for Mustache {
+    fn parse(&self, source: &str) -> Result<Message, ParseError> {
+        let mut parts: Vec<Box<dyn MessagePart>> = vec![];
+        // Whether there are ICU arguments, which start with one brace.
+        let mut icu = false;
+        let mut depth = 0;
+        let mut text = 0;
+        let mut pos = 0;
+        while pos < source.len() {
+            let rest = &source[pos..];
+            if depth == 0 && rest.starts_with("{{") {
+                let (open, close) = if rest.starts_with("{{{") { (3, "}}}") } else { (2, "}}") };
+                let len = rest[open..].find(close).ok_or(ParseError::MixedSyntax)?;
+                let name = rest[open..open + len].trim();
+                if !is_valid_argument_name(name) {
+                    return Err(ParseError::InvalidArgumentName(name.to_string()));
+                }
+                parts.push(Box::new(PlainText::new(&source[text..pos])));
+                parts.push(Box::new(SimpleFormat::new(name)));
+                pos += open + len + close.len();
+                text = pos;
+                continue;
+            }
+            match rest.as_bytes()[0] {
+                b'{' => {
+                    icu = true;
+                    depth += 1;
+                }
+                b'}' if depth > 0 => depth -= 1,
+                _ => (),
+            }
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+        match (icu, parts.is_empty()) {
+            (_, true) => parse_with(source, self.options),
+            (true, false) => Err(ParseError::MixedSyntax),
+            (false, false) => {
+                parts.push(Box::new(PlainText::new(&source[text..])));
+                Ok(Message::new(parts))
+            }
+        }
+    }
+}
+
+// Parse a whole ICU argument.
+fn parse_argument(source: &str) -> Result<Box<dyn MessagePart>, ParseError> {
+    match parser::argument(source) {
+        Ok(("", part)) => Ok(part),
+        _ => Err(ParseError::NotImplemented),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dialect, JavaMessageFormat, Mustache, Printf};
+    use icu::parse::ParseError;
+    use icu::serialize;
+
+    fn java(source: &str) -> Result<String, ParseError> {
+        JavaMessageFormat.parse(source).map(|m| serialize(&m).unwrap())
+    }
+
+    fn printf(source: &str) -> Result<String, ParseError> {
+        Printf.parse(source).map(|m| serialize(&m).unwrap())
+    }
+
+    #[test]
+    fn java_syntax() {
+        assert_eq!(
+            java("It''s '{'{0}'}' on {1,date,yyyy-MM-dd} at {1,time,short}: {2,number,#,##0.0#%}").unwrap(),
+            "It's '{'{0}'}' on {1, date, yyyy-MM-dd} at {1, time, short}: {2, number, ::percent .0#}"
+        );
+        assert_eq!(java("{0,number,0} or {0, number , integer}").unwrap(), "{0, number, ::precision-integer} or {0, number, integer}");
+        assert_eq!(java("{name}"), Err(ParseError::InvalidArgumentName("name".into())));
+        assert_eq!(java("{0,choice,0#none|1#one}"), Err(ParseError::NotImplemented));
+        assert_eq!(java("{0"), Err(ParseError::NotImplemented));
+    }
+
+    #[test]
+    fn printf_syntax() {
+        assert_eq!(printf("%s: %d/%i {%.2f%%}").unwrap(), "{0}: {1, number, integer}/{2, number, integer} '{'{3, number, ::.00}%'}'");
+        assert!(printf("%f %.0f 100%").is_err());
+        assert_eq!(printf("%f %.0f").unwrap(), "{0, number, ::.000000} {1, number, ::precision-integer}");
+        assert_eq!(printf("%x"), Err(ParseError::NotImplemented));
+        assert_eq!(printf("%0$s"), Err(ParseError::NotImplemented));
+    }
+
+    #[test]
+    fn mustache_syntax() {
+        let mustache = |source: &str| Mustache::default().parse(source).map(|m| serialize(&m).unwrap());
+        assert_eq!(mustache("{{a}}{{{b}}} # '{c}'"), Err(ParseError::MixedSyntax));
+        assert_eq!(mustache("{{a}}{{{b}}} # } don't"), Ok("{a}{b} # '}' don't".to_string()));
+        assert_eq!(mustache("{{a}"), Err(ParseError::MixedSyntax));
+        assert_eq!(mustache("{{#each}}"), Err(ParseError::InvalidArgumentName("#each".into())));
+        assert_eq!(mustache("{x, select, a {{y}} other {}}"), Ok("{x, select, a {{y}} other {}}".to_string()));
+    }
+}
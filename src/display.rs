@@ -0,0 +1,92 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use {Args, Context, MessagePart};
+
+/// A message along with its arguments that implements `fmt::Display`.
+///
+/// This allows a message to be used with `format!`, `println!` and
+/// anything else that accepts a `Display` value without formatting
+/// it to an intermediate `String`.
+///
+/// This is created by [`Message::display`] or [`Context::display`].
+///
+/// ```
+/// use message_format::{arg, icu};
+///
+/// let m = icu::parse("Hello, {name}!").unwrap();
+/// let args = arg("name", "Ann");
+/// assert_eq!(format!("[{}]", m.display(&args)), "[Hello, Ann!]");
+/// ```
+///
+/// [`Message::display`]: struct.Message.html#method.display
+/// [`Context::display`]: struct.Context.html#method.display
+pub struct MessageDisplay<'a> {
+    ctx: Option<&'a Context>,
+    message: &'a dyn MessagePart,
+    args: &'a dyn Args<'a>,
+}
+
+impl<'a> MessageDisplay<'a> {
+    /// Construct a `MessageDisplay`. When `ctx` is `None`, the
+    /// current default `Context` is used, as by `Context::current`.
+    pub fn new(
+        ctx: Option<&'a Context>,
+        message: &'a dyn MessagePart,
+        args: &'a dyn Args<'a>,
+    ) -> Self {
+        MessageDisplay { ctx, message, args }
+    }
+}
+
+impl<'a> fmt::Display for MessageDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.ctx {
+            Some(ctx) => ctx.write(self.message, f, self.args),
+            None => Context::current().write(self.message, f, self.args),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for MessageDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MessageDisplay")
+            .field("ctx", &self.ctx)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {arg, icu, Context};
+
+    #[test]
+    fn it_works() {
+        let ctx = Context {
+            language_tag: "sv".parse().unwrap(),
+            ..Context::default()
+        };
+        let m = icu::parse("{n, plural, one {# item} other {# items}}").unwrap();
+        let args = arg("n", 2);
+        assert_eq!(ctx.display(&m, &args).to_string(), "2 items");
+        assert_eq!(format!("{}", m.display(&args)), "2 items");
+    }
+
+    #[test]
+    fn uses_the_current_context() {
+        let ctx = Context {
+            language_tag: "en-u-nu-arab".parse().unwrap(),
+            ..Context::default()
+        };
+        let m = icu::parse("{n, number}").unwrap();
+        let args = arg("n", 12);
+        let s = Context::with_default(ctx, || m.display(&args).to_string());
+        assert_eq!(s, "١٢");
+    }
+}
@@ -0,0 +1,384 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Changing the AST of a message in place, for editing tools.
+
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use bind;
+use icu::ast;
+use icu::parse::is_valid_argument_name;
+use message::part_argument_names;
+use {EmptyArgs, Message, SharedMessage};
+
+/// Why an edit of a message was refused. The message is left as it
+/// was.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditError {
+    /// There is no part at an index of the message.
+    PartOutOfRange {
+        /// The index given.
+        index: usize,
+        /// The number of parts of the message.
+        len: usize,
+    },
+    /// The part at an index isn't a `select` or `plural` format.
+    NotBranching(usize),
+    /// The format has no branch with a key.
+    NoSuchBranch(String),
+    /// The `other` branch of a format can't be removed, as it is used
+    /// when no other branch matches.
+    RequiredBranch,
+    /// A name isn't a valid argument name.
+    InvalidArgumentName(String),
+    /// The message already uses the name an argument would be renamed
+    /// to.
+    ArgumentExists(String),
+    /// A part to change is shared with other messages by
+    /// [`MessageBundle::intern`].
+    ///
+    /// [`MessageBundle::intern`]: struct.MessageBundle.html#method.intern
+    Shared,
+}
+
+impl Error for EditError {}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EditError::PartOutOfRange { index, len } => {
+                write!(
+                    f,
+                    "part {} is out of range for a message of {} parts",
+                    index, len
+                )
+            }
+            EditError::NotBranching(index) => {
+                write!(f, "part {} is not a select or plural format", index)
+            }
+            EditError::NoSuchBranch(ref key) => write!(f, "no branch has the key {:?}", key),
+            EditError::RequiredBranch => f.write_str("the other branch can't be removed"),
+            EditError::InvalidArgumentName(ref name) => {
+                write!(f, "invalid argument name {:?}", name)
+            }
+            EditError::ArgumentExists(ref name) => {
+                write!(f, "the message already uses the argument {:?}", name)
+            }
+            EditError::Shared => f.write_str("the part is shared with other messages"),
+        }
+    }
+}
+
+/// A message changed by [`MessageBundle::rename_argument`].
+///
+/// [`MessageBundle::rename_argument`]: struct.MessageBundle.html#method.rename_argument
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenamedMessage {
+    /// The key of the message.
+    pub key: String,
+    /// The dimension and value of the variant changed, or `None` for
+    /// the message itself.
+    pub variant: Option<(String, String)>,
+    /// The number of formats using the argument.
+    pub uses: usize,
+    /// The message after the change, to write back to its catalog, or
+    /// `None` if it has parts which can't be serialized.
+    pub source: Option<String>,
+}
+
+/// The messages changed by [`MessageBundle::rename_argument`], and
+/// those left as they were.
+///
+/// [`MessageBundle::rename_argument`]: struct.MessageBundle.html#method.rename_argument
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenameReport {
+    /// The messages changed, in key order.
+    pub changed: Vec<RenamedMessage>,
+    /// The keys of the messages using the argument which were left
+    /// unchanged, with the reason.
+    pub conflicts: Vec<(String, EditError)>,
+}
+
+impl RenameReport {
+    /// Whether every message using the argument was changed.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+impl fmt::Display for RenameReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changed {
+            f.write_str(&change.key)?;
+            if let Some((ref dimension, ref value)) = change.variant {
+                write!(f, " [{}={}]", dimension, value)?;
+            }
+            writeln!(f, ": {} uses", change.uses)?;
+        }
+        for (key, error) in &self.conflicts {
+            writeln!(f, "{}: {}", key, error)?;
+        }
+        Ok(())
+    }
+}
+
+// The select or plural format at `index`.
+fn branching(message: &mut Message, index: usize) -> Result<&mut dyn Any, EditError> {
+    let len = message.parts.len();
+    let part = message
+        .parts
+        .get_mut(index)
+        .ok_or(EditError::PartOutOfRange { index, len })?;
+    let any = part.as_any_mut().ok_or(EditError::Shared)?;
+    if any.is::<ast::SelectFormat>() || any.is::<ast::PluralFormat>() {
+        Ok(any)
+    } else {
+        Err(EditError::NotBranching(index))
+    }
+}
+
+// The exact value of a plural key such as `=3`.
+fn literal_value(key: &str) -> Option<f64> {
+    key.strip_prefix('=')?.parse().ok()
+}
+
+pub(crate) fn branch_mut<'m>(
+    message: &'m mut Message,
+    index: usize,
+    key: &str,
+) -> Result<&'m mut Message, EditError> {
+    let any = branching(message, index)?;
+    let missing = || EditError::NoSuchBranch(key.to_string());
+    if any.is::<ast::SelectFormat>() {
+        let fmt = any.downcast_mut::<ast::SelectFormat>().unwrap();
+        if key == "other" {
+            return Ok(&mut fmt.default);
+        }
+        return fmt
+            .mappings
+            .iter_mut()
+            .find(|mapping| mapping.matches(key))
+            .map(|mapping| &mut mapping.message)
+            .ok_or_else(missing);
+    }
+    let fmt = any.downcast_mut::<ast::PluralFormat>().unwrap();
+    let branch = match key {
+        "other" => return Ok(&mut fmt.other),
+        "zero" => &mut fmt.zero,
+        "one" => &mut fmt.one,
+        "two" => &mut fmt.two,
+        "few" => &mut fmt.few,
+        "many" => &mut fmt.many,
+        _ => {
+            let value = literal_value(key).ok_or_else(missing)?;
+            return fmt
+                .literals
+                .iter_mut()
+                .find(|mapping| mapping.value == value)
+                .map(|mapping| &mut mapping.message)
+                .ok_or_else(missing);
+        }
+    };
+    branch.as_mut().ok_or_else(missing)
+}
+
+pub(crate) fn remove_branch(
+    message: &mut Message,
+    index: usize,
+    key: &str,
+) -> Result<Message, EditError> {
+    if key == "other" {
+        branching(message, index)?;
+        return Err(EditError::RequiredBranch);
+    }
+    // Check that the branch exists first.
+    branch_mut(message, index, key)?;
+    let any = branching(message, index)?;
+    if let Some(fmt) = any.downcast_mut::<ast::SelectFormat>() {
+        let i = fmt
+            .mappings
+            .iter()
+            .position(|mapping| mapping.matches(key))
+            .unwrap();
+        return Ok(fmt.mappings.remove(i).message);
+    }
+    let fmt = any.downcast_mut::<ast::PluralFormat>().unwrap();
+    let branch = match key {
+        "zero" => &mut fmt.zero,
+        "one" => &mut fmt.one,
+        "two" => &mut fmt.two,
+        "few" => &mut fmt.few,
+        "many" => &mut fmt.many,
+        _ => {
+            let value = literal_value(key);
+            let i = fmt
+                .literals
+                .iter()
+                .position(|mapping| Some(mapping.value) == value)
+                .unwrap();
+            return Ok(fmt.literals.remove(i).message);
+        }
+    };
+    Ok(branch.take().unwrap())
+}
+
+pub(crate) fn rename_argument(
+    message: &mut Message,
+    old: &str,
+    new: &str,
+) -> Result<usize, EditError> {
+    if !is_valid_argument_name(new) {
+        return Err(EditError::InvalidArgumentName(new.to_string()));
+    }
+    if old != new && message.argument_names().contains(&new) {
+        return Err(EditError::ArgumentExists(new.to_string()));
+    }
+    // Check that every part to change can be, before changing any.
+    rename(message, old, None)?;
+    rename(message, old, Some(new))
+}
+
+// Rename an argument of a message of a bundle, copying it first when
+// it, or a part of it, is shared.
+pub(crate) fn rename_shared(message: &mut SharedMessage, old: &str, new: &str) -> Result<usize, EditError> {
+    if !message.argument_names().contains(&old) {
+        return Ok(0);
+    }
+    if let Some(message) = Arc::get_mut(message) {
+        match rename_argument(message, old, new) {
+            Err(EditError::Shared) => {}
+            result => return result,
+        }
+    }
+    let mut copy = bind::bind(message, &EmptyArgs);
+    let uses = rename_argument(&mut copy, old, new)?;
+    *message = Arc::new(copy);
+    Ok(uses)
+}
+
+// Rename the argument `old` to `new`, or only count its uses when
+// `new` is `None`.
+fn rename(message: &mut Message, old: &str, new: Option<&str>) -> Result<usize, EditError> {
+    let mut count = 0;
+    let mut shared = false;
+    message.walk_mut(&mut |part| {
+        let any = match part.as_any_mut() {
+            Some(any) => any,
+            None => {
+                shared |= part_argument_names(part.as_ref()).contains(&old);
+                return;
+            }
+        };
+        // The `#` of the branches of a plural follows its argument.
+        let name = if let Some(fmt) = any.downcast_mut::<ast::SelectFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::PluralFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::SimpleFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::DateTimeFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::NumberFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::UnitFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::TextFormat>() {
+            &mut fmt.variable_name
+        } else if let Some(fmt) = any.downcast_mut::<ast::UnknownFormat>() {
+            &mut fmt.variable_name
+        } else {
+            return;
+        };
+        if name == old {
+            if let Some(new) = new {
+                *name = new.to_string();
+            }
+            count += 1;
+        }
+    });
+    if shared {
+        return Err(EditError::Shared);
+    }
+    Ok(count)
+}
+
+pub(crate) fn replace_branch(
+    message: &mut Message,
+    index: usize,
+    key: &str,
+    replacement: Message,
+) -> Result<Message, EditError> {
+    branch_mut(message, index, key).map(|branch| mem::replace(branch, replacement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EditError;
+    use icu::ast::PlainText;
+    use icu::{parse, serialize};
+    use {arg, Context};
+
+    #[test]
+    fn branches_are_edited() {
+        let mut m = parse("{g, select, male {him} other {them}}: {n, plural, =0 {none} one {# item} other {# items}}").unwrap();
+        assert_eq!(
+            m.replace_branch(0, "male", parse("he").unwrap()),
+            Ok(parse("him").unwrap())
+        );
+        assert_eq!(m.remove_branch(2, "=0"), Ok(parse("none").unwrap()));
+        assert_eq!(m.remove_branch(2, "other"), Err(EditError::RequiredBranch));
+        assert_eq!(
+            m.remove_branch(2, "few"),
+            Err(EditError::NoSuchBranch("few".into()))
+        );
+        assert_eq!(m.remove_branch(1, "one"), Err(EditError::NotBranching(1)));
+        assert_eq!(
+            m.branch_mut(5, "one").err(),
+            Some(EditError::PartOutOfRange { index: 5, len: 3 })
+        );
+        m.branch_mut(2, "one")
+            .unwrap()
+            .insert_part(0, Box::new(PlainText::new("just ")))
+            .unwrap();
+        assert_eq!(
+            serialize(&m).unwrap(),
+            "{g, select, male {he} other {them}}: {n, plural, one {just # item} other {# items}}"
+        );
+        assert_eq!(m.remove_part(1).map(|_| m.literal_len()), Ok(0));
+        assert_eq!(
+            m.remove_part(2).err(),
+            Some(EditError::PartOutOfRange { index: 2, len: 2 })
+        );
+    }
+
+    #[test]
+    fn arguments_are_renamed_everywhere() {
+        let mut m =
+            parse("{n, plural, one {{who} has # file} other {{who} has # files}} ({who})").unwrap();
+        assert_eq!(m.rename_argument("who", "user"), Ok(3));
+        assert_eq!(m.rename_argument("n", "count"), Ok(1));
+        let ctx = Context::default();
+        assert_eq!(
+            ctx.format(&m, &arg("count", 2).arg("user", "Ann")),
+            "Ann has 2 files (Ann)"
+        );
+
+        assert_eq!(
+            m.rename_argument("user", "count"),
+            Err(EditError::ArgumentExists("count".into()))
+        );
+        assert_eq!(
+            m.rename_argument("user", "a b"),
+            Err(EditError::InvalidArgumentName("a b".into()))
+        );
+        assert_eq!(m.rename_argument("missing", "other"), Ok(0));
+        assert_eq!(m.argument_names(), ["count", "user"]);
+    }
+}
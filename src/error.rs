@@ -0,0 +1,72 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::error::Error;
+use std::fmt;
+
+use {Value, ValueKind};
+
+/// An error resulting from [`Context::try_format`].
+///
+/// [`Context::try_format`]: struct.Context.html#method.try_format
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormatError {
+    /// A message referenced with `{@key}` wasn't found. This holds
+    /// the key.
+    MessageNotFound(String),
+    /// Messages reference each other in a cycle. This holds the keys
+    /// of the cycle, starting and ending with the same key.
+    ReferenceCycle(Vec<String>),
+    /// An argument has a value its format can't use, such as text
+    /// given to a `plural`.
+    TypeMismatch {
+        /// The name of the argument.
+        argument: String,
+        /// The kind of value the format expects. Any numeric kind is
+        /// accepted where `Number` is expected.
+        expected: ValueKind,
+        /// The kind of value given.
+        actual: ValueKind,
+    },
+    /// The message could not be formatted, such as because an
+    /// argument is missing or the stream failed.
+    Format,
+}
+
+impl Error for FormatError {}
+
+impl From<fmt::Error> for FormatError {
+    fn from(_: fmt::Error) -> FormatError {
+        FormatError::Format
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FormatError::MessageNotFound(ref key) => write!(f, "Message not found: {:?}", key),
+            FormatError::ReferenceCycle(ref keys) => {
+                write!(f, "Message reference cycle: {}", keys.join(" -> "))
+            }
+            FormatError::TypeMismatch {
+                ref argument,
+                expected,
+                actual,
+            } => write!(f, "Argument {:?} should be {} but is {}", argument, expected, actual),
+            FormatError::Format => "Formatting failed.".fmt(f),
+        }
+    }
+}
+
+/// The error when `value` isn't of the kind `expected` by the format
+/// of `argument`.
+pub(crate) fn mismatch(argument: &str, expected: ValueKind, value: &Value) -> FormatError {
+    FormatError::TypeMismatch {
+        argument: argument.to_string(),
+        expected,
+        actual: value.kind(),
+    }
+}
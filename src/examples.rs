@@ -0,0 +1,199 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{HashMap, HashSet};
+
+use icu::ast;
+use {Context, DateTime, Message, Value};
+
+/// A rendering of a message with arguments made up for it, produced
+/// by [`Message::example_args`].
+///
+/// [`Message::example_args`]: struct.Message.html#method.example_args
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Example {
+    /// The arguments, in the order the message uses them, with their
+    /// values as text.
+    pub args: Vec<(String, String)>,
+    /// The formatted message.
+    pub output: String,
+}
+
+// A value made up for an argument.
+#[derive(Clone, Debug, PartialEq)]
+enum Sample {
+    Text(String),
+    Number(f64),
+    Date,
+}
+
+impl Sample {
+    fn value(&self) -> Value<'static> {
+        match *self {
+            Sample::Text(ref text) => Value::String(text.clone()),
+            Sample::Number(n) if n.fract() == 0.0 => Value::Number(n as i64),
+            Sample::Number(n) => Value::Float(n),
+            Sample::Date => Value::Date(DateTime::new(2024, 3, 14).with_time(15, 9, 26)),
+        }
+    }
+}
+
+// The numbers tried for a plural, which between them fall in every
+// category of the languages supported.
+const PLURAL_CANDIDATES: &[f64] = &[
+    0.0, 1.0, 2.0, 3.0, 5.0, 6.0, 7.0, 11.0, 12.0, 21.0, 22.0, 100.0, 101.0, 1_000_000.0, 1.5,
+];
+
+// The number used for other numeric formats, to show grouping and
+// fraction digits.
+const NUMBER: f64 = 1234.5;
+
+// The most combinations of arguments rendered for one message.
+const MAX_EXAMPLES: usize = 256;
+
+struct Collector<'c> {
+    ctx: &'c Context,
+    samples: Vec<(String, Vec<Sample>)>,
+}
+
+impl<'c> Collector<'c> {
+    fn add(&mut self, name: &str, sample: Sample) {
+        match self.samples.iter_mut().find(|(n, _)| n == name) {
+            Some(&mut (_, ref mut samples)) => {
+                if !samples.contains(&sample) {
+                    samples.push(sample);
+                }
+            }
+            None => self.samples.push((name.to_string(), vec![sample])),
+        }
+    }
+
+    // A string standing for an argument, which is its capitalized name.
+    fn add_text(&mut self, name: &str) {
+        let mut chars = name.chars();
+        let text = chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default();
+        self.add(name, Sample::Text(text));
+    }
+
+    fn collect(&mut self, message: &Message) {
+        message.walk(&mut |part| {
+            let any = part.as_any();
+            if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+                self.add_text(&fmt.variable_name);
+            } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+                self.add_text(&fmt.variable_name);
+            } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+                self.add_text(&fmt.variable_name);
+            } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+                self.add(&fmt.variable_name, Sample::Number(NUMBER));
+            } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+                self.add(&fmt.variable_name, Sample::Number(NUMBER));
+            } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+                self.add(&fmt.variable_name, Sample::Date);
+            } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+                for mapping in &fmt.mappings {
+                    self.add(&fmt.variable_name, Sample::Text(mapping.value.clone()));
+                }
+                self.add(&fmt.variable_name, Sample::Text("other".to_string()));
+            } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+                self.add_plural(fmt);
+            }
+        });
+    }
+
+    // Add a number for each branch of a plural that one of the
+    // candidates reaches in the context's language.
+    fn add_plural(&mut self, fmt: &ast::PluralFormat) {
+        let literals = fmt.literals.iter().map(|mapping| mapping.value);
+        let mut reached: Vec<*const Message> = vec![];
+        for n in literals.chain(PLURAL_CANDIDATES.iter().cloned()) {
+            if let Some(message) = fmt.resolve_message(self.ctx, &Sample::Number(n).value()) {
+                if !reached.contains(&(message as *const Message)) {
+                    reached.push(message);
+                    self.add(&fmt.variable_name, Sample::Number(n));
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn examples(message: &Message, ctx: &Context) -> Vec<Example> {
+    let mut collector = Collector { ctx, samples: vec![] };
+    collector.collect(message);
+    let mut samples = collector.samples;
+    // An argument used both as text and as a number or date only
+    // formats as the latter.
+    for (_, values) in &mut samples {
+        if values.iter().any(|sample| !matches!(sample, Sample::Text(_))) {
+            values.retain(|sample| !matches!(sample, Sample::Text(_)));
+        }
+    }
+
+    let mut examples = vec![];
+    let mut outputs = HashSet::new();
+    let mut indices = vec![0; samples.len()];
+    for _ in 0..MAX_EXAMPLES {
+        let chosen: Vec<(&str, &Sample)> = samples
+            .iter()
+            .zip(&indices)
+            .map(|((name, values), &i)| (name.as_str(), &values[i]))
+            .collect();
+        let args: HashMap<&str, Value> = chosen.iter().map(|&(name, sample)| (name, sample.value())).collect();
+        let output = ctx.format(message, &args);
+        if outputs.insert(output.clone()) {
+            examples.push(Example {
+                args: chosen
+                    .iter()
+                    .map(|&(name, _)| (name.to_string(), args[name].to_string()))
+                    .collect(),
+                output,
+            });
+        }
+        // Move to the next combination, as an odometer would.
+        let mut position = 0;
+        loop {
+            if position == indices.len() {
+                return examples;
+            }
+            indices[position] += 1;
+            if indices[position] < samples[position].1.len() {
+                break;
+            }
+            indices[position] = 0;
+            position += 1;
+        }
+    }
+    examples
+}
+
+#[cfg(test)]
+mod tests {
+    use icu;
+    use Context;
+
+    #[test]
+    fn every_branch_is_rendered() {
+        let m = icu::parse(
+            "{host} invited {guests, plural, =0 {nobody} one {# guest} other {# guests}} \
+             to {gender, select, female {her} male {his} other {their}} party on {day, date, short}.",
+        )
+        .unwrap();
+        let examples = m.example_args(&Context::default());
+        // `=0`, `one` and `other`, for each of the 3 genders.
+        assert_eq!(examples.len(), 9);
+        assert_eq!(
+            examples[0].args,
+            [
+                ("host".to_string(), "Host".to_string()),
+                ("guests".to_string(), "0".to_string()),
+                ("gender".to_string(), "female".to_string()),
+                ("day".to_string(), examples[0].args[3].1.clone()),
+            ]
+        );
+        assert!(examples.iter().all(|example| example.output.starts_with("Host invited ")));
+        assert!(examples.iter().any(|example| example.output.contains("2 guests to his party")));
+    }
+}
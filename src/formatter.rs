@@ -0,0 +1,189 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::fmt;
+use std::sync::Arc;
+
+use datetime::{shared_calendar_for_language_tag, Calendar, DateTime, DateTimeData, DateTimeKind, DateTimeStyle};
+use number::NumberSymbols;
+use plural::PluralRuleSet;
+use provider::{DataProvider, DataRef};
+use {Args, Context, Message};
+
+/// A [`Message`] bound to a [`Context`], for formatting the same
+/// message many times, such as once per request in a server.
+///
+/// The calendar, plural rules, number symbols and date and time names
+/// of the context's language are looked up once, when the formatter
+/// is created, rather than on each format. The formatter also keeps
+/// its output buffer between calls to [`format`].
+///
+/// ```
+/// use message_format::{arg, icu, Context, MessageFormatter};
+///
+/// let m = icu::parse("Welcome back, {name}!").unwrap();
+/// let mut formatter = MessageFormatter::new(&Context::default(), &m);
+/// assert_eq!(formatter.format(&arg("name", "Ann")), "Welcome back, Ann!");
+/// assert_eq!(formatter.format(&arg("name", "Bo")), "Welcome back, Bo!");
+///
+/// let mut page = String::from("<p>");
+/// formatter.format_into(&mut page, &arg("name", "Cy")).unwrap();
+/// assert_eq!(page, "<p>Welcome back, Cy!");
+/// ```
+///
+/// [`Message`]: struct.Message.html
+/// [`Context`]: struct.Context.html
+/// [`format`]: #method.format
+#[derive(Debug)]
+pub struct MessageFormatter<'m> {
+    ctx: Context,
+    message: &'m Message,
+    buffer: String,
+}
+
+impl<'m> MessageFormatter<'m> {
+    /// Construct a `MessageFormatter` formatting `message` with `ctx`.
+    pub fn new(ctx: &Context, message: &'m Message) -> Self {
+        let mut ctx = ctx.clone();
+        if ctx.calendar.is_none() {
+            ctx.calendar = Some(shared_calendar_for_language_tag(&ctx.language_tag));
+        }
+        // The language never changes, so the rules it resolves to are
+        // the only ones needed.
+        ctx.plural_rules = vec![(ctx.language_tag.clone(), ctx.registered_plural_rules())];
+        let provider = ctx.data_provider.take();
+        ctx.data_provider = Some(Arc::new(ResolvedData {
+            language_tag: ctx.language_tag.clone(),
+            datetime_data: provider
+                .as_ref()
+                .and_then(|provider| provider.datetime_data(&ctx.language_tag))
+                .or_else(|| DateTimeData::find(&ctx.language_tag).map(DataRef::Static)),
+            number_symbols: provider
+                .as_ref()
+                .and_then(|provider| provider.number_symbols(&ctx.language_tag))
+                .or_else(|| NumberSymbols::find(&ctx.language_tag).map(DataRef::Static)),
+            provider,
+        }));
+        MessageFormatter {
+            ctx,
+            message,
+            buffer: String::new(),
+        }
+    }
+
+    /// The context used for formatting.
+    pub fn context(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// The message being formatted.
+    pub fn message(&self) -> &'m Message {
+        self.message
+    }
+
+    /// Append the formatted message to `output`, reserving space for
+    /// it first.
+    pub fn format_into<'f>(&self, output: &mut String, args: &'f dyn Args<'f>) -> fmt::Result {
+        output.reserve(self.message.estimated_len(args));
+        self.ctx.write(self.message, output, args)
+    }
+
+    /// Format the message, returning a string borrowed from the
+    /// formatter's buffer. The buffer is reused by the next call.
+    pub fn format<'f>(&mut self, args: &'f dyn Args<'f>) -> &str {
+        self.buffer.clear();
+        let _ = self.ctx.write(self.message, &mut self.buffer, args);
+        &self.buffer
+    }
+}
+
+// The data of one language, looked up in advance, in front of the
+// provider it came from. Languages without data are left to the
+// context, so that it reports them as missing.
+struct ResolvedData {
+    language_tag: LanguageTag,
+    datetime_data: Option<DataRef<DateTimeData>>,
+    number_symbols: Option<DataRef<NumberSymbols>>,
+    provider: Option<Arc<dyn DataProvider>>,
+}
+
+impl DataProvider for ResolvedData {
+    fn datetime_data(&self, language_tag: &LanguageTag) -> Option<DataRef<DateTimeData>> {
+        if *language_tag == self.language_tag {
+            return self.datetime_data.clone();
+        }
+        self.provider.as_ref()?.datetime_data(language_tag)
+    }
+
+    fn number_symbols(&self, language_tag: &LanguageTag) -> Option<DataRef<NumberSymbols>> {
+        if *language_tag == self.language_tag {
+            return self.number_symbols.clone();
+        }
+        self.provider.as_ref()?.number_symbols(language_tag)
+    }
+
+    fn plural_rules(&self, language_tag: &LanguageTag) -> Option<Arc<dyn PluralRuleSet>> {
+        self.provider.as_ref()?.plural_rules(language_tag)
+    }
+
+    fn write_digits(
+        &self,
+        language_tag: &LanguageTag,
+        digits: &str,
+        stream: &mut dyn fmt::Write,
+    ) -> Option<fmt::Result> {
+        self.provider.as_ref()?.write_digits(language_tag, digits, stream)
+    }
+
+    fn format_datetime(
+        &self,
+        language_tag: &LanguageTag,
+        calendar: &dyn Calendar,
+        value: &DateTime,
+        kind: DateTimeKind,
+        style: &DateTimeStyle,
+        stream: &mut dyn fmt::Write,
+    ) -> Option<fmt::Result> {
+        self.provider.as_ref()?.format_datetime(language_tag, calendar, value, kind, style, stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageFormatter;
+    use {arg, icu, Context, DateTime};
+
+    #[test]
+    fn calendar_is_resolved_once() {
+        let ctx = Context::new("en-u-ca-buddhist".parse().unwrap(), None);
+        let m = icu::parse("{d, date, ::y}").unwrap();
+        let mut formatter = MessageFormatter::new(&ctx, &m);
+        assert_eq!(formatter.context().calendar().identifier(), "buddhist");
+        assert_eq!(formatter.format(&arg("d", DateTime::new(2024, 1, 1))), "2567 BE");
+    }
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn locale_data_is_resolved_once() {
+        use plural::PluralRules;
+
+        let mut ctx = Context::new("de".parse().unwrap(), None);
+        ctx.set_plural_rules("de".parse().unwrap(), PluralRules::cardinal(&"fr".parse().unwrap()));
+        ctx.set_plural_rules("ja".parse().unwrap(), PluralRules::cardinal(&"ja".parse().unwrap()));
+        let m = icu::parse("{n, plural, one {# Datei} other {# Dateien}} {d, date, MMMM}").unwrap();
+        let mut formatter = MessageFormatter::new(&ctx, &m);
+        assert_eq!(formatter.context().plural_rules.len(), 1);
+        let march = DateTime::new(2024, 3, 1);
+        assert_eq!(formatter.format(&arg("n", 0).arg("d", march)), "0 Datei März");
+        assert_eq!(ctx.format(&m, &arg("n", 0).arg("d", march)), "0 Datei März");
+        assert_eq!(formatter.context().missing_locale_data(), ctx.missing_locale_data());
+
+        let tlh = Context::new("tlh".parse().unwrap(), None);
+        let formatter = MessageFormatter::new(&tlh, &m);
+        assert_eq!(formatter.context().missing_locale_data(), tlh.missing_locale_data());
+    }
+}
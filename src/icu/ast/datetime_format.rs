@@ -0,0 +1,119 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use datetime::{DateTimeKind, DateTimeStyle};
+use error;
+use {Args, Context, FormatError, MessagePart, Value, ValueKind};
+
+/// Format a date or time value in a locale-aware manner.
+#[derive(Debug, PartialEq)]
+pub struct DateTimeFormat {
+    /// The name of the variable whose value should be formatted.
+    pub variable_name: String,
+    /// Whether the date or the time should be formatted.
+    pub kind: DateTimeKind,
+    /// The style to use when formatting.
+    pub style: DateTimeStyle,
+}
+
+impl DateTimeFormat {
+    /// Construct a `DateTimeFormat`.
+    pub fn new(variable_name: &str, kind: DateTimeKind, style: DateTimeStyle) -> Self {
+        DateTimeFormat {
+            variable_name: variable_name.to_string(),
+            kind,
+            style,
+        }
+    }
+}
+
+impl MessagePart for DateTimeFormat {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        self.try_apply_format(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        match args.get(&self.variable_name) {
+            Some(Value::Date(value)) => {
+                Ok(ctx.write_isolated(stream, |stream| ctx.write_datetime(value, self.kind, &self.style, stream))?)
+            }
+            Some(value) => Err(error::mismatch(&self.variable_name, ValueKind::Date, value)),
+            None => Err(FormatError::Format),
+        }
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DateTimeFormat;
+    use datetime::{DateTimeKind, DateTimeStyle};
+    use {Context, DateTime, Message};
+
+    #[test]
+    fn it_works() {
+        let ctx = Context::default();
+
+        let fmt = DateTimeFormat::new("d", DateTimeKind::Date, DateTimeStyle::Long);
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        let output = format_message!(ctx, &msg, d => DateTime::new(2024, 10, 3));
+        assert_eq!("October 3, 2024", output);
+    }
+
+    #[test]
+    fn skeletons_are_localized() {
+        let style = DateTimeStyle::parse("::yMMMd").unwrap();
+        let fmt = DateTimeFormat::new("d", DateTimeKind::Date, style);
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        let ctx = Context::default();
+        let output = format_message!(ctx, &msg, d => DateTime::new(2024, 10, 3));
+        assert_eq!("Oct 3, 2024", output);
+
+        if cfg!(feature = "locale-data") {
+            let ctx = Context {
+                language_tag: "sv".parse().unwrap(),
+                ..Context::default()
+            };
+            let output = format_message!(ctx, &msg, d => DateTime::new(2024, 10, 3));
+            assert_eq!("3 okt. 2024", output);
+        }
+    }
+
+    #[test]
+    fn calendar_follows_language_tag() {
+        let ctx = Context {
+            language_tag: "en-u-ca-japanese".parse().unwrap(),
+            ..Context::default()
+        };
+
+        let fmt = DateTimeFormat::new("d", DateTimeKind::Date, DateTimeStyle::Medium);
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        let output = format_message!(ctx, &msg, d => DateTime::new(2024, 10, 3));
+        assert_eq!("Oct 3, 6 Reiwa", output);
+    }
+}
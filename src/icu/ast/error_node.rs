@@ -0,0 +1,51 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use {Args, Context, MessagePart};
+
+/// Source text that could not be parsed, produced by
+/// [`parse_recovering`] in place of the broken part of a message.
+///
+/// It is formatted as the original text, so that the rest of the
+/// message can still be previewed.
+///
+/// [`parse_recovering`]: ../fn.parse_recovering.html
+#[derive(Debug, PartialEq)]
+pub struct ErrorNode {
+    /// The source text that could not be parsed.
+    pub text: String,
+}
+
+impl ErrorNode {
+    /// Construct an `ErrorNode`.
+    pub fn new(text: &str) -> Self {
+        ErrorNode {
+            text: text.to_string(),
+        }
+    }
+}
+
+impl MessagePart for ErrorNode {
+    fn apply_format(
+        &self,
+        _ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        _args: &dyn Args,
+    ) -> fmt::Result {
+        stream.write_str(&self.text)
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
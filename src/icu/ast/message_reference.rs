@@ -0,0 +1,137 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use {Args, Context, FormatError, MessagePart};
+
+thread_local! {
+    // The keys of the references being formatted on this thread.
+    static RESOLVING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes a key from `RESOLVING` when dropped.
+struct ResolvingGuard;
+
+impl Drop for ResolvingGuard {
+    fn drop(&mut self) {
+        RESOLVING.with(|keys| keys.borrow_mut().pop());
+    }
+}
+
+/// A reference to another message, written `{@key}` when parsing
+/// with [extensions].
+///
+/// The message is looked up when formatting, in the context's
+/// [`message_source`], and formatted with the same arguments. This
+/// lets common fragments, such as a product name, be defined once.
+///
+/// [extensions]: ../parse/struct.ParseOptions.html#structfield.extensions
+/// [`message_source`]: ../../struct.Context.html#structfield.message_source
+#[derive(Debug, PartialEq)]
+pub struct MessageReference {
+    /// The key of the message referenced.
+    pub key: String,
+}
+
+impl MessageReference {
+    /// Construct a `MessageReference`.
+    pub fn new(key: &str) -> Self {
+        MessageReference {
+            key: key.to_string(),
+        }
+    }
+}
+
+impl MessagePart for MessageReference {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        self.try_apply_format(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        let message = match ctx.message_source.as_ref().and_then(|source| source.message(&self.key)) {
+            Some(message) => message,
+            None => return Err(FormatError::MessageNotFound(self.key.clone())),
+        };
+        let cycle = RESOLVING.with(|keys| {
+            let mut keys = keys.borrow_mut();
+            let cycle = keys.iter().position(|key| *key == self.key).map(|start| {
+                let mut cycle = keys[start..].to_vec();
+                cycle.push(self.key.clone());
+                cycle
+            });
+            keys.push(self.key.clone());
+            cycle
+        });
+        let _guard = ResolvingGuard;
+        if let Some(cycle) = cycle {
+            return Err(FormatError::ReferenceCycle(cycle));
+        }
+        message.try_write_message(ctx, stream, args)
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use icu::{parse, parse_with, ParseOptions};
+    use {arg, BundleSet, Context, FormatError, Localizer, MessageBundle};
+
+    #[test]
+    fn references_follow_the_localizer() {
+        let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+        let en = MessageBundle::parse_entries_with(
+            "en".parse().unwrap(),
+            vec![("footer", "Sent by {app}."), ("note", "Hi! {@footer}")],
+            options,
+        )
+        .unwrap();
+        let fr = MessageBundle::parse_entries_with(
+            "fr".parse().unwrap(),
+            vec![("note", "Salut ! {@footer}"), ("broken", "{@nothing}")],
+            options,
+        )
+        .unwrap();
+        let mut bundles = BundleSet::new(en);
+        bundles.insert(fr);
+        let localizer = Localizer::new(Arc::new(bundles), &["fr".parse().unwrap()]);
+
+        let args = arg("app", "Mailer");
+        assert_eq!(localizer.format("note", &args).unwrap(), "Salut ! Sent by Mailer.");
+        let (broken, ctx) = localizer.lookup("broken").unwrap();
+        let not_found = Err(FormatError::MessageNotFound("nothing".to_string()));
+        assert_eq!(ctx.try_format(broken, &args), not_found);
+        assert_eq!(Context::default().try_format(broken, &args), not_found);
+        let missing = parse("{x}").unwrap();
+        assert_eq!(Context::default().try_format(&missing, &args), Err(FormatError::Format));
+
+        assert!(parse("{@footer}").is_err());
+        assert_eq!(
+            ::icu::serialize(&parse_with("a {@footer} b", options).unwrap()).unwrap(),
+            "a {@footer} b"
+        );
+    }
+}
@@ -11,14 +11,29 @@
 //!
 //! [`MessagePart`]: ../../trait.MessagePart.html
 
+mod datetime_format;
+mod error_node;
+mod message_reference;
+mod number_format;
 mod placeholder_format;
 mod plain_text;
 mod plural_format;
 mod select_format;
 mod simple_format;
+mod text_format;
+mod unit_format;
+mod unknown_format;
 
+pub use self::datetime_format::DateTimeFormat;
+pub use self::error_node::ErrorNode;
+pub use self::message_reference::MessageReference;
+pub use self::number_format::NumberFormat;
 pub use self::placeholder_format::PlaceholderFormat;
+pub(crate) use self::placeholder_format::write_placeholder;
 pub use self::plain_text::PlainText;
 pub use self::plural_format::PluralFormat;
 pub use self::select_format::SelectFormat;
 pub use self::simple_format::SimpleFormat;
+pub use self::text_format::TextFormat;
+pub use self::unit_format::UnitFormat;
+pub use self::unknown_format::UnknownFormat;
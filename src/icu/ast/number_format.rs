@@ -0,0 +1,147 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use error;
+use number::{spell_out, write_number_with, NumberStyle};
+use {Args, Context, FormatError, MessagePart, Rendering, ValueKind};
+
+/// Format a number in a locale-aware manner.
+#[derive(Debug, PartialEq)]
+pub struct NumberFormat {
+    /// The name of the variable whose value should be formatted.
+    pub variable_name: String,
+    /// The style to use when formatting.
+    pub style: NumberStyle,
+}
+
+impl NumberFormat {
+    /// Construct a `NumberFormat`.
+    pub fn new(variable_name: &str, style: NumberStyle) -> Self {
+        NumberFormat {
+            variable_name: variable_name.to_string(),
+            style,
+        }
+    }
+}
+
+impl MessagePart for NumberFormat {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        self.try_apply_format(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        let value = args.get(&self.variable_name).ok_or(FormatError::Format)?;
+        let number = value
+            .number()
+            .ok_or_else(|| error::mismatch(&self.variable_name, ValueKind::Number, value))?;
+        ctx.write_isolated(stream, |stream| {
+            // Written from the value, so integers keep all their digits.
+            if self.style == NumberStyle::Raw {
+                return write!(stream, "{}", value);
+            }
+            let overrides = args.number_options(&self.variable_name);
+            if ctx.rendering == Rendering::Spoken && value.fraction_digits().is_none() && overrides.is_none() {
+                let words = match self.style {
+                    NumberStyle::Decimal => spell_out(&ctx.language_tag, number.as_f64()),
+                    NumberStyle::Integer => spell_out(&ctx.language_tag, number.as_f64().round()),
+                    _ => None,
+                };
+                if let Some(words) = words {
+                    return stream.write_str(&words);
+                }
+            }
+            // A named style is the decimal style with the options the
+            // context defines for it.
+            let (style, options) = match self.style {
+                NumberStyle::Named(ref name) => {
+                    let preset = ctx.number_style(name).ok_or(fmt::Error {})?;
+                    (NumberStyle::Decimal, ctx.number_options.or(preset))
+                }
+                ref style => (style.clone(), ctx.number_options),
+            };
+            ctx.with_number_locale(|locale| {
+                let digits = value.fraction_digits();
+                write_number_with(locale, number, digits, style, &options, overrides.as_ref(), stream).map(|_| ())
+            })
+        })?;
+        Ok(())
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::{parse, serialize};
+    use number::{Currency, NumberOptions, RoundingMode};
+    use {arg, arg_with, Context, Decimal, FormatError};
+
+    #[test]
+    fn it_works() {
+        let ctx = Context::default();
+        let m = parse("{n, number} {r, number, percent} {r, number, permille} {n, number, integer}").unwrap();
+        assert_eq!(format_message!(ctx, &m, n => 1234.5, r => 0.125), "1,234.5 12% 125‰ 1,234");
+        let n: Decimal = "1234.50".parse().unwrap();
+        assert_eq!(format_message!(ctx, &m, n => n, r => 1), "1,234.50 100% 1,000‰ 1,234");
+    }
+
+    #[test]
+    fn options_and_skeletons() {
+        let mut ctx = Context::default();
+        ctx.number_options.maximum_fraction_digits = Some(1);
+        ctx.number_options.rounding_mode = Some(RoundingMode::Down);
+        let m = parse("{n, number} {n, number, ::.00##} {n, number, ::@@@ rounding-mode-half-up}").unwrap();
+        assert_eq!(format_message!(ctx, &m, n => 2.4567), "2.4 2.4567 2.46");
+    }
+
+    #[test]
+    fn compact_counts_keep_plurals() {
+        let ctx = Context::default();
+        let m = parse("{views, plural, one {# view} other {{views, number, ::compact-short} views}}").unwrap();
+        assert_eq!(format_message!(ctx, &m, views => 1), "1 view");
+        assert_eq!(format_message!(ctx, &m, views => 1234567), "1.2M views");
+    }
+
+    #[test]
+    fn named_styles() {
+        let mut ctx = Context::default();
+        let m = parse("{n, number, price}").unwrap();
+        assert_eq!(serialize(&m).unwrap(), "{n, number, price}");
+        assert_eq!(ctx.try_format(&m, &arg("n", 2)), Err(FormatError::Format));
+
+        ctx.define_number_style("price", NumberOptions::currency(Currency::new("USD").unwrap()));
+        assert_eq!(format_message!(ctx, &m, n => 2), "$2.00");
+        ctx.define_number_style("price", NumberOptions { minimum_fraction_digits: Some(2), ..Default::default() });
+        assert_eq!(format_message!(ctx, &m, n => 2), "2.00");
+        assert_eq!(ctx.format(&m, &arg_with("n", 2, NumberOptions::currency(Currency::new("GBP").unwrap()))), "£2.00");
+    }
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn percent_is_localized() {
+        let ctx = Context::new("fr".parse().unwrap(), None);
+        let m = parse("{ratio, number, percent}").unwrap();
+        assert_eq!(format_message!(ctx, &m, ratio => 0.25), "25\u{202f}%");
+    }
+}
@@ -6,10 +6,11 @@
 
 use std::fmt;
 
-use {Args, Context, MessagePart};
+use number::{spell_out, write_number_with, Number, NumberOptions, NumberStyle};
+use {Args, Context, MessagePart, Rendering};
 
 /// A placeholder for a value. Used by `PluralFormat`.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct PlaceholderFormat {}
 
 impl PlaceholderFormat {
@@ -26,16 +27,44 @@ impl MessagePart for PlaceholderFormat {
         stream: &mut dyn fmt::Write,
         _args: &dyn Args,
     ) -> fmt::Result {
-        if let Some(value) = ctx.placeholder_value {
-            write!(stream, "{}", value)?;
-            Ok(())
-        } else {
-            Err(fmt::Error {})
+        match ctx.placeholder_number() {
+            Some(value) => {
+                let overrides = ctx.placeholder_options.as_ref();
+                write_placeholder(ctx, value, ctx.placeholder_fraction_digits, overrides, stream)
+            }
+            None => Err(fmt::Error {}),
         }
     }
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+// Write the value of a placeholder as the decimal style of a `number`
+// format does, showing the fraction digits of a `Decimal` if there are
+// any, or in words when it is spoken.
+pub(crate) fn write_placeholder(
+    ctx: &Context,
+    value: Number,
+    fraction_digits: Option<usize>,
+    overrides: Option<&NumberOptions>,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    if ctx.rendering == Rendering::Spoken && fraction_digits.is_none() && overrides.is_none() {
+        if let Some(words) = spell_out(&ctx.language_tag, value.as_f64()) {
+            return stream.write_str(&words);
+        }
+    }
+    ctx.with_number_locale(|locale| {
+        let style = NumberStyle::Decimal;
+        write_number_with(locale, value, fraction_digits, style, &ctx.number_options, overrides, stream).map(|_| ())
+    })
 }
 
 #[cfg(test)]
@@ -46,7 +75,7 @@ mod tests {
     #[test]
     fn it_works() {
         let ctx = Context {
-            placeholder_value: Some(3),
+            placeholder_value: Some(3.0),
             ..Context::default()
         };
 
@@ -54,5 +83,12 @@ mod tests {
 
         let output = format_message!(ctx, &msg);
         assert_eq!("3", output);
+
+        let ctx = Context {
+            placeholder_value: Some(0.5),
+            ..Context::default()
+        };
+        let output = format_message!(ctx, &msg);
+        assert_eq!("0.5", output);
     }
 }
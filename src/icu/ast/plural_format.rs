@@ -7,11 +7,17 @@
 use std::fmt;
 
 use english_cardinal_classifier;
-use {Args, Context, Message, MessagePart, PluralCategory, Value};
+use error;
+use number::Number;
+use plural::PluralOperands;
+use {Args, Context, Decimal, FormatError, Message, MessagePart, PluralCategory, Value, ValueKind};
 
-#[derive(Debug)]
+/// A message used when the value exactly matches `value`.
+#[derive(Debug, PartialEq)]
 pub struct PluralMapping {
-    pub value: i64,
+    /// The value to match.
+    pub value: f64,
+    /// The message to use when the value matches.
     pub message: Message,
 }
 
@@ -20,17 +26,43 @@ pub struct PluralMapping {
 pub struct PluralFormat {
     /// The name of the variable whose value should be formatted.
     pub variable_name: String,
+    /// Maps a value to its `PluralCategory`.
     pub classifier: fn(i64) -> PluralCategory,
+    /// The messages for exact values, which take precedence over
+    /// the plural categories.
     pub literals: Vec<PluralMapping>,
+    /// Subtracted from the value before choosing a plural category.
     pub offset: i64,
+    /// The message for `PluralCategory::Zero`.
     pub zero: Option<Message>,
+    /// The message for `PluralCategory::One`.
     pub one: Option<Message>,
+    /// The message for `PluralCategory::Two`.
     pub two: Option<Message>,
+    /// The message for `PluralCategory::Few`.
     pub few: Option<Message>,
+    /// The message for `PluralCategory::Many`.
     pub many: Option<Message>,
+    /// The message used when no other message applies.
     pub other: Message,
 }
 
+// The classifier is not part of the message text, so it does
+// not take part in the comparison.
+impl PartialEq for PluralFormat {
+    fn eq(&self, other: &PluralFormat) -> bool {
+        self.variable_name == other.variable_name
+            && self.literals == other.literals
+            && self.offset == other.offset
+            && self.zero == other.zero
+            && self.one == other.one
+            && self.two == other.two
+            && self.few == other.few
+            && self.many == other.many
+            && self.other == other.other
+    }
+}
+
 impl PluralFormat {
     /// Construct a `PluralFormat`.
     pub fn new(variable_name: &str, other: Message) -> Self {
@@ -44,15 +76,21 @@ impl PluralFormat {
             two: None,
             few: None,
             many: None,
-            other: other,
+            other,
         }
     }
 
     /// Set the `message` to be used for a literal value.
     pub fn literal(&mut self, literal: i64, message: Message) {
+        self.decimal_literal(literal as f64, message);
+    }
+
+    /// Set the `message` to be used for a literal decimal value,
+    /// such as `=0.5`.
+    pub fn decimal_literal(&mut self, literal: f64, message: Message) {
         self.literals.push(PluralMapping {
             value: literal,
-            message: message,
+            message,
         });
     }
 
@@ -86,25 +124,109 @@ impl PluralFormat {
         self.many = Some(message);
     }
 
-    /// Given a value adjusted by the `offset`, determine which `Message` to use.
-    fn lookup_message(&self, offset_value: i64) -> &Message {
-        if let Some(literal_message) = self
-            .literals
+    /// Given a value, determine which `Message` to use.
+    ///
+    /// Literals are matched against the value itself, while the plural
+    /// category is chosen using the value adjusted by the `offset`.
+    /// Values with a fractional part use `PluralCategory::Other`.
+    ///
+    /// The category comes from the `classifier`, which follows English
+    /// unless it is replaced, rather than from the plural rules of a
+    /// context's language as when formatting. Use [`resolve`] to choose
+    /// the message as formatting does.
+    ///
+    /// [`resolve`]: #method.resolve
+    pub fn lookup_message(&self, value: f64) -> &Message {
+        self.lookup(value, false)
+    }
+
+    /// Like [`lookup_message`], but a `Decimal` with visible fraction
+    /// digits, such as `1.0`, is treated as having a fractional part.
+    ///
+    /// [`lookup_message`]: #method.lookup_message
+    pub fn lookup_decimal(&self, value: &Decimal) -> &Message {
+        self.lookup(value.value(), value.fraction_digits() > 0)
+    }
+
+    /// The message to use for an argument value along with the context
+    /// to write it with, where `#` is the value less the offset. This
+    /// is `None` if the value isn't a number.
+    ///
+    /// The category is chosen by the plural rules of the context's
+    /// language, as given by [`Context::registered_plural_rules`],
+    /// rather than by the `classifier`.
+    ///
+    /// [`Context::registered_plural_rules`]: ../../struct.Context.html#method.registered_plural_rules
+    pub fn resolve(&self, ctx: &Context, value: &Value) -> Option<(&Message, Context)> {
+        let message = self.resolve_message(ctx, value)?;
+        let number = self.placeholder_number(value)?;
+        let ctx = Context {
+            placeholder_value: Some(number.as_f64()),
+            placeholder_integer: number.as_i128(),
+            placeholder_fraction_digits: value.fraction_digits(),
+            ..ctx.clone()
+        };
+        Some((message, ctx))
+    }
+
+    // The value `#` stands for in the branches: the argument less the
+    // offset.
+    pub(crate) fn placeholder_number(&self, value: &Value) -> Option<Number> {
+        let number = value.number()?;
+        if self.offset == 0 {
+            return Some(number);
+        }
+        match number.as_i128().and_then(|n| n.checked_sub(i128::from(self.offset))) {
+            Some(n) => Some(Number::integer(n)),
+            None => Some(Number::Float(number.as_f64() - self.offset as f64)),
+        }
+    }
+
+    // Like `resolve`, without the context.
+    pub(crate) fn resolve_message(&self, ctx: &Context, value: &Value) -> Option<&Message> {
+        let number = value.as_float()?;
+        let fraction_digits = value.fraction_digits();
+        let message = self.literal_message(number).unwrap_or_else(|| {
+            let offset_value = number - self.offset as f64;
+            let operands = match fraction_digits {
+                Some(digits) => Decimal::from_f64(offset_value, digits).operands(),
+                None => PluralOperands::from(offset_value),
+            };
+            self.category_message(ctx.registered_plural_rules().select(&operands))
+        });
+        Some(message)
+    }
+
+    fn literal_message(&self, value: f64) -> Option<&Message> {
+        self.literals
             .iter()
-            .find(|mapping| mapping.value == offset_value)
+            .find(|mapping| mapping.value == value)
             .map(|mapping| &mapping.message)
-        {
+    }
+
+    fn lookup(&self, value: f64, visible_fraction: bool) -> &Message {
+        if let Some(literal_message) = self.literal_message(value) {
             literal_message
         } else {
-            let category = (self.classifier)(offset_value);
-            match category {
-                PluralCategory::Zero => self.zero.as_ref().unwrap_or(&self.other),
-                PluralCategory::One => self.one.as_ref().unwrap_or(&self.other),
-                PluralCategory::Two => self.two.as_ref().unwrap_or(&self.other),
-                PluralCategory::Few => self.few.as_ref().unwrap_or(&self.other),
-                PluralCategory::Many => self.many.as_ref().unwrap_or(&self.other),
-                PluralCategory::Other => &self.other,
-            }
+            let offset_value = value - self.offset as f64;
+            let category = if offset_value.fract() == 0.0 && !visible_fraction {
+                (self.classifier)(offset_value as i64)
+            } else {
+                PluralCategory::Other
+            };
+            self.category_message(category)
+        }
+    }
+
+    /// The `Message` used for a plural category, ignoring literals.
+    pub fn category_message(&self, category: PluralCategory) -> &Message {
+        match category {
+            PluralCategory::Zero => self.zero.as_ref().unwrap_or(&self.other),
+            PluralCategory::One => self.one.as_ref().unwrap_or(&self.other),
+            PluralCategory::Two => self.two.as_ref().unwrap_or(&self.other),
+            PluralCategory::Few => self.few.as_ref().unwrap_or(&self.other),
+            PluralCategory::Many => self.many.as_ref().unwrap_or(&self.other),
+            PluralCategory::Other => &self.other,
         }
     }
 }
@@ -116,29 +238,62 @@ impl MessagePart for PluralFormat {
         stream: &mut dyn fmt::Write,
         args: &'f dyn Args<'f>,
     ) -> fmt::Result {
-        if let Some(&Value::Number(value)) = args.get(&self.variable_name) {
-            let offset_value = value - self.offset;
-            let message = self.lookup_message(offset_value);
-            let ctx = Context {
-                placeholder_value: Some(offset_value),
-                ..ctx.clone()
-            };
-            message.write_message(&ctx, stream, args)?;
-            Ok(())
-        } else {
-            Err(fmt::Error {})
+        self.try_apply_format(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        let value = args.get(&self.variable_name).ok_or(FormatError::Format)?;
+        match self.resolve(ctx, value) {
+            Some((message, mut ctx)) => {
+                ctx.placeholder_options = args.number_options(&self.variable_name);
+                message.try_write_message(&ctx, stream, args)
+            }
+            None => Err(error::mismatch(&self.variable_name, ValueKind::Number, value)),
         }
     }
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn children(&self) -> Vec<&Message> {
+        let categories = [&self.zero, &self.one, &self.two, &self.few, &self.many];
+        let mut children: Vec<&Message> = self.literals.iter().map(|mapping| &mapping.message).collect();
+        children.extend(categories.iter().filter_map(|m| m.as_ref()));
+        children.push(&self.other);
+        children
+    }
+    fn children_mut(&mut self) -> Vec<&mut Message> {
+        let mut children: Vec<&mut Message> = self.literals.iter_mut().map(|mapping| &mut mapping.message).collect();
+        let categories = vec![&mut self.zero, &mut self.one, &mut self.two, &mut self.few, &mut self.many];
+        children.extend(categories.into_iter().filter_map(|m| m.as_mut()));
+        children.push(&mut self.other);
+        children
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::PluralFormat;
+    use icu::ast::{PlaceholderFormat, PlainText};
     use icu::parse;
-    use {Context, Message};
+    use language_tags::LanguageTag;
+    use plural::{PluralOperands, PluralRules};
+    use {arg, Context, Decimal, FormatError, Message, PluralCategory, ValueKind};
+
+    // A branch of text followed by the number, as '#' is only parsed
+    // within a plural.
+    fn with_number(text: &str) -> Message {
+        Message::new(vec![Box::new(PlainText::new(text)), Box::new(PlaceholderFormat::new())])
+    }
 
     #[test]
     fn it_works() {
@@ -182,4 +337,98 @@ mod tests {
         let output = format_message!(ctx, &msg, count => 0);
         assert_eq!("Other", output);
     }
+
+    #[test]
+    fn decimals_keep_fraction_digits() {
+        let ctx = Context::default();
+
+        let mut fmt = PluralFormat::new("count", with_number("Other "));
+        fmt.one(with_number("One "));
+        fmt.literal(1, parse("Exactly one").unwrap());
+        fmt.offset(1);
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        let output = format_message!(ctx, &msg, count => "1.0".parse::<Decimal>().unwrap());
+        assert_eq!("Exactly one", output);
+
+        let output = format_message!(ctx, &msg, count => "2.50".parse::<Decimal>().unwrap());
+        assert_eq!("Other 1.50", output);
+
+        let output = format_message!(ctx, &msg, count => "2.0".parse::<Decimal>().unwrap());
+        assert_eq!("Other 1.0", output);
+
+        let output = format_message!(ctx, &msg, count => "2".parse::<Decimal>().unwrap());
+        assert_eq!("One 1", output);
+    }
+
+    #[test]
+    fn literals_match_source_number() {
+        let ctx = Context::default();
+
+        let mut fmt = PluralFormat::new("count", with_number("Other "));
+        fmt.one(with_number("One "));
+        fmt.literal(1, parse("Exactly one").unwrap());
+        fmt.decimal_literal(0.5, parse("Half").unwrap());
+        fmt.offset(1);
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        let output = format_message!(ctx, &msg, count => 1);
+        assert_eq!("Exactly one", output);
+
+        let output = format_message!(ctx, &msg, count => 2);
+        assert_eq!("One 1", output);
+
+        let output = format_message!(ctx, &msg, count => 1.0);
+        assert_eq!("Exactly one", output);
+
+        let output = format_message!(ctx, &msg, count => 0.5);
+        assert_eq!("Half", output);
+
+        let output = format_message!(ctx, &msg, count => 2.5);
+        assert_eq!("Other 1.5", output);
+    }
+
+    #[test]
+    fn registered_rules_choose_categories() {
+        let m = parse("{n, plural, =0 {none} one {# one} few {# few} many {# many} other {# other}}").unwrap();
+        let mut ctx = Context::new("ru".parse().unwrap(), None);
+        ctx.data_provider = None;
+        // Without registered rules, the built-in rules are used.
+        assert_eq!(format_message!(ctx, &m, n => 3), "3 few");
+        assert_eq!(format_message!(ctx, &m, n => 5), "5 many");
+
+        let ru: LanguageTag = "ru".parse().unwrap();
+        ctx.set_plural_rules(ru.clone(), PluralRules::cardinal(&ru));
+        assert_eq!(format_message!(ctx, &m, n => 0), "none");
+        assert_eq!(format_message!(ctx, &m, n => 21), "21 one");
+        assert_eq!(format_message!(ctx, &m, n => 3), "3 few");
+        assert_eq!(format_message!(ctx, &m, n => 11), "11 many");
+        let d: Decimal = "1.5".parse().unwrap();
+        assert_eq!(format_message!(ctx, &m, n => d), "1.5 other");
+
+        // Language rules apply to regional variants without their own.
+        ctx.language_tag = "ru-UA".parse().unwrap();
+        assert_eq!(format_message!(ctx, &m, n => 3), "3 few");
+        ctx.set_plural_rules("ru-UA".parse().unwrap(), |_: &PluralOperands| PluralCategory::Many);
+        assert_eq!(format_message!(ctx, &m, n => 3), "3 many");
+        ctx.language_tag = "en".parse().unwrap();
+        assert_eq!(format_message!(ctx, &m, n => 3), "3 other");
+    }
+
+    #[test]
+    fn text_is_a_type_mismatch() {
+        let m = parse("{n, plural, one {# file} other {# files}}").unwrap();
+        let ctx = Context::default();
+        let mismatch = FormatError::TypeMismatch {
+            argument: "n".to_string(),
+            expected: ValueKind::Number,
+            actual: ValueKind::Str,
+        };
+        assert_eq!(ctx.try_format(&m, &arg("n", "3")), Err(mismatch.clone()));
+
+        // Errors from nested messages are returned as they are.
+        let nested = parse("{g, select, other {{n, plural, other {# files}}}}").unwrap();
+        assert_eq!(ctx.try_format(&nested, &arg("g", "x").arg("n", "3")), Err(mismatch));
+        assert_eq!(ctx.try_format(&m, &arg("n", 3)).unwrap(), "3 files");
+    }
 }
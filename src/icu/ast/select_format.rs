@@ -6,16 +6,59 @@
 
 use std::fmt;
 
-use {Args, Context, Message, MessagePart, Value};
+use normalize::compose;
+use {Args, Context, FormatError, Message, MessagePart, SelectMatching};
 
-#[derive(Debug)]
+/// A message used when the value matches `value`, or one of
+/// `aliases`.
+#[derive(Debug, PartialEq)]
 pub struct SelectMapping {
+    /// The value to match.
     pub value: String,
+    /// Other values sharing the message, written `ios|android {...}`
+    /// when parsing with [extensions].
+    ///
+    /// [extensions]: ../parse/struct.ParseOptions.html#structfield.extensions
+    pub aliases: Vec<String>,
+    /// The message to use when the value matches.
     pub message: Message,
 }
 
+impl SelectMapping {
+    /// Whether `value` selects this mapping's message.
+    pub fn matches(&self, value: &str) -> bool {
+        self.value == value || self.aliases.iter().any(|alias| alias == value)
+    }
+
+    /// Whether `value` selects this mapping's message when compared
+    /// using `matching`.
+    pub fn matches_with(&self, matching: SelectMatching, value: &str) -> bool {
+        if matching == SelectMatching::default() {
+            return self.matches(value);
+        }
+        let value = if matching.normalize { compose(value) } else { value.into() };
+        let eq = |key: &str| {
+            let key = if matching.normalize { compose(key) } else { key.into() };
+            key == value || matching.ignore_ascii_case && key.eq_ignore_ascii_case(&value)
+        };
+        eq(&self.value) || self.aliases.iter().any(|alias| eq(alias))
+    }
+}
+
 /// Using a value, select the appropriate message and format it.
-#[derive(Debug)]
+///
+/// Strings are matched directly. Other values are matched using
+/// their formatted text, so a `bool` can select a `true` or `false`
+/// branch.
+///
+/// A value without a branch of its own can fall back to the branch
+/// of another value, declared with [`fallback`], before falling back
+/// to `other`. This lets a policy such as "`she` and `he` use the
+/// `female` and `male` branches when a translation has them" be
+/// applied without duplicating translations.
+///
+/// [`fallback`]: #method.fallback
+#[derive(Debug, PartialEq)]
 pub struct SelectFormat {
     /// The name of the variable whose value should be formatted.
     pub variable_name: String,
@@ -23,7 +66,15 @@ pub struct SelectFormat {
     pub mappings: Vec<SelectMapping>,
     /// The message format to use if no valid mapping is found for
     /// the variable value.
-    default: Message,
+    pub default: Message,
+    /// Values to try, in turn, for a value without a mapping. These
+    /// are not part of the ICU syntax and are lost when serializing.
+    pub fallbacks: Vec<(String, String)>,
+    /// How the value is compared with the keys of `mappings`. When
+    /// this is `None`, the context's [`select_matching`] is used.
+    ///
+    /// [`select_matching`]: ../../struct.Context.html#structfield.select_matching
+    pub matching: Option<SelectMatching>,
 }
 
 impl SelectFormat {
@@ -32,24 +83,98 @@ impl SelectFormat {
         SelectFormat {
             variable_name: variable_name.to_string(),
             mappings: vec![],
-            default: default,
+            default,
+            fallbacks: vec![],
+            matching: None,
         }
     }
 
     /// Map a value for a particular message.
     pub fn map(&mut self, value: &str, message: Message) {
+        self.map_all(&[value], message);
+    }
+
+    /// Map several values for the same message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty.
+    pub fn map_all(&mut self, values: &[&str], message: Message) {
+        let (value, aliases) = values.split_first().expect("no values to map");
         self.mappings.push(SelectMapping {
             value: value.to_string(),
-            message: message,
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            message,
         });
     }
 
-    /// Given a value, determine which `Message` to use.
+    /// Use the branch for `fallback` when `value` has none, replacing
+    /// any fallback declared for `value` before. Fallbacks chain, so
+    /// `fallback` may itself fall back to another value.
+    pub fn fallback(&mut self, value: &str, fallback: &str) {
+        self.fallbacks.retain(|(from, _)| from != value);
+        self.fallbacks.push((value.to_string(), fallback.to_string()));
+    }
+
+    /// The values tried, in order, when selecting a message for
+    /// `value`. This follows the declared fallbacks, stopping at a
+    /// cycle, and always ends with `other`.
+    ///
+    /// ```
+    /// use message_format::icu::ast::SelectFormat;
+    /// use message_format::Message;
+    ///
+    /// let mut fmt = SelectFormat::new("pronoun", Message::default());
+    /// fmt.fallback("she", "female");
+    /// fmt.fallback("female", "other");
+    /// assert_eq!(fmt.resolution_order("she"), ["she", "female", "other"]);
+    /// assert_eq!(fmt.resolution_order("they"), ["they", "other"]);
+    /// ```
+    pub fn resolution_order<'a>(&'a self, value: &'a str) -> Vec<&'a str> {
+        let mut order = vec![value];
+        let mut current = value;
+        while let Some((_, next)) = self.fallbacks.iter().find(|(from, _)| from == current) {
+            if order.contains(&next.as_str()) {
+                break;
+            }
+            order.push(next);
+            current = next;
+        }
+        if current != "other" {
+            order.push("other");
+        }
+        order
+    }
+
+    /// Given a value, determine which `Message` to use. Values are
+    /// compared using `matching`, or exactly if it isn't set.
     pub fn lookup_message(&self, value: &str) -> &Message {
+        self.lookup_message_with(self.matching.unwrap_or_default(), value)
+    }
+
+    /// Given a value, determine which `Message` to use when formatting
+    /// with `ctx`.
+    pub fn select_message(&self, ctx: &Context, value: &str) -> &Message {
+        self.lookup_message_with(self.matching.unwrap_or(ctx.select_matching), value)
+    }
+
+    fn lookup_message_with(&self, matching: SelectMatching, value: &str) -> &Message {
+        if self.fallbacks.is_empty() {
+            return self.mapped_message(matching, value).unwrap_or(&self.default);
+        }
+        self.resolution_order(value)
+            .into_iter()
+            .take_while(|&value| value != "other")
+            .filter_map(|value| self.mapped_message(matching, value))
+            .next()
+            .unwrap_or(&self.default)
+    }
+
+    fn mapped_message(&self, matching: SelectMatching, value: &str) -> Option<&Message> {
         self.mappings
             .iter()
-            .find(|mapping| mapping.value == value)
-            .map_or(&self.default, |mapping| &mapping.message)
+            .find(|mapping| mapping.matches_with(matching, value))
+            .map(|mapping| &mapping.message)
     }
 }
 
@@ -60,18 +185,41 @@ impl MessagePart for SelectFormat {
         stream: &mut dyn fmt::Write,
         args: &'f dyn Args<'f>,
     ) -> fmt::Result {
+        self.try_apply_format(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
         let arg = args.get(&self.variable_name);
-        if let Some(&Value::Str(value)) = arg {
-            let message = self.lookup_message(value);
-            message.write_message(ctx, stream, args)?;
-            Ok(())
+        if let Some(value) = arg {
+            let message = self.select_message(ctx, &value.select_key());
+            message.try_write_message(ctx, stream, args)
         } else {
-            Err(fmt::Error {})
+            Err(FormatError::Format)
         }
     }
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn children(&self) -> Vec<&Message> {
+        let mut children: Vec<&Message> = self.mappings.iter().map(|mapping| &mapping.message).collect();
+        children.push(&self.default);
+        children
+    }
+    fn children_mut(&mut self) -> Vec<&mut Message> {
+        let mut children: Vec<&mut Message> = self.mappings.iter_mut().map(|mapping| &mut mapping.message).collect();
+        children.push(&mut self.default);
+        children
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
 }
 
 #[cfg(test)]
@@ -95,4 +243,60 @@ mod tests {
         let output = format_message!(ctx, &msg, type => "span");
         assert_eq!("Default", output);
     }
+
+    #[test]
+    fn fallbacks_chain() {
+        let ctx = Context::default();
+
+        let mut fmt = SelectFormat::new("who", parse("They").unwrap());
+        fmt.map("female", parse("She").unwrap());
+        fmt.fallback("she", "female");
+        fmt.fallback("her", "she");
+        fmt.fallback("he", "male");
+        fmt.fallback("male", "he");
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        assert_eq!(format_message!(ctx, &msg, who => "her"), "She");
+        assert_eq!(format_message!(ctx, &msg, who => "he"), "They");
+        assert_eq!(format_message!(ctx, &msg, who => "it"), "They");
+    }
+
+    #[test]
+    fn matching_can_ignore_case_and_normalize() {
+        let mut ctx = Context::default();
+
+        let mut fmt = SelectFormat::new("who", parse("Other").unwrap());
+        fmt.map("male", parse("Male").unwrap());
+        fmt.map("caf\u{e9}", parse("Cafe").unwrap());
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        assert_eq!(format_message!(ctx, &msg, who => "MALE"), "Other");
+        ctx.select_matching.ignore_ascii_case = true;
+        assert_eq!(format_message!(ctx, &msg, who => "MALE"), "Male");
+        assert_eq!(format_message!(ctx, &msg, who => "Cafe\u{301}"), "Other");
+        ctx.select_matching.normalize = true;
+        assert_eq!(format_message!(ctx, &msg, who => "CAFe\u{301}"), "Cafe");
+        ctx.select_matching.ignore_ascii_case = false;
+        assert_eq!(format_message!(ctx, &msg, who => "cafe\u{301}"), "Cafe");
+        assert_eq!(format_message!(ctx, &msg, who => "Male"), "Other");
+    }
+
+    #[test]
+    fn non_strings_work() {
+        let ctx = Context::default();
+
+        let mut fmt = SelectFormat::new("admin", parse("Default").unwrap());
+        fmt.map("true", parse("Admin").unwrap());
+        fmt.map("2", parse("Two").unwrap());
+        let msg = Message::new(vec![Box::new(fmt)]);
+
+        let output = format_message!(ctx, &msg, admin => true);
+        assert_eq!("Admin", output);
+
+        let output = format_message!(ctx, &msg, admin => false);
+        assert_eq!("Default", output);
+
+        let output = format_message!(ctx, &msg, admin => 2);
+        assert_eq!("Two", output);
+    }
 }
@@ -9,7 +9,7 @@ use std::fmt;
 use {Args, Context, MessagePart};
 
 /// A simple message consisting of a value to be formatted.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct SimpleFormat {
     /// The name of the variable whose value should be formatted.
     pub variable_name: String,
@@ -27,14 +27,14 @@ impl SimpleFormat {
 impl MessagePart for SimpleFormat {
     fn apply_format<'f>(
         &self,
-        _ctx: &Context,
+        ctx: &Context,
         stream: &mut dyn fmt::Write,
         args: &'f dyn Args<'f>,
     ) -> fmt::Result {
         let arg = args.get(&self.variable_name);
         if let Some(arg) = arg {
-            write!(stream, "{}", arg)?;
-            Ok(())
+            let overrides = args.number_options(&self.variable_name);
+            ctx.write_isolated(stream, |stream| arg.format_with(ctx, overrides.as_ref(), stream))
         } else {
             Err(fmt::Error {})
         }
@@ -42,12 +42,18 @@ impl MessagePart for SimpleFormat {
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::SimpleFormat;
-    use {Context, Message};
+    use {arg, Context, Message};
 
     #[test]
     fn it_works() {
@@ -60,4 +66,43 @@ mod tests {
         let output = format_message!(ctx, &msg, name => "John");
         assert_eq!("John", output);
     }
+
+    #[test]
+    fn numbers_are_localized() {
+        use icu::parse;
+
+        let m = parse("{count} {n, plural, other {#}} {count, number, raw}").unwrap();
+        let ctx = Context::default();
+        assert_eq!(format_message!(ctx, &m, count => 12345, n => 12345), "12,345 12,345 12345");
+        let ctx = Context::new("en-u-nu-arab".parse().unwrap(), None);
+        assert_eq!(format_message!(ctx, &m, count => 1.5, n => 1.5), "١.٥ ١.٥ 1.5");
+        assert_eq!(
+            format_message!(ctx, &m, count => i64::MAX, n => 0),
+            "٩,٢٢٣,٣٧٢,٠٣٦,٨٥٤,٧٧٥,٨٠٧ ٠ 9223372036854775807"
+        );
+    }
+
+    #[test]
+    fn large_integers_are_written_alike() {
+        use icu::parse;
+
+        let m = parse("{n} {n, number} {n, plural, other {#}}").unwrap();
+        let ctx = Context::default();
+        let exact = (1i64 << 53) + 1;
+        assert_eq!(
+            format_message!(ctx, &m, n => exact),
+            "9,007,199,254,740,993 9,007,199,254,740,993 9,007,199,254,740,993"
+        );
+        assert_eq!(
+            format_message!(ctx, &m, n => i64::MAX),
+            "9,223,372,036,854,775,807 9,223,372,036,854,775,807 9,223,372,036,854,775,807"
+        );
+        let compiled = m.compile();
+        assert_eq!(
+            compiled.format_with(&ctx, &arg("n", i64::MIN)),
+            "-9,223,372,036,854,775,808 -9,223,372,036,854,775,808 -9,223,372,036,854,775,808"
+        );
+        let m = parse("{n, plural, offset:1 other {#}}").unwrap();
+        assert_eq!(format_message!(ctx, &m, n => i64::MAX), "9,223,372,036,854,775,806");
+    }
 }
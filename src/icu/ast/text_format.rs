@@ -0,0 +1,76 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use {Args, CaseTransform, Context, MessagePart};
+
+/// Format a value as text with a change of case, such as
+/// `{name, text, capitalize}`.
+#[derive(Debug, PartialEq)]
+pub struct TextFormat {
+    /// The name of the variable whose value should be formatted.
+    pub variable_name: String,
+    /// The change of case to apply to the formatted value.
+    pub transform: CaseTransform,
+}
+
+impl TextFormat {
+    /// Construct a `TextFormat`.
+    pub fn new(variable_name: &str, transform: CaseTransform) -> Self {
+        TextFormat {
+            variable_name: variable_name.to_string(),
+            transform,
+        }
+    }
+}
+
+impl MessagePart for TextFormat {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        let value = args.get(&self.variable_name).ok_or(fmt::Error {})?;
+        let mut text = String::new();
+        value.format(ctx, &mut text)?;
+        ctx.write_isolated(stream, |stream| {
+            stream.write_str(&self.transform.apply(&text, &ctx.language_tag))
+        })
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::{parse, serialize};
+    use Context;
+
+    #[test]
+    fn it_works() {
+        let ctx = Context::default();
+        let m = parse("{name, text, capitalize} and {name, text, upper} met {other, text, title}.").unwrap();
+        assert_eq!(
+            format_message!(ctx, &m, name => "émile", other => "jean-luc picard"),
+            "Émile and ÉMILE met Jean-Luc Picard."
+        );
+        assert_eq!(serialize(&m).unwrap(), "{name, text, capitalize} and {name, text, upper} met {other, text, title}.");
+        assert!(parse("{name, text, shout}").is_err());
+
+        let tr = Context::new("tr".parse().unwrap(), None);
+        let m = parse("{city, text, lower}").unwrap();
+        assert_eq!(format_message!(tr, &m, city => "DİYARBAKIR"), "diyarbakır");
+    }
+}
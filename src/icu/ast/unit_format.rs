@@ -0,0 +1,117 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use error;
+use number::{spell_out, write_number, write_unit, NumberStyle, UnitWidth};
+use {Args, Context, FormatError, MessagePart, Rendering, ValueKind};
+
+/// Format an amount of a measure unit, such as `5 kilometers`.
+///
+/// Languages without unit patterns are written with the English ones,
+/// which [`Context::missing_locale_data`] reports.
+///
+/// [`Context::missing_locale_data`]: ../../struct.Context.html#method.missing_locale_data
+#[derive(Debug, PartialEq)]
+pub struct UnitFormat {
+    /// The name of the variable whose value should be formatted.
+    pub variable_name: String,
+    /// The name of the unit, such as `kilometer`.
+    pub unit: String,
+    /// How long the name of the unit should be.
+    pub width: UnitWidth,
+}
+
+impl UnitFormat {
+    /// Construct a `UnitFormat`.
+    pub fn new(variable_name: &str, unit: &str, width: UnitWidth) -> Self {
+        UnitFormat {
+            variable_name: variable_name.to_string(),
+            unit: unit.to_string(),
+            width,
+        }
+    }
+}
+
+impl MessagePart for UnitFormat {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        self.try_apply_format(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        let value = args.get(&self.variable_name).ok_or(FormatError::Format)?;
+        let number = value
+            .number()
+            .ok_or_else(|| error::mismatch(&self.variable_name, ValueKind::Number, value))?;
+        let options = match args.number_options(&self.variable_name) {
+            Some(ref overrides) => ctx.number_options.or(overrides),
+            None => ctx.number_options,
+        };
+        ctx.write_isolated(stream, |stream| {
+            ctx.with_number_locale(|locale| {
+                let digits = value.fraction_digits();
+                if ctx.rendering == Rendering::Visual {
+                    return write_unit(locale, number, digits, &self.unit, self.width, &options, stream);
+                }
+                // Spoken units are written in full, with the number in
+                // words where it can be.
+                let mut unit = String::new();
+                write_unit(locale, number, digits, &self.unit, UnitWidth::Long, &options, &mut unit)?;
+                if let (None, Some(words)) = (digits, spell_out(&ctx.language_tag, number.as_f64())) {
+                    let mut numeral = String::new();
+                    write_number(locale, number, digits, NumberStyle::Decimal, &options, &mut numeral)?;
+                    unit = unit.replacen(&numeral, &words, 1);
+                }
+                stream.write_str(&unit)
+            })
+        })?;
+        Ok(())
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::parse;
+    use Context;
+
+    #[test]
+    fn it_works() {
+        let ctx = Context::default();
+        let m = parse("{d, unit, kilometer, long} ({d, unit, kilometer}, {t, unit, minute, narrow})").unwrap();
+        assert_eq!(format_message!(ctx, &m, d => 1, t => 90), "1 kilometer (1 km, 90m)");
+        assert_eq!(format_message!(ctx, &m, d => 5.25, t => 1), "5.25 kilometers (5.25 km, 1m)");
+        assert!(parse("{d, unit, furlong}").is_err());
+        assert!(parse("{d, unit, meter, tiny}").is_err());
+    }
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn units_are_pluralized_per_locale() {
+        let ctx = Context::new("de".parse().unwrap(), None);
+        let m = parse("{n, unit, day, long}").unwrap();
+        assert_eq!(format_message!(ctx, &m, n => 1), "1 Tag");
+        assert_eq!(format_message!(ctx, &m, n => 3), "3 Tage");
+    }
+}
@@ -0,0 +1,61 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use {Args, Context, MessagePart};
+
+/// An argument with a format type this crate doesn't support, such
+/// as `{when, datetime, weird-style}`, produced when parsing in
+/// [lenient] mode.
+///
+/// The argument is formatted as is, using its `Display`
+/// implementation, so that the rest of the message still works.
+///
+/// [lenient]: ../parse/struct.ParseOptions.html#structfield.lenient
+#[derive(Debug, PartialEq)]
+pub struct UnknownFormat {
+    /// The name of the variable whose value should be formatted.
+    pub variable_name: String,
+    /// The name of the unsupported format type.
+    pub type_name: String,
+    /// The style given after the type, if any, as written.
+    pub style: Option<String>,
+}
+
+impl UnknownFormat {
+    /// Construct an `UnknownFormat`.
+    pub fn new(variable_name: &str, type_name: &str, style: Option<&str>) -> Self {
+        UnknownFormat {
+            variable_name: variable_name.to_string(),
+            type_name: type_name.to_string(),
+            style: style.map(str::to_string),
+        }
+    }
+}
+
+impl MessagePart for UnknownFormat {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        match args.get(&self.variable_name) {
+            Some(arg) => ctx.write_isolated(stream, |stream| write!(stream, "{}", arg)),
+            None => Err(fmt::Error {}),
+        }
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
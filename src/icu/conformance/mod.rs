@@ -0,0 +1,103 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A harness running the ICU conformance cases in `cases.txt`.
+//!
+//! Not every case is expected to pass; the harness reports the
+//! conformance percentage and fails when the cases failing differ
+//! from `EXPECTED_FAILURES`. Remove a case from that list when
+//! support for it is added.
+//!
+//! Run `cargo test conformance -- --nocapture` to see the report.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use super::parse;
+use {Args, Context, DateTime, Value};
+
+/// The patterns of the cases known to fail.
+const EXPECTED_FAILURES: &[&str] = &[
+    // `selectordinal` isn't supported.
+    "{n, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}",
+    "It's my cat's {year, selectordinal, one {#st} two {#nd} few {#rd} other {#th}} birthday!",
+];
+
+struct CaseArgs<'a>(&'a [(String, Value<'static>)]);
+
+impl<'a> Args<'a> for CaseArgs<'a> {
+    fn get(&self, name: &str) -> Option<&'a Value<'_>> {
+        self.0.iter().find(|&(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+fn parse_value(value: &str) -> Value<'static> {
+    if let Some(timestamp) = value.strip_prefix('@') {
+        Value::Date(DateTime::from_timestamp(timestamp.parse().unwrap()))
+    } else if let Ok(n) = value.parse::<i64>() {
+        Value::Number(n)
+    } else if let Ok(f) = value.parse::<f64>() {
+        Value::Float(f)
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+fn parse_args(args: &str) -> Vec<(String, Value<'static>)> {
+    if args == "-" {
+        return vec![];
+    }
+    args.split(';')
+        .map(|pair| {
+            let mut split = pair.splitn(2, '=');
+            let name = split.next().unwrap().to_string();
+            (name, parse_value(split.next().unwrap_or("")))
+        })
+        .collect()
+}
+
+/// Run a single case, returning the actual output on failure.
+fn run_case(pattern: &str, args: &CaseArgs, expected: &str) -> Result<(), String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        parse(pattern).map(|m| Context::default().format(&m, args))
+    }));
+    match (result, expected) {
+        (Err(_), _) => Err("panicked".to_string()),
+        (Ok(Err(_)), "!error") => Ok(()),
+        (Ok(Err(e)), _) => Err(format!("error: {}", e)),
+        (Ok(Ok(output)), _) if output == expected => Ok(()),
+        (Ok(Ok(output)), _) => Err(format!("{:?}", output)),
+    }
+}
+
+#[test]
+fn conformance() {
+    let mut failures = vec![];
+    let mut total = 0;
+    for line in include_str!("cases.txt").lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert_eq!(fields.len(), 3, "malformed case {:?}", line);
+        total += 1;
+        let args = parse_args(fields[1]);
+        if let Err(actual) = run_case(fields[0], &CaseArgs(&args), fields[2]) {
+            println!("FAIL {:?}: expected {:?}, got {}", fields[0], fields[2], actual);
+            failures.push(fields[0]);
+        }
+    }
+    let passed = total - failures.len();
+    println!(
+        "ICU conformance: {}/{} cases pass ({:.1}%)",
+        passed,
+        total,
+        100.0 * passed as f64 / total as f64
+    );
+    let unexpected: Vec<&&str> = failures.iter().filter(|case| !EXPECTED_FAILURES.contains(case)).collect();
+    assert!(unexpected.is_empty(), "conformance regressed: {:?}", unexpected);
+    let fixed: Vec<&&str> = EXPECTED_FAILURES.iter().filter(|case| !failures.contains(case)).collect();
+    assert!(fixed.is_empty(), "cases now pass, remove them from EXPECTED_FAILURES: {:?}", fixed);
+}
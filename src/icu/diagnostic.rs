@@ -0,0 +1,34 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::ops::Range;
+
+/// A problem found in a message, with the byte range of the source
+/// text it applies to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The byte range of the source text the diagnostic applies to.
+    pub span: Range<usize>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Construct a `Diagnostic`.
+    pub fn new(span: Range<usize>, message: &str) -> Self {
+        Diagnostic {
+            span,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}: {}", self.span.start, self.span.end, self.message)
+    }
+}
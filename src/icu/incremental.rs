@@ -0,0 +1,280 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ops::Range;
+
+use super::ast::PlainText;
+use super::parse::message_part;
+use super::Diagnostic;
+use {Message, MessagePart};
+
+/// A change to the source of a message: the bytes in `range` are
+/// replaced by `replacement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The byte range of the old source text being replaced.
+    pub range: Range<usize>,
+    /// The new text.
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Construct a `TextEdit`.
+    pub fn new(range: Range<usize>, replacement: &str) -> Self {
+        TextEdit {
+            range,
+            replacement: replacement.to_string(),
+        }
+    }
+}
+
+/// A top level part of a message along with the byte range of the
+/// source text it was parsed from.
+#[derive(Debug)]
+pub struct SpannedPart {
+    /// The byte range of the source text.
+    pub span: Range<usize>,
+    /// The parsed part.
+    pub part: Box<dyn MessagePart>,
+}
+
+/// The result of parsing with a [`Parser`]: the source text, its top
+/// level parts and any problems found.
+///
+/// Parsing stops at the first part that can't be parsed, which is
+/// reported as a diagnostic.
+///
+/// [`Parser`]: struct.Parser.html
+#[derive(Debug)]
+pub struct ParsedMessage {
+    source: String,
+    parts: Vec<SpannedPart>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedMessage {
+    /// The source text that was parsed.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The top level parts, in source order.
+    pub fn parts(&self) -> &[SpannedPart] {
+        &self.parts
+    }
+
+    /// The problems found while parsing.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Convert into a `Message`, dropping the source and spans.
+    pub fn into_message(self) -> Message {
+        Message::new(self.parts.into_iter().map(|p| p.part).collect())
+    }
+}
+
+/// Parses messages for editor tooling, where the same message is
+/// parsed again after every small edit.
+///
+/// ```
+/// use message_format::icu::{Parser, TextEdit};
+///
+/// let parsed = Parser::parse("Hi {name}, you have {n} new messages.");
+/// let parsed = Parser::reparse(parsed, &TextEdit::new(0..2, "Hello"));
+/// assert_eq!(parsed.source(), "Hello {name}, you have {n} new messages.");
+/// assert!(parsed.diagnostics().is_empty());
+/// ```
+#[derive(Debug)]
+pub struct Parser;
+
+fn is_text(part: &SpannedPart) -> bool {
+    part.part.as_any().is::<PlainText>()
+}
+
+// Parse parts of `source` starting at `pos`, appending them to `parts`.
+// Parsing stops at the end of the source, at an error or when
+// `resume` returns true for the position of the next part.
+fn parse_parts<F>(
+    source: &str,
+    mut pos: usize,
+    parts: &mut Vec<SpannedPart>,
+    diagnostics: &mut Vec<Diagnostic>,
+    mut resume: F,
+) -> usize
+where
+    F: FnMut(usize, Option<&SpannedPart>) -> bool,
+{
+    while pos < source.len() && !resume(pos, parts.last()) {
+        match message_part(&source[pos..]) {
+            Ok((rest, part)) => {
+                let end = source.len() - rest.len();
+                parts.push(SpannedPart {
+                    span: pos..end,
+                    part,
+                });
+                pos = end;
+            }
+            Err(_) => {
+                diagnostics.push(Diagnostic::new(pos..source.len(), "unexpected input"));
+                break;
+            }
+        }
+    }
+    pos
+}
+
+impl Parser {
+    /// Parse a message from scratch.
+    pub fn parse(source: &str) -> ParsedMessage {
+        let mut parts = vec![];
+        let mut diagnostics = vec![];
+        parse_parts(source, 0, &mut parts, &mut diagnostics, |_, _| false);
+        ParsedMessage {
+            source: source.to_string(),
+            parts,
+            diagnostics,
+        }
+    }
+
+    /// Apply `edit` to a previously parsed message and parse it again,
+    /// reusing the parts before and after the edited region.
+    ///
+    /// The result is the same as parsing the edited source from
+    /// scratch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of the edit is out of bounds or doesn't lie
+    /// on `char` boundaries, like `String::replace_range`.
+    pub fn reparse(old: ParsedMessage, edit: &TextEdit) -> ParsedMessage {
+        let ParsedMessage {
+            mut source,
+            parts: old_parts,
+            diagnostics: old_diagnostics,
+        } = old;
+        let old_len = source.len();
+        source.replace_range(edit.range.clone(), &edit.replacement);
+        let edit_end = edit.range.start + edit.replacement.len();
+        let shift = |n: usize| n + source.len() - old_len;
+
+        // Parts ending before the edit are kept. A trailing text part
+        // is parsed again, as new text may need to be merged into it.
+        let mut parts = vec![];
+        let mut after = vec![];
+        for part in old_parts {
+            if part.span.end < edit.range.start {
+                parts.push(part);
+            } else if part.span.start >= edit.range.end {
+                after.push(part);
+            }
+        }
+        if parts.last().is_some_and(is_text) {
+            parts.pop();
+        }
+        let start = parts.last().map_or(0, |p| p.span.end);
+
+        // Parse until reaching the start of an old part after the edit,
+        // which can then be reused along with everything following it.
+        let mut diagnostics = vec![];
+        let mut reuse_from = None;
+        parse_parts(&source, start, &mut parts, &mut diagnostics, |pos, last| {
+            if pos < edit_end {
+                return false;
+            }
+            let index = after.iter().position(|p| shift(p.span.start) == pos);
+            match index {
+                Some(i) if !(last.is_some_and(is_text) && is_text(&after[i])) => {
+                    reuse_from = Some(i);
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        if let Some(i) = reuse_from {
+            parts.extend(after.into_iter().skip(i).map(|p| SpannedPart {
+                span: shift(p.span.start)..shift(p.span.end),
+                part: p.part,
+            }));
+            diagnostics.extend(
+                old_diagnostics
+                    .into_iter()
+                    .filter(|d| d.span.start >= edit.range.end)
+                    .map(|d| Diagnostic {
+                        span: shift(d.span.start)..shift(d.span.end),
+                        message: d.message,
+                    }),
+            );
+        }
+
+        ParsedMessage {
+            source,
+            parts,
+            diagnostics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParsedMessage, Parser, TextEdit};
+    use icu::Diagnostic;
+
+    fn address(parsed: &ParsedMessage, index: usize) -> *const u8 {
+        &*parsed.parts()[index].part as *const _ as *const u8
+    }
+
+    fn assert_same_as_full_parse(parsed: &ParsedMessage) {
+        let full = Parser::parse(parsed.source());
+        assert_eq!(parsed.parts().len(), full.parts().len(), "{:?}", parsed.source());
+        for (a, b) in parsed.parts().iter().zip(full.parts()) {
+            assert_eq!(a.span, b.span);
+            assert!(a.part.eq_part(b.part.as_ref()));
+        }
+        assert_eq!(parsed.diagnostics(), full.diagnostics());
+    }
+
+    #[test]
+    fn unchanged_parts_are_reused() {
+        let parsed = Parser::parse("{a} and {b}, {n, plural, one {#} other {# more}}");
+        let first = address(&parsed, 0);
+        let plural = address(&parsed, 4);
+
+        let parsed = Parser::reparse(parsed, &TextEdit::new(8..11, "{bee}"));
+        assert_eq!(parsed.source(), "{a} and {bee}, {n, plural, one {#} other {# more}}");
+        assert_same_as_full_parse(&parsed);
+        assert_eq!(address(&parsed, 0), first);
+        assert_eq!(address(&parsed, 4), plural);
+    }
+
+    #[test]
+    fn matches_full_parse() {
+        let source = "Hi {name}, # {n, select, a {x} other {y}} bye";
+        let edits = [
+            TextEdit::new(0..0, "Oh "),
+            TextEdit::new(2..3, ""),
+            TextEdit::new(9..9, " there"),
+            TextEdit::new(9..10, "{"),
+            TextEdit::new(12..13, "text"),
+            TextEdit::new(source.len()..source.len(), "}"),
+            TextEdit::new(0..source.len(), ""),
+        ];
+        for edit in &edits {
+            let parsed = Parser::reparse(Parser::parse(source), edit);
+            assert_same_as_full_parse(&parsed);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let parsed = Parser::parse("Hi {name");
+        assert_eq!(parsed.diagnostics(), &[Diagnostic::new(3..8, "unexpected input")]);
+        let parsed = Parser::reparse(parsed, &TextEdit::new(8..8, "}"));
+        assert!(parsed.diagnostics().is_empty());
+        assert_eq!(parsed.into_message(), ::icu::parse("Hi {name}").unwrap());
+    }
+}
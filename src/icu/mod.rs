@@ -37,17 +37,100 @@
 //!
 //! ## Pluralized Messages
 //!
-//! Parsing of `plural` messages is not yet supported.
+//! A `plural` message chooses a branch by the plural category of a
+//! number in the context's language. Exact values are matched with
+//! `=`, and `#` is replaced by the number:
+//!
+//! ```text
+//! "{count, plural, =0 {No files} one {# file} other {# files}}"
+//! ```
 //!
 //! ## Select Messages
 //!
-//! Parsing of `select` messages is not yet supported.
+//! A `select` message chooses a branch by the value of a variable,
+//! falling back to `other`:
+//!
+//! ```text
+//! "{gender, select, female {She} male {He} other {They}} replied."
+//! ```
+//!
+//! ## Nested Messages
+//!
+//! Branches are themselves messages, so formats may be nested. The
+//! most common case selects on the gender of a subject and then
+//! pluralizes a count. Each branch should contain the whole sentence,
+//! rather than splicing fragments together, so that translators can
+//! reorder it:
+//!
+//! ```text
+//! "{gender, select,
+//!     female {{count, plural, one {She has a cat} other {She has # cats}}}
+//!     other {{count, plural, one {They have a cat} other {They have # cats}}}}"
+//! ```
+//!
+//! [`MessageBuilder::nested_select`] builds this shape from code and
+//! ensures every branch pluralizes the same variable.
+//!
+//! ## Quoting
+//!
+//! `#` only stands for the number within the branches of a plural,
+//! including those of a select nested in one; elsewhere it is plain
+//! text. As in ICU, an apostrophe before a brace, or before `#` in a
+//! plural, quotes the text up to the next apostrophe, and `''` is an
+//! apostrophe:
+//!
+//! ```text
+//! "Use '{'braces'}' for {count, plural, other {# items, not '#'1}}, don''t guess."
+//! ```
+//!
+//! ## Dates and Times
+//!
+//! A [`DateTime`] value can be formatted as a date or a time using
+//! one of the `short`, `medium` (the default), `long` or `full` styles:
+//!
+//! ```text
+//! "Your order shipped on {shipped, date, long}."
+//! ```
+//!
+//! Alternatively, a skeleton listing the desired fields can be given.
+//! The order of the fields and the punctuation between them is taken
+//! from the locale, so `::yMMMd` is formatted as "Oct 3, 2024" in
+//! English and "3 okt. 2024" in Swedish:
+//!
+//! ```text
+//! "Your order shipped on {shipped, date, ::yMMMd}."
+//! ```
+//!
+//! A `DateTime` constructed with [`DateTime::from_instant`] carries a
+//! [`TimeZone`], which the `long` and `full` time styles include:
+//!
+//! ```text
+//! "The meeting starts at {start, time, long}."
+//! ```
 //!
 //! [`icu::parse`]: fn.parse.html
+//! [`MessageBuilder::nested_select`]: ../struct.MessageBuilder.html#method.nested_select
 //! [`Message`]: ../struct.Message.html
+//! [`DateTime`]: ../struct.DateTime.html
+//! [`DateTime::from_instant`]: ../struct.DateTime.html#method.from_instant
+//! [`TimeZone`]: ../datetime/struct.TimeZone.html
 //! [ICU-style message formatting]: http://userguide.icu-project.org/formatparse/messages
 
 pub mod ast;
+mod diagnostic;
+mod incremental;
 pub mod parse;
+pub mod parser;
+mod recover;
+mod serialize;
+mod tokenize;
+
+#[cfg(test)]
+mod conformance;
 
-pub use self::parse::parse;
+pub use self::diagnostic::Diagnostic;
+pub use self::incremental::{ParsedMessage, Parser, SpannedPart, TextEdit};
+pub use self::parse::{parse, parse_with, ParseOptions};
+pub use self::recover::parse_recovering;
+pub use self::serialize::{serialize, write_message};
+pub use self::tokenize::{tokenize, Span, TokenKind};
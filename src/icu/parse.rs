@@ -4,40 +4,119 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::cell::Cell;
 use std::error::Error;
 use std::fmt;
 use std::str;
 
-use nom::character::complete::{ alphanumeric1, digit1, multispace0 };
-use nom::bytes::complete::{ tag, is_not, take_while };
-use nom::sequence::delimited;
-use nom::{dbg_dmp, IResult};
-use nom::combinator::{ opt, map_parser, flat_map, map };
-use nom::multi::many1;
+use nom::character::complete::{ alpha1, alphanumeric1, digit1 };
+use nom::bytes::complete::{ tag, is_not, take_while, take_while1 };
+use nom::sequence::{ delimited, pair, preceded, tuple };
+use nom::{Err, IResult};
+use nom::error::ErrorKind;
+use nom::combinator::{ opt, map, map_opt, map_res, recognize, value, verify };
+use nom::multi::{ many0, many1 };
 use nom::branch::alt;
 
 use super::ast;
-use super::ast::PlainText;
-use {Message, MessagePart};
+use datetime::{is_valid_pattern, DateTimeKind, DateTimeStyle};
+use number::{NumberStyle, UnitPatterns, UnitWidth};
+use {CaseTransform, Message, MessagePart};
 
 /// An error resulting from `parse`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ParseError {
     /// The message could not be parsed.
     NotImplemented,
+    /// An argument name is neither a valid name nor a valid number.
+    /// This holds the offending text.
+    InvalidArgumentName(String),
+    /// The message mixes the syntax of a [`Dialect`] with that of the
+    /// ICU Message Format, such as `{{name}}` with `{count, number}`.
+    ///
+    /// [`Dialect`]: ../../dialect/trait.Dialect.html
+    MixedSyntax,
+    /// The text from this byte offset couldn't be parsed, such as an
+    /// unclosed `{` or an unmatched `}`.
+    Unparsed(usize),
 }
 
-impl Error for ParseError {
-    fn description(&self) -> &str {
+impl Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
-            ParseError::NotImplemented => "Not implemented.",
+            ParseError::NotImplemented => "Not implemented.".fmt(f),
+            ParseError::InvalidArgumentName(ref name) => {
+                write!(f, "Invalid argument name: {:?}", name)
+            }
+            ParseError::MixedSyntax => "Mixed message syntaxes.".fmt(f),
+            ParseError::Unparsed(offset) => write!(f, "Unexpected text at offset {}.", offset),
         }
     }
 }
 
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        self.description().fmt(f)
+/// The format types understood by the parser.
+pub(crate) const FORMAT_TYPES: &[&str] = &["plural", "select", "date", "time", "number", "unit", "text"];
+
+/// Options controlling how [`parse_with`] parses a message.
+///
+/// [`parse_with`]: fn.parse_with.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Parse arguments with an unsupported format type, such as
+    /// `{when, datetime, weird-style}`, into an [`UnknownFormat`]
+    /// rather than failing, so that one unsupported feature doesn't
+    /// break a whole catalog.
+    ///
+    /// [`UnknownFormat`]: ../ast/struct.UnknownFormat.html
+    pub lenient: bool,
+    /// Accept syntax beyond the ICU Message Format:
+    ///
+    /// * `select` branches with several keys separated by `|` or `,`,
+    ///   as in `{os, select, ios|android {mobile} other {desktop}}`.
+    /// * References to other messages, as in `{@product.name}`, which
+    ///   are parsed into a [`MessageReference`].
+    /// * Arguments taken from the [attributes] of the context, as in
+    ///   `{$viewer_gender, select, ...}`.
+    ///
+    /// [`MessageReference`]: ../ast/struct.MessageReference.html
+    /// [attributes]: ../../struct.Context.html#structfield.attributes
+    pub extensions: bool,
+}
+
+thread_local! {
+    static OPTIONS: Cell<ParseOptions> = const { Cell::new(ParseOptions { lenient: false, extensions: false }) };
+}
+
+/// Restores the options in use before a `parse_with` call when dropped.
+pub(crate) struct OptionsGuard(ParseOptions);
+
+impl OptionsGuard {
+    /// Use `options` until the guard is dropped.
+    pub(crate) fn set(options: ParseOptions) -> Self {
+        OptionsGuard(OPTIONS.with(|current| current.replace(options)))
+    }
+}
+
+impl Drop for OptionsGuard {
+    fn drop(&mut self) {
+        OPTIONS.with(|options| options.set(self.0));
+    }
+}
+
+thread_local! {
+    // Whether the part being parsed is within a branch of a plural,
+    // where '#' stands for the number.
+    static IN_PLURAL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores whether parsing was within a plural when dropped.
+struct PluralGuard(bool);
+
+impl Drop for PluralGuard {
+    fn drop(&mut self) {
+        IN_PLURAL.with(|in_plural| in_plural.set(self.0));
     }
 }
 
@@ -46,13 +125,65 @@ fn mk_simple(name: &str) -> Box<dyn MessagePart> {
     Box::new(ast::SimpleFormat::new(name))
 }
 
+/// Whether `c` is in the Unicode `Pattern_White_Space` set.
+fn is_pattern_white_space(c: char) -> bool {
+    matches!(
+        c,
+        '\u{9}'..='\u{d}' | ' ' | '\u{85}' | '\u{200e}' | '\u{200f}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Whether `c` is in the Unicode `Pattern_Syntax` set.
+fn is_pattern_syntax(c: char) -> bool {
+    matches!(
+        c,
+        '!'..='/' | ':'..='@' | '['..='^' | '`' | '{'..='~'
+        | '\u{a1}'..='\u{a7}' | '\u{a9}' | '\u{ab}' | '\u{ac}' | '\u{ae}' | '\u{b0}' | '\u{b1}'
+        | '\u{b6}' | '\u{bb}' | '\u{bf}' | '\u{d7}' | '\u{f7}'
+        | '\u{2010}'..='\u{2027}' | '\u{2030}'..='\u{203e}' | '\u{2041}'..='\u{2053}'
+        | '\u{2055}'..='\u{205e}' | '\u{2190}'..='\u{245f}' | '\u{2500}'..='\u{2775}'
+        | '\u{2794}'..='\u{2bff}' | '\u{2e00}'..='\u{2e7f}' | '\u{3001}'..='\u{3003}'
+        | '\u{3008}'..='\u{3020}' | '\u{3030}' | '\u{fd3e}' | '\u{fd3f}' | '\u{fe45}'
+        | '\u{fe46}'
+    )
+}
+
+/// Whether `name` matches ICU's `argNameOrNumber`. A number is `0` or
+/// has no leading zeros, while a name may contain anything except
+/// pattern syntax and white space. Names may not start with a digit.
+pub(crate) fn is_valid_argument_name(name: &str) -> bool {
+    match name.chars().next() {
+        None => false,
+        Some('0') => name == "0",
+        Some(c) if c.is_ascii_digit() => name.chars().all(|c| c.is_ascii_digit()),
+        Some(_) => !name.chars().any(|c| is_pattern_syntax(c) || is_pattern_white_space(c)),
+    }
+}
+
+// Optional pattern white space between structural tokens.
+pub(crate) fn white_space(s: &str) -> IResult<&str, &str> {
+    take_while(is_pattern_white_space)(s)
+}
+
 // This grabs the variable name from a format, which is
 // the first thing after the '{' and extends to the first
-// ',' or '}'.
+// ',' or '}'. Surrounding white space is ignored.
 //
 // '{name}' has a variable name of 'name'.
+//
+// An invalid name is a failure rather than an error so that
+// the alternatives for other formats aren't tried.
 fn variable_name(s: &str) -> IResult<&str, &str> {
-    is_not(",}")(s)
+    let (rest, name) = is_not(",}")(s)?;
+    let name = name.trim_matches(is_pattern_white_space);
+    let attribute = |name: &str| {
+        OPTIONS.with(Cell::get).extensions && name.strip_prefix('$').is_some_and(is_valid_argument_name)
+    };
+    if is_valid_argument_name(name) || attribute(name) {
+        Ok((rest, name))
+    } else {
+        Err(Err::Failure((s, ErrorKind::Verify)))
+    }
 }
 
 // A simple format has only a name, delimited by braces.
@@ -67,50 +198,69 @@ pub fn simple_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
     )(s)
 }
 
+// A submessage is a complete message delimited by braces. It may
+// be empty and may itself contain nested formats.
 fn submessage(s: &str) -> IResult<&str, Message> {
     delimited(
         tag("{"),
-        map_parser(is_not("}"), message_parser),
+        map(many0(message_part), Message::new),
         tag("}")
     )(s)
 }
 
-fn plural_literal(s: &str) -> IResult<&str, PluralPart> {
+// A submessage of a plural, in which '#' is the number, as it is in
+// the branches of a select nested in it.
+fn plural_branch(s: &str) -> IResult<&str, Message> {
+    let _guard = PluralGuard(IN_PLURAL.with(|in_plural| in_plural.replace(true)));
+    submessage(s)
+}
+
+// A decimal number such as '3', '-1' or '0.5'.
+fn decimal(s: &str) -> IResult<&str, f64> {
+    map_res(
+        recognize(tuple((opt(tag("-")), digit1, opt(pair(tag("."), digit1))))),
+        str::parse
+    )(s)
+}
+
+pub(crate) fn plural_literal(s: &str) -> IResult<&str, PluralPart> {
     do_parse!(s,
         call!(tag("="))             >>
-        offset: call!(digit1)       >>
-        call!(opt(multispace0))     >>
-        msg: call!(submessage)      >>
-        multispace0                 >>
-        (PluralPart::Literal(offset.parse().unwrap(), msg))
+        value: decimal              >>
+        white_space                 >>
+        msg: call!(plural_branch)   >>
+        white_space                 >>
+        (PluralPart::Literal(value, msg))
     )
 }
 
-//one {1 day}
-fn plural_one(s: &str) -> IResult<&str,PluralPart> {
-    do_parse!(s,
-        multispace0             >>
-        tag!("one")             >>
-        multispace0             >>
-        msg: call!(submessage)  >>
-        multispace0             >>
-        (PluralPart::One(msg))
-    )
+// The keyword selecting a plural category.
+fn plural_keyword(s: &str) -> IResult<&str, fn(Message) -> PluralPart> {
+    alt((
+        value(PluralPart::Zero as fn(Message) -> PluralPart, tag("zero")),
+        value(PluralPart::One as fn(Message) -> PluralPart, tag("one")),
+        value(PluralPart::Two as fn(Message) -> PluralPart, tag("two")),
+        value(PluralPart::Few as fn(Message) -> PluralPart, tag("few")),
+        value(PluralPart::Many as fn(Message) -> PluralPart, tag("many")),
+        value(PluralPart::Other as fn(Message) -> PluralPart, tag("other")),
+    ))(s)
 }
 
-fn plural_other(s: &str) -> IResult<&str,PluralPart> {
+//one {1 day}
+pub(crate) fn plural_category(s: &str) -> IResult<&str,PluralPart> {
     do_parse!(s,
-        multispace0                 >>
-        tag!("other")               >>
-        multispace0                 >>
-        msg: call!(submessage)      >>
-        multispace0                 >>
-        (PluralPart::Other(msg))
+        white_space                 >>
+        category: plural_keyword    >>
+        white_space                 >>
+        msg: call!(plural_branch)   >>
+        white_space                 >>
+        (category(msg))
     )
 }
+
 #[derive(Debug)]
-enum PluralPart {
-    Literal(i64, Message),
+pub(crate) enum PluralPart {
+    Literal(f64, Message),
     Zero(Message),
     One(Message),
     Two(Message),
@@ -119,25 +269,16 @@ enum PluralPart {
     Other(Message),
 }
 
-fn plural_from_parts(var_name: &str, mut parts: Vec<PluralPart>) -> ast::PluralFormat {
-    // println!("parts = {:?}", parts);
-    let other_part_pos = parts.iter().position(|pp| {
-        match pp {
-            PluralPart::Other(_) => true,
-            _ => false
-        }
-    });
-
-    let mut fmt = if let Some(other_part_pos) = other_part_pos {
-        let other_part = match parts.remove(other_part_pos) {
-            PluralPart::Other(m) => m,
-            _ => panic!("unreachable")
-        };
-
-        ast::PluralFormat::new(var_name, other_part)
-    } else {
-        panic!("no other part contained in plural")
+// A plural format must have an 'other' part, so this returns
+// `None` without one.
+fn plural_from_parts(var_name: &str, offset: i64, mut parts: Vec<PluralPart>) -> Option<ast::PluralFormat> {
+    let other_part_pos = parts.iter().position(|pp| matches!(pp, PluralPart::Other(_)))?;
+    let other_part = match parts.remove(other_part_pos) {
+        PluralPart::Other(m) => m,
+        _ => unreachable!(),
     };
+    let mut fmt = ast::PluralFormat::new(var_name, other_part);
+    fmt.offset(offset);
 
     for part in parts {
         match part {
@@ -146,35 +287,50 @@ fn plural_from_parts(var_name: &str, mut parts: Vec<PluralPart>) -> ast::PluralF
             PluralPart::Two(m) => fmt.two(m),
             PluralPart::Few(m) => fmt.few(m),
             PluralPart::Many(m) => fmt.many(m),
-            PluralPart::Literal(c,m) => fmt.literal(c,m),
+            PluralPart::Literal(c,m) => fmt.decimal_literal(c,m),
             PluralPart::Other(_) => (), //already added in constructor
         }
     }
 
-    fmt
+    Some(fmt)
 }
 
 named!(plural_submessage <&str, Vec<PluralPart>>,
     many1!(
         alt!(
             call!(plural_literal) |
-            call!(plural_one)     |
-            call!(plural_other)
+            call!(plural_category)
         )
     )
 );
 
 fn plural_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    map_opt(plural_inner_parts, |(name, offset, parts)| {
+        plural_from_parts(name, offset, parts).map(|fmt| Box::new(fmt) as Box<dyn MessagePart>)
+    })(s)
+}
+
+// The 'offset:1' before the branches of a plural.
+fn plural_offset(s: &str) -> IResult<&str, i64> {
+    delimited(
+        tuple((tag("offset"), white_space, tag(":"), white_space)),
+        map_res(recognize(pair(opt(tag("-")), digit1)), str::parse),
+        white_space,
+    )(s)
+}
+
+fn plural_inner_parts(s: &str) -> IResult<&str, (&str, i64, Vec<PluralPart>)> {
     do_parse!(s,
         name: variable_name             >>
         call!(tag(","))                 >>
-        call!(opt(multispace0))         >>
+        white_space                     >>
         call!(tag("plural"))            >>
-        call!(opt(multispace0))         >>
+        white_space                     >>
         call!(tag(","))                 >>
-        call!(opt(multispace0))         >>
+        white_space                     >>
+        offset: call!(opt(plural_offset)) >>
         parts: call!(plural_submessage) >>
-        (Box::new(plural_from_parts(name, parts)) as Box<dyn MessagePart>)
+        ((name, offset.unwrap_or(0), parts))
     )
 }
 //{number, plural, one {1 day} other {# days}}
@@ -186,18 +342,34 @@ fn plural_format(s: &str) -> IResult<&str,Box<dyn MessagePart>> {
     )(s)
 }
 
-fn select_match(s: &str) -> IResult<&str, (&str, Message)> {
+// Further keys for the same branch, such as '|android' in
+// 'ios|android {mobile}', accepted with extensions.
+fn select_alias(s: &str) -> IResult<&str, &str> {
+    preceded(
+        tuple((extensions, white_space, alt((tag("|"), tag(","))), white_space)),
+        alphanumeric1
+    )(s)
+}
+
+fn select_keys(s: &str) -> IResult<&str, Vec<&str>> {
+    map(
+        pair(alphanumeric1, many0(select_alias)),
+        |(first, aliases)| Some(first).into_iter().chain(aliases).collect()
+    )(s)
+}
+
+pub(crate) fn select_match(s: &str) -> IResult<&str, (Vec<&str>, Message)> {
     do_parse!(s,
-        multispace0                 >>
-        match_cond: alphanumeric1   >>
-        multispace0                 >>
+        white_space                 >>
+        match_cond: select_keys     >>
+        white_space                 >>
         msg: call!(submessage)      >>
-        multispace0                 >>
+        white_space                 >>
         ((match_cond,msg))
     )
 }
 
-fn select_submessage(s: &str) -> IResult<&str, Vec<(&str, Message)>> {
+fn select_submessage(s: &str) -> IResult<&str, Vec<(Vec<&str>, Message)>> {
     many1(select_match)(s)
 }
 
@@ -205,18 +377,18 @@ fn select_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
     do_parse!(s,
         name: variable_name             >>
         call!(tag(","))                 >>
-        call!(opt(multispace0))         >>
+        white_space                     >>
         call!(tag("select"))            >>
-        call!(opt(multispace0))         >>
+        white_space                     >>
         call!(tag(","))                 >>
-        call!(opt(multispace0))         >>
+        white_space                     >>
         parts: call!(select_submessage) >>
         (Box::new(select_from_parts(name, parts)) as Box<dyn MessagePart>)
     )
 }
 
-fn select_from_parts(variable_name: &str, mut parts: Vec<(&str, Message)>) -> ast::SelectFormat {
-    let other_part_pos = parts.iter().position(|(n,_)| *n == "other");
+fn select_from_parts(variable_name: &str, mut parts: Vec<(Vec<&str>, Message)>) -> ast::SelectFormat {
+    let other_part_pos = parts.iter().position(|(n,_)| n[..] == ["other"]);
 
     let mut fmt = if let Some(other_part_pos) = other_part_pos {
         let (_,other_part) = parts.remove(other_part_pos);
@@ -225,7 +397,7 @@ fn select_from_parts(variable_name: &str, mut parts: Vec<(&str, Message)>) -> as
         ast::SelectFormat::new(variable_name, Message::default())
     };
     for (s,p) in parts {
-        fmt.map(s, p);
+        fmt.map_all(&s, p);
     }
 
     fmt
@@ -239,32 +411,337 @@ fn select_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
     )(s)
 }
 
-fn plain_text(s: &str) -> IResult<&str, Box<dyn MessagePart> > {
-    map(
-        is_not("{#"),
-        |text| Box::new(ast::PlainText::new(text)) as Box<dyn MessagePart>,
+// The style of an argument, as written: the text up to the brace
+// closing the argument, skipping over any nested braces. As in ICU,
+// an apostrophe quotes the text up to the next one, so that quoted
+// braces don't count, and the quotes are kept in the style.
+fn style_text(s: &str) -> IResult<&str, &str> {
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            _ if quoted => (),
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                let style = s[..i].trim_end_matches(is_pattern_white_space);
+                return match style {
+                    "" => Err(Err::Error((s, ErrorKind::TakeUntil))),
+                    _ => Ok((&s[style.len()..], style)),
+                };
+            }
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+    Err(Err::Error((s, ErrorKind::TakeUntil)))
+}
+
+// A named style or skeleton, such as 'short' or '::yMMMd', or else a
+// date pattern, such as 'EEEE dd MMM'.
+fn datetime_style(s: &str) -> IResult<&str, DateTimeStyle> {
+    map_opt(
+        style_text,
+        |style: &str| match DateTimeStyle::parse(style) {
+            Some(style) => Some(style),
+            None if style.starts_with("::") || !is_valid_pattern(style) => None,
+            None => Some(DateTimeStyle::Pattern(style.to_string())),
+        }
+    )(s)
+}
+
+fn datetime_kind(s: &str) -> IResult<&str, DateTimeKind> {
+    alt((
+        value(DateTimeKind::Date, tag("date")),
+        value(DateTimeKind::Time, tag("time")),
+    ))(s)
+}
+
+fn datetime_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    do_parse!(s,
+        name: variable_name             >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        kind: datetime_kind             >>
+        white_space                     >>
+        style: call!(opt(preceded(pair(tag(","), white_space), datetime_style))) >>
+        white_space                     >>
+        (Box::new(ast::DateTimeFormat::new(
+            name,
+            kind,
+            style.unwrap_or(DateTimeStyle::Medium),
+        )) as Box<dyn MessagePart>)
+    )
+}
+
+//{when, date, ::yMMMd}
+fn datetime_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    delimited(
+        tag("{"),
+        datetime_inner,
+        tag("}"),
     )(s)
 }
 
+// The name of a number argument and its style, if any, as written.
+fn number_parts(s: &str) -> IResult<&str, (&str, Option<&str>)> {
+    do_parse!(s,
+        name: variable_name             >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        call!(tag("number"))            >>
+        white_space                     >>
+        style: call!(opt(preceded(pair(tag(","), white_space), style_text))) >>
+        white_space                     >>
+        ((name, style))
+    )
+}
+
+fn number_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    map_opt(
+        number_parts,
+        |(name, style): (&str, Option<&str>)| match style.map(|style| (style, NumberStyle::parse(style))) {
+            None => Some(Box::new(ast::NumberFormat::new(name, NumberStyle::Decimal)) as Box<dyn MessagePart>),
+            Some((_, Some(style))) => Some(Box::new(ast::NumberFormat::new(name, style)) as Box<dyn MessagePart>),
+            // An invalid skeleton is an error, while a pattern or a
+            // style this crate doesn't support, such as 'currency', is
+            // kept so that the message can still be used.
+            Some((style, None)) if style.starts_with("::") => None,
+            Some((style, None)) => Some(Box::new(ast::UnknownFormat::new(name, "number", Some(style)))),
+        }
+    )(s)
+}
+
+//{ratio, number, percent}
+fn number_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    delimited(
+        tag("{"),
+        number_inner,
+        tag("}"),
+    )(s)
+}
+
+/// Scan message text up to the next brace, or the next `#` when
+/// `in_plural`, returning the length scanned and the text with its
+/// quoting removed.
+///
+/// As in ICU, `''` is an apostrophe, and an apostrophe before a
+/// character that would otherwise be special quotes everything up to
+/// the next lone apostrophe, or the end of the text. Any other
+/// apostrophe is kept as is.
+pub(crate) fn scan_text(s: &str, in_plural: bool) -> (usize, String) {
+    let special = |c: char| c == '{' || c == '}' || (in_plural && c == '#');
+    let mut text = String::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        if special(c) {
+            return (i, text);
+        }
+        chars.next();
+        if c != '\'' {
+            text.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some(&(_, '\'')) => {
+                chars.next();
+                text.push('\'');
+            }
+            Some(&(_, next)) if special(next) => loop {
+                match chars.next() {
+                    None => return (s.len(), text),
+                    Some((_, '\'')) if chars.peek().map(|&(_, c)| c) == Some('\'') => {
+                        chars.next();
+                        text.push('\'');
+                    }
+                    Some((_, '\'')) => break,
+                    Some((_, c)) => text.push(c),
+                }
+            },
+            _ => text.push('\''),
+        }
+    }
+    (s.len(), text)
+}
+
+pub(crate) fn plain_text(s: &str) -> IResult<&str, Box<dyn MessagePart> > {
+    match scan_text(s, IN_PLURAL.with(Cell::get)) {
+        (0, _) => Err(Err::Error((s, ErrorKind::IsNot))),
+        (len, text) => Ok((&s[len..], Box::new(ast::PlainText::new(&text)) as Box<dyn MessagePart>)),
+    }
+}
+
+// The '#' standing for the number of the enclosing plural. Outside
+// plurals, '#' is plain text.
 fn placeholder(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    if !IN_PLURAL.with(Cell::get) {
+        return Err(Err::Error((s, ErrorKind::Tag)));
+    }
     map(
         tag("#"),
         |_| Box::new(ast::PlaceholderFormat::new()) as Box<dyn MessagePart>,
     )(s)
 }
 
-pub fn message_parts(s: &str) -> IResult<&str,Vec<Box<dyn MessagePart>>> {
-    many1(
-        alt((
-            placeholder,
-            simple_format,
-            plural_format,
-            select_format,
-            plain_text,
-        ))
+// The name of a supported measure unit, such as 'kilometer'.
+fn unit_name(s: &str) -> IResult<&str, &str> {
+    verify(
+        recognize(many1(alt((alpha1, tag("-"))))),
+        |unit: &str| UnitPatterns::is_supported(unit)
+    )(s)
+}
+
+fn unit_width(s: &str) -> IResult<&str, UnitWidth> {
+    map_opt(alpha1, UnitWidth::parse)(s)
+}
+
+fn unit_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    do_parse!(s,
+        name: variable_name             >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        call!(tag("unit"))              >>
+        white_space                     >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        unit: unit_name                 >>
+        white_space                     >>
+        width: call!(opt(preceded(pair(tag(","), white_space), unit_width))) >>
+        white_space                     >>
+        (Box::new(ast::UnitFormat::new(
+            name,
+            unit,
+            width.unwrap_or(UnitWidth::Short),
+        )) as Box<dyn MessagePart>)
+    )
+}
+
+//{distance, unit, kilometer, long}
+fn unit_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    delimited(
+        tag("{"),
+        unit_inner,
+        tag("}"),
+    )(s)
+}
+
+fn case_transform(s: &str) -> IResult<&str, CaseTransform> {
+    map_opt(alpha1, CaseTransform::parse)(s)
+}
+
+fn text_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    do_parse!(s,
+        name: variable_name             >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        call!(tag("text"))              >>
+        white_space                     >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        transform: case_transform       >>
+        white_space                     >>
+        (Box::new(ast::TextFormat::new(name, transform)) as Box<dyn MessagePart>)
+    )
+}
+
+//{name, text, capitalize}
+fn text_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    delimited(
+        tag("{"),
+        text_inner,
+        tag("}"),
+    )(s)
+}
+
+// Succeeds without consuming input when parsing with extensions.
+fn extensions(s: &str) -> IResult<&str, ()> {
+    if OPTIONS.with(Cell::get).extensions {
+        Ok((s, ()))
+    } else {
+        Err(Err::Error((s, ErrorKind::Verify)))
+    }
+}
+
+//{@product.name}
+fn message_reference(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    map(
+        preceded(
+            extensions,
+            delimited(
+                tag("{@"),
+                take_while1(|c: char| !matches!(c, '{' | '}' | ',') && !is_pattern_white_space(c)),
+                tag("}"),
+            ),
+        ),
+        |key| Box::new(ast::MessageReference::new(key)) as Box<dyn MessagePart>,
     )(s)
 }
 
+// Succeeds without consuming input when parsing in lenient mode.
+fn lenient(s: &str) -> IResult<&str, ()> {
+    if OPTIONS.with(Cell::get).lenient {
+        Ok((s, ()))
+    } else {
+        Err(Err::Error((s, ErrorKind::Verify)))
+    }
+}
+
+// The name of a format type that isn't supported, such as 'datetime'.
+fn unknown_type(s: &str) -> IResult<&str, &str> {
+    verify(
+        recognize(many1(alt((alphanumeric1, tag("-"), tag("_"))))),
+        |name: &str| !FORMAT_TYPES.contains(&name)
+    )(s)
+}
+
+fn unknown_inner(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    do_parse!(s,
+        name: variable_name             >>
+        call!(tag(","))                 >>
+        white_space                     >>
+        type_name: unknown_type         >>
+        white_space                     >>
+        style: call!(opt(preceded(pair(tag(","), white_space), style_text))) >>
+        white_space                     >>
+        (Box::new(ast::UnknownFormat::new(name, type_name, style)) as Box<dyn MessagePart>)
+    )
+}
+
+//{when, datetime, weird-style}
+fn unknown_format(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    preceded(
+        lenient,
+        delimited(
+            tag("{"),
+            unknown_inner,
+            tag("}"),
+        ),
+    )(s)
+}
+
+// Any argument in braces, whatever its format.
+pub(crate) fn argument(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    alt((
+        message_reference,
+        simple_format,
+        plural_format,
+        select_format,
+        datetime_format,
+        number_format,
+        unit_format,
+        text_format,
+        unknown_format,
+    ))(s)
+}
+
+pub(crate) fn message_part(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    alt((placeholder, argument, plain_text))(s)
+}
+
+pub fn message_parts(s: &str) -> IResult<&str,Vec<Box<dyn MessagePart>>> {
+    many1(message_part)(s)
+}
+
 // Given a set of `MessagePart`s, create a `Message`.
 pub fn message_parser(s: &str) -> IResult<&str, Message> {
     map(message_parts, Message::new)(s)
@@ -274,9 +751,35 @@ pub fn message_parser(s: &str) -> IResult<&str, Message> {
 ///
 /// [`Message`]: ../struct.Message.html
 pub fn parse(message: &str) -> Result<Message, ParseError> {
+    parse_with(message, ParseOptions::default())
+}
+
+/// Parse some text into a [`Message`] using `options`.
+///
+/// ```
+/// use message_format::icu::{self, ParseOptions};
+/// use message_format::{arg, Context};
+///
+/// let source = "{n, plural, one {Sent {when, datetime, weird-style}} other {# sent}}";
+/// assert!(icu::parse(source).is_err());
+///
+/// let options = ParseOptions { lenient: true, ..ParseOptions::default() };
+/// let m = icu::parse_with(source, options).unwrap();
+/// let args = arg("n", 1);
+/// assert_eq!(Context::default().format(&m, &args.arg("when", "today")), "Sent today");
+/// ```
+///
+/// [`Message`]: ../struct.Message.html
+pub fn parse_with(message: &str, options: ParseOptions) -> Result<Message, ParseError> {
+    let _guard = OptionsGuard::set(options);
     match message_parser(message) {
+        Err(Err::Failure((rest, ErrorKind::Verify))) => {
+            let name = rest.split([',', '}']).next().unwrap_or(rest);
+            Err(ParseError::InvalidArgumentName(name.to_string()))
+        }
         Err(_) => Err(ParseError::NotImplemented),
-        Ok((_, m)) => Ok(m),
+        Ok(("", m)) => Ok(m),
+        Ok((rest, _)) => Err(ParseError::Unparsed(message.len() - rest.len())),
     }
 }
 
@@ -292,7 +795,7 @@ mod tests {
         match r {
             Ok((rem, pt)) => {
                 assert_eq!(rem, "{name}");
-                // assert_eq!(pt, ast::PlainText::new("hello "));
+                assert!(pt.eq_part(&ast::PlainText::new("hello ")));
             },
             Err(err) => panic!("parse error: {:?}", err),
         }
@@ -320,6 +823,73 @@ mod tests {
     //     }
     // }
 
+    #[test]
+    fn argument_names_are_validated() {
+        let ctx = Context::default();
+        let m = parse("{ name }, {0}, {naïve}").unwrap();
+        assert_eq!(
+            ctx.format(&m, &arg("name", "a").arg("0", "b").arg("naïve", "c")),
+            "a, b, c"
+        );
+
+        let invalid = |name: &str| Err(ParseError::InvalidArgumentName(name.to_string()));
+        assert_eq!(parse("Hi {first name}!").map(|_| ()), invalid("first name"));
+        assert_eq!(parse("{1abc}").map(|_| ()), invalid("1abc"));
+        assert_eq!(parse("{01}").map(|_| ()), invalid("01"));
+        assert_eq!(parse("{a.b, plural, other {x}}").map(|_| ()), invalid("a.b"));
+        assert_eq!(parse("{}").map(|_| ()), Err(ParseError::NotImplemented));
+    }
+
+    #[test]
+    fn white_space_is_allowed_between_tokens() {
+        let ctx = Context::default();
+        let cases = [
+            "{ count , plural , =0 {none} one {# day} other {# days} }",
+            "{count,plural,=0{none}one{# day}other{# days}}",
+            "{\tcount\n,\u{200e}plural\r\n,\n  =0 {none}\n  one {# day}\n  other {# days}\n}",
+        ];
+        for case in &cases {
+            let m = parse(case).unwrap();
+            assert_eq!(ctx.format(&m, &arg("count", 0)), "none", "{:?}", case);
+            assert_eq!(ctx.format(&m, &arg("count", 1)), "1 day", "{:?}", case);
+            assert_eq!(ctx.format(&m, &arg("count", 3)), "3 days", "{:?}", case);
+        }
+
+        let m = parse("{ gender , select , female { She } other { They } }").unwrap();
+        assert_eq!(ctx.format(&m, &arg("gender", "female")), " She ");
+
+        let m = parse("{ due , date , short }").unwrap();
+        let expected = parse("{due, date, short}").unwrap();
+        assert_eq!(m, expected);
+    }
+
+    // Patterns from ICU4J, where white space is allowed after `{`,
+    // around commas and between the keys and branches of a format.
+    #[test]
+    fn icu4j_white_space_cases() {
+        let ctx = Context::default();
+
+        // TestMessageFormat.testSelectFormat
+        let m = parse("{0} est {1, select, female {all\u{e9}e} other {all\u{e9}}} \u{e0} Paris.").unwrap();
+        let format = |name, gender| ctx.format(&m, &arg("0", name).arg("1", gender));
+        assert_eq!(format("Kirti", "female"), "Kirti est all\u{e9}e \u{e0} Paris.");
+        assert_eq!(format("Victor", "other"), "Victor est all\u{e9} \u{e0} Paris.");
+        assert_eq!(format("Ash", "unknown"), "Ash est all\u{e9} \u{e0} Paris.");
+
+        let source = "{0} est {1, select, female {{2,number,integer} all\u{e9}e} other {all\u{e9}}} \u{e0} Paris.";
+        let m = parse(source).unwrap();
+        let formatted = ctx.format(&m, &arg("0", "Kirti").arg("1", "female").arg("2", 6));
+        assert_eq!(formatted, "Kirti est 6 all\u{e9}e \u{e0} Paris.");
+
+        // The plural example of the MessageFormat class documentation.
+        let source = "{num_files, plural, =0{There are no files on disk.}=1{There is one file on disk.}\
+                      other{There are # files on disk.}}";
+        let m = parse(source).unwrap();
+        assert_eq!(ctx.format(&m, &arg("num_files", 0)), "There are no files on disk.");
+        assert_eq!(ctx.format(&m, &arg("num_files", 1)), "There is one file on disk.");
+        assert_eq!(ctx.format(&m, &arg("num_files", 1273)), "There are 1,273 files on disk.");
+    }
+
     #[test]
     fn all_text_works() {
         match message_parser("Hello, world!") {
@@ -343,6 +913,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn unparsed_text_is_an_error() {
+        assert_eq!(parse("Hello {name").err(), Some(ParseError::Unparsed(6)));
+        assert_eq!(parse("Hello} there").err(), Some(ParseError::Unparsed(5)));
+    }
+
+    #[test]
+    fn plural_offset_is_parsed() {
+        let ctx = Context::default();
+        let m = parse("{n, plural, offset:1 =0 {nobody} =1 {{host}} one {{host} and # other} other {{host} and # others}}").unwrap();
+        assert_eq!(ctx.format(&m, &arg("n", 1).arg("host", "Ann")), "Ann");
+        assert_eq!(ctx.format(&m, &arg("n", 2).arg("host", "Ann")), "Ann and 1 other");
+        assert_eq!(ctx.format(&m, &arg("n", 3).arg("host", "Ann")), "Ann and 2 others");
+        assert!(parse("{n, plural, offset: 2 other {#}}").is_ok());
+        assert!(parse("{n, plural, offset:x other {#}}").is_err());
+    }
+
+    #[test]
+    fn datetime_format_works() {
+        use DateTime;
+
+        let ctx = Context::default();
+        let m = parse("Due {due, date, ::yMMMd} at {due, time, short}.").unwrap();
+        let due = DateTime::new(2024, 10, 3).with_time(9, 30, 0);
+        assert_eq!(ctx.format(&m, &arg("due", due)), "Due Oct 3, 2024 at 9:30 AM.");
+
+        let m = parse("{due, date}").unwrap();
+        assert_eq!(ctx.format(&m, &arg("due", due)), "Oct 3, 2024");
+
+        assert!(parse("{due, date, ::yQ}").is_err());
+    }
+
+    #[test]
+    fn free_text_styles_are_kept() {
+        use DateTime;
+
+        let ctx = Context::default();
+        let source = "{due, date, EEEE dd MMM} at {due, time, HH:mm}: {price, number, #,##0.00} {cost, number, currency}";
+        let m = parse(source).unwrap();
+        let due = DateTime::new(2024, 10, 3).with_time(9, 5, 0);
+        assert_eq!(ctx.format(&m, &arg("due", due).arg("price", 1.5).arg("cost", 2)), "Thursday 03 Oct at 09:05: 1.5 2");
+        assert_eq!(::icu::serialize(&m).unwrap(), source);
+        let unknown = m.unknown_formats();
+        assert_eq!(unknown[0], &ast::UnknownFormat::new("price", "number", Some("#,##0.00")));
+        assert_eq!(unknown[1].style.as_ref().unwrap(), "currency");
+
+        let m = parse("{due, date, k 'o''clock, week ww'}").unwrap();
+        assert_eq!(ctx.format(&m, &arg("due", due)), "9 o'clock, week ww");
+        assert!(parse("{due, date, qqq ww}").is_err());
+        assert!(parse("{due, date, wiki}").is_err());
+        assert!(parse("{n, number, ::bogus}").is_err());
+    }
+
+    #[test]
+    fn quoted_style_text() {
+        use DateTime;
+
+        let ctx = Context::default();
+        let source = "{due, time, h 'o''clock' '{sharp}'} {n, number, '}'#}";
+        let m = parse(source).unwrap();
+        let due = DateTime::new(2024, 10, 3).with_time(9, 0, 0);
+        assert_eq!(ctx.format(&m, &arg("due", due).arg("n", 2)), "9 o'clock {sharp} 2");
+        assert_eq!(::icu::serialize(&m).unwrap(), source);
+        assert_eq!(m.unknown_formats()[0].style.as_ref().unwrap(), "'}'#");
+
+        assert!(parse("{due, time, h 'o}").is_err());
+    }
+
+    #[test]
+    fn lenient_mode_passes_unknown_types_through() {
+        let lenient = ParseOptions { lenient: true, ..ParseOptions::default() };
+        let ctx = Context::default();
+        let source = "{n, plural, one {at {t, clock}} other {# at {t, clock, hh {mm}}}}";
+        assert_eq!(parse(source), Err(ParseError::NotImplemented));
+
+        let m = parse_with(source, lenient).unwrap();
+        assert_eq!(ctx.format(&m, &arg("n", 2).arg("t", "noon")), "2 at noon");
+        let unknown = m.unknown_formats();
+        assert_eq!(unknown.len(), 2);
+        assert_eq!(unknown[1], &ast::UnknownFormat::new("t", "clock", Some("hh {mm}")));
+
+        assert!(parse_with("{n, plural, one {x}}", lenient).is_err());
+        assert!(parse_with("{due, date, weird}", lenient).is_err());
+        assert!(parse("{t, clock}").is_err());
+    }
+
+    #[test]
+    fn extensions_allow_several_select_keys() {
+        let extensions = ParseOptions { extensions: true, ..ParseOptions::default() };
+        let ctx = Context::default();
+        let source = "{os, select, ios|android {mobile} windows, mac , linux {desktop} other {web}}";
+        assert_eq!(parse(source), Err(ParseError::NotImplemented));
+
+        let m = parse_with(source, extensions).unwrap();
+        assert_eq!(ctx.format(&m, &arg("os", "android")), "mobile");
+        assert_eq!(ctx.format(&m, &arg("os", "linux")), "desktop");
+        assert_eq!(ctx.format(&m, &arg("os", "beos")), "web");
+        assert_eq!(
+            ::icu::serialize(&m).unwrap(),
+            "{os, select, ios|android {mobile} windows|mac|linux {desktop} other {web}}"
+        );
+    }
+
+    #[test]
+    fn decimal_literals_parse() {
+        let ctx = Context::default();
+        let m = parse("{n, plural, =0.5 {half} =-1 {minus one} other {#}}").unwrap();
+        assert_eq!(ctx.format(&m, &arg("n", 0.5)), "half");
+        assert_eq!(ctx.format(&m, &arg("n", -1)), "minus one");
+        assert_eq!(ctx.format(&m, &arg("n", 1.25)), "1.25");
+    }
+
     #[test]
     fn select_format_works() {
         match message_parser("{gender, select, male {He} female {She} other {They}} will respond shortly.") {
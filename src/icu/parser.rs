@@ -0,0 +1,193 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsers for the parts of a message, for embedding messages in a
+//! larger grammar, such as a templating language.
+//!
+//! Each parser is a [`nom`] function taking the input and returning
+//! the rest of it along with what was parsed, so they combine with
+//! the host grammar's own parsers. Unlike [`parse`], they don't
+//! require the whole input to be a message.
+//!
+//! The extension point is [`part`]: a host grammar can add its own
+//! parts to a message by trying its parsers before it, and collecting
+//! the parts with [`Message::new`]. Since text stops at braces, host
+//! syntax starting with a brace is never taken as text:
+//!
+//! ```
+//! extern crate message_format;
+//! extern crate nom;
+//!
+//! use message_format::icu::ast::PlainText;
+//! use message_format::icu::parser;
+//! use message_format::{Message, MessagePart};
+//! use nom::branch::alt;
+//! use nom::bytes::complete::{tag, take_until};
+//! use nom::combinator::map;
+//! use nom::multi::many0;
+//! use nom::sequence::delimited;
+//! use nom::IResult;
+//!
+//! // The host's comments, `{# ... #}`, are dropped from the message.
+//! fn comment(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+//!     map(delimited(tag("{#"), take_until("#}"), tag("#}")), |_| {
+//!         Box::new(PlainText::new("")) as Box<dyn MessagePart>
+//!     })(s)
+//! }
+//!
+//! fn message(s: &str) -> IResult<&str, Message> {
+//!     map(many0(alt((comment, parser::part))), Message::new)(s)
+//! }
+//!
+//! # fn main() {
+//! let (rest, m) = message("Hi {# the user #}{name}!} and more").unwrap();
+//! assert_eq!(rest, "} and more");
+//! assert_eq!(m.debug_sexpr(), r#"(message (literal "Hi ") (argument name) (literal "!"))"#);
+//! # }
+//! ```
+//!
+//! The branches of plurals and selects are always parsed with the
+//! parsers of this crate, so host parts can only appear outside them.
+//!
+//! The signatures of these parsers are stable, and don't change when
+//! the parsers used by [`parse`] are reorganized.
+//!
+//! [`nom`]: https://docs.rs/nom/5
+//! [`parse`]: ../fn.parse.html
+//! [`part`]: fn.part.html
+//! [`Message::new`]: ../../struct.Message.html#method.new
+
+use nom::branch::alt;
+use nom::combinator::map;
+use nom::multi::many0;
+use nom::sequence::preceded;
+use nom::IResult;
+
+use super::parse::{self, OptionsGuard, ParseOptions, PluralPart};
+use {Message, MessagePart, PluralCategory};
+
+/// The key of a plural branch, parsed by [`plural_branch`].
+///
+/// [`plural_branch`]: fn.plural_branch.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PluralKey {
+    /// An exact value, as in `=0`.
+    Exact(f64),
+    /// A plural category, as in `one`.
+    Category(PluralCategory),
+}
+
+/// Parse text up to the next brace, or the next `#` within a plural
+/// branch, removing its quoting. This fails on empty text.
+///
+/// ```
+/// use message_format::icu::parser::literal;
+///
+/// let (rest, part) = literal("It''s '{'here'}' {name}").unwrap();
+/// assert_eq!(rest, "{name}");
+/// assert_eq!(format!("{:?}", part), r#"PlainText { text: "It's {here} " }"#);
+/// ```
+pub fn literal(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    parse::plain_text(s)
+}
+
+/// Parse an argument in braces, whatever its format, such as
+/// `{name}`, `{n, number}` or a whole plural.
+///
+/// ```
+/// use message_format::icu::parser::argument;
+///
+/// let (rest, _) = argument("{n, plural, one {# day} other {# days}} left").unwrap();
+/// assert_eq!(rest, " left");
+/// assert!(argument("text").is_err());
+/// ```
+pub fn argument(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    parse::argument(s)
+}
+
+/// Parse one part of a message: an [`argument`], a [`literal`], or
+/// `#` within a plural branch.
+///
+/// [`argument`]: fn.argument.html
+/// [`literal`]: fn.literal.html
+pub fn part(s: &str) -> IResult<&str, Box<dyn MessagePart>> {
+    parse::message_part(s)
+}
+
+/// Parse the parts of a message up to a closing brace that doesn't
+/// belong to it, or the end of the input. The message may be empty.
+///
+/// ```
+/// use message_format::icu::parser::message;
+///
+/// let (rest, m) = message("Hello {name}} rest").unwrap();
+/// assert_eq!(rest, "} rest");
+/// assert_eq!(m.argument_names(), ["name"]);
+/// ```
+pub fn message(s: &str) -> IResult<&str, Message> {
+    map(many0(part), Message::new)(s)
+}
+
+/// Parse a branch of a plural, such as `one {# day}` or `=0 {none}`,
+/// along with the white space around it. Within the branch, `#` is
+/// the number.
+///
+/// ```
+/// use message_format::icu::parser::{plural_branch, PluralKey};
+/// use message_format::PluralCategory;
+///
+/// let (rest, (key, m)) = plural_branch(" one {# day} other {# days}").unwrap();
+/// assert_eq!(key, PluralKey::Category(PluralCategory::One));
+/// assert_eq!(m.debug_sexpr(), r#"(message (placeholder) (literal " day"))"#);
+/// assert_eq!(rest, "other {# days}");
+/// ```
+pub fn plural_branch(s: &str) -> IResult<&str, (PluralKey, Message)> {
+    let (rest, part) = preceded(parse::white_space, alt((parse::plural_literal, parse::plural_category)))(s)?;
+    let branch = match part {
+        PluralPart::Literal(value, m) => (PluralKey::Exact(value), m),
+        PluralPart::Zero(m) => (PluralKey::Category(PluralCategory::Zero), m),
+        PluralPart::One(m) => (PluralKey::Category(PluralCategory::One), m),
+        PluralPart::Two(m) => (PluralKey::Category(PluralCategory::Two), m),
+        PluralPart::Few(m) => (PluralKey::Category(PluralCategory::Few), m),
+        PluralPart::Many(m) => (PluralKey::Category(PluralCategory::Many), m),
+        PluralPart::Other(m) => (PluralKey::Category(PluralCategory::Other), m),
+    };
+    Ok((rest, branch))
+}
+
+/// Parse a branch of a select, such as `female {She}`, along with the
+/// white space around it, returning its keys. A branch has several
+/// keys only with [`ParseOptions::extensions`].
+///
+/// ```
+/// use message_format::icu::parser::select_branch;
+///
+/// let (rest, (keys, _)) = select_branch("female {She} other {They}").unwrap();
+/// assert_eq!(keys, ["female"]);
+/// assert_eq!(rest, "other {They}");
+/// ```
+///
+/// [`ParseOptions::extensions`]: ../struct.ParseOptions.html#structfield.extensions
+pub fn select_branch(s: &str) -> IResult<&str, (Vec<String>, Message)> {
+    let (rest, (keys, m)) = parse::select_match(s)?;
+    Ok((rest, (keys.into_iter().map(str::to_string).collect(), m)))
+}
+
+/// Run `f` with the parsers of this module using `options`, which
+/// are otherwise the defaults.
+///
+/// ```
+/// use message_format::icu::parser::{argument, with_options};
+/// use message_format::icu::ParseOptions;
+///
+/// assert!(argument("{@common.ok}").is_err());
+/// let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+/// assert!(with_options(options, || argument("{@common.ok}").is_ok()));
+/// ```
+pub fn with_options<T, F: FnOnce() -> T>(options: ParseOptions, f: F) -> T {
+    let _guard = OptionsGuard::set(options);
+    f()
+}
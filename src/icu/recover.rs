@@ -0,0 +1,131 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use nom::error::ErrorKind;
+use nom::Err;
+
+use super::ast::ErrorNode;
+use super::parse::{message_part, FORMAT_TYPES};
+use super::Diagnostic;
+use {Message, MessagePart};
+
+/// Parse a message without failing, for tooling that needs to show
+/// as much of a broken message as possible.
+///
+/// Each part that can't be parsed, such as an argument with an
+/// unclosed brace or an unknown format type, is replaced by an
+/// [`ErrorNode`] holding its source text and reported by a
+/// [`Diagnostic`]. The rest of the message is parsed as usual.
+///
+/// ```
+/// use message_format::icu::parse_recovering;
+///
+/// let (message, diagnostics) = parse_recovering("Hi {name}, {n, plurl, other {#}} left");
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].span, 11..32);
+/// assert_eq!(diagnostics[0].message, "unknown format type `plurl`");
+/// assert_eq!(message.parts().len(), 5);
+/// ```
+///
+/// [`ErrorNode`]: ast/struct.ErrorNode.html
+/// [`Diagnostic`]: struct.Diagnostic.html
+pub fn parse_recovering(source: &str) -> (Message, Vec<Diagnostic>) {
+    let mut parts: Vec<Box<dyn MessagePart>> = vec![];
+    let mut diagnostics = vec![];
+    let mut pos = 0;
+    while pos < source.len() {
+        let rest = &source[pos..];
+        let error = match message_part(rest) {
+            Ok((remaining, part)) => {
+                parts.push(part);
+                pos = source.len() - remaining.len();
+                continue;
+            }
+            Err(Err::Failure((_, ErrorKind::Verify))) => Some("invalid argument name"),
+            Err(_) => None,
+        };
+        let (len, message) = match matching_brace(rest) {
+            _ if rest.starts_with('}') => (1, "unmatched closing brace".to_string()),
+            Some(len) => (len, error.map_or_else(|| describe(&rest[..len]), str::to_string)),
+            None => (rest.len(), "unclosed brace".to_string()),
+        };
+        parts.push(Box::new(ErrorNode::new(&rest[..len])));
+        diagnostics.push(Diagnostic::new(pos..pos + len, &message));
+        pos += len;
+    }
+    (Message::new(parts), diagnostics)
+}
+
+// The length of the text up to and including the brace closing the
+// one at the start of `s`.
+fn matching_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+// Describe what is wrong with an argument that failed to parse.
+fn describe(argument: &str) -> String {
+    let mut fields = argument[1..argument.len() - 1].splitn(3, ',').map(str::trim);
+    let _name = fields.next();
+    match fields.next() {
+        Some(kind) if !FORMAT_TYPES.contains(&kind) => format!("unknown format type `{}`", kind),
+        Some(kind) if kind == "plural" && !argument.contains("other") => {
+            "plural format is missing an `other` branch".to_string()
+        }
+        Some(kind) => format!("invalid {} format", kind),
+        None => "invalid argument".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_recovering;
+    use icu::ast::ErrorNode;
+    use icu::{parse, Diagnostic};
+    use {arg, Context};
+
+    #[test]
+    fn valid_messages_are_unchanged() {
+        let source = "{n, plural, one {# file} other {# files}} in {dir}";
+        let (message, diagnostics) = parse_recovering(source);
+        assert!(diagnostics.is_empty());
+        assert_eq!(message, parse(source).unwrap());
+    }
+
+    #[test]
+    fn broken_regions_become_error_nodes() {
+        let (message, diagnostics) =
+            parse_recovering("A {bad name} B } C {n, plural, one {x}} D {open");
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::new(2..12, "invalid argument name"),
+                Diagnostic::new(15..16, "unmatched closing brace"),
+                Diagnostic::new(19..39, "plural format is missing an `other` branch"),
+                Diagnostic::new(42..47, "unclosed brace"),
+            ]
+        );
+        assert!(message.parts[1].eq_part(&ErrorNode::new("{bad name}")));
+
+        let ctx = Context::default();
+        assert_eq!(
+            ctx.format(&message, &arg("n", 1)),
+            "A {bad name} B } C {n, plural, one {x}} D {open"
+        );
+    }
+}
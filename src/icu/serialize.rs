@@ -0,0 +1,316 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use super::ast;
+use datetime::DateTimeKind;
+use number::{NumberStyle, UnitWidth};
+use {Message, MessagePart};
+
+/// Write a [`Message`] to a stream using the ICU Message Format syntax.
+///
+/// This is the inverse of [`parse`]. Messages containing parts that
+/// are not part of the ICU AST can not be written and result in an
+/// error.
+///
+/// [`Message`]: ../struct.Message.html
+/// [`parse`]: fn.parse.html
+pub fn write_message(message: &Message, stream: &mut dyn fmt::Write) -> fmt::Result {
+    write_parts(message, false, stream)
+}
+
+fn write_parts(message: &Message, in_plural: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+    for part in &message.parts {
+        write_part(part.as_ref(), in_plural, stream)?;
+    }
+    Ok(())
+}
+
+// Write text, quoting the characters the parser would otherwise read
+// as syntax, and doubling apostrophes that could be read as quotes.
+// A quote runs over the apostrophes and syntax characters following
+// it, so that an apostrophe never ends it early.
+fn write_text(text: &str, in_plural: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+    let special = |c: char| c == '{' || c == '}' || (in_plural && c == '#');
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' && (quoted || chars.peek().is_none_or(|&next| next == '\'' || special(next))) {
+            stream.write_str("''")?;
+            continue;
+        }
+        if special(c) && !quoted {
+            stream.write_char('\'')?;
+            quoted = true;
+        } else if !special(c) && quoted {
+            stream.write_char('\'')?;
+            quoted = false;
+        }
+        stream.write_char(c)?;
+    }
+    if quoted {
+        stream.write_char('\'')?;
+    }
+    Ok(())
+}
+
+/// Serialize a [`Message`] to a string using the ICU Message Format syntax.
+///
+/// ```
+/// use message_format::icu;
+///
+/// let m = icu::parse("Connecting to {host}...").unwrap();
+/// assert_eq!(icu::serialize(&m).unwrap(), "Connecting to {host}...");
+/// ```
+///
+/// [`Message`]: ../struct.Message.html
+pub fn serialize(message: &Message) -> Result<String, fmt::Error> {
+    let mut output = String::new();
+    write_message(message, &mut output)?;
+    Ok(output)
+}
+
+fn write_part(part: &dyn MessagePart, in_plural: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+    let any = part.as_any();
+    if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+        write_text(&text.text, in_plural, stream)
+    } else if let Some(error) = any.downcast_ref::<ast::ErrorNode>() {
+        stream.write_str(&error.text)
+    } else if any.downcast_ref::<ast::PlaceholderFormat>().is_some() {
+        stream.write_str("#")
+    } else if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+        write!(stream, "{{{}}}", fmt.variable_name)
+    } else if let Some(reference) = any.downcast_ref::<ast::MessageReference>() {
+        write!(stream, "{{@{}}}", reference.key)
+    } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+        write_plural(fmt, stream)
+    } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+        write_select(fmt, in_plural, stream)
+    } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+        let kind = match fmt.kind {
+            DateTimeKind::Date => "date",
+            DateTimeKind::Time => "time",
+        };
+        write!(stream, "{{{}, {}, {}}}", fmt.variable_name, kind, fmt.style)
+    } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+        match fmt.style {
+            NumberStyle::Decimal => write!(stream, "{{{}, number}}", fmt.variable_name),
+            ref style => write!(stream, "{{{}, number, {}}}", fmt.variable_name, style),
+        }
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+        match fmt.width {
+            UnitWidth::Short => write!(stream, "{{{}, unit, {}}}", fmt.variable_name, fmt.unit),
+            width => write!(stream, "{{{}, unit, {}, {}}}", fmt.variable_name, fmt.unit, width),
+        }
+    } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+        write!(stream, "{{{}, text, {}}}", fmt.variable_name, fmt.transform)
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+        match fmt.style {
+            Some(ref style) => write!(stream, "{{{}, {}, {}}}", fmt.variable_name, fmt.type_name, style),
+            None => write!(stream, "{{{}, {}}}", fmt.variable_name, fmt.type_name),
+        }
+    } else {
+        Err(fmt::Error {})
+    }
+}
+
+fn write_branch(selector: &str, message: &Message, in_plural: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+    write!(stream, " {} {{", selector)?;
+    write_parts(message, in_plural, stream)?;
+    stream.write_str("}")
+}
+
+fn write_plural(fmt: &ast::PluralFormat, stream: &mut dyn fmt::Write) -> fmt::Result {
+    write!(stream, "{{{}, plural,", fmt.variable_name)?;
+    if fmt.offset != 0 {
+        write!(stream, " offset:{}", fmt.offset)?;
+    }
+    for mapping in &fmt.literals {
+        write_branch(&format!("={}", mapping.value), &mapping.message, true, stream)?;
+    }
+    let categories = [
+        ("zero", &fmt.zero),
+        ("one", &fmt.one),
+        ("two", &fmt.two),
+        ("few", &fmt.few),
+        ("many", &fmt.many),
+    ];
+    for &(keyword, message) in &categories {
+        if let Some(ref message) = *message {
+            write_branch(keyword, message, true, stream)?;
+        }
+    }
+    write_branch("other", &fmt.other, true, stream)?;
+    stream.write_str("}")
+}
+
+fn write_select(fmt: &ast::SelectFormat, in_plural: bool, stream: &mut dyn fmt::Write) -> fmt::Result {
+    write!(stream, "{{{}, select,", fmt.variable_name)?;
+    for mapping in &fmt.mappings {
+        let mut keys = mapping.value.clone();
+        for alias in &mapping.aliases {
+            keys.push('|');
+            keys.push_str(alias);
+        }
+        write_branch(&keys, &mapping.message, in_plural, stream)?;
+    }
+    write_branch("other", &fmt.default, in_plural, stream)?;
+    stream.write_str("}")
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::collection::vec;
+    use proptest::option;
+    use proptest::prelude::*;
+    use proptest::strategy::{LazyJust, Union};
+
+    use super::serialize;
+    use icu::ast::{PlaceholderFormat, PlainText, PluralFormat, SelectFormat, SimpleFormat};
+    use icu::parse;
+    use {Message, MessagePart};
+
+    const NAME: &str = "[a-z]{1,6}";
+    const TEXT: &str = "[a-hXYZ0-3 ,.!?='#{}-]{1,12}";
+
+    fn gen_plural(depth: usize) -> BoxedStrategy<PluralFormat> {
+        let branch = move || gen_message(depth + 1, true, 0);
+        (
+            NAME,
+            branch(),
+            vec((1..6i64, branch()), 0..3),
+            option::weighted(0.25, 1..4i64),
+            option::weighted(0.3, branch()),
+            option::weighted(0.6, branch()),
+            option::weighted(0.3, branch()),
+            option::weighted(0.3, branch()),
+            option::weighted(0.3, branch()),
+        )
+            .prop_map(|(name, other, literals, offset, zero, one, two, few, many)| {
+                let mut fmt = PluralFormat::new(&name, other);
+                let mut literal = 0;
+                for (step, message) in literals {
+                    literal += step;
+                    fmt.literal(literal, message);
+                }
+                if let Some(offset) = offset {
+                    fmt.offset(offset);
+                }
+                if let Some(message) = zero {
+                    fmt.zero(message);
+                }
+                if let Some(message) = one {
+                    fmt.one(message);
+                }
+                if let Some(message) = two {
+                    fmt.two(message);
+                }
+                if let Some(message) = few {
+                    fmt.few(message);
+                }
+                if let Some(message) = many {
+                    fmt.many(message);
+                }
+                fmt
+            })
+            .boxed()
+    }
+
+    fn gen_select(depth: usize, in_plural: bool) -> BoxedStrategy<SelectFormat> {
+        let branch = move || gen_message(depth + 1, in_plural, 0);
+        (NAME, branch(), vec(("[abcxyz019]{1,5}", branch()), 0..4))
+            .prop_map(|(name, default, mappings)| {
+                let mut fmt = SelectFormat::new(&name, default);
+                let mut keys: Vec<String> = vec![];
+                for (key, message) in mappings {
+                    if !keys.contains(&key) && key != "other" {
+                        fmt.map(&key, message);
+                        keys.push(key);
+                    }
+                }
+                fmt
+            })
+            .boxed()
+    }
+
+    /// Generate a message which the parser is able to read, with at
+    /// least `min_parts` parts.
+    ///
+    /// Adjacent text parts are never generated as the parser merges
+    /// them into one.
+    fn gen_message(depth: usize, in_plural: bool, min_parts: usize) -> BoxedStrategy<Message> {
+        let mut choices: Vec<BoxedStrategy<Box<dyn MessagePart>>> = vec![
+            TEXT.prop_map(|text| Box::new(PlainText::new(&text)) as Box<dyn MessagePart>).boxed(),
+            NAME.prop_map(|name| Box::new(SimpleFormat::new(&name)) as Box<dyn MessagePart>).boxed(),
+        ];
+        if in_plural {
+            choices.push(LazyJust::new(|| Box::new(PlaceholderFormat::new()) as Box<dyn MessagePart>).boxed());
+        }
+        if depth < 2 {
+            choices.push(gen_plural(depth).prop_map(|fmt| Box::new(fmt) as Box<dyn MessagePart>).boxed());
+            choices.push(gen_select(depth, in_plural).prop_map(|fmt| Box::new(fmt) as Box<dyn MessagePart>).boxed());
+        }
+        vec(Union::new(choices), min_parts..min_parts + 4)
+            .prop_map(|parts| {
+                let mut kept: Vec<Box<dyn MessagePart>> = vec![];
+                for part in parts {
+                    let is_text = |part: &dyn MessagePart| part.as_any().is::<PlainText>();
+                    if !(is_text(part.as_ref()) && kept.last().is_some_and(|last| is_text(last.as_ref()))) {
+                        kept.push(part);
+                    }
+                }
+                Message::new(kept)
+            })
+            .boxed()
+    }
+
+    #[test]
+    fn simple_round_trip() {
+        let source = "{gender, select, male {He has {count, plural, offset:1 \
+                      =0 {no days} one {# day} other {# days}}} other {They}} left.";
+        let m = parse(source).unwrap();
+        assert_eq!(serialize(&m).unwrap(), source);
+    }
+
+    #[test]
+    fn quoting_round_trip() {
+        let source = "It''s '{'free'}', don't {n, plural, other {'#'# ''{x}''}} #1";
+        let m = parse(source).unwrap();
+        assert_eq!(serialize(&m).unwrap(), "It's '{'free'}', don't {n, plural, other {'#'# ''{x}''}} #1");
+        assert_eq!(m.debug_sexpr(), "(message (literal \"It's {free}, don't \") \
+             (plural n (other (literal \"#\") (placeholder) (literal \" '\") (argument x) (literal \"'\"))) \
+             (literal \" #1\"))");
+    }
+
+    #[test]
+    fn datetime_round_trip() {
+        let source = "{due, date, ::yMMMd} {due, time, short}";
+        let m = parse(source).unwrap();
+        assert_eq!(serialize(&m).unwrap(), source);
+    }
+
+    #[test]
+    fn number_round_trip() {
+        let source = "{n, number} {r, number, percent} {r, number, permille} {n, number, ::.00 rounding-mode-up}";
+        let m = parse(source).unwrap();
+        assert_eq!(serialize(&m).unwrap(), source);
+        let source = "{d, unit, kilometer} {d, unit, mile, long} {t, unit, hour, narrow}";
+        let m = parse(source).unwrap();
+        assert_eq!(serialize(&m).unwrap(), source);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(512))]
+
+        #[test]
+        fn generated_round_trip(m in gen_message(0, false, 1)) {
+            let source = serialize(&m).unwrap();
+            let parsed = parse(&source).map_err(|e| e.to_string());
+            prop_assert_eq!(parsed, Ok(m), "round trip of {:?}", source);
+        }
+    }
+}
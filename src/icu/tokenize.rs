@@ -0,0 +1,309 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ops::Range;
+
+use super::parse::scan_text;
+
+/// A byte range of source text.
+pub type Span = Range<usize>;
+
+/// The kind of a token produced by [`tokenize`].
+///
+/// [`tokenize`]: fn.tokenize.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Plain text.
+    Text,
+    /// The name of an argument, like `name` in `{name}`.
+    ArgumentName,
+    /// A format type such as `plural` or `date`, a plural category
+    /// such as `one`, `other` or `offset:`.
+    Keyword,
+    /// A `select` key or an exact plural value such as `=0`.
+    Selector,
+    /// The number following `offset:`.
+    Number,
+    /// The style of a format, such as `short` or `::yMMMd`.
+    Style,
+    /// `{`
+    OpenBrace,
+    /// `}`
+    CloseBrace,
+    /// `,`
+    Comma,
+    /// `#` within a plural branch.
+    Hash,
+    /// Text that doesn't fit the syntax at its position.
+    Unknown,
+}
+
+/// Split a message into classified tokens, for syntax highlighting.
+///
+/// Unlike [`parse`], this never fails: incomplete and invalid input is
+/// tokenized as far as possible, with unexpected text marked as
+/// [`TokenKind::Unknown`]. White space between the parts of an
+/// argument is not covered by any token.
+///
+/// ```
+/// use message_format::icu::{tokenize, TokenKind};
+///
+/// let tokens = tokenize("Hi {name, select, a {");
+/// let kinds: Vec<TokenKind> = tokens.iter().map(|&(_, kind)| kind).collect();
+/// assert_eq!(kinds, [
+///     TokenKind::Text,
+///     TokenKind::OpenBrace,
+///     TokenKind::ArgumentName,
+///     TokenKind::Comma,
+///     TokenKind::Keyword,
+///     TokenKind::Comma,
+///     TokenKind::Selector,
+///     TokenKind::OpenBrace,
+/// ]);
+/// assert_eq!(tokens[2].0, 4..8);
+/// ```
+///
+/// [`parse`]: fn.parse.html
+/// [`TokenKind::Unknown`]: enum.TokenKind.html#variant.Unknown
+pub fn tokenize(source: &str) -> Vec<(Span, TokenKind)> {
+    let mut tokenizer = Tokenizer {
+        source,
+        pos: 0,
+        tokens: vec![],
+    };
+    while tokenizer.pos < source.len() {
+        tokenizer.message(false);
+        // A stray '}' at the top level.
+        if tokenizer.peek() == Some('}') {
+            tokenizer.take_char(TokenKind::Unknown);
+        }
+    }
+    tokenizer.tokens
+}
+
+const PLURAL_KEYWORDS: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+struct Tokenizer<'a> {
+    source: &'a str,
+    pos: usize,
+    tokens: Vec<(Span, TokenKind)>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn push(&mut self, len: usize, kind: TokenKind) {
+        if len > 0 {
+            self.tokens.push((self.pos..self.pos + len, kind));
+            self.pos += len;
+        }
+    }
+
+    fn take_char(&mut self, kind: TokenKind) {
+        let len = self.peek().map_or(0, char::len_utf8);
+        self.push(len, kind);
+    }
+
+    // Take characters while `f` holds, returning the text taken.
+    fn take_while<F: Fn(char) -> bool>(&mut self, kind: TokenKind, f: F) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.push(len, kind);
+        &rest[..len]
+    }
+
+    fn skip_white_space(&mut self) {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+        self.pos += len;
+    }
+
+    // Tokenize message text up to a closing brace or the end.
+    fn message(&mut self, in_plural: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => self.argument(in_plural),
+                '}' => return,
+                '#' if in_plural => self.take_char(TokenKind::Hash),
+                _ => {
+                    // Quoted braces and '#' are part of the text.
+                    let (len, _) = scan_text(self.rest(), in_plural);
+                    self.push(len, TokenKind::Text);
+                }
+            }
+        }
+    }
+
+    fn word(&mut self, kind: TokenKind) -> &'a str {
+        self.take_while(kind, |c| !c.is_whitespace() && !"{},".contains(c))
+    }
+
+    fn argument(&mut self, in_plural: bool) {
+        self.take_char(TokenKind::OpenBrace);
+        self.skip_white_space();
+        self.word(TokenKind::ArgumentName);
+        self.skip_white_space();
+        if self.peek() == Some(',') {
+            self.take_char(TokenKind::Comma);
+            self.skip_white_space();
+            let kind = self.word(TokenKind::Keyword);
+            self.skip_white_space();
+            if self.peek() == Some(',') {
+                self.take_char(TokenKind::Comma);
+                self.skip_white_space();
+                match kind {
+                    "plural" | "selectordinal" => self.branches(true),
+                    "select" => self.branches(in_plural),
+                    _ => {
+                        let rest = self.rest();
+                        let len = rest.find(['{', '}']).unwrap_or(rest.len());
+                        let style = rest[..len].trim_end();
+                        self.push(style.len(), TokenKind::Style);
+                        self.skip_white_space();
+                    }
+                }
+            }
+        }
+        self.close(in_plural);
+    }
+
+    // Consume the closing brace of an argument, marking anything
+    // unexpected before it.
+    fn close(&mut self, in_plural: bool) {
+        loop {
+            match self.peek() {
+                None => return,
+                Some('}') => {
+                    self.take_char(TokenKind::CloseBrace);
+                    return;
+                }
+                Some('{') => self.argument(in_plural),
+                Some(_) => {
+                    self.take_while(TokenKind::Unknown, |c| c != '{' && c != '}');
+                }
+            }
+        }
+    }
+
+    fn branches(&mut self, in_plural: bool) {
+        loop {
+            self.skip_white_space();
+            match self.peek() {
+                None | Some('}') => return,
+                Some('{') => {
+                    self.take_char(TokenKind::OpenBrace);
+                    self.message(in_plural);
+                    if self.peek() == Some('}') {
+                        self.take_char(TokenKind::CloseBrace);
+                    }
+                }
+                Some(_) if self.rest().starts_with("offset:") => {
+                    self.push("offset:".len(), TokenKind::Keyword);
+                    self.skip_white_space();
+                    self.take_while(TokenKind::Number, |c| c.is_ascii_digit() || c == '-');
+                }
+                Some(_) => {
+                    let before = self.pos;
+                    let rest = self.rest();
+                    let len = rest
+                        .find(|c: char| c.is_whitespace() || "{},".contains(c))
+                        .unwrap_or(rest.len());
+                    let kind = if PLURAL_KEYWORDS.contains(&&rest[..len]) {
+                        TokenKind::Keyword
+                    } else {
+                        TokenKind::Selector
+                    };
+                    self.push(len, kind);
+                    if self.pos == before {
+                        self.take_char(TokenKind::Unknown);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tokenize;
+    use super::TokenKind::*;
+
+    #[test]
+    fn classifies_plural() {
+        let source = "{n, plural, offset:1 =0 {none} one {# item} other {{n} '#'items}} left";
+        let tokens: Vec<_> = tokenize(source)
+            .into_iter()
+            .map(|(span, kind)| (&source[span], kind))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("{", OpenBrace),
+                ("n", ArgumentName),
+                (",", Comma),
+                ("plural", Keyword),
+                (",", Comma),
+                ("offset:", Keyword),
+                ("1", Number),
+                ("=0", Selector),
+                ("{", OpenBrace),
+                ("none", Text),
+                ("}", CloseBrace),
+                ("one", Keyword),
+                ("{", OpenBrace),
+                ("#", Hash),
+                (" item", Text),
+                ("}", CloseBrace),
+                ("other", Keyword),
+                ("{", OpenBrace),
+                ("{", OpenBrace),
+                ("n", ArgumentName),
+                ("}", CloseBrace),
+                (" '#'items", Text),
+                ("}", CloseBrace),
+                ("}", CloseBrace),
+                (" left", Text),
+            ]
+        );
+    }
+
+    #[test]
+    fn tolerates_bad_input() {
+        let source = "# '{' {d, date, ::yMMMd x} } {a b} {";
+        let tokens: Vec<_> = tokenize(source)
+            .into_iter()
+            .map(|(span, kind)| (&source[span], kind))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("# '{' ", Text),
+                ("{", OpenBrace),
+                ("d", ArgumentName),
+                (",", Comma),
+                ("date", Keyword),
+                (",", Comma),
+                ("::yMMMd x", Style),
+                ("}", CloseBrace),
+                (" ", Text),
+                ("}", Unknown),
+                (" ", Text),
+                ("{", OpenBrace),
+                ("a", ArgumentName),
+                ("b", Unknown),
+                ("}", CloseBrace),
+                (" ", Text),
+                ("{", OpenBrace),
+            ]
+        );
+    }
+}
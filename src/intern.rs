@@ -0,0 +1,150 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sharing of identical parts between the messages of a bundle.
+
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::Arc;
+
+use debug_tree;
+use icu::ast;
+use {Args, Context, FormatError, Message, MessagePart};
+
+/// What [`MessageBundle::intern`] shared.
+///
+/// [`MessageBundle::intern`]: struct.MessageBundle.html#method.intern
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InternStats {
+    /// The number of parts looked at.
+    pub parts: usize,
+    /// The number of distinct parts that are now shared.
+    pub shared: usize,
+    /// The number of parts replaced by a shared copy.
+    pub replaced: usize,
+    /// An estimate of the bytes freed by dropping the replaced parts.
+    pub bytes_saved: usize,
+}
+
+/// A part shared between messages.
+///
+/// It behaves as the part it shares in every way, including to code
+/// downcasting it through `as_any`.
+pub(crate) struct SharedPart(Arc<dyn MessagePart>);
+
+impl fmt::Debug for SharedPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl MessagePart for SharedPart {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        self.0.apply_format(ctx, stream, args)
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        self.0.try_apply_format(ctx, stream, args)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self.0.as_any()
+    }
+    fn children(&self) -> Vec<&Message> {
+        self.0.children()
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        self.0.eq_part(other)
+    }
+}
+
+// The approximate memory used by a part: the part itself, its plain
+// text and its nested messages.
+fn part_size(part: &dyn MessagePart) -> usize {
+    let text = part.as_any().downcast_ref::<ast::PlainText>().map_or(0, |text| text.text.len());
+    size_of_val(part) + text + part.children().into_iter().map(message_size).sum::<usize>()
+}
+
+fn message_size(message: &Message) -> usize {
+    message
+        .parts
+        .iter()
+        .map(|part| size_of::<Box<dyn MessagePart>>() + part_size(part.as_ref()))
+        .sum()
+}
+
+/// The distinct parts of the messages of a bundle, by a hash of
+/// their canonical rendering.
+#[derive(Debug, Default)]
+pub(crate) struct Interner {
+    parts: HashMap<u64, Vec<Arc<dyn MessagePart>>>,
+}
+
+impl Interner {
+    /// Forget the parts no message uses any more.
+    pub(crate) fn prune(&mut self) {
+        for parts in self.parts.values_mut() {
+            parts.retain(|part| Arc::strong_count(part) > 1);
+        }
+        self.parts.retain(|_, parts| !parts.is_empty());
+    }
+
+    /// The number of distinct parts used by more than one message.
+    pub(crate) fn shared(&self) -> usize {
+        self.parts.values().flatten().filter(|part| Arc::strong_count(part) > 2).count()
+    }
+
+    /// Replace the parts of `message` with shared copies of identical
+    /// parts seen before, and make the others available to share.
+    pub(crate) fn intern(&mut self, message: &mut Message, stats: &mut InternStats) {
+        for part in message.parts.iter_mut() {
+            stats.parts += 1;
+            let size = part_size(part.as_ref());
+            // Sharing a part smaller than the handle to it saves nothing.
+            if size <= size_of::<SharedPart>() {
+                continue;
+            }
+            let mut hasher = DefaultHasher::new();
+            debug_tree::part_canonical(part.as_ref()).hash(&mut hasher);
+            let candidates = self.parts.entry(hasher.finish()).or_default();
+            if candidates.iter().any(|shared| same_part(shared.as_ref(), part.as_ref())) {
+                continue;
+            }
+            let shared = match candidates.iter().find(|shared| shared.eq_part(part.as_ref())) {
+                Some(shared) => {
+                    stats.replaced += 1;
+                    stats.bytes_saved += size - size_of::<SharedPart>();
+                    shared.clone()
+                }
+                None => {
+                    let owned = mem::replace(part, Box::new(ast::PlainText::new("")));
+                    let shared: Arc<dyn MessagePart> = Arc::from(owned);
+                    candidates.push(shared.clone());
+                    shared
+                }
+            };
+            *part = Box::new(SharedPart(shared));
+        }
+    }
+}
+
+// Whether two parts are the same value, as when one is a shared copy
+// of the other.
+fn same_part(a: &dyn MessagePart, b: &dyn MessagePart) -> bool {
+    a.as_any() as *const dyn Any as *const u8 == b.as_any() as *const dyn Any as *const u8
+}
@@ -104,6 +104,14 @@
 //!
 //! For details on the [ICU Message Format] syntax, see the [`icu` module].
 //!
+//! Messages can also be constructed directly using a [`MessageBuilder`]:
+//!
+//! ```
+//! use message_format::Message;
+//!
+//! let m = Message::builder().text("Connecting to ").arg("host").text("...").build();
+//! ```
+//!
 //! ## Fluent Formatted Messages
 //!
 //! Support for the [Fluent] localization format is under development.
@@ -118,22 +126,24 @@
 //! Arguments can be specified as `name => value` or, if you have a variable
 //! with the same name as the argument already, then as just `name`.
 //!
+//! The context may be left out when the first argument is given as
+//! `name => value`, in which case [`Context::current`] is used.
+//!
+//! The macros can be imported along with the commonly used types from
+//! the [`prelude`], or with `#[macro_use]`:
+//!
 //! ```
-//! #[macro_use]
-//! extern crate message_format;
+//! use message_format::prelude::*;
+//!
+//! let ctx = Context::default();
+//! let m1 = icu::parse("Connecting to {host}...").unwrap();
+//! assert_eq!(format_message!(ctx, &m1, host => "localhost"),
+//!            "Connecting to localhost...");
 //!
-//! fn main() {
-//!     use message_format::Args;
-//!     let ctx = message_format::Context::default();
-//!     let m1 = message_format::icu::parse("Connecting to {host}...").unwrap();
-//!     assert_eq!(format_message!(ctx, &m1, host => "localhost"),
-//!                "Connecting to localhost...");
-//!
-//!     let m2 = message_format::icu::parse("{name} went to {place}.").unwrap();
-//!     let name = "Jacob";
-//!     assert_eq!(format_message!(ctx, &m2, name, place => "the store"),
-//!                "Jacob went to the store.");
-//! }
+//! let m2 = icu::parse("{name} went to {place}.").unwrap();
+//! let name = "Jacob";
+//! assert_eq!(format_message!(ctx, &m2, name, place => "the store"),
+//!            "Jacob went to the store.");
 //! ```
 //!
 //! ## Future Directions
@@ -160,131 +170,334 @@
 //! [Fluent]: http://projectfluent.org/
 //! [`parse`]: icu/fn.parse.html
 //! [`Message`]: struct.Message.html
+//! [`MessageBuilder`]: struct.MessageBuilder.html
+//! [`Context::current`]: struct.Context.html#method.current
+//! [`prelude`]: prelude/index.html
 //! [`Value`]: enum.Value.html
 
 // #![warn(missing_docs)]
 #![deny(trivial_numeric_casts, unsafe_code, unstable_features, unused_import_braces,
         unused_qualifications)]
 
+#[cfg(feature = "actix-web")]
+extern crate actix_web;
+#[cfg(feature = "any-ascii")]
+extern crate any_ascii;
+#[cfg(feature = "axum")]
+extern crate axum;
+#[cfg(feature = "icu4x")]
+extern crate fixed_decimal;
+#[cfg(feature = "icu4x")]
+extern crate icu_calendar;
+#[cfg(feature = "icu4x")]
+extern crate icu_datetime;
+#[cfg(feature = "icu4x")]
+extern crate icu_decimal;
+#[cfg(feature = "icu4x")]
+extern crate icu_locid;
+#[cfg(feature = "icu4x")]
+extern crate icu_plurals;
 extern crate language_tags;
 #[macro_use]
 extern crate nom;
+#[cfg(feature = "notify")]
+extern crate notify;
+#[cfg(test)]
+extern crate proptest;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "smallvec")]
+extern crate smallvec;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "unicode-normalization")]
+extern crate unicode_normalization;
 
 mod args;
+mod bind;
+mod bounded;
+mod bundle;
+mod bundle_set;
+mod canonical;
+mod case;
+pub mod catalog;
+mod compile;
 mod context;
+mod data_version;
+mod debug_tree;
+pub mod dialect;
+mod decimal;
+pub mod datetime;
+mod display;
+mod edit;
+mod error;
+mod examples;
+mod formatter;
+mod intern;
+pub mod id;
+pub mod lint;
+pub mod locale;
 mod message;
 mod message_part;
+mod parts;
+mod normalize;
+pub mod number;
+pub mod plural;
 mod plural_category;
 mod plural_classifiers;
+mod profiler;
+pub mod provider;
+mod record;
+mod similarity;
+pub mod snapshot;
+mod specialize;
+mod stats;
+#[cfg(feature = "term")]
+pub mod term;
+mod transliterate;
+mod usage;
 mod value;
+mod variants;
+#[cfg(feature = "notify")]
+mod watch;
+pub mod width;
+#[cfg(feature = "web")]
+pub mod web;
 
-pub use self::args::{arg, Args, ListArgs, EmptyArgs};
-pub use self::context::Context;
-pub use self::message::Message;
+pub use self::args::{arg, arg_with, ArgTransform, Args, ListArgs, EmptyArgs};
+pub use self::bounded::{format_message_bounded, Truncation};
+pub use self::bundle::{
+    BundleError, BundleIter, LoadPolicy, LoadReport, MessageBundle, MessageMetadata, MessageSource, Namespace,
+    PlaceholderMetadata,
+};
+pub use self::bundle_set::{BundleSet, Localizer};
+pub use self::canonical::DuplicateKey;
+pub use self::case::CaseTransform;
+pub use self::compile::{format_message_generic, write_message_generic, ArgsSlots, CompiledMessage};
+pub use self::builder::{MessageBuilder, NestedSelectBuilder, PluralBuilder, SelectBuilder};
+pub use self::context::{BidiStrategy, ColorChoice, Context, OutputMode, Rendering, SelectMatching};
+pub use self::data_version::{data_version, DataVersion};
+pub use self::datetime::DateTime;
+pub use self::decimal::{Decimal, ParseDecimalError};
+pub use self::display::MessageDisplay;
+pub use self::edit::{EditError, RenameReport, RenamedMessage};
+pub use self::error::FormatError;
+pub use self::examples::Example;
+pub use self::formatter::MessageFormatter;
+pub use self::intern::InternStats;
+pub use self::message::{Message, SharedMessage};
+pub use self::parts::{FormattedPart, PartRole};
+pub use self::similarity::similarity;
 pub use self::message_part::MessagePart;
 pub use self::plural_category::PluralCategory;
 pub use self::plural_classifiers::*;
-pub use self::value::Value;
+pub use self::profiler::{MessageStats, ProfileReport, Profiler};
+pub use self::record::MessageRecord;
+pub use self::specialize::{ArgsShape, SpecializedMessage};
+pub use self::static_message::{StaticMessage, StaticPart};
+pub use self::stats::BundleStats;
+#[cfg(feature = "any-ascii")]
+pub use self::transliterate::AnyAscii;
+pub use self::transliterate::Transliterator;
+pub use self::usage::{UsageReport, UsageTracker};
+pub use self::value::{Formattable, SelectKey, Value, ValueKind};
+pub use self::variants::{Selector, Variant};
+#[cfg(feature = "notify")]
+pub use self::watch::WatchingBundleSet;
 pub use self::icu::*;
 
+/// Format a message, returning a `String`.
+///
+/// The context may be omitted, in which case [`Context::current`] is
+/// used. Arguments are given as `name => value`. When a context is
+/// given, `name` alone may be used for a variable of the same name.
+///
+/// ```
+/// use message_format::prelude::*;
+///
+/// let m = icu::parse("Hello, {name}!").unwrap();
+/// assert_eq!(format_message!(&m, name => "Ann"), "Hello, Ann!");
+///
+/// let name = "Bo";
+/// assert_eq!(format_message!(Context::default(), &m, name), "Hello, Bo!");
+/// ```
+///
+/// [`Context::current`]: struct.Context.html#method.current
 #[macro_export]
 macro_rules! format_message {
+    ($msg:expr) => {
+        $crate::Context::current().format($msg, &$crate::EmptyArgs {})
+    };
+    ($msg:expr, $name:ident => $($rest:tt)*) => {
+        $crate::Context::current().format($msg, $crate::message_args!($name => $($rest)*))
+    };
     ($ctx:expr, $msg:expr) => {
-        $ctx.format($msg, &$crate::EmptyArgs { })
+        $ctx.format($msg, &$crate::EmptyArgs {})
+    };
+    ($ctx:expr, $msg:expr, $($rest:tt)*) => {
+        $ctx.format($msg, $crate::message_args!($($rest)*))
     };
-    ($ctx:expr, $msg:expr, $($rest:tt)*) => ({
-        use $crate::Value;
-        $ctx.format($msg, message_args!($($rest)*))
-    });
 }
 
+/// Write a message to a stream implementing either `fmt::Write` or
+/// `io::Write`.
+///
+/// This takes the same arguments as [`format_message!`], with the
+/// stream following the message. It returns a `fmt::Result` or an
+/// `io::Result<()>` to match the stream.
+///
+/// The output can be padded with spaces to a minimum number of
+/// terminal columns by giving `width = n` before the arguments, which
+/// is useful for aligning columns. Wide characters count as two
+/// columns, as measured by [`width::display_width`]:
+///
+/// ```
+/// use message_format::prelude::*;
+///
+/// let ctx = Context::default();
+/// let m = icu::parse("{n, plural, one {# file} other {# files}}").unwrap();
+///
+/// let mut out: Vec<u8> = vec![];
+/// write_message!(ctx, &m, &mut out, width = 10, n => 3).unwrap();
+/// write_message!(ctx, &m, &mut out, width = 10, n => 1).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "3 files   1 file    ");
+///
+/// let mut s = String::new();
+/// write_message!(ctx, &m, &mut s, n => 2).unwrap();
+/// assert_eq!(s, "2 files");
+///
+/// let m = icu::parse("{name}|").unwrap();
+/// let mut s = String::new();
+/// write_message!(ctx, &m, &mut s, width = 6, name => "東京").unwrap();
+/// assert_eq!(s, "東京| ");
+/// ```
+///
+/// [`format_message!`]: macro.format_message.html
+/// [`width::display_width`]: width/fn.display_width.html
 #[macro_export]
 macro_rules! write_message {
+    ($ctx:expr, $msg:expr, $stream:expr, width = $width:expr) => {
+        $crate::write_message!(@write $ctx, $msg, $stream, Some($width), &$crate::EmptyArgs {})
+    };
+    ($ctx:expr, $msg:expr, $stream:expr, width = $width:expr, $($rest:tt)*) => {
+        $crate::write_message!(@write $ctx, $msg, $stream, Some($width),
+                               $crate::message_args!($($rest)*))
+    };
     ($ctx:expr, $msg:expr, $stream:expr) => {
-        $ctx.write($msg, $stream, &$crate::EmptyArgs { })
+        $crate::write_message!(@write $ctx, $msg, $stream, None, &$crate::EmptyArgs {})
+    };
+    ($ctx:expr, $msg:expr, $stream:expr, $($rest:tt)*) => {
+        $crate::write_message!(@write $ctx, $msg, $stream, None, $crate::message_args!($($rest)*))
     };
-    ($ctx:expr, $msg:expr, $stream:expr, $($rest:tt)*) => ({
-        use $crate::Value;
-        $ctx.write($msg, $stream, message_args!($($rest)*))
-    });
+    (@write $ctx:expr, $msg:expr, $stream:expr, $width:expr, $args:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::target::{ViaFmt, ViaIo};
+        $crate::target::Target($stream).write_message_to(&$ctx, $msg, $args, $width)
+    }};
 }
 
+#[doc(hidden)]
 #[macro_export]
 macro_rules! message_args_aux {
     ($prev:expr, $name:ident => $value:expr) => {
         &$crate::ListArgs {
             name: stringify!($name),
-            value: Value::from($value),
+            value: $crate::Value::from($value),
             prev: $prev,
+            options: None,
         }
     };
     ($prev:expr, $name:ident) => {
         &$crate::ListArgs {
             name: stringify!($name),
-            value: Value::from($name),
+            value: $crate::Value::from($name),
             prev: $prev,
+            options: None,
         }
     };
     ($prev:expr, $name:ident, $($rest:tt)*) => {
-        message_args_aux!(
-            &$crate::ListArgs {
+        $crate::message_args_aux!(
+            Some(&$crate::ListArgs {
                 name: stringify!($name),
-                value: Value::from($name),
+                value: $crate::Value::from($name),
                 prev: $prev,
-            },
+                options: None,
+            }),
             $($rest)*)
     };
     ($prev:expr, $name:ident => $value:expr, $($rest:tt)*) => {
-        message_args_aux!(
+        $crate::message_args_aux!(
             Some(&$crate::ListArgs {
                 name: stringify!($name),
-                value: Value::from($value),
+                value: $crate::Value::from($value),
                 prev: $prev,
+                options: None,
             }),
             $($rest)*)
     };
 }
 
+/// Build the arguments for a message from `name => value` pairs, or
+/// `name` to use a variable of the same name.
 #[macro_export]
 macro_rules! message_args {
-    () => { /*&$crate::EmptyArgs { }*/ None };
-    ($name:ident => $value:expr) => {
-        &$crate::ListArgs {
-            name: stringify!($name),
-            value: Value::from($value),
-            prev: None,
-        }
+    () => { &$crate::EmptyArgs {} };
+    ($($rest:tt)*) => { $crate::message_args_aux!(None, $($rest)*) };
+}
+
+/// Define a [`StaticMessage`] from string literals and `arg!` parts.
+///
+/// [`StaticMessage`]: struct.StaticMessage.html
+#[macro_export]
+macro_rules! static_message {
+    ($($rest:tt)*) => {
+        $crate::StaticMessage::new(&$crate::static_message_parts!([] $($rest)*))
     };
-    ($name:ident) => {
-        &$crate::ListArgs {
-            name: stringify!($name),
-            value: Value::from($name),
-            prev: None,
-        }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! static_message_parts {
+    ([$($out:expr,)*]) => { [$($out,)*] };
+    ([$($out:expr,)*] $text:literal $(, $($rest:tt)*)?) => {
+        $crate::static_message_parts!([$($out,)* $crate::StaticPart::Text($text),] $($($rest)*)?)
     };
-    ($name:ident, $($rest:tt)*) => {
-        message_args_aux!(
-            Some(&$crate::ListArgs {
-                name: stringify!($name),
-                value: Value::from($name),
-                prev: None,
-            }),
-            $($rest)*)
+    ([$($out:expr,)*] $part:expr $(, $($rest:tt)*)?) => {
+        $crate::static_message_parts!([$($out,)* $part,] $($($rest)*)?)
     };
-    ($name:ident => $value:expr, $($rest:tt)*) => {
-        message_args_aux!(
-            Some(&$crate::ListArgs {
-                name: stringify!($name),
-                value: Value::from($value),
-                prev: None,
-            }),
-            $($rest)*)
+}
+
+/// An argument within a [`StaticMessage`].
+///
+/// [`StaticMessage`]: struct.StaticMessage.html
+#[macro_export]
+macro_rules! arg {
+    ($name:ident) => {
+        $crate::StaticPart::Arg(stringify!($name))
+    };
+}
+
+/// Format a message for each of a list of languages and arguments,
+/// and compare the result with a golden file.
+///
+/// The arguments are the `Arc<BundleSet>` to look the message up in,
+/// its key, an array of arguments, an array of language tags, and the
+/// path of the golden file. See the [`snapshot`] module.
+///
+/// [`snapshot`]: snapshot/index.html
+#[macro_export]
+macro_rules! assert_message_matrix {
+    ($bundles:expr, $key:expr, $cases:expr, $locales:expr, $path:expr) => {
+        $crate::snapshot::assert_snapshot(
+            $path,
+            &$crate::snapshot::render_matrix(&$bundles, $key, &$cases, &$locales),
+        )
     };
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{icu, Context};
+    use super::{icu, BundleSet, Context, Localizer, MessageBundle};
 
     #[test]
     fn format_without_args() {
@@ -333,6 +546,16 @@ mod tests {
         assert_eq!(s, "132");
     }
 
+    #[test]
+    fn format_without_context() {
+        let m = icu::parse("{a}{b}").unwrap();
+        let b = "2";
+        assert_eq!(format_message!(&m, a => "1", b), "12");
+
+        let m = icu::parse("Hello!").unwrap();
+        assert_eq!(format_message!(&m), "Hello!");
+    }
+
     #[test]
     fn write_without_args() {
         let ctx = Context::default();
@@ -355,7 +578,7 @@ mod tests {
 
     #[test]
     fn write_hashmap_args() {
-        use super::{ Value, Args };
+        use super::Value;
 
         let ctx = Context::default();
 
@@ -370,6 +593,41 @@ mod tests {
 
         assert_eq!(stream, "John times 3");
     }
+
+    #[test]
+    fn formatting_state_can_move_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Context>();
+        assert_send_sync::<MessageBundle>();
+        assert_send_sync::<BundleSet>();
+        assert_send_sync::<Localizer>();
+    }
 }
 
+mod builder;
 pub mod icu;
+mod static_message;
+#[doc(hidden)]
+pub mod target;
+
+/// The macros and the types most often used with them, for glob
+/// importing.
+///
+/// The macros refer to everything they use through full paths, so
+/// nothing else needs to be in scope:
+///
+/// ```
+/// use message_format::prelude::*;
+///
+/// static GREETING: StaticMessage = static_message!("Hello, ", arg!(name), "!");
+///
+/// fn greet(name: &str) -> String {
+///     format_message!(Context::default(), &GREETING, name)
+/// }
+///
+/// assert_eq!(greet("Ann"), "Hello, Ann!");
+/// ```
+pub mod prelude {
+    pub use {arg, format_message, message_args, static_message, write_message};
+    pub use {icu, Args, Context, Message, StaticMessage, Value};
+}
@@ -0,0 +1,373 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checking the messages of a bundle for likely mistakes.
+//!
+//! Each check is a rule with an id, such as `missing-plural-category`,
+//! and reports its findings at a [level]. The level of a rule can be
+//! changed for the keys matching a glob with a [`LintConfig`], or for
+//! one message in its [metadata], which takes precedence. In a catalog
+//! file, that is a comment such as `# @deny missing-plural-category`.
+//!
+//! ```
+//! use message_format::lint::{LintConfig, LintLevel, MISSING_PLURAL_CATEGORY};
+//! use message_format::MessageBundle;
+//!
+//! let bundle = MessageBundle::parse_entries("pl".parse().unwrap(), vec![
+//!     ("checkout.items", "{n, plural, one {# produkt} other {# produktów}}"),
+//!     ("cart.items", "{n, plural, one {# produkt} other {# produktów}}"),
+//! ]).unwrap();
+//! let config = LintConfig::new().deny(MISSING_PLURAL_CATEGORY, "checkout.*");
+//!
+//! let diagnostics = bundle.lint(&config);
+//! assert_eq!(diagnostics[0].key, "cart.items");
+//! assert_eq!(diagnostics[0].level, LintLevel::Warn);
+//! assert_eq!(diagnostics[1].key, "checkout.items");
+//! assert_eq!(diagnostics[1].level, LintLevel::Deny);
+//! ```
+//!
+//! [level]: enum.LintLevel.html
+//! [`LintConfig`]: struct.LintConfig.html
+//! [metadata]: ../struct.MessageMetadata.html#structfield.lint_levels
+
+use language_tags::LanguageTag;
+use std::collections::HashMap;
+use std::fmt;
+
+use canonical;
+use {DateTime, FormatError, Message, MessageMetadata, Value};
+
+/// A `plural` format lacks a branch for a category of the language.
+pub const MISSING_PLURAL_CATEGORY: &str = "missing-plural-category";
+/// An argument has a format type that isn't supported.
+pub const UNKNOWN_FORMAT: &str = "unknown-format";
+/// A `select` or `plural` has a key already matched by an earlier
+/// branch.
+pub const DUPLICATE_KEY: &str = "duplicate-key";
+/// The text of a message is longer than its `max_length`.
+pub const TOO_LONG: &str = "too-long";
+/// An argument is declared in the `placeholders` of the message with a
+/// type its formats can't use, such as a `String` given to a `plural`.
+pub const ARGUMENT_TYPE: &str = "argument-type";
+
+/// The ids of the rules, in the order they are checked.
+pub const RULES: &[&str] = &[MISSING_PLURAL_CATEGORY, UNKNOWN_FORMAT, DUPLICATE_KEY, TOO_LONG, ARGUMENT_TYPE];
+
+/// How the findings of a rule are reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintLevel {
+    /// Not reported.
+    Allow,
+    /// Reported as a warning, which is the default.
+    Warn,
+    /// Reported as an error.
+    Deny,
+}
+
+impl LintLevel {
+    /// The level named `name`: `allow`, `warn` or `deny`.
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+
+    /// The name of the level, as accepted by [`from_name`].
+    ///
+    /// [`from_name`]: #method.from_name
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The levels of the lint rules for the keys of a bundle.
+///
+/// Every rule warns unless configured otherwise. When several settings
+/// match a message, the one given last wins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LintConfig {
+    // (rule, key glob, level), in the order given.
+    settings: Vec<(String, String, LintLevel)>,
+}
+
+impl LintConfig {
+    /// A configuration in which every rule warns.
+    pub fn new() -> Self {
+        LintConfig::default()
+    }
+
+    /// Set the level of `rule` for the keys matching `keys`, a glob in
+    /// which `*` matches any text, including dots. The rule `*` stands
+    /// for every rule.
+    pub fn set(mut self, rule: &str, keys: &str, level: LintLevel) -> Self {
+        self.settings.push((rule.to_string(), keys.to_string(), level));
+        self
+    }
+
+    /// Don't report `rule` for the keys matching `keys`.
+    pub fn allow(self, rule: &str, keys: &str) -> Self {
+        self.set(rule, keys, LintLevel::Allow)
+    }
+
+    /// Report `rule` as a warning for the keys matching `keys`.
+    pub fn warn(self, rule: &str, keys: &str) -> Self {
+        self.set(rule, keys, LintLevel::Warn)
+    }
+
+    /// Report `rule` as an error for the keys matching `keys`.
+    pub fn deny(self, rule: &str, keys: &str) -> Self {
+        self.set(rule, keys, LintLevel::Deny)
+    }
+
+    /// The level of `rule` for the message `key`, taking the levels in
+    /// its `metadata` first.
+    pub fn level(&self, rule: &str, key: &str, metadata: Option<&MessageMetadata>) -> LintLevel {
+        let annotated = metadata
+            .into_iter()
+            .flat_map(|metadata| metadata.lint_levels.iter().rev())
+            .find(|(r, _)| r == rule || r == "*")
+            .map(|&(_, level)| level);
+        let configured = || {
+            self.settings
+                .iter()
+                .rev()
+                .find(|(r, keys, _)| (r == rule || r == "*") && glob_matches(keys, key))
+                .map(|&(_, _, level)| level)
+        };
+        annotated.or_else(configured).unwrap_or(LintLevel::Warn)
+    }
+}
+
+/// A finding of a lint rule. See [`MessageBundle::lint`].
+///
+/// [`MessageBundle::lint`]: ../struct.MessageBundle.html#method.lint
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintDiagnostic {
+    /// The id of the rule, such as `unknown-format`.
+    pub rule: &'static str,
+    /// The key of the message.
+    pub key: String,
+    /// The level the rule is reported at, never `Allow`.
+    pub level: LintLevel,
+    /// What the rule found.
+    pub message: String,
+}
+
+impl fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}] {}: {}", self.level, self.rule, self.key, self.message)
+    }
+}
+
+/// Check one message, adding what the rules which aren't allowed find
+/// to `found`.
+pub(crate) fn lint_message(
+    key: &str,
+    message: &Message,
+    metadata: Option<&MessageMetadata>,
+    language_tag: &LanguageTag,
+    config: &LintConfig,
+    found: &mut Vec<LintDiagnostic>,
+) {
+    let mut report = |rule: &'static str, text: String| {
+        let level = config.level(rule, key, metadata);
+        if level != LintLevel::Allow {
+            found.push(LintDiagnostic {
+                rule,
+                key: key.to_string(),
+                level,
+                message: text,
+            });
+        }
+    };
+    for coverage in message.check_plural_coverage(language_tag) {
+        let missing: Vec<String> = coverage
+            .missing
+            .iter()
+            .map(|category| format!("{:?}", category).to_lowercase())
+            .collect();
+        report(
+            MISSING_PLURAL_CATEGORY,
+            format!("`{}` has no branch for {}", coverage.argument, missing.join(", ")),
+        );
+    }
+    for format in message.unknown_formats() {
+        report(
+            UNKNOWN_FORMAT,
+            format!("`{}` uses the unknown format `{}`", format.variable_name, format.type_name),
+        );
+    }
+    let mut duplicates = vec![];
+    canonical::find_duplicates(message, &mut duplicates);
+    for duplicate in duplicates {
+        report(
+            DUPLICATE_KEY,
+            format!("`{}` matches `{}` in an earlier branch", duplicate.argument, duplicate.key),
+        );
+    }
+    if let Some(max_length) = metadata.and_then(|metadata| metadata.max_length) {
+        let len = message.literal_len();
+        if len > max_length {
+            report(
+                TOO_LONG,
+                format!("the text is {} characters long, more than {}", len, max_length),
+            );
+        }
+    }
+    let placeholders = metadata.map_or(&[][..], |metadata| &metadata.placeholders[..]);
+    let declared: HashMap<&str, Value> = placeholders
+        .iter()
+        .filter_map(|placeholder| {
+            let value = declared_value(placeholder.type_name.as_ref()?)?;
+            Some((placeholder.name.as_str(), value))
+        })
+        .collect();
+    for mismatch in message.check_arg_types(&declared) {
+        if let FormatError::TypeMismatch { argument, expected, .. } = mismatch {
+            let placeholder = placeholders.iter().find(|placeholder| placeholder.name == argument);
+            let type_name = placeholder.and_then(|placeholder| placeholder.type_name.as_ref());
+            report(
+                ARGUMENT_TYPE,
+                format!(
+                    "`{}` is declared as `{}` but should be {}",
+                    argument,
+                    type_name.map_or("", |t| t.as_str()),
+                    expected
+                ),
+            );
+        }
+    }
+}
+
+// A value of the type an argument is declared with, as named in ARB
+// catalogs, or `None` if the type isn't known.
+fn declared_value(type_name: &str) -> Option<Value<'static>> {
+    match type_name.to_ascii_lowercase().as_str() {
+        "int" | "integer" => Some(Value::Number(1)),
+        "num" | "double" => Some(Value::Float(1.5)),
+        "datetime" => Some(Value::Date(DateTime::new(2024, 1, 1))),
+        "string" => Some(Value::Str("")),
+        _ => None,
+    }
+}
+
+// Whether `text` matches `pattern`, in which `*` matches any text.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_matches, LintConfig, LintLevel, ARGUMENT_TYPE, DUPLICATE_KEY, TOO_LONG, UNKNOWN_FORMAT};
+    use icu::ParseOptions;
+    use {MessageBundle, MessageMetadata, PlaceholderMetadata};
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("checkout.*", "checkout.pay.button"));
+        assert!(glob_matches("*.title", "home.title"));
+        assert!(glob_matches("a*b*c", "abc"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("a*b*c", "acb"));
+        assert!(!glob_matches("checkout", "checkout.pay"));
+        assert!(!glob_matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn metadata_takes_precedence() {
+        let options = ParseOptions {
+            lenient: true,
+            extensions: true,
+        };
+        let mut bundle = MessageBundle::parse_entries_with(
+            "en".parse().unwrap(),
+            vec![
+                ("a", "{g, select, x {X} x {Y} other {Z}} {d, duration}"),
+                ("b", "A rather long label"),
+            ],
+            options,
+        )
+        .unwrap();
+        bundle.set_metadata(
+            "b",
+            MessageMetadata {
+                max_length: Some(10),
+                lint_levels: vec![(TOO_LONG.to_string(), LintLevel::Deny)],
+                ..Default::default()
+            },
+        );
+        let config = LintConfig::new().allow("*", "*").warn(UNKNOWN_FORMAT, "a");
+        let found: Vec<String> = bundle.lint(&config).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            found,
+            [
+                "warn[unknown-format] a: `d` uses the unknown format `duration`",
+                "deny[too-long] b: the text is 19 characters long, more than 10",
+            ]
+        );
+        let found = bundle.lint(&LintConfig::new().deny(DUPLICATE_KEY, "*"));
+        assert_eq!(found.len(), 3);
+        assert_eq!((found[1].rule, found[1].level), (DUPLICATE_KEY, LintLevel::Deny));
+    }
+
+    #[test]
+    fn declared_types_are_checked() {
+        let mut bundle = MessageBundle::parse_entries(
+            "en".parse().unwrap(),
+            vec![("due", "{n, plural, one {Due {when, date}} other {Due {when, date} ({count})}}")],
+        )
+        .unwrap();
+        let placeholder = |name: &str, type_name: &str| PlaceholderMetadata {
+            name: name.to_string(),
+            type_name: Some(type_name.to_string()),
+            example: None,
+        };
+        bundle.set_metadata(
+            "due",
+            MessageMetadata {
+                placeholders: vec![
+                    placeholder("n", "String"),
+                    placeholder("when", "DateTime"),
+                    placeholder("count", "Uri"),
+                ],
+                ..Default::default()
+            },
+        );
+        let config = LintConfig::new().allow("*", "*").deny(ARGUMENT_TYPE, "*");
+        let found: Vec<String> = bundle.lint(&config).iter().map(|d| d.to_string()).collect();
+        assert_eq!(found, ["deny[argument-type] due: `n` is declared as `String` but should be a number"]);
+    }
+}
@@ -0,0 +1,361 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Choosing between available languages.
+//!
+//! [`negotiate`] combines the [RFC 4647] filtering and lookup schemes
+//! with the [CLDR likely subtags] so that, for example, a request for
+//! `zh-TW` prefers `zh-Hant` over `zh-Hans`, and a request for `en-GB`
+//! still finds `en-US`.
+//!
+//! [`negotiate`]: fn.negotiate.html
+//! [RFC 4647]: https://tools.ietf.org/html/rfc4647
+//! [CLDR likely subtags]: http://cldr.unicode.org/development/development-process/design-proposals/likely-subtags
+
+use language_tags::LanguageTag;
+
+/// Choose the `available` languages to use for the `requested` ones,
+/// most preferred first and ending with `default`.
+///
+/// For each requested language in turn, the available languages are
+/// matched in these passes:
+///
+/// 1. An exact match.
+/// 2. A match once both are maximized with the likely subtags, so
+///    `zh-TW` matches `zh-Hant`.
+/// 3. Available languages within the requested range, so `de`
+///    matches `de-AT`.
+/// 4. A lookup of the requested language with subtags removed from
+///    the end, so `de-CH-1996` matches `de-CH` and then `de`.
+/// 5. Available languages with the same maximized language and
+///    script, so `en-GB` matches `en-US`, but `sr-Latn` doesn't match
+///    `sr-Cyrl`. Those in a region closer to the requested one come
+///    first, so `en-GB` prefers `en-AU` to `en-US`, and `es-AR`
+///    prefers `es-MX` to `es-ES`.
+///
+/// Extensions and private use subtags are ignored when matching.
+///
+/// ```
+/// use message_format::locale::negotiate;
+///
+/// let tags = |tags: &[&str]| -> Vec<_> { tags.iter().map(|t| t.parse().unwrap()).collect() };
+/// let available = tags(&["en-US", "de", "zh-Hans", "zh-Hant"]);
+/// let chosen = negotiate(&tags(&["zh-TW", "de-AT"]), &available, &available[0]);
+/// assert_eq!(chosen, tags(&["zh-Hant", "de", "en-US"]));
+/// ```
+pub fn negotiate(
+    requested: &[LanguageTag],
+    available: &[LanguageTag],
+    default: &LanguageTag,
+) -> Vec<LanguageTag> {
+    let available: Vec<(&LanguageTag, Subtags)> =
+        available.iter().map(|tag| (tag, Subtags::of(tag))).collect();
+    let mut chosen: Vec<LanguageTag> = vec![];
+    {
+        let mut choose = |tag: &LanguageTag| {
+            if !chosen.contains(tag) {
+                chosen.push(tag.clone());
+            }
+        };
+        for requested in requested {
+            let requested = Subtags::of(requested);
+            if requested.language.is_empty() {
+                continue;
+            }
+            let maximized = requested.maximize();
+
+            for (tag, subtags) in &available {
+                if subtags.key() == requested.key() {
+                    choose(tag);
+                }
+            }
+            for (tag, subtags) in &available {
+                if subtags.maximize() == maximized {
+                    choose(tag);
+                }
+            }
+            for (tag, subtags) in &available {
+                if subtags.is_within(&requested) {
+                    choose(tag);
+                }
+            }
+            let mut range = requested.key();
+            while let Some(end) = range.rfind('-') {
+                range.truncate(end);
+                // A singleton can't end a tag, so it goes too.
+                if range.len() >= 2 && range.as_bytes()[range.len() - 2] == b'-' {
+                    range.truncate(range.len() - 2);
+                }
+                for (tag, subtags) in &available {
+                    if subtags.key() == range {
+                        choose(tag);
+                    }
+                }
+            }
+            let mut similar: Vec<(u8, &LanguageTag)> = available
+                .iter()
+                .filter_map(|(tag, subtags)| {
+                    let other = subtags.maximize();
+                    if other.language == maximized.language && other.script == maximized.script {
+                        Some((maximized.region_distance(&other), *tag))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            similar.sort_by_key(|&(distance, _)| distance);
+            for (_, tag) in similar {
+                choose(tag);
+            }
+        }
+        choose(default);
+    }
+    chosen
+}
+
+/// Add the likely script and region to a language tag, as described
+/// by the CLDR "Add Likely Subtags" algorithm.
+///
+/// Only a small table of common languages is included, and other
+/// languages are returned unchanged apart from dropping extensions.
+///
+/// ```
+/// use message_format::locale::maximize;
+///
+/// assert_eq!(maximize(&"zh-TW".parse().unwrap()).to_string(), "zh-Hant-TW");
+/// assert_eq!(maximize(&"sr".parse().unwrap()).to_string(), "sr-Cyrl-RS");
+/// ```
+pub fn maximize(language_tag: &LanguageTag) -> LanguageTag {
+    let subtags = Subtags::of(language_tag).maximize();
+    let mut tag = language_tag.clone();
+    tag.extensions.clear();
+    tag.privateuse.clear();
+    tag.language = Some(subtags.language);
+    tag.script = subtags.script.map(|script| title_case(&script));
+    tag.region = subtags.region.map(|region| region.to_ascii_uppercase());
+    tag
+}
+
+// The subtags that matter for negotiation, in lower case.
+#[derive(Clone, Debug, PartialEq)]
+struct Subtags {
+    language: String,
+    script: Option<String>,
+    region: Option<String>,
+    // The extended language and variant subtags, which are only
+    // compared as part of the whole tag.
+    extlangs: Vec<String>,
+    variants: Vec<String>,
+}
+
+impl Subtags {
+    fn of(tag: &LanguageTag) -> Self {
+        let lower = |s: &String| s.to_ascii_lowercase();
+        Subtags {
+            language: tag.language.as_ref().map(lower).unwrap_or_default(),
+            script: tag.script.as_ref().map(lower),
+            region: tag.region.as_ref().map(lower),
+            extlangs: tag.extlangs.iter().map(lower).collect(),
+            variants: tag.variants.iter().map(lower).collect(),
+        }
+    }
+
+    fn key(&self) -> String {
+        let mut key = self.language.clone();
+        let subtags = self
+            .extlangs
+            .iter()
+            .chain(&self.script)
+            .chain(&self.region)
+            .chain(&self.variants);
+        for subtag in subtags {
+            key.push('-');
+            key.push_str(subtag);
+        }
+        key
+    }
+
+    // Whether this tag is within the basic language range `range`.
+    fn is_within(&self, range: &Subtags) -> bool {
+        let (key, range) = (self.key(), range.key());
+        key.starts_with(&range) && key[range.len()..].starts_with('-')
+    }
+
+    fn maximize(&self) -> Subtags {
+        let mut maximized = self.clone();
+        if maximized.script.is_some() && maximized.region.is_some() {
+            return maximized;
+        }
+        let script = self.script.as_deref();
+        let region = self.region.as_deref();
+        let language = self.language.as_str();
+        let candidates = [
+            (language, script, region),
+            (language, None, region),
+            (language, script, None),
+            (language, None, None),
+        ];
+        let likely = candidates.iter().find_map(|&(language, script, region)| {
+            LIKELY_SUBTAGS.iter().find(|&&(l, s, r, _)| {
+                l == language
+                    && s.eq_ignore_ascii_case(script.unwrap_or(""))
+                    && r.eq_ignore_ascii_case(region.unwrap_or(""))
+            })
+        });
+        if let Some(&(_, _, _, likely)) = likely {
+            let mut likely = likely.split('-').skip(1);
+            let (likely_script, likely_region) = (likely.next(), likely.next());
+            if maximized.script.is_none() {
+                maximized.script = likely_script.map(str::to_ascii_lowercase);
+            }
+            if maximized.region.is_none() {
+                maximized.region = likely_region.map(str::to_ascii_lowercase);
+            }
+        }
+        maximized
+    }
+
+    // How far apart the regions of two maximized tags of the same
+    // language are: 0 for the same region, 1 for regions in the same
+    // group, or both outside its groups, and 2 otherwise.
+    fn region_distance(&self, other: &Subtags) -> u8 {
+        let (a, b) = match (&self.region, &other.region) {
+            (Some(a), Some(b)) => (a.as_str(), b.as_str()),
+            (a, b) => return if a == b { 0 } else { 2 },
+        };
+        if a == b {
+            return 0;
+        }
+        let groups = REGION_GROUPS.iter().filter(|&&(language, _)| language == self.language);
+        let mut groups = groups.map(|&(_, regions)| {
+            let contains = |region: &str| regions.iter().any(|r| r.eq_ignore_ascii_case(region));
+            (contains(a), contains(b))
+        });
+        if groups.any(|(a, b)| a != b) {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+fn title_case(subtag: &str) -> String {
+    let mut title = subtag.to_ascii_lowercase();
+    title[..1].make_ascii_uppercase();
+    title
+}
+
+// A subset of the CLDR likely subtags, as language, script, region
+// and the maximized tag.
+static LIKELY_SUBTAGS: &[(&str, &str, &str, &str)] = &[
+    ("ar", "", "", "ar-Arab-EG"),
+    ("bn", "", "", "bn-Beng-BD"),
+    ("cs", "", "", "cs-Latn-CZ"),
+    ("da", "", "", "da-Latn-DK"),
+    ("de", "", "", "de-Latn-DE"),
+    ("el", "", "", "el-Grek-GR"),
+    ("en", "", "", "en-Latn-US"),
+    ("es", "", "", "es-Latn-ES"),
+    ("fa", "", "", "fa-Arab-IR"),
+    ("fi", "", "", "fi-Latn-FI"),
+    ("fr", "", "", "fr-Latn-FR"),
+    ("he", "", "", "he-Hebr-IL"),
+    ("hi", "", "", "hi-Deva-IN"),
+    ("hu", "", "", "hu-Latn-HU"),
+    ("id", "", "", "id-Latn-ID"),
+    ("it", "", "", "it-Latn-IT"),
+    ("ja", "", "", "ja-Jpan-JP"),
+    ("ko", "", "", "ko-Kore-KR"),
+    ("nb", "", "", "nb-Latn-NO"),
+    ("nl", "", "", "nl-Latn-NL"),
+    ("no", "", "", "no-Latn-NO"),
+    ("pl", "", "", "pl-Latn-PL"),
+    ("pt", "", "", "pt-Latn-BR"),
+    ("ro", "", "", "ro-Latn-RO"),
+    ("ru", "", "", "ru-Cyrl-RU"),
+    ("sr", "", "", "sr-Cyrl-RS"),
+    ("sr", "", "ME", "sr-Latn-ME"),
+    ("sv", "", "", "sv-Latn-SE"),
+    ("th", "", "", "th-Thai-TH"),
+    ("tr", "", "", "tr-Latn-TR"),
+    ("uk", "", "", "uk-Cyrl-UA"),
+    ("vi", "", "", "vi-Latn-VN"),
+    ("zh", "", "", "zh-Hans-CN"),
+    ("zh", "", "HK", "zh-Hant-HK"),
+    ("zh", "", "MO", "zh-Hant-MO"),
+    ("zh", "", "TW", "zh-Hant-TW"),
+    ("zh", "Hant", "", "zh-Hant-TW"),
+];
+
+// The regions of the Americas.
+const AMERICAS: &[&str] = &[
+    "ar", "bo", "br", "bz", "cl", "co", "cr", "cu", "do", "ec", "gt", "hn", "mx", "ni", "pa", "pe", "pr", "py", "sv",
+    "us", "uy", "ve",
+];
+
+// Groups of regions whose variants of a language are closer to each
+// other than to those elsewhere, from the CLDR language matching
+// data.
+static REGION_GROUPS: &[(&str, &[&str])] = &[
+    ("ar", &["dz", "eh", "ly", "ma", "mr", "tn"]),
+    ("en", &["as", "ca", "gu", "mh", "mp", "ph", "pr", "um", "us", "vi"]),
+    ("es", AMERICAS),
+    ("pt", AMERICAS),
+    ("zh", &["hk", "mo"]),
+];
+
+// The bundled tables, written out so that `data_version` can check
+// that a change to them comes with a new data revision.
+#[cfg(all(test, feature = "locale-data"))]
+pub(crate) fn bundled_tables() -> String {
+    format!("{:?}{:?}", LIKELY_SUBTAGS, REGION_GROUPS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::negotiate;
+    use language_tags::LanguageTag;
+
+    fn tags(tags: &[&str]) -> Vec<LanguageTag> {
+        tags.iter().map(|t| t.parse().unwrap()).collect()
+    }
+
+    fn chosen(requested: &[&str], available: &[&str]) -> Vec<String> {
+        let available = tags(available);
+        negotiate(&tags(requested), &available, &available[0])
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn exact_and_filtered_matches() {
+        assert_eq!(chosen(&["de"], &["en", "de-AT", "de"]), ["de", "de-AT", "en"]);
+        assert_eq!(chosen(&["DE-at"], &["en", "de-AT"]), ["de-AT", "en"]);
+    }
+
+    #[test]
+    fn lookup_removes_subtags() {
+        assert_eq!(chosen(&["de-CH-1996"], &["en", "de", "de-CH"]), ["de-CH", "de", "en"]);
+        assert_eq!(chosen(&["de-x-foo"], &["en", "de"]), ["de", "en"]);
+    }
+
+    #[test]
+    fn likely_subtags_are_used() {
+        assert_eq!(chosen(&["zh-TW"], &["en", "zh-Hans", "zh-Hant"]), ["zh-Hant", "en"]);
+        assert_eq!(chosen(&["zh"], &["en", "zh-Hant", "zh-Hans"]), ["zh-Hans", "zh-Hant", "en"]);
+        assert_eq!(chosen(&["en-GB"], &["de", "en-US"]), ["en-US", "de"]);
+        assert_eq!(chosen(&["sr-Latn"], &["en", "sr-Cyrl"]), ["en"]);
+    }
+
+    #[test]
+    fn closer_regions_are_preferred() {
+        assert_eq!(chosen(&["en-GB"], &["de", "en-US", "en-AU"]), ["en-AU", "en-US", "de"]);
+        assert_eq!(chosen(&["es-AR"], &["es", "es-MX"]), ["es", "es-MX"]);
+        assert_eq!(chosen(&["es-AR"], &["es-ES", "es-MX"]), ["es-MX", "es-ES"]);
+        assert_eq!(chosen(&["zh-HK"], &["zh-Hans", "zh-Hant", "zh-MO"]), ["zh-MO", "zh-Hant", "zh-Hans"]);
+    }
+}
@@ -4,48 +4,890 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use language_tags::LanguageTag;
+#[cfg(feature = "smallvec")]
+use smallvec::SmallVec;
 use std::fmt;
+use std::sync::Arc;
 
-use {Args, Context, MessagePart};
+use bind;
+use canonical::{self, DuplicateKey};
+use debug_tree;
+use edit;
+use examples::{self, Example};
+use icu::ast;
+use id;
+use plural::{self, MissingCategories};
+use variants::{self, Variant};
+use {
+    Args, ArgsShape, CompiledMessage, Context, EditError, FormatError, MessageBuilder, MessageDisplay, MessagePart,
+    SpecializedMessage, Value, ValueKind,
+};
+
+// The storage for the parts of a message. With the `smallvec`
+// feature, the parts of messages with up to four parts after merging
+// text, which includes most `plural` and `select` branches, are
+// stored inline rather than in a separate allocation. This type is
+// private so that the feature doesn't change the public API.
+#[cfg(feature = "smallvec")]
+pub(crate) type MessageParts = SmallVec<[Box<dyn MessagePart>; 4]>;
+
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type MessageParts = Vec<Box<dyn MessagePart>>;
+
+/// A handle to a message shared between owners, such as a bundle and
+/// the async tasks formatting it.
+///
+/// Formatting a [`Message`] only reads it, and every part is `Send`
+/// and `Sync`, so a handle can be cloned into any thread without
+/// copying or reparsing the message.
+///
+/// [`Message`]: struct.Message.html
+pub type SharedMessage = Arc<Message>;
 
 /// A message that has been localized and can be formatted in a
 /// locale-aware manner.
 ///
 /// While a `Message` can be created directly from [`MessagePart`]
-/// components, it is easiest to create it from [`icu::parse`].
+/// components or with a [`MessageBuilder`], it is easiest to create
+/// it from [`icu::parse`].
 ///
 /// [`MessagePart`]: trait.MessagePart.html
+/// [`MessageBuilder`]: struct.MessageBuilder.html
 /// [`icu::parse`]: icu/fn.parse.html
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Message {
-    pub parts: Vec<Box<dyn MessagePart>>,
+    pub(crate) parts: MessageParts,
+    // The total length of the plain text parts when constructed,
+    // used to size the output buffer.
+    literal_len: usize,
 }
 
-impl Default for Message {
-    fn default() -> Self {
-        Self { parts: vec![] }
+impl PartialEq for Message {
+    fn eq(&self, other: &Message) -> bool {
+        self.parts.len() == other.parts.len()
+            && self
+                .parts
+                .iter()
+                .zip(other.parts.iter())
+                .all(|(a, b)| a.eq_part(b.as_ref()))
+    }
+}
+
+impl MessagePart for Message {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        self.write_message(ctx, stream, args)
+    }
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        self.try_write_message(ctx, stream, args)
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+    fn children(&self) -> Vec<&Message> {
+        vec![self]
+    }
+    fn children_mut(&mut self) -> Vec<&mut Message> {
+        vec![self]
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
     }
 }
 
 impl Message {
     /// Construct a message from constituent parts.
+    ///
+    /// Adjacent [`PlainText`] parts are merged into one and empty ones
+    /// are removed, so that the text is written in as few calls as
+    /// possible.
+    ///
+    /// [`PlainText`]: icu/ast/struct.PlainText.html
     pub fn new(parts: Vec<Box<dyn MessagePart>>) -> Self {
-        Message { parts: parts }
+        let mut merged = MessageParts::with_capacity(parts.len());
+        let mut literal_len = 0;
+        for part in parts {
+            if let Some(text) = part.as_any().downcast_ref::<ast::PlainText>() {
+                literal_len += text.text.len();
+                if text.text.is_empty() {
+                    continue;
+                }
+                let previous = merged
+                    .last()
+                    .and_then(|last| last.as_any().downcast_ref::<ast::PlainText>());
+                if let Some(previous) = previous {
+                    let joined = ast::PlainText::new(&(previous.text.clone() + &text.text));
+                    *merged.last_mut().unwrap() = Box::new(joined);
+                    continue;
+                }
+            }
+            merged.push(part);
+        }
+        Message {
+            parts: merged,
+            literal_len,
+        }
+    }
+
+    /// The parts of the message, in order.
+    ///
+    /// ```
+    /// use message_format::icu;
+    ///
+    /// let m = icu::parse("Hello, {name}!").unwrap();
+    /// assert_eq!(m.parts().len(), 3);
+    /// ```
+    pub fn parts(&self) -> &[Box<dyn MessagePart>] {
+        &self.parts
+    }
+
+    /// The parts of the message, for changing them in place. Use
+    /// [`insert_part`] and [`remove_part`] to add or remove parts.
+    ///
+    /// [`insert_part`]: #method.insert_part
+    /// [`remove_part`]: #method.remove_part
+    pub fn parts_mut(&mut self) -> &mut [Box<dyn MessagePart>] {
+        &mut self.parts
+    }
+
+    /// The total length of the plain text in the message, not counting
+    /// nested formats. This is computed when the message is constructed.
+    pub fn literal_len(&self) -> usize {
+        self.literal_len
+    }
+
+    /// Start building a message from code.
+    pub fn builder() -> MessageBuilder {
+        MessageBuilder::new()
+    }
+
+    /// Format the message using `ctx`, returning a string.
+    ///
+    /// This is the same as [`Context::format`].
+    ///
+    /// [`Context::format`]: struct.Context.html#method.format
+    pub fn format_with<'f>(&self, ctx: &Context, args: &'f dyn Args<'f>) -> String {
+        ctx.format(self, args)
+    }
+
+    /// Write the message to a stream using `ctx`.
+    ///
+    /// This is the same as [`Context::write`].
+    ///
+    /// [`Context::write`]: struct.Context.html#method.write
+    pub fn write_with<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        ctx.write(self, stream, args)
+    }
+
+    /// Pair the message with its arguments in a value implementing
+    /// `fmt::Display`, formatted using [`Context::current`].
+    ///
+    /// Unless a default has been set, that context uses English and
+    /// the Gregorian calendar. Use [`Context::display`] to format for
+    /// another locale.
+    ///
+    /// [`Context::current`]: struct.Context.html#method.current
+    /// [`Context::display`]: struct.Context.html#method.display
+    pub fn display<'a>(&'a self, args: &'a dyn Args<'a>) -> MessageDisplay<'a> {
+        MessageDisplay::new(None, self, args)
+    }
+
+    /// Estimate the length of the formatted message, for sizing a
+    /// buffer before formatting into it.
+    ///
+    /// This is the length of the plain text plus the length of each
+    /// string argument, with a rough guess for other values. The
+    /// branches of `plural` and `select` formats are resolved against
+    /// `args`, so only the text that will be written is counted.
+    ///
+    /// ```
+    /// use message_format::{arg, icu};
+    ///
+    /// let m = icu::parse("Hello, {name}!").unwrap();
+    /// assert_eq!(m.estimated_len(&arg("name", "Ann")), 11);
+    /// ```
+    pub fn estimated_len<'f>(&self, args: &'f dyn Args<'f>) -> usize {
+        self.parts
+            .iter()
+            .map(|part| estimated_part_len(part.as_ref(), args))
+            .sum()
+    }
+
+    /// The names of the arguments used by the message, including
+    /// those in nested formats, in the order they first appear.
+    ///
+    /// ```
+    /// use message_format::icu;
+    ///
+    /// let m = icu::parse("{n, plural, one {{who} has one} other {{who} has #}} at {when, time}").unwrap();
+    /// assert_eq!(m.argument_names(), ["n", "who", "when"]);
+    /// ```
+    pub fn argument_names(&self) -> Vec<&str> {
+        let mut names = vec![];
+        self.walk(&mut |part| add_argument_name(part, &mut names));
+        names
+    }
+
+    /// Find the plural arguments, including nested ones, that lack a
+    /// branch for a plural category used by a language.
+    ///
+    /// ```
+    /// use message_format::{icu, PluralCategory};
+    ///
+    /// let m = icu::parse("{n, plural, one {# plik} other {# pliki}}").unwrap();
+    /// let missing = m.check_plural_coverage(&"pl".parse().unwrap());
+    /// assert_eq!(missing[0].argument, "n");
+    /// assert_eq!(missing[0].missing, [PluralCategory::Few, PluralCategory::Many]);
+    /// ```
+    pub fn check_plural_coverage(&self, language_tag: &LanguageTag) -> Vec<MissingCategories> {
+        let mut found = vec![];
+        plural::check_coverage(self, plural::required_categories(language_tag), &mut found);
+        found
+    }
+
+    /// Render the AST of the message as an indented tree, one node per
+    /// line, for snapshot tests of the parser. Unlike the `Debug`
+    /// output, this is stable and readable.
+    ///
+    /// ```
+    /// use message_format::icu;
+    ///
+    /// let m = icu::parse("Hi {name}, {n, plural, =0 {no files} other {# files}}").unwrap();
+    /// assert_eq!(m.debug_tree(), "\
+    /// message
+    ///   literal \"Hi \"
+    ///   argument name
+    ///   literal \", \"
+    ///   plural n
+    ///     =0
+    ///       literal \"no files\"
+    ///     other
+    ///       placeholder
+    ///       literal \" files\"
+    /// ");
+    /// ```
+    pub fn debug_tree(&self) -> String {
+        debug_tree::tree(self)
+    }
+
+    /// Render the AST of the message as a compact S-expression, with
+    /// the same labels as [`debug_tree`].
+    ///
+    /// ```
+    /// use message_format::icu;
+    ///
+    /// let m = icu::parse("{g, select, female {She} other {They}} paid {p, number, percent}").unwrap();
+    /// assert_eq!(
+    ///     m.debug_sexpr(),
+    ///     r#"(message (select g (female (literal "She")) (other (literal "They"))) (literal " paid ") (number p percent))"#,
+    /// );
+    /// ```
+    ///
+    /// [`debug_tree`]: #method.debug_tree
+    pub fn debug_sexpr(&self) -> String {
+        debug_tree::sexpr(self)
+    }
+
+    /// A hash of the structure of the message, for keying caches of
+    /// compiled messages and telling real changes from edits to the
+    /// formatting of the source.
+    ///
+    /// The hash covers the AST rather than the source, so whitespace
+    /// within arguments, quoting and the order of the branches of a
+    /// `select` don't change it. It is computed with SHA-512 and is
+    /// the same across platforms and releases.
+    ///
+    /// ```
+    /// use message_format::icu;
+    ///
+    /// let a = icu::parse("{n, plural, one {# file} other {# files}}").unwrap();
+    /// let b = icu::parse("{ n , plural ,\n    one {# file}\n    other {# files} }").unwrap();
+    /// let c = icu::parse("{n, plural, one {# file} other {# documents}}").unwrap();
+    /// assert_eq!(a.structural_hash(), b.structural_hash());
+    /// assert_ne!(a.structural_hash(), c.structural_hash());
+    /// ```
+    pub fn structural_hash(&self) -> u64 {
+        let digest = id::sha512(debug_tree::canonical(self).as_bytes());
+        let mut hash = 0;
+        for &byte in &digest[..8] {
+            hash = hash << 8 | u64::from(byte);
+        }
+        hash
+    }
+
+    /// Rewrite the message into a canonical form, so that messages
+    /// meaning the same thing have the same AST.
+    ///
+    /// The branches of each `select` and their keys are sorted, as are
+    /// the exact matches of each `plural`, which come before its
+    /// categories in their usual order. Runs of whitespace in the
+    /// style of arguments of unknown types are collapsed. A key already
+    /// matched by an earlier branch, which could never be selected, is
+    /// removed and returned, with the branch if it has no other key.
+    ///
+    /// Parts shared between the messages of a bundle by
+    /// [`MessageBundle::intern`] are left as they are.
+    ///
+    /// ```
+    /// use message_format::{icu, DuplicateKey};
+    ///
+    /// let mut m = icu::parse("{g, select, male {He} female {She} male {Him} other {They}}").unwrap();
+    /// let duplicates = m.normalize();
+    /// assert_eq!(m.debug_sexpr(), "(message (select g (female (literal \"She\")) (male (literal \"He\")) (other (literal \"They\"))))");
+    /// assert_eq!(duplicates, [DuplicateKey { argument: "g".to_string(), key: "male".to_string() }]);
+    /// ```
+    ///
+    /// [`MessageBundle::intern`]: struct.MessageBundle.html#method.intern
+    pub fn normalize(&mut self) -> Vec<DuplicateKey> {
+        let mut duplicates = vec![];
+        canonical::normalize(self, &mut duplicates);
+        duplicates
+    }
+
+    /// Substitute the arguments known ahead of time, returning a
+    /// smaller message which awaits the rest, such as to bind the
+    /// branding of a tenant once rather than on every format.
+    ///
+    /// Text values replace the `{name}` arguments using them, and a
+    /// `select` on a bound argument is replaced by the branch chosen,
+    /// comparing keys as [`SelectFormat::lookup_message`] does. Other
+    /// formats, whose output depends on the language of the context,
+    /// keep their arguments, but the branches of `plural` formats are
+    /// bound. Text bound in is no longer escaped or isolated as the
+    /// [`output_mode`] and [`bidi_isolation`] of the context do for
+    /// arguments.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context};
+    ///
+    /// let m = icu::parse("{brand} {plan, select, pro {Pro} other {Free}}: {n, plural, one {# seat} other {# seats}}").unwrap();
+    /// let tenant = m.bind(&arg("brand", "Acme").arg("plan", "pro"));
+    /// assert_eq!(icu::serialize(&tenant).unwrap(), "Acme Pro: {n, plural, one {# seat} other {# seats}}");
+    /// assert_eq!(Context::default().format(&tenant, &arg("n", 5)), "Acme Pro: 5 seats");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the message has a part of a type defined outside this crate,
+    /// which can't be copied.
+    ///
+    /// [`SelectFormat::lookup_message`]: icu/ast/struct.SelectFormat.html#method.lookup_message
+    /// [`output_mode`]: struct.Context.html#structfield.output_mode
+    /// [`bidi_isolation`]: struct.Context.html#structfield.bidi_isolation
+    pub fn bind<'f>(&self, args: &'f dyn Args<'f>) -> Message {
+        bind::bind(self, args)
+    }
+
+    /// Insert `part` before the part at `index`, or at the end when
+    /// `index` is the number of parts.
+    ///
+    /// Unlike [`Message::new`], this doesn't merge the part with
+    /// adjacent text, so that the indices of the other parts stay
+    /// predictable for an editor.
+    ///
+    /// [`Message::new`]: #method.new
+    pub fn insert_part(&mut self, index: usize, part: Box<dyn MessagePart>) -> Result<(), EditError> {
+        let len = self.parts.len();
+        if index > len {
+            return Err(EditError::PartOutOfRange { index, len });
+        }
+        if let Some(text) = part.as_any().downcast_ref::<ast::PlainText>() {
+            self.literal_len += text.text.len();
+        }
+        self.parts.insert(index, part);
+        Ok(())
+    }
+
+    /// Remove and return the part at `index`.
+    pub fn remove_part(&mut self, index: usize) -> Result<Box<dyn MessagePart>, EditError> {
+        let len = self.parts.len();
+        if index >= len {
+            return Err(EditError::PartOutOfRange { index, len });
+        }
+        let part = self.parts.remove(index);
+        if let Some(text) = part.as_any().downcast_ref::<ast::PlainText>() {
+            self.literal_len = self.literal_len.saturating_sub(text.text.len());
+        }
+        Ok(part)
+    }
+
+    /// The branch with the key `key` of the `select` or `plural` format
+    /// at `index`, for editing.
+    ///
+    /// The key of a `select` branch is one of its values, and that of
+    /// a `plural` branch is a category name such as `one`, or an exact
+    /// value such as `=0`. Either format's default branch is `other`.
+    pub fn branch_mut(&mut self, index: usize, key: &str) -> Result<&mut Message, EditError> {
+        edit::branch_mut(self, index, key)
+    }
+
+    /// Replace the branch with the key `key` of the `select` or `plural`
+    /// format at `index` by `message`, returning the old branch. See
+    /// [`branch_mut`] for the keys.
+    ///
+    /// ```
+    /// use message_format::{icu, EditError};
+    ///
+    /// let mut m = icu::parse("{n, plural, one {# file} other {# files}}").unwrap();
+    /// m.replace_branch(0, "one", icu::parse("a file").unwrap()).unwrap();
+    /// assert_eq!(icu::serialize(&m).unwrap(), "{n, plural, one {a file} other {# files}}");
+    /// assert_eq!(m.remove_branch(0, "other"), Err(EditError::RequiredBranch));
+    /// ```
+    ///
+    /// [`branch_mut`]: #method.branch_mut
+    pub fn replace_branch(&mut self, index: usize, key: &str, message: Message) -> Result<Message, EditError> {
+        edit::replace_branch(self, index, key, message)
+    }
+
+    /// Remove the branch with the key `key` of the `select` or `plural`
+    /// format at `index`, returning it. See [`branch_mut`] for the keys.
+    ///
+    /// A `select` branch is removed with all of its values. The `other`
+    /// branch can't be removed, as it is used when no other matches.
+    ///
+    /// [`branch_mut`]: #method.branch_mut
+    pub fn remove_branch(&mut self, index: usize, key: &str) -> Result<Message, EditError> {
+        edit::remove_branch(self, index, key)
+    }
+
+    /// Rename the argument `old` to `new` everywhere in the message,
+    /// including the `plural` formats whose `#` shows it, returning
+    /// the number of formats changed.
+    ///
+    /// The message is left unchanged when `new` isn't a valid argument
+    /// name, is already used by the message, or when a part using
+    /// `old` is shared by [`MessageBundle::intern`].
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context};
+    ///
+    /// let mut m = icu::parse("{n, plural, one {{who} has # file} other {{who} has # files}}").unwrap();
+    /// assert_eq!(m.rename_argument("who", "user"), Ok(2));
+    /// assert_eq!(Context::default().format(&m, &arg("n", 1).arg("user", "Ann")), "Ann has 1 file");
+    /// ```
+    ///
+    /// [`MessageBundle::intern`]: struct.MessageBundle.html#method.intern
+    pub fn rename_argument(&mut self, old: &str, new: &str) -> Result<usize, EditError> {
+        edit::rename_argument(self, old, new)
+    }
+
+    /// Check the kind of each value in `args` against the formats that
+    /// use it, anywhere in the message, as a linter does with the
+    /// example arguments of a catalog.
+    ///
+    /// Unlike formatting, which only fails on the branches taken, this
+    /// finds every [`FormatError::TypeMismatch`], once per argument.
+    /// Arguments missing from `args` are skipped.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, FormatError, ValueKind};
+    ///
+    /// let m = icu::parse("{n, plural, one {Due {when, date}} other {Due soon}}").unwrap();
+    /// let mismatches = m.check_arg_types(&arg("n", "three").arg("when", 3));
+    /// assert_eq!(mismatches, [
+    ///     FormatError::TypeMismatch { argument: "n".into(), expected: ValueKind::Number, actual: ValueKind::Str },
+    ///     FormatError::TypeMismatch { argument: "when".into(), expected: ValueKind::Date, actual: ValueKind::Number },
+    /// ]);
+    /// ```
+    ///
+    /// [`FormatError::TypeMismatch`]: enum.FormatError.html#variant.TypeMismatch
+    pub fn check_arg_types<'f>(&self, args: &'f dyn Args<'f>) -> Vec<FormatError> {
+        let mut mismatches = vec![];
+        collect_type_mismatches(self, args, &mut mismatches);
+        mismatches
+    }
+
+    /// Render the message with made up arguments, once for every
+    /// combination of its branches, so that QA tooling can show
+    /// reviewers each variant without writing arguments by hand.
+    ///
+    /// Each argument gets a value fitting how it is formatted: its
+    /// capitalized name for text, the keys of a `select` and another
+    /// value for `other`, a number for each branch of a `plural` in
+    /// the language of `ctx`, and a fixed number or date otherwise.
+    /// Variants rendering to the same text are only listed once.
+    ///
+    /// ```
+    /// use message_format::{icu, Context};
+    ///
+    /// let m = icu::parse("{name} has {n, plural, one {# file} other {# files}}").unwrap();
+    /// let outputs: Vec<String> = m.example_args(&Context::default())
+    ///     .into_iter()
+    ///     .map(|example| example.output)
+    ///     .collect();
+    /// assert_eq!(outputs, ["Name has 0 files", "Name has 1 file"]);
+    /// ```
+    pub fn example_args(&self, ctx: &Context) -> Vec<Example> {
+        examples::examples(self, ctx)
+    }
+
+    /// List every combination of the branches of the message's
+    /// `plural` and `select` formats, including nested ones, with the
+    /// branch taken for each argument, so that screenshot tests can
+    /// render every string the message can produce.
+    ///
+    /// Combinations taking different branches for the same argument
+    /// can't happen, so they are left out. [`Variant::args`] gives
+    /// values taking the branches of a variant.
+    ///
+    /// ```
+    /// use message_format::plural::PluralRules;
+    /// use message_format::{icu, Context, Selector};
+    ///
+    /// let m = icu::parse("{g, select, female {She} other {They}} sent {n, plural, =0 {nothing} other {# files}}").unwrap();
+    /// let variants = m.enumerate_variants();
+    /// assert_eq!(variants.len(), 4);
+    /// assert_eq!(variants[0].selectors[1], ("n".to_string(), Selector::Exact(0.0)));
+    ///
+    /// let rules = PluralRules::cardinal(&"en".parse().unwrap());
+    /// let args = variants[3].args(&rules).unwrap();
+    /// assert_eq!(Context::default().format(&m, &args), "They sent 2 files");
+    /// ```
+    ///
+    /// [`Variant::args`]: struct.Variant.html#method.args
+    pub fn enumerate_variants(&self) -> Vec<Variant> {
+        variants::enumerate(self)
+    }
+
+    /// Find the arguments, including nested ones, with a format type
+    /// that isn't supported, so that they can be reported when a
+    /// catalog is parsed in [lenient] mode.
+    ///
+    /// [lenient]: icu/parse/struct.ParseOptions.html#structfield.lenient
+    pub fn unknown_formats(&self) -> Vec<&ast::UnknownFormat> {
+        let mut found = vec![];
+        self.walk(&mut |part| found.extend(part.as_any().downcast_ref::<ast::UnknownFormat>()));
+        found
+    }
+
+    /// Resolve the branches expected by `shape` ahead of time, for a
+    /// message formatted many times with similar arguments.
+    ///
+    /// ```
+    /// use message_format::{icu, ArgsShape, Context, PluralCategory};
+    ///
+    /// let m = icu::parse("Processed {count, plural, one {# item} other {# items}}.").unwrap();
+    /// let shape = ArgsShape::new().plural("count", PluralCategory::Other);
+    /// let fast = m.specialize(&shape);
+    ///
+    /// let ctx = Context::default();
+    /// let args = message_format::arg("count", 12);
+    /// assert_eq!(fast.format_with(&ctx, &args), "Processed 12 items.");
+    /// ```
+    pub fn specialize(&self, shape: &ArgsShape) -> SpecializedMessage<'_> {
+        SpecializedMessage::new(self, shape)
+    }
+
+    /// Compile the message to a list of instructions which format it
+    /// faster, for a message formatted many times. See
+    /// [`CompiledMessage`].
+    ///
+    /// [`CompiledMessage`]: struct.CompiledMessage.html
+    pub fn compile(&self) -> CompiledMessage<'_> {
+        CompiledMessage::new(self)
     }
 
     /// Write a message to a stream.
     ///
     /// This shouldn't be called directly in the usual case.
-    /// Use `Context::write` or `Context::format` instead.
+    /// Use `Context::write` or `Context::format` (or `write_with`
+    /// and `format_with`) instead.
     pub fn write_message<'f>(
         &self,
         ctx: &Context,
         stream: &mut dyn fmt::Write,
         args: &'f dyn Args<'f>,
     ) -> fmt::Result {
+        self.try_write_message(ctx, stream, args).map_err(|_| fmt::Error {})
+    }
+
+    // Call `f` with every part of the message, at any depth, each
+    // before the parts nested in it.
+    pub(crate) fn walk<'m>(&'m self, f: &mut dyn FnMut(&'m dyn MessagePart)) {
+        for part in &self.parts {
+            walk_part(part.as_ref(), f);
+        }
+    }
+
+    // Like `walk`, with mutable access to the parts. Parts that can't
+    // be changed are visited, but not the parts nested in them.
+    pub(crate) fn walk_mut(&mut self, f: &mut dyn FnMut(&mut Box<dyn MessagePart>)) {
+        for part in &mut self.parts {
+            f(part);
+            for child in part.children_mut() {
+                child.walk_mut(f);
+            }
+        }
+    }
+
+    // Like `write_message`, returning why it failed if it did.
+    pub(crate) fn try_write_message<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
         for part in &self.parts {
-            part.apply_format(ctx, stream, args)?
+            part.try_apply_format(ctx, stream, args)?
         }
         Ok(())
     }
 }
+
+// Call `f` with `part` and every part nested in it, each before the
+// parts nested in it.
+pub(crate) fn walk_part<'m>(part: &'m dyn MessagePart, f: &mut dyn FnMut(&'m dyn MessagePart)) {
+    f(part);
+    for child in part.children() {
+        child.walk(f);
+    }
+}
+
+// The names of the arguments used by a part, as by
+// `Message::argument_names`.
+pub(crate) fn part_argument_names(part: &dyn MessagePart) -> Vec<&str> {
+    let mut names = vec![];
+    walk_part(part, &mut |part| add_argument_name(part, &mut names));
+    names
+}
+
+// Add the name of the argument `part` formats, if it formats one and
+// the name isn't in `names` yet.
+fn add_argument_name<'m>(part: &'m dyn MessagePart, names: &mut Vec<&'m str>) {
+    let any = part.as_any();
+    let name = if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+        &fmt.variable_name
+    } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+        &fmt.variable_name
+    } else {
+        return;
+    };
+    if !names.contains(&name.as_str()) {
+        names.push(name);
+    }
+}
+
+fn collect_type_mismatches<'f>(message: &Message, args: &'f dyn Args<'f>, found: &mut Vec<FormatError>) {
+    let mut check = |name: &str, expected: ValueKind| {
+        let value = match args.get(name) {
+            Some(value) => value,
+            None => return,
+        };
+        let fits = match expected {
+            ValueKind::Date => value.kind() == ValueKind::Date,
+            _ => value.as_float().is_some(),
+        };
+        let seen = found.iter().any(|error| match *error {
+            FormatError::TypeMismatch { ref argument, .. } => argument == name,
+            _ => false,
+        });
+        if !fits && !seen {
+            found.push(FormatError::TypeMismatch {
+                argument: name.to_string(),
+                expected,
+                actual: value.kind(),
+            });
+        }
+    };
+    message.walk(&mut |part| {
+        let any = part.as_any();
+        if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+            check(&fmt.variable_name, ValueKind::Date);
+        } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+            check(&fmt.variable_name, ValueKind::Number);
+        } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+            check(&fmt.variable_name, ValueKind::Number);
+        } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+            check(&fmt.variable_name, ValueKind::Number);
+        }
+    });
+}
+
+// A guess at the length of a formatted value that isn't a string.
+const VALUE_LEN_GUESS: usize = 8;
+
+fn estimated_value_len(value: &Value) -> usize {
+    match *value {
+        Value::Str(s) => s.len(),
+        Value::String(ref s) => s.len(),
+        Value::Number(n) => n.to_string().len(),
+        Value::Bool(_) => 5,
+        _ => VALUE_LEN_GUESS,
+    }
+}
+
+fn estimated_part_len<'f>(part: &dyn MessagePart, args: &'f dyn Args<'f>) -> usize {
+    let any = part.as_any();
+    if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+        text.text.len()
+    } else if let Some(message) = any.downcast_ref::<Message>() {
+        message.estimated_len(args)
+    } else if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+        args.get(&fmt.variable_name).map_or(0, estimated_value_len)
+    } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+        match args.get(&fmt.variable_name) {
+            Some(value) => fmt.lookup_message(&value.select_key()).estimated_len(args),
+            None => 0,
+        }
+    } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+        match args.get(&fmt.variable_name).and_then(|v| v.as_float()) {
+            Some(value) => fmt.lookup_message(value).estimated_len(args),
+            None => 0,
+        }
+    } else if any.downcast_ref::<ast::DateTimeFormat>().is_some() {
+        2 * VALUE_LEN_GUESS
+    } else {
+        VALUE_LEN_GUESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use {arg, icu, Args, Context, DateTime, Message, MessagePart, SharedMessage};
+
+    #[test]
+    fn context_is_used() {
+        let ctx = Context {
+            language_tag: "en-u-ca-buddhist".parse().unwrap(),
+            ..Context::default()
+        };
+        let m = icu::parse("{d, date, ::y}").unwrap();
+        let args = arg("d", DateTime::new(2024, 1, 1));
+        assert_eq!(m.format_with(&ctx, &args), "2567 BE");
+
+        let mut s = String::new();
+        m.write_with(&Context::default(), &mut s, &args).unwrap();
+        assert_eq!(s, "2024");
+    }
+
+    #[test]
+    fn adjacent_text_is_merged() {
+        let m = Message::builder()
+            .text("Hello")
+            .text("")
+            .text(", ")
+            .arg("name")
+            .text("!")
+            .build();
+        assert_eq!(m.parts.len(), 3);
+        assert_eq!(m.literal_len(), 8);
+        assert_eq!(m, icu::parse("Hello, {name}!").unwrap());
+    }
+
+    #[test]
+    fn estimated_len_follows_branches() {
+        let m = icu::parse("{n, plural, one {one item} other {# items in {dir}}}").unwrap();
+        let one = arg("n", 1);
+        assert_eq!(m.estimated_len(&one), 8);
+        let many = arg("n", 5);
+        let many = many.arg("dir", "/tmp");
+        let estimate = m.estimated_len(&many);
+        assert_eq!(estimate, " items in ".len() + "/tmp".len() + 8);
+        assert!(estimate >= m.format_with(&Context::default(), &many).len());
+    }
+
+    #[test]
+    fn structural_hash_ignores_select_order() {
+        let a = icu::parse("{g, select, female {She} male {He} other {They}} left").unwrap();
+        let b = icu::parse("{g, select, male {He} female {She} other {They}} left").unwrap();
+        let c = icu::parse("{g, select, female {He} male {She} other {They}} left").unwrap();
+        assert_eq!(a.structural_hash(), b.structural_hash());
+        assert_ne!(a.structural_hash(), c.structural_hash());
+    }
+
+    #[test]
+    fn normalize_sorts_and_dedupes_branches() {
+        let options = icu::ParseOptions { lenient: true, extensions: true };
+        let mut m = icu::parse_with(
+            "{n, plural, other {#} =2 {two} one {one} =0 {none} =2 {deux}} \
+             {g, select, b|a {x} c|a {y} other {z}} {d, fancy,  long   style }",
+            options,
+        )
+        .unwrap();
+        let duplicates = m.normalize();
+        assert_eq!(
+            m.debug_sexpr(),
+            "(message (plural n (=0 (literal \"none\")) (=2 (literal \"two\")) (one (literal \"one\")) \
+             (other (placeholder))) (literal \" \") (select g (a|b (literal \"x\")) (c (literal \"y\")) \
+             (other (literal \"z\"))) (literal \" \") (unknown d fancy \"long style\"))"
+        );
+        let keys: Vec<_> = duplicates.iter().map(|d| (d.argument.as_str(), d.key.as_str())).collect();
+        assert_eq!(keys, [("n", "=2"), ("g", "a")]);
+        let normalized = m.debug_sexpr();
+        assert!(m.normalize().is_empty());
+        assert_eq!(m.debug_sexpr(), normalized);
+    }
+
+    #[test]
+    fn messages_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedMessage>();
+    }
+
+    // A part defined outside the crate, formatting its message twice.
+    #[derive(Debug)]
+    struct Twice(Message);
+
+    impl MessagePart for Twice {
+        fn apply_format<'f>(&self, ctx: &Context, stream: &mut dyn fmt::Write, args: &'f dyn Args<'f>) -> fmt::Result {
+            self.0.write_message(ctx, stream, args)?;
+            self.0.write_message(ctx, stream, args)
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+            Some(self)
+        }
+        fn children(&self) -> Vec<&Message> {
+            vec![&self.0]
+        }
+        fn children_mut(&mut self) -> Vec<&mut Message> {
+            vec![&mut self.0]
+        }
+    }
+
+    #[test]
+    fn parts_defined_elsewhere_are_walked() {
+        let nested = icu::parse("{n, plural, one {# {what}} other {# {what}s}} ").unwrap();
+        let mut m = Message::new(vec![Box::new(Twice(nested))]);
+        assert_eq!(m.argument_names(), ["n", "what"]);
+        assert_eq!(m.rename_argument("what", "item"), Ok(2));
+        assert_eq!(m.argument_names(), ["n", "item"]);
+        assert_eq!(m.enumerate_variants().len(), 2);
+        let ctx = Context::default();
+        assert_eq!(ctx.format(&m, &arg("n", 1).arg("item", "cat")), "1 cat 1 cat ");
+    }
+}
@@ -6,11 +6,14 @@
 
 use std::{any::Any, fmt};
 
-use {Args, Context};
+use {Args, Context, FormatError, Message};
 
 /// Part of a message. May be something that requires formatting a
 /// value or just plain text.
-pub trait MessagePart: fmt::Debug {
+///
+/// Parts are `Send` and `Sync` so that parsed messages can be shared
+/// between threads.
+pub trait MessagePart: fmt::Debug + Send + Sync {
     /// Format this message part.
     fn apply_format<'f>(
         &self,
@@ -18,5 +21,52 @@ pub trait MessagePart: fmt::Debug {
         stream: &mut dyn fmt::Write,
         args: &'f dyn Args<'f>,
     ) -> fmt::Result;
+    /// Format this message part, returning why it failed if it did.
+    ///
+    /// By default, a failure of [`apply_format`] is reported as
+    /// `FormatError::Format`. Parts that can tell why they failed, or
+    /// that format other parts, override this and implement
+    /// `apply_format` with it.
+    ///
+    /// [`apply_format`]: #tymethod.apply_format
+    fn try_apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> Result<(), FormatError> {
+        Ok(self.apply_format(ctx, stream, args)?)
+    }
     fn as_any(&self) -> &dyn Any;
+    /// The messages nested in this part, such as the branches of a
+    /// `select`, in the order they are written. Code walking a message
+    /// uses this to reach every part, including those of types it
+    /// doesn't know about.
+    ///
+    /// By default, a part has no nested messages.
+    fn children(&self) -> Vec<&Message> {
+        Vec::new()
+    }
+    /// Mutable access to the messages nested in this part, in the same
+    /// order as [`children`]. Parts that can't be changed, like those
+    /// for which [`as_any_mut`] is `None`, have none.
+    ///
+    /// [`children`]: #method.children
+    /// [`as_any_mut`]: #method.as_any_mut
+    fn children_mut(&mut self) -> Vec<&mut Message> {
+        Vec::new()
+    }
+    /// Mutable access to this part, for code rewriting messages, or
+    /// `None` if it can't be changed, as when it is shared between
+    /// messages.
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+    /// Compare this part structurally with another part. Parts of
+    /// different types are never equal.
+    ///
+    /// By default, a part is only equal to itself.
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        std::ptr::eq(self.as_any() as *const dyn Any as *const u8, other.as_any() as *const dyn Any as *const u8)
+    }
 }
@@ -0,0 +1,64 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::str;
+
+/// A currency, by its ISO 4217 code, for writing a number as an
+/// amount of money. See [`NumberOptions::currency`].
+///
+/// [`NumberOptions::currency`]: struct.NumberOptions.html#method.currency
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Currency {
+    code: [u8; 3],
+}
+
+// The symbol and fraction digits of the currencies with a symbol of
+// their own. Others are written with their code and two digits.
+pub(super) static CURRENCIES: &[(&str, &str, usize)] = &[
+    ("EUR", "€", 2),
+    ("GBP", "£", 2),
+    ("INR", "₹", 2),
+    ("JPY", "¥", 0),
+    ("KRW", "₩", 0),
+    ("USD", "$", 2),
+];
+
+impl Currency {
+    /// The currency with the code `code`, such as `USD`, ignoring
+    /// case, or `None` if it isn't three ASCII letters.
+    pub fn new(code: &str) -> Option<Currency> {
+        match *code.as_bytes() {
+            [a, b, c] if code.bytes().all(|b| b.is_ascii_alphabetic()) => Some(Currency {
+                code: [a.to_ascii_uppercase(), b.to_ascii_uppercase(), c.to_ascii_uppercase()],
+            }),
+            _ => None,
+        }
+    }
+
+    /// The ISO 4217 code, in upper case.
+    pub fn code(&self) -> &str {
+        str::from_utf8(&self.code).expect("currency codes are ASCII")
+    }
+
+    fn data(&self) -> Option<&'static (&'static str, &'static str, usize)> {
+        CURRENCIES.iter().find(|&&(code, _, _)| code == self.code())
+    }
+
+    /// The symbol amounts are written with, such as `$`, or the code
+    /// for currencies without a well known symbol.
+    pub fn symbol(&self) -> &str {
+        match self.data() {
+            Some(&(_, symbol, _)) => symbol,
+            None => self.code(),
+        }
+    }
+
+    /// The number of fraction digits amounts are written with, such
+    /// as 2 for cents.
+    pub fn fraction_digits(&self) -> usize {
+        self.data().map_or(2, |&(_, _, digits)| digits)
+    }
+}
@@ -0,0 +1,176 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::borrow::Cow;
+
+/// Locale specific symbols used when formatting numbers.
+///
+/// This data is derived from the [CLDR].
+///
+/// [CLDR]: http://cldr.unicode.org/
+#[derive(Clone, Debug)]
+pub struct NumberSymbols {
+    /// The language these symbols are for.
+    pub language: Cow<'static, str>,
+    /// The separator between the integer and fraction digits.
+    pub decimal: Cow<'static, str>,
+    /// The separator between groups of integer digits.
+    pub group: Cow<'static, str>,
+    /// The sign written before negative numbers.
+    pub minus: Cow<'static, str>,
+    /// The pattern for percentages, where `#` is the number.
+    pub percent_pattern: Cow<'static, str>,
+    /// The pattern for per mille values, where `#` is the number.
+    pub permille_pattern: Cow<'static, str>,
+    /// The pattern for amounts of money, where `#` is the number and
+    /// `¤` the currency symbol.
+    pub currency_pattern: Cow<'static, str>,
+    /// The patterns for `compact-short` notation, in increasing order.
+    pub compact_short: &'static [CompactPattern],
+    /// The patterns for `compact-long` notation, in increasing order.
+    pub compact_long: &'static [CompactPattern],
+}
+
+/// A pattern for numbers written in compact notation, such as `0K`.
+#[derive(Debug)]
+pub struct CompactPattern {
+    /// The power of ten from which the pattern is used. The number is
+    /// divided by this power of ten.
+    pub exponent: i32,
+    /// The pattern when the divided number is in the `one` plural
+    /// category, where `0` is the number.
+    pub one: &'static str,
+    /// The pattern for the other plural categories.
+    pub other: &'static str,
+}
+
+// A pattern which doesn't change with the plural category.
+const fn compact(exponent: i32, pattern: &'static str) -> CompactPattern {
+    CompactPattern {
+        exponent,
+        one: pattern,
+        other: pattern,
+    }
+}
+
+#[cfg(feature = "locale-data")]
+const fn compact_plural(exponent: i32, one: &'static str, other: &'static str) -> CompactPattern {
+    CompactPattern { exponent, one, other }
+}
+
+impl NumberSymbols {
+    /// Find the symbols for a language, falling back to English if
+    /// there are none available. [`Context::missing_locale_data`]
+    /// reports when that happens.
+    ///
+    /// [`Context::missing_locale_data`]: ../struct.Context.html#method.missing_locale_data
+    pub fn for_language_tag(language_tag: &LanguageTag) -> &'static NumberSymbols {
+        NumberSymbols::find(language_tag).unwrap_or(&EN)
+    }
+
+    // The bundled data for a language, without falling back.
+    pub(crate) fn find(language_tag: &LanguageTag) -> Option<&'static NumberSymbols> {
+        let language = language_tag.language.as_ref()?;
+        ALL.iter().find(|data| data.language == **language).cloned()
+    }
+}
+
+#[cfg(feature = "locale-data")]
+pub(super) static ALL: &[&NumberSymbols] = &[&EN, &DE, &FR, &SV];
+
+#[cfg(not(feature = "locale-data"))]
+pub(super) static ALL: &[&NumberSymbols] = &[&EN];
+
+static EN: NumberSymbols = NumberSymbols {
+    language: Cow::Borrowed("en"),
+    decimal: Cow::Borrowed("."),
+    group: Cow::Borrowed(","),
+    minus: Cow::Borrowed("-"),
+    percent_pattern: Cow::Borrowed("#%"),
+    permille_pattern: Cow::Borrowed("#‰"),
+    currency_pattern: Cow::Borrowed("¤#"),
+    compact_short: &[
+        compact(3, "0K"),
+        compact(6, "0M"),
+        compact(9, "0B"),
+        compact(12, "0T"),
+    ],
+    compact_long: &[
+        compact(3, "0 thousand"),
+        compact(6, "0 million"),
+        compact(9, "0 billion"),
+        compact(12, "0 trillion"),
+    ],
+};
+
+#[cfg(feature = "locale-data")]
+static DE: NumberSymbols = NumberSymbols {
+    language: Cow::Borrowed("de"),
+    decimal: Cow::Borrowed(","),
+    group: Cow::Borrowed("."),
+    minus: Cow::Borrowed("-"),
+    percent_pattern: Cow::Borrowed("#\u{a0}%"),
+    permille_pattern: Cow::Borrowed("#\u{a0}‰"),
+    currency_pattern: Cow::Borrowed("#\u{a0}¤"),
+    compact_short: &[
+        compact(6, "0\u{a0}Mio."),
+        compact(9, "0\u{a0}Mrd."),
+        compact(12, "0\u{a0}Bio."),
+    ],
+    compact_long: &[
+        compact(3, "0 Tausend"),
+        compact_plural(6, "0 Million", "0 Millionen"),
+        compact_plural(9, "0 Milliarde", "0 Milliarden"),
+        compact_plural(12, "0 Billion", "0 Billionen"),
+    ],
+};
+
+#[cfg(feature = "locale-data")]
+static FR: NumberSymbols = NumberSymbols {
+    language: Cow::Borrowed("fr"),
+    decimal: Cow::Borrowed(","),
+    group: Cow::Borrowed("\u{202f}"),
+    minus: Cow::Borrowed("-"),
+    percent_pattern: Cow::Borrowed("#\u{202f}%"),
+    permille_pattern: Cow::Borrowed("#\u{202f}‰"),
+    currency_pattern: Cow::Borrowed("#\u{a0}¤"),
+    compact_short: &[
+        compact(3, "0\u{a0}k"),
+        compact(6, "0\u{a0}M"),
+        compact(9, "0\u{a0}Md"),
+        compact(12, "0\u{a0}Bn"),
+    ],
+    compact_long: &[
+        compact_plural(3, "0 millier", "0 mille"),
+        compact_plural(6, "0 million", "0 millions"),
+        compact_plural(9, "0 milliard", "0 milliards"),
+        compact_plural(12, "0 billion", "0 billions"),
+    ],
+};
+
+#[cfg(feature = "locale-data")]
+static SV: NumberSymbols = NumberSymbols {
+    language: Cow::Borrowed("sv"),
+    decimal: Cow::Borrowed(","),
+    group: Cow::Borrowed("\u{a0}"),
+    minus: Cow::Borrowed("\u{2212}"),
+    percent_pattern: Cow::Borrowed("#\u{a0}%"),
+    permille_pattern: Cow::Borrowed("#\u{a0}‰"),
+    currency_pattern: Cow::Borrowed("#\u{a0}¤"),
+    compact_short: &[
+        compact(3, "0\u{a0}tn"),
+        compact(6, "0\u{a0}mn"),
+        compact(9, "0\u{a0}md"),
+        compact(12, "0\u{a0}bn"),
+    ],
+    compact_long: &[
+        compact(3, "0 tusen"),
+        compact_plural(6, "0 miljon", "0 miljoner"),
+        compact_plural(9, "0 miljard", "0 miljarder"),
+        compact_plural(12, "0 biljon", "0 biljoner"),
+    ],
+};
@@ -0,0 +1,187 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::RoundingMode;
+
+/// The decimal digits of a finite number, for rounding without the
+/// error that arithmetic on an `f64` would add.
+///
+/// The value is `0.d₁d₂…dₙ × 10^exponent`, with no leading or trailing
+/// zeros in `digits`. Zero has no digits.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Digits {
+    pub negative: bool,
+    pub digits: Vec<u8>,
+    pub exponent: i32,
+}
+
+impl Digits {
+    /// The digits of the shortest representation of `value` that
+    /// round trips, which is what ICU rounds too.
+    pub fn from_f64(value: f64) -> Self {
+        let text = format!("{:e}", value.abs());
+        let (mantissa, exponent) = text.split_at(text.find('e').unwrap());
+        let exponent: i32 = exponent[1..].parse().unwrap();
+        let mut digits: Vec<u8> = mantissa.bytes().filter(u8::is_ascii_digit).map(|b| b - b'0').collect();
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Digits {
+            negative: value.is_sign_negative(),
+            exponent: if digits.is_empty() { 0 } else { exponent + 1 },
+            digits,
+        }
+    }
+
+    /// The digits of an integer, all kept, even those an `f64` would
+    /// lose.
+    pub fn from_integer(negative: bool, magnitude: u128) -> Self {
+        let mut digits: Vec<u8> = magnitude.to_string().bytes().map(|b| b - b'0').collect();
+        let exponent = if magnitude == 0 { 0 } else { digits.len() as i32 };
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Digits {
+            negative,
+            digits,
+            exponent,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Round to `fraction_digits` digits after the decimal point.
+    pub fn round_to_fraction(&mut self, fraction_digits: i32, mode: RoundingMode) {
+        let keep = self.exponent + fraction_digits;
+        if keep < 0 {
+            // Only zeros are kept, so a rounded up value is one unit.
+            self.exponent = -fraction_digits;
+            self.round(0, false, mode);
+        } else {
+            self.round(keep as usize, true, mode);
+        }
+    }
+
+    /// Round to `significant` significant digits.
+    pub fn round_to_significant(&mut self, significant: usize, mode: RoundingMode) {
+        self.round(significant, true, mode);
+    }
+
+    // Keep the first `keep` digits. When `aligned` is false, the
+    // discarded digits are preceded by zeros.
+    fn round(&mut self, keep: usize, aligned: bool, mode: RoundingMode) {
+        if keep >= self.digits.len() {
+            return;
+        }
+        let (first, rest_nonzero) = if aligned {
+            (self.digits[keep], self.digits[keep + 1..].iter().any(|&d| d != 0))
+        } else {
+            (0, !self.digits.is_empty())
+        };
+        let any_nonzero = first != 0 || rest_nonzero;
+        let last_odd = keep > 0 && self.digits[keep - 1] % 2 == 1;
+        let up = match mode {
+            RoundingMode::Ceiling => any_nonzero && !self.negative,
+            RoundingMode::Floor => any_nonzero && self.negative,
+            RoundingMode::Down => false,
+            RoundingMode::Up => any_nonzero,
+            RoundingMode::HalfEven => first > 5 || (first == 5 && (rest_nonzero || last_odd)),
+            RoundingMode::HalfDown => first > 5 || (first == 5 && rest_nonzero),
+            RoundingMode::HalfUp => first >= 5,
+        };
+        self.digits.truncate(keep);
+        if up {
+            let mut i = keep;
+            loop {
+                if i == 0 {
+                    self.digits.insert(0, 1);
+                    self.exponent += 1;
+                    break;
+                }
+                i -= 1;
+                if self.digits[i] == 9 {
+                    self.digits[i] = 0;
+                } else {
+                    self.digits[i] += 1;
+                    break;
+                }
+            }
+        }
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+        if self.digits.is_empty() {
+            self.exponent = 0;
+        }
+    }
+
+    /// The integer digits, which are at least `0`.
+    pub fn integer(&self) -> String {
+        if self.exponent <= 0 {
+            return "0".to_string();
+        }
+        (0..self.exponent as usize)
+            .map(|i| char::from(b'0' + self.digits.get(i).cloned().unwrap_or(0)))
+            .collect()
+    }
+
+    /// The fraction digits, without trailing zeros.
+    pub fn fraction(&self) -> String {
+        let start = self.exponent.max(0) as usize;
+        let leading_zeros = (-self.exponent).max(0) as usize;
+        let digits = self.digits.iter().skip(start).map(|&d| char::from(b'0' + d));
+        "0".repeat(leading_zeros).chars().chain(digits).collect()
+    }
+
+    /// The number of significant digits, ignoring trailing zeros.
+    pub fn significant(&self) -> usize {
+        self.digits.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Digits;
+    use number::RoundingMode::{self, *};
+
+    fn round(value: f64, fraction_digits: i32, mode: RoundingMode) -> (String, String) {
+        let mut digits = Digits::from_f64(value);
+        digits.round_to_fraction(fraction_digits, mode);
+        (digits.integer(), digits.fraction())
+    }
+
+    #[test]
+    fn digits_are_shortest() {
+        let d = Digits::from_f64(0.1 + 0.2);
+        assert_eq!((d.integer(), d.fraction()), ("0".to_string(), "30000000000000004".to_string()));
+        let d = Digits::from_f64(1200.0);
+        assert_eq!((d.digits, d.exponent), (vec![1, 2], 4));
+        assert!(Digits::from_f64(0.0).is_zero());
+    }
+
+    #[test]
+    fn rounding_modes() {
+        let r = |v, mode| round(v, 0, mode).0;
+        let modes = [Ceiling, Floor, Down, Up, HalfEven, HalfDown, HalfUp];
+        let expected = [
+            (2.5, ["3", "2", "2", "3", "2", "2", "3"]),
+            (-2.5, ["2", "3", "2", "3", "2", "2", "3"]),
+            (3.5, ["4", "3", "3", "4", "4", "3", "4"]),
+            (2.51, ["3", "2", "2", "3", "3", "3", "3"]),
+        ];
+        for (value, results) in &expected {
+            for (mode, result) in modes.iter().zip(results) {
+                assert_eq!(&r(*value, *mode), result, "{} {:?}", value, mode);
+            }
+        }
+        assert_eq!(round(9.995, 2, HalfUp), ("10".to_string(), String::new()));
+        assert_eq!(round(0.004, 2, Up), ("0".to_string(), "01".to_string()));
+        assert_eq!(round(0.004, 2, HalfEven), ("0".to_string(), String::new()));
+        assert_eq!(round(1234.0, -2, HalfEven), ("1200".to_string(), String::new()));
+    }
+}
@@ -0,0 +1,887 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Locale-aware number formatting.
+
+use language_tags::LanguageTag;
+use std::convert::TryFrom;
+use std::fmt;
+
+mod currency;
+mod data;
+mod digits;
+mod numbering;
+mod skeleton;
+mod spellout;
+mod unit_data;
+
+pub use self::currency::Currency;
+pub use self::data::{CompactPattern, NumberSymbols};
+pub use self::numbering::NumberingSystem;
+pub use self::skeleton::NumberSkeleton;
+pub use self::spellout::spell_out;
+pub use self::unit_data::UnitPatterns;
+
+use self::digits::Digits;
+pub(crate) use self::numbering::write_digits_in;
+use plural::{PluralOperands, PluralRuleSet, PluralRules};
+use provider::DataProvider;
+use PluralCategory;
+
+/// How a number is formatted by a `number` format.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NumberStyle {
+    /// A decimal number with up to 3 fraction digits. This is the
+    /// default.
+    Decimal,
+    /// A number rounded to an integer.
+    Integer,
+    /// A ratio shown as a percentage, so `0.25` is `25%`.
+    Percent,
+    /// A ratio shown per mille, so `0.025` is `25‰`.
+    Permille,
+    /// A [`NumberSkeleton`], such as `::percent .00`.
+    ///
+    /// [`NumberSkeleton`]: struct.NumberSkeleton.html
+    Skeleton(NumberSkeleton),
+    /// The value as Rust writes it, with ASCII digits and no grouping,
+    /// for identifiers such as order numbers which mustn't be
+    /// localized.
+    Raw,
+    /// A style defined by the app on the [`Context`], such as `price`.
+    /// See [`Context::define_number_style`].
+    ///
+    /// [`Context`]: ../struct.Context.html
+    /// [`Context::define_number_style`]: ../struct.Context.html#method.define_number_style
+    Named(String),
+}
+
+impl NumberStyle {
+    /// Parse a style as it appears in a message, such as `percent`
+    /// or `::.00`. Other names, except ICU's `currency` which this
+    /// crate doesn't support, are parsed as a `Named` style.
+    pub fn parse(style: &str) -> Option<Self> {
+        match style {
+            "integer" => Some(NumberStyle::Integer),
+            "percent" => Some(NumberStyle::Percent),
+            "permille" => Some(NumberStyle::Permille),
+            "raw" => Some(NumberStyle::Raw),
+            "currency" => None,
+            _ if style.starts_with("::") => NumberSkeleton::parse(&style[2..]).map(NumberStyle::Skeleton),
+            _ if is_style_name(style) => Some(NumberStyle::Named(style.to_string())),
+            _ => None,
+        }
+    }
+
+    // The options this style uses unless others are given.
+    fn default_options(&self, fraction_digits: Option<usize>) -> NumberOptions {
+        let (min, max) = match *self {
+            NumberStyle::Decimal | NumberStyle::Named(_) => {
+                fraction_digits.map_or((0, MAX_DECIMAL_FRACTION_DIGITS), |d| (d, d))
+            }
+            // Compact notation rounds according to the magnitude instead.
+            NumberStyle::Skeleton(ref skeleton) if skeleton.is_compact() => {
+                return NumberOptions::default();
+            }
+            // Amounts of money have the fraction digits of their currency.
+            NumberStyle::Skeleton(ref skeleton) => match skeleton.currency() {
+                Some(currency) => (currency.fraction_digits(), currency.fraction_digits()),
+                None => return skeleton.unit_style().default_options(fraction_digits),
+            },
+            _ => (0, 0),
+        };
+        NumberOptions {
+            minimum_fraction_digits: Some(min),
+            maximum_fraction_digits: Some(max),
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Display for NumberStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NumberStyle::Decimal => f.write_str("decimal"),
+            NumberStyle::Integer => f.write_str("integer"),
+            NumberStyle::Percent => f.write_str("percent"),
+            NumberStyle::Permille => f.write_str("permille"),
+            NumberStyle::Skeleton(ref skeleton) => write!(f, "::{}", skeleton),
+            NumberStyle::Raw => f.write_str("raw"),
+            NumberStyle::Named(ref name) => f.write_str(name),
+        }
+    }
+}
+
+// Whether `name` can name a style: letters, digits, `-` and `_`,
+// starting with a letter.
+fn is_style_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// How a number is rounded to the digits shown. The names follow
+/// ICU's rounding modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Towards positive infinity.
+    Ceiling,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards zero.
+    Down,
+    /// Away from zero.
+    Up,
+    /// To the nearest value, with ties to the even neighbor. This is
+    /// the default.
+    #[default]
+    HalfEven,
+    /// To the nearest value, with ties towards zero.
+    HalfDown,
+    /// To the nearest value, with ties away from zero.
+    HalfUp,
+}
+
+impl RoundingMode {
+    /// Parse a rounding mode as it appears in a skeleton after
+    /// `rounding-mode-`, such as `half-up`.
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "ceiling" => Some(RoundingMode::Ceiling),
+            "floor" => Some(RoundingMode::Floor),
+            "down" => Some(RoundingMode::Down),
+            "up" => Some(RoundingMode::Up),
+            "half-even" => Some(RoundingMode::HalfEven),
+            "half-down" => Some(RoundingMode::HalfDown),
+            "half-up" => Some(RoundingMode::HalfUp),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            RoundingMode::Ceiling => "ceiling",
+            RoundingMode::Floor => "floor",
+            RoundingMode::Down => "down",
+            RoundingMode::Up => "up",
+            RoundingMode::HalfEven => "half-even",
+            RoundingMode::HalfDown => "half-down",
+            RoundingMode::HalfUp => "half-up",
+        })
+    }
+}
+
+/// Options controlling the digits shown when formatting a number.
+///
+/// Options that are `None` are left to the style. When significant
+/// digits are given, they are used instead of fraction digits.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NumberOptions {
+    /// The fewest fraction digits to show, padding with zeros.
+    pub minimum_fraction_digits: Option<usize>,
+    /// The most fraction digits to show, rounding the rest.
+    pub maximum_fraction_digits: Option<usize>,
+    /// The fewest significant digits to show, padding with zeros.
+    pub minimum_significant_digits: Option<usize>,
+    /// The most significant digits to show, rounding the rest.
+    pub maximum_significant_digits: Option<usize>,
+    /// How to round. When this is `None`, ties round to even.
+    pub rounding_mode: Option<RoundingMode>,
+    /// The currency the number is an amount of, written with the
+    /// currency pattern of the locale.
+    pub currency: Option<Currency>,
+}
+
+impl NumberOptions {
+    /// These options, with any given in `other` taking precedence.
+    pub fn or(&self, other: &NumberOptions) -> NumberOptions {
+        NumberOptions {
+            minimum_fraction_digits: other.minimum_fraction_digits.or(self.minimum_fraction_digits),
+            maximum_fraction_digits: other.maximum_fraction_digits.or(self.maximum_fraction_digits),
+            minimum_significant_digits: other.minimum_significant_digits.or(self.minimum_significant_digits),
+            maximum_significant_digits: other.maximum_significant_digits.or(self.maximum_significant_digits),
+            rounding_mode: other.rounding_mode.or(self.rounding_mode),
+            currency: other.currency.or(self.currency),
+        }
+    }
+
+    /// Options writing a number as an amount of `currency`, with the
+    /// usual fraction digits of the currency.
+    ///
+    /// ```
+    /// use message_format::number::{format_number, Currency, NumberOptions, NumberStyle};
+    ///
+    /// let mut s = String::new();
+    /// let options = NumberOptions::currency(Currency::new("USD").unwrap());
+    /// format_number(&"en".parse().unwrap(), 1234.5, None, NumberStyle::Decimal, &options, &mut s).unwrap();
+    /// assert_eq!(s, "$1,234.50");
+    /// ```
+    pub fn currency(currency: Currency) -> NumberOptions {
+        NumberOptions {
+            minimum_fraction_digits: Some(currency.fraction_digits()),
+            maximum_fraction_digits: Some(currency.fraction_digits()),
+            currency: Some(currency),
+            ..Default::default()
+        }
+    }
+}
+
+// The most fraction digits shown by the decimal style.
+const MAX_DECIMAL_FRACTION_DIGITS: usize = 3;
+
+// The most significant digits shown when only a minimum is given.
+const MAX_SIGNIFICANT_DIGITS: usize = 21;
+
+/// Format `value` for the locale `language_tag`.
+///
+/// When `fraction_digits` is given, as it is for a `Decimal`, the
+/// decimal style shows exactly that many fraction digits. Otherwise
+/// trailing zeros are dropped. The `options`, such as those from the
+/// [`Context`], take precedence over the style's defaults, and a
+/// skeleton's options take precedence over both. Values are rounded
+/// half to even unless another rounding mode is given, as in ICU.
+///
+/// ```
+/// use message_format::number::{format_number, NumberOptions, NumberStyle, RoundingMode};
+///
+/// let en = "en".parse().unwrap();
+/// let mut s = String::new();
+/// format_number(&en, 1234.5678, None, NumberStyle::Decimal, &NumberOptions::default(), &mut s).unwrap();
+/// assert_eq!(s, "1,234.568");
+///
+/// let options = NumberOptions {
+///     minimum_fraction_digits: Some(2),
+///     maximum_fraction_digits: Some(2),
+///     rounding_mode: Some(RoundingMode::Floor),
+///     ..Default::default()
+/// };
+/// s.clear();
+/// format_number(&en, 1234.5678, None, NumberStyle::Decimal, &options, &mut s).unwrap();
+/// assert_eq!(s, "1,234.56");
+/// ```
+///
+/// [`Context`]: ../struct.Context.html
+pub fn format_number(
+    language_tag: &LanguageTag,
+    value: f64,
+    fraction_digits: Option<usize>,
+    style: NumberStyle,
+    options: &NumberOptions,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let rules = PluralRules::cardinal(language_tag);
+    let locale = NumberLocale {
+        language_tag,
+        symbols: NumberSymbols::for_language_tag(language_tag),
+        plural_rules: &rules,
+        provider: None,
+        numbering_system: NumberingSystem::for_language_tag(language_tag),
+    };
+    write_number(&locale, value.into(), fraction_digits, style, options, stream).map(|_| ())
+}
+
+// A number to format. Integers are written with all their digits,
+// even past the 2^53 up to which an `f64` holds them exactly. They are
+// kept by sign and magnitude, so that any `i128` or `u128` fits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Number {
+    Float(f64),
+    Integer { negative: bool, magnitude: u128 },
+}
+
+impl Number {
+    pub fn integer(value: i128) -> Number {
+        Number::Integer {
+            negative: value < 0,
+            magnitude: value.unsigned_abs(),
+        }
+    }
+
+    // The integer, if it fits in an `i128`.
+    pub fn as_i128(self) -> Option<i128> {
+        match self {
+            Number::Integer { negative: false, magnitude } => i128::try_from(magnitude).ok(),
+            Number::Integer { negative: true, magnitude } => 0i128.checked_sub_unsigned(magnitude),
+            Number::Float(_) => None,
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::Float(value) => value,
+            Number::Integer { negative, magnitude } => {
+                let value = magnitude as f64;
+                if negative {
+                    -value
+                } else {
+                    value
+                }
+            }
+        }
+    }
+
+    fn digits(self) -> Digits {
+        match self {
+            Number::Float(value) => Digits::from_f64(value),
+            Number::Integer { negative, magnitude } => Digits::from_integer(negative, magnitude),
+        }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Number {
+        Number::Float(value)
+    }
+}
+
+// The locale data used to format numbers, which may come from a
+// `DataProvider`.
+pub(crate) struct NumberLocale<'a> {
+    pub language_tag: &'a LanguageTag,
+    pub symbols: &'a NumberSymbols,
+    pub plural_rules: &'a dyn PluralRuleSet,
+    pub provider: Option<&'a dyn DataProvider>,
+    pub numbering_system: NumberingSystem,
+}
+
+/// Like [`format_number`], returning the plural operands of the number
+/// as it was written, after rounding and compacting.
+///
+/// [`format_number`]: fn.format_number.html
+pub(crate) fn write_number(
+    locale: &NumberLocale,
+    value: Number,
+    fraction_digits: Option<usize>,
+    style: NumberStyle,
+    options: &NumberOptions,
+    stream: &mut dyn fmt::Write,
+) -> Result<PluralOperands, fmt::Error> {
+    write_number_with(locale, value, fraction_digits, style, options, None, stream)
+}
+
+/// Like [`write_number`], with `overrides`, such as those given with an
+/// argument, taking precedence over a skeleton's options too.
+///
+/// [`write_number`]: fn.write_number.html
+pub(crate) fn write_number_with(
+    locale: &NumberLocale,
+    value: Number,
+    fraction_digits: Option<usize>,
+    style: NumberStyle,
+    options: &NumberOptions,
+    overrides: Option<&NumberOptions>,
+    stream: &mut dyn fmt::Write,
+) -> Result<PluralOperands, fmt::Error> {
+    if style == NumberStyle::Raw {
+        match (value, fraction_digits) {
+            (Number::Integer { negative, magnitude }, None) => {
+                write!(stream, "{}{}", if negative { "-" } else { "" }, magnitude)?
+            }
+            (value, Some(digits)) => write!(stream, "{:.*}", digits, value.as_f64())?,
+            (value, None) => write!(stream, "{}", value.as_f64())?,
+        }
+        return Ok(PluralOperands::from(value.as_f64().abs()));
+    }
+    write_digits_in(locale.numbering_system, stream, |stream| {
+        write_latin_number(locale, value, fraction_digits, style, options, overrides, stream)
+    })
+}
+
+// Like `write_number`, writing the digits as ASCII.
+fn write_latin_number(
+    locale: &NumberLocale,
+    number: Number,
+    fraction_digits: Option<usize>,
+    style: NumberStyle,
+    options: &NumberOptions,
+    overrides: Option<&NumberOptions>,
+    stream: &mut dyn fmt::Write,
+) -> Result<PluralOperands, fmt::Error> {
+    let symbols = locale.symbols;
+    let mut options = style.default_options(fraction_digits).or(options);
+    let mut compact_patterns = None;
+    let unit_style = match style {
+        NumberStyle::Skeleton(ref skeleton) => {
+            options = options.or(skeleton.options());
+            compact_patterns = skeleton.compact_patterns(symbols);
+            skeleton.unit_style()
+        }
+        style => style,
+    };
+    if let Some(overrides) = overrides {
+        options = options.or(overrides);
+    }
+    let currency_pattern;
+    let (scale, pattern) = match (unit_style, options.currency) {
+        (NumberStyle::Percent, _) => (2, &symbols.percent_pattern[..]),
+        (NumberStyle::Permille, _) => (3, &symbols.permille_pattern[..]),
+        (_, Some(currency)) => {
+            currency_pattern = symbols.currency_pattern.replace('¤', currency.symbol());
+            (0, &currency_pattern[..])
+        }
+        _ => (0, "#"),
+    };
+    let (prefix, suffix) = pattern.split_at(pattern.find('#').unwrap_or(0));
+    let suffix = &suffix[1..];
+    let value = number.as_f64();
+    if !value.is_finite() {
+        if value == f64::NEG_INFINITY {
+            stream.write_str(&symbols.minus)?;
+        }
+        stream.write_str(prefix)?;
+        stream.write_str(if value.is_nan() { "NaN" } else { "∞" })?;
+        stream.write_str(suffix)?;
+        return Ok(PluralOperands::from(value));
+    }
+
+    let mut digits = number.digits();
+    if !digits.is_zero() {
+        digits.exponent += scale;
+    }
+    let (digits, fraction, compact) = match compact_patterns {
+        Some(patterns) => round_compact(digits, patterns, &options),
+        None => {
+            let fraction = round(&mut digits, &options);
+            (digits, fraction, None)
+        }
+    };
+    let mut text = digits.integer();
+    if !fraction.is_empty() {
+        text = format!("{}.{}", text, fraction);
+    }
+    // Only numbers too long for the operands fail to parse.
+    let operands = text.parse().unwrap_or_else(|_| PluralOperands::from(value.abs()));
+    let (compact_prefix, compact_suffix) = match compact {
+        Some(compact) => {
+            let one = locale.plural_rules.select(&operands) == PluralCategory::One;
+            let pattern = if one { compact.one } else { compact.other };
+            let zero = pattern.find('0').unwrap_or(0);
+            (&pattern[..zero], &pattern[zero + 1..])
+        }
+        None => ("", ""),
+    };
+
+    // Rounding may have made a small negative number zero.
+    let negative = digits.negative && !digits.is_zero();
+    if let (Some(provider), None) = (locale.provider, compact) {
+        let signed = if negative { format!("-{}", text) } else { text };
+        let mut localized = String::new();
+        if let Some(result) = provider.write_digits(locale.language_tag, &signed, &mut localized) {
+            result?;
+            stream.write_str(prefix)?;
+            stream.write_str(&localized)?;
+            stream.write_str(suffix)?;
+            return Ok(operands);
+        }
+    }
+    if negative {
+        stream.write_str(&symbols.minus)?;
+    }
+    stream.write_str(prefix)?;
+    stream.write_str(compact_prefix)?;
+    let integer = digits.integer();
+    // Like ICU, compact notation only groups when the leading group
+    // would have at least two digits.
+    if compact_patterns.is_some() && integer.len() < 5 {
+        stream.write_str(&integer)?;
+    } else {
+        write_grouped(&integer, &symbols.group, stream)?;
+    }
+    if !fraction.is_empty() {
+        stream.write_str(&symbols.decimal)?;
+        stream.write_str(&fraction)?;
+    }
+    stream.write_str(compact_suffix)?;
+    stream.write_str(suffix)?;
+    Ok(operands)
+}
+
+/// How long the name of a measure unit is in a `unit` format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitWidth {
+    /// The full name, such as `5 kilometers`.
+    Long,
+    /// An abbreviation, such as `5 km`. This is the default.
+    Short,
+    /// The shortest abbreviation, such as `5km`.
+    Narrow,
+}
+
+impl UnitWidth {
+    /// Parse a width as it appears in a message, such as `long`.
+    pub fn parse(width: &str) -> Option<Self> {
+        match width {
+            "long" => Some(UnitWidth::Long),
+            "short" => Some(UnitWidth::Short),
+            "narrow" => Some(UnitWidth::Narrow),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for UnitWidth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UnitWidth::Long => f.write_str("long"),
+            UnitWidth::Short => f.write_str("short"),
+            UnitWidth::Narrow => f.write_str("narrow"),
+        }
+    }
+}
+
+/// Format `value` as an amount of `unit` for the locale `language_tag`.
+///
+/// The number is formatted as by [`format_number`] with the decimal
+/// style, and the pattern is chosen by the plural category of the
+/// number as written. Languages without unit data fall back to
+/// English. Fails if the unit is not supported.
+///
+/// ```
+/// use message_format::number::{format_unit, NumberOptions, UnitWidth};
+///
+/// let en = "en".parse().unwrap();
+/// let options = NumberOptions::default();
+/// let mut s = String::new();
+/// format_unit(&en, 1.0, None, "kilometer", UnitWidth::Long, &options, &mut s).unwrap();
+/// assert_eq!(s, "1 kilometer");
+/// ```
+///
+/// [`format_number`]: fn.format_number.html
+pub fn format_unit(
+    language_tag: &LanguageTag,
+    value: f64,
+    fraction_digits: Option<usize>,
+    unit: &str,
+    width: UnitWidth,
+    options: &NumberOptions,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let rules = PluralRules::cardinal(language_tag);
+    let locale = NumberLocale {
+        language_tag,
+        symbols: NumberSymbols::for_language_tag(language_tag),
+        plural_rules: &rules,
+        provider: None,
+        numbering_system: NumberingSystem::for_language_tag(language_tag),
+    };
+    write_unit(&locale, value.into(), fraction_digits, unit, width, options, stream)
+}
+
+// Like `format_unit`, with the locale data given.
+pub(crate) fn write_unit(
+    locale: &NumberLocale,
+    value: Number,
+    fraction_digits: Option<usize>,
+    unit: &str,
+    width: UnitWidth,
+    options: &NumberOptions,
+    stream: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let english = LanguageTag {
+        language: Some("en".to_string()),
+        ..Default::default()
+    };
+    let english_rules = PluralRules::cardinal(&english);
+    // The plural rules must match the language of the patterns.
+    let (rules, patterns): (&dyn PluralRuleSet, _) = match UnitPatterns::find(locale.language_tag, unit) {
+        Some(patterns) => (locale.plural_rules, patterns),
+        None => (&english_rules, UnitPatterns::find(&english, unit).ok_or(fmt::Error {})?),
+    };
+    let mut number = String::new();
+    let operands = write_number(locale, value, fraction_digits, NumberStyle::Decimal, options, &mut number)?;
+    let (one, other) = match width {
+        UnitWidth::Long => patterns.long,
+        UnitWidth::Short => patterns.short,
+        UnitWidth::Narrow => patterns.narrow,
+    };
+    let pattern = if rules.select(&operands) == PluralCategory::One { one } else { other };
+    stream.write_str(&pattern.replace("{0}", &number))
+}
+
+// Round `digits` as the options say, returning the fraction digits.
+fn round(digits: &mut Digits, options: &NumberOptions) -> String {
+    let mode = options.rounding_mode.unwrap_or_default();
+    if options.minimum_significant_digits.is_some() || options.maximum_significant_digits.is_some() {
+        let min = options.minimum_significant_digits.unwrap_or(1).max(1);
+        let max = options.maximum_significant_digits.unwrap_or(MAX_SIGNIFICANT_DIGITS).max(min);
+        digits.round_to_significant(max, mode);
+        let mut fraction = digits.fraction();
+        let shown = if digits.exponent > 0 {
+            digits.exponent as usize + fraction.len()
+        } else {
+            digits.significant()
+        };
+        let shown = shown.max(if digits.is_zero() { 1 } else { 0 });
+        fraction.extend((shown..min).map(|_| '0'));
+        fraction
+    } else {
+        let min = options.minimum_fraction_digits.unwrap_or(0);
+        let max = options.maximum_fraction_digits.unwrap_or(MAX_DECIMAL_FRACTION_DIGITS).max(min);
+        digits.round_to_fraction(max as i32, mode);
+        let mut fraction = digits.fraction();
+        fraction.extend((fraction.len()..min).map(|_| '0'));
+        fraction
+    }
+}
+
+// Divide `digits` by the power of ten of the pattern for its magnitude
+// and round it. Without precision options, values of 10 or more are
+// rounded to integers and smaller values to 2 significant digits, as
+// in ICU. Rounding up may reach the next pattern, as 999,999 is `1M`.
+fn round_compact(
+    digits: Digits,
+    patterns: &'static [CompactPattern],
+    options: &NumberOptions,
+) -> (Digits, String, Option<&'static CompactPattern>) {
+    let has_precision = options.minimum_fraction_digits.is_some()
+        || options.maximum_fraction_digits.is_some()
+        || options.minimum_significant_digits.is_some()
+        || options.maximum_significant_digits.is_some();
+    let mut magnitude = digits.exponent - 1;
+    loop {
+        let pattern = patterns.iter().rev().find(|p| p.exponent <= magnitude);
+        let mut divided = digits.clone();
+        if let Some(pattern) = pattern {
+            divided.exponent -= pattern.exponent;
+        }
+        let fraction = if has_precision {
+            round(&mut divided, options)
+        } else {
+            let mode = options.rounding_mode.unwrap_or_default();
+            if divided.exponent >= 2 {
+                divided.round_to_fraction(0, mode);
+            } else {
+                divided.round_to_significant(2, mode);
+            }
+            divided.fraction()
+        };
+        let rounded = divided.exponent - 1 + pattern.map_or(0, |p| p.exponent);
+        if divided.is_zero() || rounded <= magnitude {
+            return (divided, fraction, pattern);
+        }
+        magnitude = rounded;
+    }
+}
+
+// Write integer digits with a separator between each group of three.
+fn write_grouped(digits: &str, group: &str, stream: &mut dyn fmt::Write) -> fmt::Result {
+    for (i, digit) in digits.char_indices() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            stream.write_str(group)?;
+        }
+        stream.write_char(digit)?;
+    }
+    Ok(())
+}
+
+// The bundled tables, written out so that `data_version` can check
+// that a change to them comes with a new data revision.
+#[cfg(all(test, feature = "locale-data"))]
+pub(crate) fn bundled_tables() -> String {
+    format!(
+        "{:?}{:?}{:?}{:?}",
+        data::ALL,
+        unit_data::ALL,
+        currency::CURRENCIES,
+        numbering::SYSTEMS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_number, format_unit, NumberOptions, NumberStyle, RoundingMode, UnitWidth};
+
+    fn format(tag: &str, value: f64, digits: Option<usize>, style: NumberStyle) -> String {
+        format_with(tag, value, digits, style, &NumberOptions::default())
+    }
+
+    fn format_with(tag: &str, value: f64, digits: Option<usize>, style: NumberStyle, options: &NumberOptions) -> String {
+        let mut s = String::new();
+        format_number(&tag.parse().unwrap(), value, digits, style, options, &mut s).unwrap();
+        s
+    }
+
+    #[test]
+    fn currency_skeletons() {
+        let style = |s: &str| NumberStyle::parse(s).unwrap();
+        assert_eq!(format("en", 1234.5, None, style("::currency/EUR")), "€1,234.50");
+        assert_eq!(format("en", 1234.4, None, style("::currency/jpy")), "¥1,234");
+        assert_eq!(format("en", 1234.56, None, style("::currency/EUR .0")), "€1,234.6");
+        assert_eq!(format("en", 5.0, None, style("::currency/CHF")), "CHF5.00");
+        #[cfg(feature = "locale-data")]
+        assert_eq!(format("de", 1234.5, None, style("::currency/EUR")), "1.234,50\u{a0}€");
+        assert_eq!(NumberStyle::parse("::currency/EURO"), None);
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(format("en", 1234567.0, None, NumberStyle::Decimal), "1,234,567");
+        assert_eq!(format("en", -0.5, None, NumberStyle::Decimal), "-0.5");
+        assert_eq!(format("en", -0.0001, None, NumberStyle::Decimal), "0");
+        assert_eq!(format("en", 2.0, Some(2), NumberStyle::Decimal), "2.00");
+        assert_eq!(format("en", 2.5, None, NumberStyle::Integer), "2");
+        assert_eq!(format("en", 999.9999, None, NumberStyle::Decimal), "1,000");
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(format("en", 0.25, None, NumberStyle::Percent), "25%");
+        assert_eq!(format("en", -0.025, None, NumberStyle::Permille), "-25‰");
+        assert_eq!(format("en", 12.346, None, NumberStyle::Percent), "1,235%");
+        // Ties round to even, as in ICU.
+        assert_eq!(format("en", 0.125, None, NumberStyle::Percent), "12%");
+    }
+
+    #[test]
+    fn options_control_digits() {
+        let fraction = |min, max| NumberOptions {
+            minimum_fraction_digits: min,
+            maximum_fraction_digits: max,
+            ..Default::default()
+        };
+        let decimal = NumberStyle::Decimal;
+        assert_eq!(format_with("en", 2.5, None, decimal.clone(), &fraction(Some(2), None)), "2.50");
+        assert_eq!(format_with("en", 2.555, None, decimal.clone(), &fraction(None, Some(1))), "2.6");
+        assert_eq!(format_with("en", 2.0, Some(2), decimal.clone(), &fraction(None, Some(5))), "2.00");
+        assert_eq!(format_with("en", 1.23456, None, decimal.clone(), &fraction(Some(5), Some(2))), "1.23456");
+        assert_eq!(format_with("en", 0.5, None, NumberStyle::Percent, &fraction(Some(1), None)), "50.0%");
+
+        let significant = |min, max| NumberOptions {
+            minimum_significant_digits: min,
+            maximum_significant_digits: max,
+            ..Default::default()
+        };
+        assert_eq!(format_with("en", 123456.0, None, decimal.clone(), &significant(None, Some(2))), "120,000");
+        assert_eq!(format_with("en", 0.0012345, None, decimal.clone(), &significant(None, Some(3))), "0.00123");
+        assert_eq!(format_with("en", 1.5, None, decimal.clone(), &significant(Some(4), None)), "1.500");
+        assert_eq!(format_with("en", 0.0, None, decimal.clone(), &significant(Some(3), None)), "0.00");
+
+        let options = NumberOptions {
+            maximum_fraction_digits: Some(2),
+            rounding_mode: Some(RoundingMode::HalfUp),
+            ..Default::default()
+        };
+        assert_eq!(format_with("en", 1.005, None, decimal.clone(), &options), "1.01");
+        assert_eq!(format_with("en", -1.005, None, decimal.clone(), &options), "-1.01");
+        assert_eq!(format("en", 1.005, None, NumberStyle::Percent), "100%");
+        assert_eq!(format("en", 0.145, None, NumberStyle::Percent), "14%");
+    }
+
+    #[test]
+    fn skeletons() {
+        let skeleton = |s| NumberStyle::parse(s).unwrap();
+        assert_eq!(format("en", 0.14567, None, skeleton("::percent .0#")), "14.57%");
+        assert_eq!(format("en", 2.0, None, skeleton("::.00")), "2.00");
+        assert_eq!(format("en", 2.999, None, skeleton("::.00 rounding-mode-floor")), "2.99");
+        assert_eq!(format("en", 1234.5, None, skeleton("::@@")), "1,200");
+        assert_eq!(format("en", 2.5, Some(1), skeleton("::precision-integer")), "2");
+        // A skeleton's options override the context's.
+        let options = NumberOptions {
+            maximum_fraction_digits: Some(0),
+            rounding_mode: Some(RoundingMode::Up),
+            ..Default::default()
+        };
+        assert_eq!(format_with("en", 1.234, None, skeleton("::.0"), &options), "1.3");
+    }
+
+    #[test]
+    fn compact() {
+        let short = NumberStyle::parse("::compact-short").unwrap();
+        let long = NumberStyle::parse("::compact-long").unwrap();
+        let cases = [
+            (0.0, "0"),
+            (12.345, "12"),
+            (1.234, "1.2"),
+            (999.0, "999"),
+            (1000.0, "1K"),
+            (1234.0, "1.2K"),
+            (12345.0, "12K"),
+            (123456.0, "123K"),
+            (999999.0, "1M"),
+            (-1500000.0, "-1.5M"),
+            (3.4e9, "3.4B"),
+            (5.0e15, "5000T"),
+            (5.0e16, "50,000T"),
+        ];
+        for &(value, expected) in &cases {
+            assert_eq!(format("en", value, None, short.clone()), expected);
+        }
+        assert_eq!(format("en", 1234.0, None, long.clone()), "1.2 thousand");
+        assert_eq!(format("en", 1234.0, None, NumberStyle::parse("::compact-short .00").unwrap()), "1.23K");
+    }
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn compact_is_localized() {
+        let short = NumberStyle::parse("::compact-short").unwrap();
+        let long = NumberStyle::parse("::compact-long").unwrap();
+        assert_eq!(format("de", 1234567.0, None, short.clone()), "1,2\u{a0}Mio.");
+        assert_eq!(format("de", 1234.0, None, short.clone()), "1234");
+        assert_eq!(format("de", 1000000.0, None, long.clone()), "1 Million");
+        assert_eq!(format("de", 2000000.0, None, long.clone()), "2 Millionen");
+        assert_eq!(format("fr", 1500.0, None, long.clone()), "1,5 millier");
+        assert_eq!(format("fr", 2500.0, None, short.clone()), "2,5\u{a0}k");
+    }
+
+    #[test]
+    fn units() {
+        let unit = |tag: &str, value, digits, unit, width| {
+            let mut s = String::new();
+            let options = NumberOptions::default();
+            format_unit(&tag.parse().unwrap(), value, digits, unit, width, &options, &mut s).map(|_| s)
+        };
+        assert_eq!(unit("en", 5.0, None, "kilometer", UnitWidth::Long).unwrap(), "5 kilometers");
+        assert_eq!(unit("en", 1.0, None, "kilometer", UnitWidth::Long).unwrap(), "1 kilometer");
+        // "1.0" is plural in English.
+        assert_eq!(unit("en", 1.0, Some(1), "mile", UnitWidth::Long).unwrap(), "1.0 miles");
+        assert_eq!(unit("en", 1234.5, None, "meter", UnitWidth::Short).unwrap(), "1,234.5 m");
+        assert_eq!(unit("en", 30.0, None, "second", UnitWidth::Narrow).unwrap(), "30s");
+        // Unsupported languages fall back to English.
+        assert_eq!(unit("xx", 2.0, None, "hour", UnitWidth::Long).unwrap(), "2 hours");
+        assert!(unit("en", 2.0, None, "furlong", UnitWidth::Long).is_err());
+    }
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn units_are_localized() {
+        let unit = |tag: &str, value, unit, width| {
+            let mut s = String::new();
+            let options = NumberOptions::default();
+            format_unit(&tag.parse().unwrap(), value, None, unit, width, &options, &mut s).unwrap();
+            s
+        };
+        assert_eq!(unit("de", 1.0, "hour", UnitWidth::Long), "1 Stunde");
+        assert_eq!(unit("de", 2.5, "hour", UnitWidth::Long), "2,5 Stunden");
+        assert_eq!(unit("fr", 1.5, "kilometer", UnitWidth::Long), "1,5 kilomètre");
+        assert_eq!(unit("fr", 12.0, "gigabyte", UnitWidth::Short), "12\u{a0}Go");
+    }
+
+    #[test]
+    fn infinity() {
+        assert_eq!(format("en", f64::NEG_INFINITY, None, NumberStyle::Decimal), "-∞");
+        assert_eq!(format("en", f64::NAN, None, NumberStyle::Percent), "NaN%");
+    }
+
+    #[cfg(feature = "locale-data")]
+    #[test]
+    fn symbols_are_localized() {
+        assert_eq!(format("fr", 0.25, None, NumberStyle::Percent), "25\u{202f}%");
+        assert_eq!(format("de", 1234.5, None, NumberStyle::Decimal), "1.234,5");
+        assert_eq!(format("sv", -1234.0, None, NumberStyle::Decimal), "\u{2212}1\u{a0}234");
+    }
+
+    #[test]
+    fn numbering_system_keyword() {
+        assert_eq!(format("en-u-nu-arab", 1234.5, None, NumberStyle::Decimal), "١,٢٣٤.٥");
+        #[cfg(feature = "locale-data")]
+        assert_eq!(format("de-u-nu-arab", 1234.5, None, NumberStyle::Decimal), "١.٢٣٤,٥");
+        assert_eq!(format("th-u-nu-thai", 0.25, None, NumberStyle::Percent), "๒๕%");
+        let compact = NumberStyle::parse("::compact-short").unwrap();
+        assert_eq!(format("en-u-nu-deva", 12000.0, None, compact), "१२K");
+        assert_eq!(format("en-u-nu-bogus", 12.0, None, NumberStyle::Decimal), "12");
+    }
+}
@@ -0,0 +1,126 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::fmt;
+
+/// A decimal numbering system, which gives the digits numbers are
+/// written with, as named by the `nu` keyword of a locale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberingSystem {
+    /// The identifier of the system, such as `arab`.
+    pub name: &'static str,
+    /// The digits from zero to nine.
+    pub digits: [char; 10],
+}
+
+const fn system(name: &'static str, zero: u32) -> NumberingSystem {
+    let mut digits = ['0'; 10];
+    let mut i = 0;
+    while i < 10 {
+        digits[i] = match char::from_u32(zero + i as u32) {
+            Some(c) => c,
+            None => panic!("not a digit"),
+        };
+        i += 1;
+    }
+    NumberingSystem { name, digits }
+}
+
+pub(super) static SYSTEMS: &[NumberingSystem] = &[
+    system("latn", 0x30),
+    system("arab", 0x660),
+    system("arabext", 0x6f0),
+    system("beng", 0x9e6),
+    system("deva", 0x966),
+    system("fullwide", 0xff10),
+    system("gujr", 0xae6),
+    system("guru", 0xa66),
+    system("khmr", 0x17e0),
+    system("knda", 0xce6),
+    system("laoo", 0xed0),
+    system("mlym", 0xd66),
+    system("mymr", 0x1040),
+    system("orya", 0xb66),
+    system("tamldec", 0xbe6),
+    system("telu", 0xc66),
+    system("thai", 0xe50),
+    system("tibt", 0xf20),
+    NumberingSystem {
+        name: "hanidec",
+        digits: ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'],
+    },
+];
+
+impl NumberingSystem {
+    /// The Latin digits, `0` to `9`, which the data compiled into this
+    /// crate uses for every language.
+    pub const LATIN: NumberingSystem = system("latn", 0x30);
+
+    /// Look up a numbering system by its identifier, ignoring case.
+    pub fn find(name: &str) -> Option<NumberingSystem> {
+        SYSTEMS.iter().find(|system| system.name.eq_ignore_ascii_case(name)).cloned()
+    }
+
+    /// The numbering system requested by the `nu` keyword of the
+    /// Unicode locale extension of `language_tag`, as in
+    /// `ar-EG-u-nu-arab`, or the Latin digits.
+    ///
+    /// ```
+    /// use message_format::number::NumberingSystem;
+    ///
+    /// let system = NumberingSystem::for_language_tag(&"hi-IN-u-nu-deva".parse().unwrap());
+    /// assert_eq!(system.digits[4], '४');
+    /// assert_eq!(NumberingSystem::for_language_tag(&"hi-IN".parse().unwrap()), NumberingSystem::LATIN);
+    /// ```
+    pub fn for_language_tag(language_tag: &LanguageTag) -> NumberingSystem {
+        let keywords = language_tag.extensions.get(&b'u').map_or(&[][..], |k| &k[..]);
+        keywords
+            .iter()
+            .position(|k| k.eq_ignore_ascii_case("nu"))
+            .and_then(|i| keywords.get(i + 1))
+            .and_then(|name| NumberingSystem::find(name))
+            .unwrap_or(NumberingSystem::LATIN)
+    }
+
+    /// Whether these are the Latin digits.
+    pub fn is_latin(&self) -> bool {
+        self.name == "latn"
+    }
+}
+
+/// A stream which replaces the ASCII digits written to it with those
+/// of a numbering system.
+struct DigitWriter<'a> {
+    stream: &'a mut dyn fmt::Write,
+    system: NumberingSystem,
+}
+
+impl<'a> fmt::Write for DigitWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        while let Some(i) = rest.find(|c: char| c.is_ascii_digit()) {
+            self.stream.write_str(&rest[..i])?;
+            let digit = usize::from(rest.as_bytes()[i] - b'0');
+            self.stream.write_char(self.system.digits[digit])?;
+            rest = &rest[i + 1..];
+        }
+        self.stream.write_str(rest)
+    }
+}
+
+/// Write with `f` to `stream`, replacing the ASCII digits it writes
+/// with those of `system`.
+pub(crate) fn write_digits_in<R, F>(system: NumberingSystem, stream: &mut dyn fmt::Write, f: F) -> Result<R, fmt::Error>
+where
+    F: FnOnce(&mut dyn fmt::Write) -> Result<R, fmt::Error>,
+{
+    if system.is_latin() {
+        f(stream)
+    } else {
+        f(&mut DigitWriter { stream, system })
+    }
+}
@@ -0,0 +1,208 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use super::{CompactPattern, Currency, NumberOptions, NumberStyle, NumberSymbols, RoundingMode};
+
+/// What a skeleton formats the number as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Unit {
+    Number,
+    Percent,
+    Permille,
+    // An amount of the currency in the options.
+    Currency,
+}
+
+/// How the magnitude of a number is written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Notation {
+    Standard,
+    CompactShort,
+    CompactLong,
+}
+
+/// An ICU number skeleton, such as `percent .00 rounding-mode-floor`.
+///
+/// The supported tokens are `percent`, `permille`, currencies such as
+/// `currency/EUR`, `compact-short`, `compact-long`, `precision-integer`,
+/// fraction precision such as `.00##`, significant digits such as
+/// `@@#`, and the `rounding-mode-` tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberSkeleton {
+    unit: Unit,
+    notation: Notation,
+    options: NumberOptions,
+}
+
+impl NumberSkeleton {
+    /// Parse a skeleton. Returns `None` if it contains unsupported
+    /// or repeated tokens.
+    ///
+    /// ```
+    /// use message_format::number::NumberSkeleton;
+    ///
+    /// assert!(NumberSkeleton::parse("percent .0#").is_some());
+    /// assert!(NumberSkeleton::parse(".00 .0").is_none());
+    /// ```
+    pub fn parse(skeleton: &str) -> Option<Self> {
+        let mut unit = None;
+        let mut notation = None;
+        let mut options = NumberOptions::default();
+        for token in skeleton.split_whitespace() {
+            match token {
+                "percent" | "permille" if unit.is_none() => {
+                    unit = Some(if token == "percent" { Unit::Percent } else { Unit::Permille });
+                }
+                _ if token.starts_with("currency/") && unit.is_none() => {
+                    options.currency = Some(Currency::new(&token["currency/".len()..])?);
+                    unit = Some(Unit::Currency);
+                }
+                "compact-short" | "compact-long" if notation.is_none() => {
+                    notation = Some(if token == "compact-short" {
+                        Notation::CompactShort
+                    } else {
+                        Notation::CompactLong
+                    });
+                }
+                _ if token.starts_with("rounding-mode-") && options.rounding_mode.is_none() => {
+                    options.rounding_mode = Some(RoundingMode::parse(&token["rounding-mode-".len()..])?);
+                }
+                _ if options.maximum_fraction_digits.is_some() || options.maximum_significant_digits.is_some() => {
+                    return None;
+                }
+                "precision-integer" => {
+                    options.minimum_fraction_digits = Some(0);
+                    options.maximum_fraction_digits = Some(0);
+                }
+                _ if token.starts_with('.') => {
+                    let (min, max) = count_digits(&token[1..], '0')?;
+                    options.minimum_fraction_digits = Some(min);
+                    options.maximum_fraction_digits = Some(max);
+                }
+                _ if token.starts_with('@') => {
+                    let (min, max) = count_digits(token, '@')?;
+                    options.minimum_significant_digits = Some(min);
+                    options.maximum_significant_digits = Some(max);
+                }
+                _ => return None,
+            }
+        }
+        if unit.is_none() && notation.is_none() && options == NumberOptions::default() {
+            return None;
+        }
+        Some(NumberSkeleton {
+            unit: unit.unwrap_or(Unit::Number),
+            notation: notation.unwrap_or(Notation::Standard),
+            options,
+        })
+    }
+
+    /// The number options this skeleton sets.
+    pub fn options(&self) -> &NumberOptions {
+        &self.options
+    }
+
+    /// The style whose symbols and scale the skeleton uses.
+    pub(crate) fn unit_style(&self) -> NumberStyle {
+        match self.unit {
+            Unit::Number | Unit::Currency => NumberStyle::Decimal,
+            Unit::Percent => NumberStyle::Percent,
+            Unit::Permille => NumberStyle::Permille,
+        }
+    }
+
+    /// The currency the skeleton formats an amount of, if any.
+    pub(crate) fn currency(&self) -> Option<Currency> {
+        self.options.currency
+    }
+
+    pub(crate) fn is_compact(&self) -> bool {
+        self.notation != Notation::Standard
+    }
+
+    /// The compact patterns to use from `symbols`, if any.
+    pub(crate) fn compact_patterns(&self, symbols: &NumberSymbols) -> Option<&'static [CompactPattern]> {
+        match self.notation {
+            Notation::Standard => None,
+            Notation::CompactShort => Some(symbols.compact_short),
+            Notation::CompactLong => Some(symbols.compact_long),
+        }
+    }
+}
+
+// Count the required digits, written as `required`, followed by the
+// optional digits, written as `#`. There must be at least one digit.
+fn count_digits(token: &str, required: char) -> Option<(usize, usize)> {
+    let min = token.chars().take_while(|&c| c == required).count();
+    let optional = &token[min..];
+    if !optional.chars().all(|c| c == '#') || (required == '@' && min == 0) {
+        return None;
+    }
+    Some((min, min + optional.len()))
+}
+
+impl fmt::Display for NumberSkeleton {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tokens = vec![];
+        match self.unit {
+            Unit::Number => {}
+            Unit::Percent => tokens.push("percent".to_string()),
+            Unit::Permille => tokens.push("permille".to_string()),
+            Unit::Currency => {
+                let code = self.options.currency.as_ref().map_or("", |currency| currency.code());
+                tokens.push(format!("currency/{}", code));
+            }
+        }
+        match self.notation {
+            Notation::Standard => {}
+            Notation::CompactShort => tokens.push("compact-short".to_string()),
+            Notation::CompactLong => tokens.push("compact-long".to_string()),
+        }
+        let options = &self.options;
+        let digits = |required: &str, min: usize, max: usize| {
+            format!("{}{}", required.repeat(min), "#".repeat(max - min))
+        };
+        if let Some(max) = options.maximum_significant_digits {
+            let min = options.minimum_significant_digits.unwrap_or(1);
+            tokens.push(digits("@", min, max));
+        } else if let Some(0) = options.maximum_fraction_digits {
+            tokens.push("precision-integer".to_string());
+        } else if let Some(max) = options.maximum_fraction_digits {
+            let min = options.minimum_fraction_digits.unwrap_or(0);
+            tokens.push(format!(".{}", digits("0", min, max)));
+        }
+        if let Some(mode) = options.rounding_mode {
+            tokens.push(format!("rounding-mode-{}", mode));
+        }
+        f.write_str(&tokens.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NumberSkeleton;
+    use number::RoundingMode;
+
+    #[test]
+    fn skeletons_round_trip() {
+        for s in &["percent", ".00##", "@@#", "permille precision-integer", ".0 rounding-mode-half-up", "compact-short", "compact-long @@", "currency/EUR", "currency/JPY .0"] {
+            assert_eq!(NumberSkeleton::parse(s).unwrap().to_string(), *s);
+        }
+        let skeleton = NumberSkeleton::parse("rounding-mode-floor   .00").unwrap();
+        assert_eq!(skeleton.to_string(), ".00 rounding-mode-floor");
+        assert_eq!(skeleton.options().rounding_mode, Some(RoundingMode::Floor));
+        assert_eq!(skeleton.options().maximum_fraction_digits, Some(2));
+    }
+
+    #[test]
+    fn invalid_skeletons() {
+        for s in &["", "percent percent", ".0a", "@#@", "#", "rounding-mode-sideways", "@@ .00", "currency/EURO", "percent currency/EUR", "compact-short compact-long"] {
+            assert_eq!(NumberSkeleton::parse(s), None, "{}", s);
+        }
+    }
+}
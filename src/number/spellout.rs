@@ -0,0 +1,78 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [(u64, &str); 4] = [
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+// Spell out a number below one thousand.
+fn spell_hundreds(n: u64, words: &mut Vec<String>) {
+    if n >= 100 {
+        words.push(format!("{} hundred", ONES[(n / 100) as usize]));
+    }
+    match n % 100 {
+        0 => (),
+        n if n < 20 => words.push(ONES[n as usize].to_string()),
+        n if n % 10 == 0 => words.push(TENS[(n / 10) as usize].to_string()),
+        n => words.push(format!("{}-{}", TENS[(n / 10) as usize], ONES[(n % 10) as usize])),
+    }
+}
+
+/// Spell out `number` in words, as a screen reader would say it.
+///
+/// Only whole numbers below a quadrillion are spelled out, and only
+/// in English, which is also used for a tag without a language;
+/// `None` is returned for anything else.
+///
+/// ```
+/// use message_format::number::spell_out;
+///
+/// let en = "en-GB".parse().unwrap();
+/// assert_eq!(spell_out(&en, 42.0).unwrap(), "forty-two");
+/// assert_eq!(spell_out(&en, -1_000_215.0).unwrap(), "minus one million two hundred fifteen");
+/// assert_eq!(spell_out(&en, 2.5), None);
+/// assert_eq!(spell_out(&"fr".parse().unwrap(), 2.0), None);
+/// ```
+pub fn spell_out(language_tag: &LanguageTag, number: f64) -> Option<String> {
+    match language_tag.language {
+        Some(ref language) if !language.eq_ignore_ascii_case("en") => return None,
+        _ => (),
+    }
+    if number.fract() != 0.0 || number.abs() >= 1e15 {
+        return None;
+    }
+    let mut n = number.abs() as u64;
+    if n == 0 {
+        return Some(ONES[0].to_string());
+    }
+    let mut words = vec![];
+    if number < 0.0 {
+        words.push("minus".to_string());
+    }
+    for &(scale, name) in &SCALES {
+        if n >= scale {
+            spell_hundreds(n / scale, &mut words);
+            words.push(name.to_string());
+            n %= scale;
+        }
+    }
+    spell_hundreds(n, &mut words);
+    Some(words.join(" "))
+}
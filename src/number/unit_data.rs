@@ -0,0 +1,364 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+
+/// The patterns for a measure unit in one language, where `{0}` is the
+/// number.
+///
+/// This data is derived from the [CLDR].
+///
+/// [CLDR]: http://cldr.unicode.org/
+#[derive(Debug)]
+pub struct UnitPatterns {
+    /// The name of the unit, such as `kilometer`.
+    pub unit: &'static str,
+    /// The patterns for the long width, for the `one` plural category
+    /// and the others.
+    pub long: (&'static str, &'static str),
+    /// The patterns for the short width.
+    pub short: (&'static str, &'static str),
+    /// The patterns for the narrow width.
+    pub narrow: (&'static str, &'static str),
+}
+
+impl UnitPatterns {
+    /// Whether a unit is supported, such as `kilometer`.
+    pub fn is_supported(unit: &str) -> bool {
+        EN.iter().any(|patterns| patterns.unit == unit)
+    }
+
+    // Whether there are patterns for the language of `language_tag`.
+    pub(crate) fn has_language(language_tag: &LanguageTag) -> bool {
+        let language = match language_tag.language {
+            Some(ref language) => language,
+            None => return false,
+        };
+        ALL.iter().any(|&(l, _)| l.eq_ignore_ascii_case(language))
+    }
+
+    /// Find the patterns for a unit in a language. Returns `None` if
+    /// the unit or the language is not supported.
+    pub fn find(language_tag: &LanguageTag, unit: &str) -> Option<&'static UnitPatterns> {
+        let language = language_tag.language.as_ref()?;
+        let &(_, table) = ALL.iter().find(|&&(l, _)| l.eq_ignore_ascii_case(language))?;
+        table.iter().find(|patterns| patterns.unit == unit)
+    }
+}
+
+// A pattern which doesn't change with the plural category.
+const fn same(pattern: &'static str) -> (&'static str, &'static str) {
+    (pattern, pattern)
+}
+
+const fn plural(one: &'static str, other: &'static str) -> (&'static str, &'static str) {
+    (one, other)
+}
+
+#[cfg(feature = "locale-data")]
+pub(super) static ALL: &[(&str, &[UnitPatterns])] = &[("en", EN), ("de", DE), ("fr", FR)];
+
+#[cfg(not(feature = "locale-data"))]
+pub(super) static ALL: &[(&str, &[UnitPatterns])] = &[("en", EN)];
+
+static EN: &[UnitPatterns] = &[
+    UnitPatterns {
+        unit: "kilometer",
+        long: plural("{0} kilometer", "{0} kilometers"),
+        short: same("{0} km"),
+        narrow: same("{0}km"),
+    },
+    UnitPatterns {
+        unit: "meter",
+        long: plural("{0} meter", "{0} meters"),
+        short: same("{0} m"),
+        narrow: same("{0}m"),
+    },
+    UnitPatterns {
+        unit: "centimeter",
+        long: plural("{0} centimeter", "{0} centimeters"),
+        short: same("{0} cm"),
+        narrow: same("{0}cm"),
+    },
+    UnitPatterns {
+        unit: "mile",
+        long: plural("{0} mile", "{0} miles"),
+        short: same("{0} mi"),
+        narrow: same("{0}mi"),
+    },
+    UnitPatterns {
+        unit: "kilogram",
+        long: plural("{0} kilogram", "{0} kilograms"),
+        short: same("{0} kg"),
+        narrow: same("{0}kg"),
+    },
+    UnitPatterns {
+        unit: "gram",
+        long: plural("{0} gram", "{0} grams"),
+        short: same("{0} g"),
+        narrow: same("{0}g"),
+    },
+    UnitPatterns {
+        unit: "liter",
+        long: plural("{0} liter", "{0} liters"),
+        short: same("{0} L"),
+        narrow: same("{0}L"),
+    },
+    UnitPatterns {
+        unit: "second",
+        long: plural("{0} second", "{0} seconds"),
+        short: plural("{0} sec", "{0} secs"),
+        narrow: same("{0}s"),
+    },
+    UnitPatterns {
+        unit: "minute",
+        long: plural("{0} minute", "{0} minutes"),
+        short: same("{0} min"),
+        narrow: same("{0}m"),
+    },
+    UnitPatterns {
+        unit: "hour",
+        long: plural("{0} hour", "{0} hours"),
+        short: same("{0} hr"),
+        narrow: same("{0}h"),
+    },
+    UnitPatterns {
+        unit: "day",
+        long: plural("{0} day", "{0} days"),
+        short: plural("{0} day", "{0} days"),
+        narrow: same("{0}d"),
+    },
+    UnitPatterns {
+        unit: "byte",
+        long: plural("{0} byte", "{0} bytes"),
+        short: same("{0} byte"),
+        narrow: same("{0}B"),
+    },
+    UnitPatterns {
+        unit: "kilobyte",
+        long: plural("{0} kilobyte", "{0} kilobytes"),
+        short: same("{0} kB"),
+        narrow: same("{0}kB"),
+    },
+    UnitPatterns {
+        unit: "megabyte",
+        long: plural("{0} megabyte", "{0} megabytes"),
+        short: same("{0} MB"),
+        narrow: same("{0}MB"),
+    },
+    UnitPatterns {
+        unit: "gigabyte",
+        long: plural("{0} gigabyte", "{0} gigabytes"),
+        short: same("{0} GB"),
+        narrow: same("{0}GB"),
+    },
+    UnitPatterns {
+        unit: "celsius",
+        long: plural("{0} degree Celsius", "{0} degrees Celsius"),
+        short: same("{0}°C"),
+        narrow: same("{0}°C"),
+    },
+];
+
+#[cfg(feature = "locale-data")]
+static DE: &[UnitPatterns] = &[
+    UnitPatterns {
+        unit: "kilometer",
+        long: same("{0} Kilometer"),
+        short: same("{0} km"),
+        narrow: same("{0} km"),
+    },
+    UnitPatterns {
+        unit: "meter",
+        long: same("{0} Meter"),
+        short: same("{0} m"),
+        narrow: same("{0} m"),
+    },
+    UnitPatterns {
+        unit: "centimeter",
+        long: same("{0} Zentimeter"),
+        short: same("{0} cm"),
+        narrow: same("{0} cm"),
+    },
+    UnitPatterns {
+        unit: "mile",
+        long: plural("{0} Meile", "{0} Meilen"),
+        short: same("{0} mi"),
+        narrow: same("{0} mi"),
+    },
+    UnitPatterns {
+        unit: "kilogram",
+        long: same("{0} Kilogramm"),
+        short: same("{0} kg"),
+        narrow: same("{0} kg"),
+    },
+    UnitPatterns {
+        unit: "gram",
+        long: same("{0} Gramm"),
+        short: same("{0} g"),
+        narrow: same("{0} g"),
+    },
+    UnitPatterns {
+        unit: "liter",
+        long: same("{0} Liter"),
+        short: same("{0} l"),
+        narrow: same("{0} l"),
+    },
+    UnitPatterns {
+        unit: "second",
+        long: plural("{0} Sekunde", "{0} Sekunden"),
+        short: same("{0} Sek."),
+        narrow: same("{0} s"),
+    },
+    UnitPatterns {
+        unit: "minute",
+        long: plural("{0} Minute", "{0} Minuten"),
+        short: same("{0} Min."),
+        narrow: same("{0} Min."),
+    },
+    UnitPatterns {
+        unit: "hour",
+        long: plural("{0} Stunde", "{0} Stunden"),
+        short: same("{0} Std."),
+        narrow: same("{0} Std."),
+    },
+    UnitPatterns {
+        unit: "day",
+        long: plural("{0} Tag", "{0} Tage"),
+        short: same("{0} Tg."),
+        narrow: same("{0} T"),
+    },
+    UnitPatterns {
+        unit: "byte",
+        long: same("{0} Byte"),
+        short: same("{0} Byte"),
+        narrow: same("{0} B"),
+    },
+    UnitPatterns {
+        unit: "kilobyte",
+        long: same("{0} Kilobyte"),
+        short: same("{0} kB"),
+        narrow: same("{0} kB"),
+    },
+    UnitPatterns {
+        unit: "megabyte",
+        long: same("{0} Megabyte"),
+        short: same("{0} MB"),
+        narrow: same("{0} MB"),
+    },
+    UnitPatterns {
+        unit: "gigabyte",
+        long: same("{0} Gigabyte"),
+        short: same("{0} GB"),
+        narrow: same("{0} GB"),
+    },
+    UnitPatterns {
+        unit: "celsius",
+        long: same("{0} Grad Celsius"),
+        short: same("{0} °C"),
+        narrow: same("{0}°C"),
+    },
+];
+
+#[cfg(feature = "locale-data")]
+static FR: &[UnitPatterns] = &[
+    UnitPatterns {
+        unit: "kilometer",
+        long: plural("{0} kilomètre", "{0} kilomètres"),
+        short: same("{0}\u{a0}km"),
+        narrow: same("{0}km"),
+    },
+    UnitPatterns {
+        unit: "meter",
+        long: plural("{0} mètre", "{0} mètres"),
+        short: same("{0}\u{a0}m"),
+        narrow: same("{0}m"),
+    },
+    UnitPatterns {
+        unit: "centimeter",
+        long: plural("{0} centimètre", "{0} centimètres"),
+        short: same("{0}\u{a0}cm"),
+        narrow: same("{0}cm"),
+    },
+    UnitPatterns {
+        unit: "mile",
+        long: plural("{0} mile", "{0} miles"),
+        short: same("{0}\u{a0}mi"),
+        narrow: same("{0}mi"),
+    },
+    UnitPatterns {
+        unit: "kilogram",
+        long: plural("{0} kilogramme", "{0} kilogrammes"),
+        short: same("{0}\u{a0}kg"),
+        narrow: same("{0}kg"),
+    },
+    UnitPatterns {
+        unit: "gram",
+        long: plural("{0} gramme", "{0} grammes"),
+        short: same("{0}\u{a0}g"),
+        narrow: same("{0}g"),
+    },
+    UnitPatterns {
+        unit: "liter",
+        long: plural("{0} litre", "{0} litres"),
+        short: same("{0}\u{a0}l"),
+        narrow: same("{0}l"),
+    },
+    UnitPatterns {
+        unit: "second",
+        long: plural("{0} seconde", "{0} secondes"),
+        short: same("{0}\u{a0}s"),
+        narrow: same("{0}s"),
+    },
+    UnitPatterns {
+        unit: "minute",
+        long: plural("{0} minute", "{0} minutes"),
+        short: same("{0}\u{a0}min"),
+        narrow: same("{0}min"),
+    },
+    UnitPatterns {
+        unit: "hour",
+        long: plural("{0} heure", "{0} heures"),
+        short: same("{0}\u{a0}h"),
+        narrow: same("{0}h"),
+    },
+    UnitPatterns {
+        unit: "day",
+        long: plural("{0} jour", "{0} jours"),
+        short: same("{0}\u{a0}j"),
+        narrow: same("{0}j"),
+    },
+    UnitPatterns {
+        unit: "byte",
+        long: plural("{0} octet", "{0} octets"),
+        short: same("{0}\u{a0}o"),
+        narrow: same("{0}o"),
+    },
+    UnitPatterns {
+        unit: "kilobyte",
+        long: plural("{0} kilooctet", "{0} kilooctets"),
+        short: same("{0}\u{a0}ko"),
+        narrow: same("{0}ko"),
+    },
+    UnitPatterns {
+        unit: "megabyte",
+        long: plural("{0} mégaoctet", "{0} mégaoctets"),
+        short: same("{0}\u{a0}Mo"),
+        narrow: same("{0}Mo"),
+    },
+    UnitPatterns {
+        unit: "gigabyte",
+        long: plural("{0} gigaoctet", "{0} gigaoctets"),
+        short: same("{0}\u{a0}Go"),
+        narrow: same("{0}Go"),
+    },
+    UnitPatterns {
+        unit: "celsius",
+        long: plural("{0} degré Celsius", "{0} degrés Celsius"),
+        short: same("{0}\u{a0}°C"),
+        narrow: same("{0}°C"),
+    },
+];
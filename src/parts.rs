@@ -0,0 +1,139 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::any::Any;
+use std::fmt;
+
+use icu::ast;
+use {Args, Context, Message};
+
+/// What a [`FormattedPart`] of a message is.
+///
+/// [`FormattedPart`]: struct.FormattedPart.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartRole {
+    /// Text written in the message itself.
+    Literal,
+    /// A formatted argument.
+    Argument {
+        /// The name of the argument.
+        name: String,
+    },
+    /// The number shown by `#` in a `plural` format.
+    PluralNumber,
+}
+
+/// A piece of a formatted message, returned by
+/// [`Context::format_to_parts`].
+///
+/// [`Context::format_to_parts`]: struct.Context.html#method.format_to_parts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormattedPart {
+    /// What this part is.
+    pub role: PartRole,
+    /// The formatted text.
+    pub text: String,
+}
+
+impl FormattedPart {
+    fn new(role: PartRole, text: String) -> Self {
+        FormattedPart { role, text }
+    }
+}
+
+fn push_literal(parts: &mut Vec<FormattedPart>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match parts.last_mut() {
+        Some(part) if part.role == PartRole::Literal => part.text.push_str(text),
+        _ => parts.push(FormattedPart::new(PartRole::Literal, text.to_string())),
+    }
+}
+
+// The argument formatted by a part, if it formats one in place.
+fn argument_name(any: &dyn Any) -> Option<&str> {
+    if let Some(fmt) = any.downcast_ref::<ast::SimpleFormat>() {
+        Some(&fmt.variable_name)
+    } else if let Some(fmt) = any.downcast_ref::<ast::NumberFormat>() {
+        Some(&fmt.variable_name)
+    } else if let Some(fmt) = any.downcast_ref::<ast::DateTimeFormat>() {
+        Some(&fmt.variable_name)
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnitFormat>() {
+        Some(&fmt.variable_name)
+    } else if let Some(fmt) = any.downcast_ref::<ast::TextFormat>() {
+        Some(&fmt.variable_name)
+    } else if let Some(fmt) = any.downcast_ref::<ast::UnknownFormat>() {
+        Some(&fmt.variable_name)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn collect_parts<'f>(
+    message: &Message,
+    ctx: &Context,
+    args: &'f dyn Args<'f>,
+    parts: &mut Vec<FormattedPart>,
+) -> fmt::Result {
+    for part in &message.parts {
+        let any = part.as_any();
+        if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+            push_literal(parts, &text.text);
+        } else if let Some(nested) = any.downcast_ref::<Message>() {
+            collect_parts(nested, ctx, args, parts)?;
+        } else if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+            let value = args.get(&fmt.variable_name).ok_or(fmt::Error {})?;
+            let branch = fmt.select_message(ctx, &value.select_key());
+            collect_parts(branch, ctx, args, parts)?;
+        } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+            let value = args.get(&fmt.variable_name).ok_or(fmt::Error {})?;
+            let (branch, ctx) = fmt.resolve(ctx, value).ok_or(fmt::Error {})?;
+            collect_parts(branch, &ctx, args, parts)?;
+        } else {
+            let mut text = String::new();
+            part.apply_format(ctx, &mut text, args)?;
+            let role = if any.is::<ast::PlaceholderFormat>() {
+                PartRole::PluralNumber
+            } else if let Some(name) = argument_name(any) {
+                PartRole::Argument { name: name.to_string() }
+            } else {
+                push_literal(parts, &text);
+                continue;
+            };
+            parts.push(FormattedPart::new(role, text));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PartRole;
+    use icu::{parse_with, ParseOptions};
+    use {arg, icu, Context, EmptyArgs, MessageBundle};
+    use std::sync::Arc;
+
+    #[test]
+    fn nested_branches_and_references() {
+        let options = ParseOptions { extensions: true, ..ParseOptions::default() };
+        let bundle = MessageBundle::parse_entries_with("en".parse().unwrap(), vec![("brand", "Acme")], options).unwrap();
+        let ctx = Context::default().with_message_source(Arc::new(bundle));
+        let m = parse_with("{g, select, female {She} other {They}} bought {@brand} {item}.", options).unwrap();
+        let args = arg("g", "female");
+        let args = args.arg("item", "shoes");
+
+        let parts = ctx.format_to_parts(&m, &args).unwrap();
+        let roles: Vec<(&PartRole, &str)> = parts.iter().map(|p| (&p.role, p.text.as_str())).collect();
+        assert_eq!(roles, vec![
+            (&PartRole::Literal, "She bought Acme "),
+            (&PartRole::Argument { name: "item".into() }, "shoes"),
+            (&PartRole::Literal, "."),
+        ]);
+
+        assert_eq!(ctx.format_to_parts(&icu::parse("{x}").unwrap(), &EmptyArgs), None);
+    }
+}
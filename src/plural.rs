@@ -0,0 +1,1081 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Plural rules for each language.
+
+use language_tags::LanguageTag;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::FromStr;
+
+use icu::ast;
+use {Message, PluralCategory};
+
+use PluralCategory::{Few, Many, One, Other, Two, Zero};
+
+/// The cardinal plural categories used by a language, which a plural
+/// message needs a branch for to be translated correctly.
+///
+/// These come from the [CLDR plural rules]. Languages that aren't
+/// known only require `other`.
+///
+/// ```
+/// use message_format::{plural, PluralCategory};
+///
+/// let ru = plural::required_categories(&"ru".parse().unwrap());
+/// assert_eq!(ru, [
+///     PluralCategory::One,
+///     PluralCategory::Few,
+///     PluralCategory::Many,
+///     PluralCategory::Other,
+/// ]);
+/// ```
+///
+/// [CLDR plural rules]: https://www.unicode.org/cldr/charts/latest/supplemental/language_plural_rules.html
+pub fn required_categories(language_tag: &LanguageTag) -> &'static [PluralCategory] {
+    PluralRules::cardinal(language_tag).categories()
+}
+
+/// The values of a number that plural rules depend on, as defined by
+/// [Unicode TR 35].
+///
+/// These are usually made from a number with `From`, but can also be
+/// parsed from a string to keep trailing zeros in the fraction, which
+/// some languages treat differently: `"1.50"` has `v = 2`.
+///
+/// [Unicode TR 35]: https://unicode.org/reports/tr35/tr35-numbers.html#Operands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PluralOperands {
+    /// The absolute value of the number.
+    pub n: f64,
+    /// The integer digits of `n`.
+    pub i: u64,
+    /// The number of visible fraction digits, with trailing zeros.
+    pub v: usize,
+    /// The visible fraction digits, with trailing zeros, as an integer.
+    pub f: u64,
+    /// The visible fraction digits, without trailing zeros, as an
+    /// integer.
+    pub t: u64,
+}
+
+impl FromStr for PluralOperands {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let digits = s.strip_prefix('-').unwrap_or(s);
+        let (integer, fraction) = match digits.find('.') {
+            Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+            None => (digits, ""),
+        };
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+            return Err(());
+        }
+        // Digits beyond what fits are too small to affect the rules.
+        let fraction = &fraction[..fraction.len().min(18)];
+        let trimmed = fraction.trim_end_matches('0');
+        Ok(PluralOperands {
+            n: digits.parse().map_err(|_| ())?,
+            i: integer.parse().unwrap_or(u64::MAX),
+            v: fraction.len(),
+            f: fraction.parse().unwrap_or(0),
+            t: trimmed.parse().unwrap_or(0),
+        })
+    }
+}
+
+impl From<f64> for PluralOperands {
+    fn from(n: f64) -> Self {
+        if !n.is_finite() {
+            return PluralOperands {
+                n: n.abs(),
+                i: 0,
+                v: 0,
+                f: 0,
+                t: 0,
+            };
+        }
+        // The shortest representation that round trips, which is
+        // never written with an exponent.
+        n.to_string().parse().unwrap()
+    }
+}
+
+impl From<f32> for PluralOperands {
+    fn from(n: f32) -> Self {
+        n.to_string().parse().unwrap_or_else(|_| f64::from(n).into())
+    }
+}
+
+macro_rules! integer_operands {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for PluralOperands {
+                fn from(n: $t) -> Self {
+                    let i = (n as i128).unsigned_abs() as u64;
+                    PluralOperands {
+                        n: i as f64,
+                        i,
+                        v: 0,
+                        f: 0,
+                        t: 0,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_operands!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Whether plural rules are for counting things or for ordering them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PluralRuleType {
+    /// For counts, such as "1 file" and "2 files".
+    Cardinal,
+    /// For positions, such as "1st" and "2nd".
+    Ordinal,
+}
+
+/// The plural rules of a language, for choosing the plural category
+/// of a number outside of a message.
+///
+/// Cardinal rules are included for the languages listed by
+/// [`required_categories`], and ordinal rules for a smaller set of
+/// languages. Other languages use `other` for every number.
+///
+/// ```
+/// use message_format::plural::PluralRules;
+/// use message_format::PluralCategory;
+///
+/// let en = "en".parse().unwrap();
+/// assert_eq!(PluralRules::cardinal(&en).select(1), PluralCategory::One);
+/// assert_eq!(PluralRules::cardinal(&en).select(3.5), PluralCategory::Other);
+/// assert_eq!(PluralRules::ordinal(&en).select(2), PluralCategory::Two);
+/// assert_eq!(PluralRules::ordinal(&en).select(13), PluralCategory::Other);
+///
+/// let pl = "pl".parse().unwrap();
+/// assert_eq!(PluralRules::cardinal(&pl).select(22), PluralCategory::Few);
+/// assert_eq!(PluralRules::cardinal(&pl).select(25), PluralCategory::Many);
+/// ```
+///
+/// [`required_categories`]: fn.required_categories.html
+#[derive(Clone, Copy, Debug)]
+pub struct PluralRules {
+    rule_type: PluralRuleType,
+    categories: &'static [PluralCategory],
+    rule: Rule,
+}
+
+type Rule = fn(&PluralOperands) -> PluralCategory;
+
+impl PluralRules {
+    /// The rules of a type for a language. Languages that aren't known
+    /// only use `other`.
+    pub fn new(language_tag: &LanguageTag, rule_type: PluralRuleType) -> Self {
+        PluralRules::find(language_tag, rule_type).unwrap_or(PluralRules {
+            rule_type,
+            categories: &[Other],
+            rule: other,
+        })
+    }
+
+    // The built in rules for a language, without falling back.
+    pub(crate) fn find(language_tag: &LanguageTag, rule_type: PluralRuleType) -> Option<Self> {
+        let language = language_tag.language.as_ref().map(|l| l.to_ascii_lowercase());
+        let language = language.as_ref().map_or("", String::as_str);
+        let region = language_tag.region.as_ref().map(|r| r.to_ascii_lowercase());
+        let regional_table = match rule_type {
+            PluralRuleType::Cardinal => REGIONAL_CARDINAL_RULES,
+            PluralRuleType::Ordinal => &[],
+        };
+        let regional = regional_table
+            .iter()
+            .find(|&&(l, r, _, _)| l == language && Some(r) == region.as_deref());
+        if let Some(&(_, _, categories, rule)) = regional {
+            return Some(PluralRules {
+                rule_type,
+                categories,
+                rule,
+            });
+        }
+        let table = match rule_type {
+            PluralRuleType::Cardinal => CARDINAL_RULES,
+            PluralRuleType::Ordinal => ORDINAL_RULES,
+        };
+        table
+            .iter()
+            .find(|(languages, _, _)| languages.contains(&language))
+            .map(|&(_, categories, rule)| PluralRules {
+                rule_type,
+                categories,
+                rule,
+            })
+    }
+
+    /// The cardinal rules for a language.
+    pub fn cardinal(language_tag: &LanguageTag) -> Self {
+        PluralRules::new(language_tag, PluralRuleType::Cardinal)
+    }
+
+    /// The ordinal rules for a language.
+    pub fn ordinal(language_tag: &LanguageTag) -> Self {
+        PluralRules::new(language_tag, PluralRuleType::Ordinal)
+    }
+
+    /// Whether these are cardinal or ordinal rules.
+    pub fn rule_type(&self) -> PluralRuleType {
+        self.rule_type
+    }
+
+    /// The categories these rules can select, ending with `other`.
+    pub fn categories(&self) -> &'static [PluralCategory] {
+        self.categories
+    }
+
+    /// The plural category of a number.
+    pub fn select<N: Into<PluralOperands>>(&self, n: N) -> PluralCategory {
+        (self.rule)(&n.into())
+    }
+}
+
+/// Plural rules that can be registered on a [`Context`] to replace the
+/// built in rules for a language, such as for an in-house locale or to
+/// choose categories deterministically in tests.
+///
+/// This is implemented for [`PluralRules`] and for closures.
+///
+/// ```
+/// # #[macro_use] extern crate message_format;
+/// # fn main() {
+/// use message_format::{icu, Context, PluralCategory};
+/// use message_format::plural::PluralOperands;
+///
+/// let mut ctx = Context::new("tlh".parse().unwrap(), None);
+/// ctx.set_plural_rules("tlh".parse().unwrap(), |n: &PluralOperands| {
+///     if n.i == 2 && n.v == 0 { PluralCategory::Two } else { PluralCategory::Other }
+/// });
+/// let m = icu::parse("{n, plural, two {a pair} other {# items}}").unwrap();
+/// assert_eq!(format_message!(ctx, &m, n => 2), "a pair");
+/// assert_eq!(format_message!(ctx, &m, n => 1), "1 items");
+/// # }
+/// ```
+///
+/// [`Context`]: ../struct.Context.html
+/// [`PluralRules`]: struct.PluralRules.html
+pub trait PluralRuleSet: Send + Sync {
+    /// The plural category of a number.
+    fn select(&self, operands: &PluralOperands) -> PluralCategory;
+}
+
+impl PluralRuleSet for PluralRules {
+    fn select(&self, operands: &PluralOperands) -> PluralCategory {
+        (self.rule)(operands)
+    }
+}
+
+impl<F> PluralRuleSet for F
+where
+    F: Fn(&PluralOperands) -> PluralCategory + Send + Sync,
+{
+    fn select(&self, operands: &PluralOperands) -> PluralCategory {
+        self(operands)
+    }
+}
+
+impl fmt::Debug for dyn PluralRuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("PluralRuleSet")
+    }
+}
+
+// Whether `n` is an integer from `low` to `high`.
+fn within(n: f64, low: u64, high: u64) -> bool {
+    n.fract() == 0.0 && n >= low as f64 && n <= high as f64
+}
+
+fn other(_: &PluralOperands) -> PluralCategory {
+    Other
+}
+
+// The rule shared by many languages, where only exactly 1 is `one`.
+fn one_if_n_is_1(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 {
+        One
+    } else {
+        Other
+    }
+}
+
+fn one_if_integer_1(o: &PluralOperands) -> PluralCategory {
+    if o.i == 1 && o.v == 0 {
+        One
+    } else {
+        Other
+    }
+}
+
+fn one_if_n_is_0_or_1(o: &PluralOperands) -> PluralCategory {
+    if within(o.n, 0, 1) {
+        One
+    } else {
+        Other
+    }
+}
+
+fn one_if_0_or_n_is_1(o: &PluralOperands) -> PluralCategory {
+    if o.i == 0 || o.n == 1.0 {
+        One
+    } else {
+        Other
+    }
+}
+
+fn danish(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 || (o.t != 0 && o.i <= 1) {
+        One
+    } else {
+        Other
+    }
+}
+
+fn icelandic(o: &PluralOperands) -> PluralCategory {
+    if (o.t == 0 && o.i % 10 == 1 && o.i % 100 != 11) || (o.t % 10 == 1 && o.t % 100 != 11) {
+        One
+    } else {
+        Other
+    }
+}
+
+fn macedonian(o: &PluralOperands) -> PluralCategory {
+    if (o.v == 0 && o.i % 10 == 1 && o.i % 100 != 11) || (o.f % 10 == 1 && o.f % 100 != 11) {
+        One
+    } else {
+        Other
+    }
+}
+
+fn sinhala(o: &PluralOperands) -> PluralCategory {
+    if o.n == 0.0 || o.n == 1.0 || (o.i == 0 && o.f == 1) {
+        One
+    } else {
+        Other
+    }
+}
+
+fn armenian(o: &PluralOperands) -> PluralCategory {
+    if o.i <= 1 {
+        One
+    } else {
+        Other
+    }
+}
+
+// Millions are `many` in the Romance languages.
+fn is_romance_many(o: &PluralOperands) -> bool {
+    o.i != 0 && o.i.is_multiple_of(1_000_000) && o.v == 0
+}
+
+fn french(o: &PluralOperands) -> PluralCategory {
+    if o.i <= 1 {
+        One
+    } else if is_romance_many(o) {
+        Many
+    } else {
+        Other
+    }
+}
+
+fn spanish(o: &PluralOperands) -> PluralCategory {
+    if o.n == 1.0 {
+        One
+    } else if is_romance_many(o) {
+        Many
+    } else {
+        Other
+    }
+}
+
+fn italian(o: &PluralOperands) -> PluralCategory {
+    if o.i == 1 && o.v == 0 {
+        One
+    } else if is_romance_many(o) {
+        Many
+    } else {
+        Other
+    }
+}
+
+fn latvian(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if n % 10.0 == 0.0 || within(n % 100.0, 11, 19) || (o.v == 2 && (11..=19).contains(&(o.f % 100))) {
+        Zero
+    } else if (n % 10.0 == 1.0 && n % 100.0 != 11.0)
+        || (o.f % 10 == 1 && (o.v != 2 || o.f % 100 != 11))
+    {
+        One
+    } else {
+        Other
+    }
+}
+
+fn hebrew(o: &PluralOperands) -> PluralCategory {
+    if (o.i == 1 && o.v == 0) || (o.i == 0 && o.v != 0) {
+        One
+    } else if o.i == 2 && o.v == 0 {
+        Two
+    } else {
+        Other
+    }
+}
+
+fn serbo_croatian(o: &PluralOperands) -> PluralCategory {
+    let (i, f) = (o.i, o.f);
+    if (o.v == 0 && i % 10 == 1 && i % 100 != 11) || (f % 10 == 1 && f % 100 != 11) {
+        One
+    } else if (o.v == 0 && (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)))
+        || ((2..=4).contains(&(f % 10)) && !(12..=14).contains(&(f % 100)))
+    {
+        Few
+    } else {
+        Other
+    }
+}
+
+fn romanian(o: &PluralOperands) -> PluralCategory {
+    if o.i == 1 && o.v == 0 {
+        One
+    } else if o.v != 0 || o.n == 0.0 || within(o.n % 100.0, 1, 19) {
+        Few
+    } else {
+        Other
+    }
+}
+
+fn slovenian(o: &PluralOperands) -> PluralCategory {
+    match (o.v, o.i % 100) {
+        (0, 1) => One,
+        (0, 2) => Two,
+        (0, 3..=4) => Few,
+        (0, _) => Other,
+        _ => Few,
+    }
+}
+
+fn belarusian(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if n % 10.0 == 1.0 && n % 100.0 != 11.0 {
+        One
+    } else if within(n % 10.0, 2, 4) && !within(n % 100.0, 12, 14) {
+        Few
+    } else if n % 10.0 == 0.0 || within(n % 10.0, 5, 9) || within(n % 100.0, 11, 14) {
+        Many
+    } else {
+        Other
+    }
+}
+
+fn russian(o: &PluralOperands) -> PluralCategory {
+    let i = o.i;
+    if o.v != 0 {
+        Other
+    } else if i % 10 == 1 && i % 100 != 11 {
+        One
+    } else if (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {
+        Few
+    } else {
+        Many
+    }
+}
+
+fn polish(o: &PluralOperands) -> PluralCategory {
+    let i = o.i;
+    if o.v != 0 {
+        Other
+    } else if i == 1 {
+        One
+    } else if (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {
+        Few
+    } else {
+        Many
+    }
+}
+
+fn czech(o: &PluralOperands) -> PluralCategory {
+    match (o.i, o.v) {
+        (1, 0) => One,
+        (2..=4, 0) => Few,
+        (_, 0) => Other,
+        _ => Many,
+    }
+}
+
+fn lithuanian(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if o.f != 0 {
+        Many
+    } else if n % 10.0 == 1.0 && !within(n % 100.0, 11, 19) {
+        One
+    } else if within(n % 10.0, 2, 9) && !within(n % 100.0, 11, 19) {
+        Few
+    } else {
+        Other
+    }
+}
+
+fn irish(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if n == 1.0 {
+        One
+    } else if n == 2.0 {
+        Two
+    } else if within(n, 3, 6) {
+        Few
+    } else if within(n, 7, 10) {
+        Many
+    } else {
+        Other
+    }
+}
+
+fn arabic(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if n == 0.0 {
+        Zero
+    } else if n == 1.0 {
+        One
+    } else if n == 2.0 {
+        Two
+    } else if within(n % 100.0, 3, 10) {
+        Few
+    } else if within(n % 100.0, 11, 99) {
+        Many
+    } else {
+        Other
+    }
+}
+
+fn welsh(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if n == 0.0 {
+        Zero
+    } else if n == 1.0 {
+        One
+    } else if n == 2.0 {
+        Two
+    } else if n == 3.0 {
+        Few
+    } else if n == 6.0 {
+        Many
+    } else {
+        Other
+    }
+}
+
+// The languages sharing each set of cardinal rules, with the
+// categories the rules use.
+static CARDINAL_RULES: &[(&[&str], &[PluralCategory], Rule)] = &[
+    (&["id", "ja", "km", "ko", "lo", "ms", "my", "th", "vi", "zh"], &[Other], other),
+    (
+        &[
+            "af", "az", "bg", "el", "eu", "hu", "ka", "kk", "ky", "mn", "nb", "ne", "no",
+            "sq", "ta", "te", "tr", "uz",
+        ],
+        &[One, Other],
+        one_if_n_is_1,
+    ),
+    (
+        &["de", "en", "et", "fi", "gl", "nl", "nn", "sv", "sw", "ur"],
+        &[One, Other],
+        one_if_integer_1,
+    ),
+    (&["bn", "fa", "gu", "hi", "kn", "mr", "zu"], &[One, Other], one_if_0_or_n_is_1),
+    (&["ml"], &[One, Other], one_if_n_is_1),
+    (&["pa"], &[One, Other], one_if_n_is_0_or_1),
+    (&["da"], &[One, Other], danish),
+    (&["is"], &[One, Other], icelandic),
+    (&["mk"], &[One, Other], macedonian),
+    (&["si"], &[One, Other], sinhala),
+    (&["hy"], &[One, Other], armenian),
+    (&["fr", "pt"], &[One, Many, Other], french),
+    (&["es"], &[One, Many, Other], spanish),
+    (&["ca", "it"], &[One, Many, Other], italian),
+    (&["lv"], &[Zero, One, Other], latvian),
+    (&["he"], &[One, Two, Other], hebrew),
+    (&["bs", "hr", "sr"], &[One, Few, Other], serbo_croatian),
+    (&["ro"], &[One, Few, Other], romanian),
+    (&["sl"], &[One, Two, Few, Other], slovenian),
+    (&["be"], &[One, Few, Many, Other], belarusian),
+    (&["ru", "uk"], &[One, Few, Many, Other], russian),
+    (&["pl"], &[One, Few, Many, Other], polish),
+    (&["cs", "sk"], &[One, Few, Many, Other], czech),
+    (&["lt"], &[One, Few, Many, Other], lithuanian),
+    (&["ga"], &[One, Two, Few, Many, Other], irish),
+    (&["ar"], &[Zero, One, Two, Few, Many, Other], arabic),
+    (&["cy"], &[Zero, One, Two, Few, Many, Other], welsh),
+];
+
+// The regions whose cardinal rules differ from those of their language,
+// with the categories the rules use.
+static REGIONAL_CARDINAL_RULES: &[(&str, &str, &[PluralCategory], Rule)] =
+    &[("pt", "pt", &[One, Many, Other], italian)];
+
+fn english_ordinal(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if n % 10.0 == 1.0 && n % 100.0 != 11.0 {
+        One
+    } else if n % 10.0 == 2.0 && n % 100.0 != 12.0 {
+        Two
+    } else if n % 10.0 == 3.0 && n % 100.0 != 13.0 {
+        Few
+    } else {
+        Other
+    }
+}
+
+fn swedish_ordinal(o: &PluralOperands) -> PluralCategory {
+    let n = o.n;
+    if (n % 10.0 == 1.0 || n % 10.0 == 2.0) && n % 100.0 != 11.0 && n % 100.0 != 12.0 {
+        One
+    } else {
+        Other
+    }
+}
+
+fn italian_ordinal(o: &PluralOperands) -> PluralCategory {
+    if [8.0, 11.0, 80.0, 800.0].contains(&o.n) {
+        Many
+    } else {
+        Other
+    }
+}
+
+static ORDINAL_RULES: &[(&[&str], &[PluralCategory], Rule)] = &[
+    (&["en"], &[One, Two, Few, Other], english_ordinal),
+    (&["fr", "ms", "vi"], &[One, Other], one_if_n_is_1),
+    (&["sv"], &[One, Other], swedish_ordinal),
+    (&["it"], &[Many, Other], italian_ordinal),
+];
+
+/// A plural argument that lacks branches for some of the categories
+/// its language uses. See [`Message::check_plural_coverage`].
+///
+/// [`Message::check_plural_coverage`]: ../struct.Message.html#method.check_plural_coverage
+#[derive(Clone, Debug, PartialEq)]
+pub struct MissingCategories {
+    /// The name of the plural argument.
+    pub argument: String,
+    /// The required categories that have no branch.
+    pub missing: Vec<PluralCategory>,
+}
+
+pub(crate) fn check_coverage(message: &Message, required: &[PluralCategory], found: &mut Vec<MissingCategories>) {
+    message.walk(&mut |part| {
+        let fmt = match part.as_any().downcast_ref::<ast::PluralFormat>() {
+            Some(fmt) => fmt,
+            None => return,
+        };
+        let missing: Vec<PluralCategory> = required
+            .iter()
+            .cloned()
+            .filter(|&category| {
+                let branch = match category {
+                    Zero => &fmt.zero,
+                    One => &fmt.one,
+                    Two => &fmt.two,
+                    Few => &fmt.few,
+                    Many => &fmt.many,
+                    Other => return false,
+                };
+                branch.is_none()
+            })
+            .collect();
+        if !missing.is_empty() {
+            found.push(MissingCategories {
+                argument: fmt.variable_name.clone(),
+                missing,
+            });
+        }
+    });
+}
+
+/// Plural rules written in the [CLDR plural rule syntax], such as
+/// `i = 1 and v = 0`, for languages without built in rules.
+///
+/// Samples after `@integer` or `@decimal` are ignored. A number that
+/// matches no rule is `other`.
+///
+/// ```
+/// use message_format::PluralCategory;
+/// use message_format::plural::PluralRuleSyntax;
+///
+/// let rules = PluralRuleSyntax::parse(&[
+///     (PluralCategory::One, "v = 0 and i % 10 = 1 and i % 100 != 11 @integer 1, 21"),
+///     (PluralCategory::Few, "v = 0 and i % 10 = 2..4 and i % 100 != 12..14"),
+/// ]).unwrap();
+/// assert_eq!(rules.select(21), PluralCategory::One);
+/// assert_eq!(rules.select(23), PluralCategory::Few);
+/// assert_eq!(rules.select(13), PluralCategory::Other);
+/// ```
+///
+/// [CLDR plural rule syntax]: https://unicode.org/reports/tr35/tr35-numbers.html#Language_Plural_Rules
+#[derive(Clone, Debug, PartialEq)]
+pub struct PluralRuleSyntax {
+    rules: Vec<(PluralCategory, Vec<Vec<Relation>>)>,
+}
+
+// A relation such as `i % 10 in 2..4,6`.
+#[derive(Clone, Debug, PartialEq)]
+struct Relation {
+    operand: char,
+    modulus: Option<u64>,
+    negated: bool,
+    // Whether the ranges include non-integer values.
+    within: bool,
+    ranges: Vec<(u64, u64)>,
+}
+
+impl PluralRuleSyntax {
+    /// Parse the rule for each category. Returns `None` if a rule is
+    /// malformed or a rule is given for `other`.
+    pub fn parse(rules: &[(PluralCategory, &str)]) -> Option<Self> {
+        let mut parsed = vec![];
+        for &(category, rule) in rules {
+            if category == Other {
+                return None;
+            }
+            let rule = rule.split('@').next().unwrap_or("");
+            let tokens = tokenize(rule)?;
+            let mut tokens = tokens.iter().map(String::as_str).peekable();
+            let mut condition = vec![];
+            loop {
+                let mut and = vec![relation(&mut tokens)?];
+                while tokens.next_if_eq(&"and").is_some() {
+                    and.push(relation(&mut tokens)?);
+                }
+                condition.push(and);
+                if tokens.next_if_eq(&"or").is_none() {
+                    break;
+                }
+            }
+            if tokens.next().is_some() {
+                return None;
+            }
+            parsed.push((category, condition));
+        }
+        Some(PluralRuleSyntax { rules: parsed })
+    }
+
+    /// The plural category of a number.
+    pub fn select<N: Into<PluralOperands>>(&self, n: N) -> PluralCategory {
+        PluralRuleSet::select(self, &n.into())
+    }
+}
+
+impl PluralRuleSet for PluralRuleSyntax {
+    fn select(&self, operands: &PluralOperands) -> PluralCategory {
+        self.rules
+            .iter()
+            .find(|(_, condition)| {
+                condition
+                    .iter()
+                    .any(|and| and.iter().all(|relation| relation.matches(operands)))
+            })
+            .map_or(Other, |&(category, _)| category)
+    }
+}
+
+impl Relation {
+    fn matches(&self, operands: &PluralOperands) -> bool {
+        let value = match self.operand {
+            'n' => operands.n,
+            'i' => operands.i as f64,
+            'v' => operands.v as f64,
+            'w' => visible_fraction_digits(operands) as f64,
+            'f' => operands.f as f64,
+            't' => operands.t as f64,
+            // The compact exponent operands, which are always 0 here.
+            _ => 0.0,
+        };
+        let value = self.modulus.map_or(value, |modulus| value % modulus as f64);
+        let found = self.ranges.iter().any(|&(low, high)| {
+            (self.within || value.fract() == 0.0) && low as f64 <= value && value <= high as f64
+        });
+        found != self.negated
+    }
+}
+
+// The `w` operand: the number of fraction digits without trailing zeros.
+fn visible_fraction_digits(operands: &PluralOperands) -> usize {
+    let (mut w, mut f) = (operands.v, operands.f);
+    while w > 0 && f % 10 == 0 {
+        f /= 10;
+        w -= 1;
+    }
+    w
+}
+
+// Split a rule into words, numbers and the symbols `=`, `!=`, `%`, `,`
+// and `..`.
+fn tokenize(rule: &str) -> Option<Vec<String>> {
+    let mut tokens = vec![];
+    let mut chars = rule.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_alphanumeric() {
+            let mut token = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_alphanumeric) {
+                token.push(c);
+            }
+            tokens.push(token);
+        } else {
+            chars.next();
+            let token = match (c, chars.peek()) {
+                ('!', Some('=')) | ('.', Some('.')) => {
+                    let next = chars.next()?;
+                    format!("{}{}", c, next)
+                }
+                ('=' | '%' | ',', _) => c.to_string(),
+                _ => return None,
+            };
+            tokens.push(token);
+        }
+    }
+    Some(tokens)
+}
+
+fn relation<'t, I: Iterator<Item = &'t str>>(tokens: &mut Peekable<I>) -> Option<Relation> {
+    let operand = match tokens.next()? {
+        o @ ("n" | "i" | "v" | "w" | "f" | "t" | "c" | "e") => o.chars().next()?,
+        _ => return None,
+    };
+    let modulus = match tokens.next_if(|&t| t == "%" || t == "mod") {
+        Some(_) => Some(tokens.next()?.parse().ok()?),
+        None => None,
+    };
+    let (negated, within) = match tokens.next()? {
+        "=" | "in" => (false, false),
+        "!=" => (true, false),
+        "within" => (false, true),
+        "is" => (tokens.next_if_eq(&"not").is_some(), false),
+        "not" => match tokens.next()? {
+            "in" => (true, false),
+            "within" => (true, true),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let mut ranges = vec![];
+    loop {
+        let low: u64 = tokens.next()?.parse().ok()?;
+        let high = match tokens.next_if_eq(&"..") {
+            Some(_) => tokens.next()?.parse().ok()?,
+            None => low,
+        };
+        ranges.push((low, high));
+        if tokens.next_if_eq(&",").is_none() {
+            break;
+        }
+    }
+    Some(Relation {
+        operand,
+        modulus,
+        negated,
+        within,
+        ranges,
+    })
+}
+
+// The bundled rules, written out so that `data_version` can check
+// that a change to them comes with a new data revision. The rules are
+// code, so they are written as the category each one gives a range of
+// numbers.
+#[cfg(all(test, feature = "locale-data"))]
+pub(crate) fn bundled_tables() -> String {
+    let mut numbers: Vec<String> = (0..=1000).map(|n| n.to_string()).collect();
+    numbers.extend((0..=30).map(|n| format!("{}.{}", n / 10, n % 10)));
+    numbers.extend(["0.00", "1.00", "1.50", "1000000", "2000000", "1000001"].iter().map(|n| n.to_string()));
+    let operands: Vec<PluralOperands> = numbers.iter().map(|n| n.parse().unwrap()).collect();
+    let mut tables = String::new();
+    for &(languages, categories, rule) in CARDINAL_RULES.iter().chain(ORDINAL_RULES) {
+        let chosen: Vec<PluralCategory> = operands.iter().map(rule).collect();
+        tables.push_str(&format!("{:?}{:?}{:?}", languages, categories, chosen));
+    }
+    for &(language, region, categories, rule) in REGIONAL_CARDINAL_RULES {
+        let chosen: Vec<PluralCategory> = operands.iter().map(rule).collect();
+        tables.push_str(&format!("{:?}{:?}{:?}{:?}", language, region, categories, chosen));
+    }
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        required_categories, MissingCategories, PluralOperands, PluralRuleSyntax, PluralRules, CARDINAL_RULES,
+    };
+    use icu::parse;
+    use PluralCategory::{Few, Many, One, Other, Two, Zero};
+    use {arg, Context};
+
+    #[test]
+    fn categories_by_language() {
+        assert_eq!(required_categories(&"en-GB".parse().unwrap()), [One, Other]);
+        assert_eq!(required_categories(&"lv".parse().unwrap()), [Zero, One, Other]);
+        assert_eq!(required_categories(&"ja".parse().unwrap()), [Other]);
+        assert_eq!(required_categories(&"tlh".parse().unwrap()), [Other]);
+    }
+
+    #[test]
+    fn operands() {
+        let o: PluralOperands = "-1.50".parse().unwrap();
+        assert_eq!((o.n, o.i, o.v, o.f, o.t), (1.5, 1, 2, 50, 5));
+        let o = PluralOperands::from(3.25);
+        assert_eq!((o.i, o.v, o.f, o.t), (3, 2, 25, 25));
+        assert_eq!(PluralOperands::from(-7i64).i, 7);
+        assert!("1e3".parse::<PluralOperands>().is_err());
+    }
+
+    #[test]
+    fn cardinal_rules() {
+        let rules = |tag: &str| PluralRules::cardinal(&tag.parse().unwrap());
+        assert_eq!(rules("en").select("1.0".parse::<PluralOperands>().unwrap()), Other);
+        assert_eq!(rules("fr").select(1.5), One);
+        assert_eq!(rules("fr").select(2_000_000), Many);
+        assert_eq!(rules("ru").select(21), One);
+        assert_eq!(rules("ru").select(111), Many);
+        assert_eq!(rules("ru").select(2.5), Other);
+        assert_eq!(rules("ar").select(103), Few);
+        assert_eq!(rules("cs").select(0.5), Many);
+        assert_eq!(rules("lv").select(0), Zero);
+        // Every rule only selects the categories it lists.
+        for (languages, categories, _) in CARDINAL_RULES {
+            let rules = rules(languages[0]);
+            assert_eq!(rules.categories(), *categories);
+            for n in 0..250 {
+                for &value in &[f64::from(n), f64::from(n) + 0.5] {
+                    assert!(categories.contains(&rules.select(value)), "{} {}", languages[0], value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cldr_samples() {
+        let select = |tag: &str, n: &str| {
+            PluralRules::cardinal(&tag.parse().unwrap()).select(n.parse::<PluralOperands>().unwrap())
+        };
+        let ro: Vec<_> = ["1", "0", "2", "16", "19", "101", "119", "0.0", "1.5", "20", "100", "1000"]
+            .iter()
+            .map(|n| select("ro", n))
+            .collect();
+        assert_eq!(ro, [One, Few, Few, Few, Few, Few, Few, Few, Few, Other, Other, Other]);
+        let pa: Vec<_> = ["0", "1", "0.0", "1.0", "0.5", "2", "1.5"].iter().map(|n| select("pa", n)).collect();
+        assert_eq!(pa, [One, One, One, One, Other, Other, Other]);
+        let pt: Vec<_> = ["0", "1", "1.5", "2", "1000000"].iter().map(|n| select("pt", n)).collect();
+        assert_eq!(pt, [One, One, One, Other, Many]);
+        let pt_pt: Vec<_> = ["0", "1", "1.0", "1.5", "2", "1000000"].iter().map(|n| select("pt-PT", n)).collect();
+        assert_eq!(pt_pt, [Other, One, Other, Other, Other, Many]);
+        assert_eq!(select("pt-BR", "0"), One);
+        assert_eq!(PluralRules::ordinal(&"pt-PT".parse().unwrap()).select(1), Other);
+    }
+
+    #[test]
+    fn formatting_uses_built_in_rules() {
+        let format = |tag: &str, source: &str, n: i64| {
+            let mut ctx = Context::new(tag.parse().unwrap(), None);
+            ctx.data_provider = None;
+            ctx.format(&parse(source).unwrap(), &arg("n", n))
+        };
+        let ru = "{n, plural, one {# файл} few {# файла} many {# файлов} other {# файла}}";
+        assert_eq!(format("ru", ru, 2), "2 файла");
+        assert_eq!(format("ru", ru, 5), "5 файлов");
+        assert_eq!(format("ru", ru, 21), "21 файл");
+        let pl = "{n, plural, one {# plik} few {# pliki} many {# plików} other {# pliku}}";
+        assert_eq!(format("pl", pl, 1), "1 plik");
+        assert_eq!(format("pl", pl, 22), "22 pliki");
+        assert_eq!(format("pl", pl, 12), "12 plików");
+        let ar = "{n, plural, zero {zero} one {one} two {two} few {few} many {many} other {other}}";
+        let categories: Vec<String> = [0, 1, 2, 3, 11, 100].iter().map(|&n| format("ar", ar, n)).collect();
+        assert_eq!(categories, ["zero", "one", "two", "few", "many", "other"]);
+    }
+
+    #[test]
+    fn ordinal_rules() {
+        let en = PluralRules::ordinal(&"en".parse().unwrap());
+        let suffixes: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 23, 101].iter().map(|&n| en.select(n)).collect();
+        assert_eq!(suffixes, [One, Two, Few, Other, Other, Other, Other, One, Two, Few, One]);
+        assert_eq!(PluralRules::ordinal(&"de".parse().unwrap()).select(1), Other);
+        assert_eq!(PluralRules::ordinal(&"sv".parse().unwrap()).select(32), One);
+    }
+
+    #[test]
+    fn coverage_is_checked() {
+        let ru = "ru".parse().unwrap();
+        let m = parse(
+            "{n, plural, one {# файл} few {# файла} many {# файлов} other {# файла}} \
+             {g, select, x {{k, plural, one {#} other {#}}} other {}}",
+        )
+        .unwrap();
+        assert_eq!(
+            m.check_plural_coverage(&ru),
+            [MissingCategories {
+                argument: "k".to_string(),
+                missing: vec![Few, Many],
+            }]
+        );
+        assert!(m.check_plural_coverage(&"en".parse().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn rule_syntax_matches_built_in_rules() {
+        let ru = PluralRuleSyntax::parse(&[
+            (One, "v = 0 and i % 10 = 1 and i % 100 != 11"),
+            (Few, "v = 0 and i % 10 = 2..4 and i % 100 != 12..14"),
+            (Many, "v = 0 and i % 10 = 0 or v = 0 and i % 10 = 5..9 or v = 0 and i % 100 = 11..14"),
+        ])
+        .unwrap();
+        let fr = PluralRuleSyntax::parse(&[
+            (One, "i = 0,1 @integer 0, 1 @decimal 0.0~1.5"),
+            (Many, "e = 0 and i != 0 and i % 1000000 = 0 and v = 0 or e != 0..5"),
+        ])
+        .unwrap();
+        let en = PluralRuleSyntax::parse(&[(One, "i = 1 and v = 0")]).unwrap();
+        let cases = [
+            ("ru", &ru),
+            ("fr", &fr),
+            ("en", &en),
+        ];
+        for (tag, syntax) in &cases {
+            let built_in = PluralRules::cardinal(&tag.parse().unwrap());
+            for n in &["0", "1", "2", "5", "11", "21", "22", "112", "1000000", "0.5", "1.0", "1.5", "2.25"] {
+                let operands: PluralOperands = n.parse().unwrap();
+                assert_eq!(syntax.select(operands), built_in.select(operands), "{} {}", tag, n);
+            }
+        }
+    }
+
+    #[test]
+    fn rule_syntax_operators() {
+        let rules = PluralRuleSyntax::parse(&[
+            (Zero, "n is 0"),
+            (Two, "n within 1..3 and n is not 2"),
+            (Few, "w = 2 and t mod 5 not in 0"),
+            (Many, "n not within 0..100"),
+        ])
+        .unwrap();
+        assert_eq!(rules.select(0), Zero);
+        assert_eq!(rules.select(1.5), Two);
+        assert_eq!(rules.select(2), Other);
+        assert_eq!(rules.select("7.120".parse::<PluralOperands>().unwrap()), Few);
+        assert_eq!(rules.select(101), Many);
+        for bad in &["", "x = 1", "n = ", "n = 1 and", "n == 1", "n = 1..", "n % = 1", "n = 1 2"] {
+            assert_eq!(PluralRuleSyntax::parse(&[(One, bad)]), None, "{}", bad);
+        }
+        assert_eq!(PluralRuleSyntax::parse(&[(Other, "n = 1")]), None);
+    }
+}
@@ -0,0 +1,305 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use icu::serialize;
+use {Args, Message};
+
+/// Statistics gathered for one message by a [`Profiler`].
+///
+/// [`Profiler`]: struct.Profiler.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageStats {
+    /// The number of times the message was formatted.
+    pub count: u64,
+    /// The time spent formatting the message, in total.
+    pub total_time: Duration,
+    /// The time spent on the slowest format.
+    pub max_time: Duration,
+    /// The number of formats missing at least one argument used by
+    /// the message.
+    pub argument_misses: u64,
+    /// The number of formats whose output stream returned an error.
+    pub errors: u64,
+}
+
+impl MessageStats {
+    /// The average time spent formatting the message.
+    pub fn mean_time(&self) -> Duration {
+        match self.count {
+            0 => Duration::default(),
+            count => {
+                let nanos = self.total_time.as_nanos() / u128::from(count);
+                Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+            }
+        }
+    }
+
+    fn add(&mut self, other: &MessageStats) {
+        self.count += other.count;
+        self.total_time += other.total_time;
+        self.max_time = self.max_time.max(other.max_time);
+        self.argument_misses += other.argument_misses;
+        self.errors += other.errors;
+    }
+
+    /// The fraction of formats missing an argument, from `0.0` to `1.0`.
+    pub fn argument_miss_rate(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.argument_misses as f64 / count as f64,
+        }
+    }
+}
+
+/// Records how often, and how quickly, messages are formatted.
+///
+/// Attach a profiler to a context with [`Context::with_profiler`].
+/// Every message formatted by [`Context::format`] or
+/// [`Context::write`] is then counted and timed. Messages are told
+/// apart by their [`structural_hash`], so that equivalent messages are
+/// combined, and their source text is only written out the first time
+/// each is formatted. Nested messages, such as the branches of a
+/// `plural` format, count towards the message containing them.
+///
+/// An argument counts as missing when the message uses it anywhere,
+/// even in a branch that was not chosen.
+///
+/// ```
+/// use message_format::{arg, icu, Context, Profiler};
+/// use std::sync::Arc;
+///
+/// let profiler = Arc::new(Profiler::new());
+/// let ctx = Context::default().with_profiler(profiler.clone());
+/// let m = icu::parse("Hello, {name}!").unwrap();
+///
+/// ctx.format(&m, &arg("name", "Ann"));
+/// ctx.format(&m, &arg("nom", "Ann"));
+///
+/// let report = profiler.report();
+/// let (source, stats) = &report.messages[0];
+/// assert_eq!(source, "Hello, {name}!");
+/// assert_eq!(stats.count, 2);
+/// assert_eq!(stats.argument_miss_rate(), 0.5);
+/// ```
+///
+/// [`structural_hash`]: struct.Message.html#method.structural_hash
+/// [`Context::with_profiler`]: struct.Context.html#method.with_profiler
+/// [`Context::format`]: struct.Context.html#method.format
+/// [`Context::write`]: struct.Context.html#method.write
+#[derive(Debug, Default)]
+pub struct Profiler {
+    messages: Mutex<HashMap<u64, Profiled>>,
+}
+
+// A message seen by a profiler, with what was found about it the first
+// time it was formatted.
+#[derive(Debug)]
+struct Profiled {
+    source: String,
+    arguments: Vec<String>,
+    stats: MessageStats,
+}
+
+impl Profiler {
+    /// Create a profiler with nothing recorded.
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    /// Record one format of `message`.
+    pub(crate) fn record<'f>(
+        &self,
+        message: &Message,
+        args: &'f dyn Args<'f>,
+        elapsed: Duration,
+        failed: bool,
+    ) {
+        let key = message.structural_hash();
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        let profiled = match messages.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let source = match serialize(message) {
+                    Ok(source) => source,
+                    Err(_) => return,
+                };
+                let arguments = message.argument_names().into_iter().map(String::from).collect();
+                entry.insert(Profiled {
+                    source,
+                    arguments,
+                    stats: MessageStats::default(),
+                })
+            }
+        };
+        let missed = profiled.arguments.iter().any(|name| args.get(name).is_none());
+        let entry = &mut profiled.stats;
+        entry.count += 1;
+        entry.total_time += elapsed;
+        entry.max_time = entry.max_time.max(elapsed);
+        entry.argument_misses += missed as u64;
+        entry.errors += failed as u64;
+    }
+
+    /// The statistics recorded so far.
+    pub fn report(&self) -> ProfileReport {
+        let mut by_source: HashMap<&str, MessageStats> = HashMap::new();
+        let profiled = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        for message in profiled.values() {
+            by_source.entry(&message.source).or_default().add(&message.stats);
+        }
+        let mut messages: Vec<(String, MessageStats)> =
+            by_source.into_iter().map(|(source, stats)| (source.to_string(), stats)).collect();
+        messages.sort_by(|a, b| b.1.total_time.cmp(&a.1.total_time).then_with(|| a.0.cmp(&b.0)));
+        ProfileReport { messages }
+    }
+
+    /// Forget everything recorded so far.
+    pub fn reset(&self) {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// A snapshot of the statistics recorded by a [`Profiler`].
+///
+/// Displaying a report writes one line per message.
+///
+/// [`Profiler`]: struct.Profiler.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    /// The source of each message formatted, with its statistics,
+    /// ordered by the total time spent formatting it, most first.
+    pub messages: Vec<(String, MessageStats)>,
+}
+
+impl ProfileReport {
+    /// The messages missing an argument at least once, ordered by how
+    /// often they were.
+    pub fn broken(&self) -> Vec<(&str, &MessageStats)> {
+        let mut broken: Vec<(&str, &MessageStats)> = self
+            .messages
+            .iter()
+            .filter(|(_, stats)| stats.argument_misses > 0 || stats.errors > 0)
+            .map(|(source, stats)| (source.as_str(), stats))
+            .collect();
+        broken.sort_by(|a, b| {
+            b.1.argument_miss_rate()
+                .partial_cmp(&a.1.argument_miss_rate())
+                .unwrap_or(::std::cmp::Ordering::Equal)
+        });
+        broken
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (source, stats) in &self.messages {
+            writeln!(
+                f,
+                "{:?}: {} formats, {:?} total, {:?} mean, {:?} max, {:.1}% missing arguments, {} errors",
+                source,
+                stats.count,
+                stats.total_time,
+                stats.mean_time(),
+                stats.max_time,
+                stats.argument_miss_rate() * 100.0,
+                stats.errors,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{MessageStats, Profiler};
+    use {arg, icu, Context, EmptyArgs};
+
+    #[test]
+    fn records_per_message() {
+        let profiler = Arc::new(Profiler::new());
+        let ctx = Context::default().with_profiler(profiler.clone());
+        let greeting = icu::parse("Hi {name}").unwrap();
+        let files = icu::parse("{n, plural, one {# file} other {# files}}").unwrap();
+
+        ctx.format(&greeting, &arg("name", "Ann"));
+        ctx.format(&files, &arg("n", 1));
+        ctx.format(&files, &arg("n", 2));
+        ctx.format(&files, &EmptyArgs);
+
+        let report = profiler.report();
+        assert_eq!(report.messages.len(), 2);
+        let files_stats = &report.messages.iter().find(|(s, _)| s.starts_with("{n")).unwrap().1;
+        assert_eq!(files_stats.count, 3);
+        assert_eq!(files_stats.argument_misses, 1);
+        assert!(files_stats.max_time <= files_stats.total_time);
+
+        let broken = report.broken();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].0, "{n, plural, one {# file} other {# files}}");
+        assert_eq!(report.to_string().lines().count(), 2);
+
+        profiler.reset();
+        assert!(profiler.report().messages.is_empty());
+    }
+
+    #[test]
+    fn combines_messages_with_the_same_source() {
+        let profiler = Arc::new(Profiler::new());
+        let ctx = Context::default().with_profiler(profiler.clone());
+        let a = icu::parse("Hi {name}").unwrap();
+        let b = icu::parse("Hi {name}").unwrap();
+        ctx.format(&a, &arg("name", "Ann"));
+        ctx.format(&b, &EmptyArgs);
+
+        let report = profiler.report();
+        assert_eq!(report.messages.len(), 1);
+        assert_eq!((report.messages[0].1.count, report.messages[0].1.argument_misses), (2, 1));
+    }
+
+    #[test]
+    fn messages_dropped_in_turn_are_told_apart() {
+        let profiler = Arc::new(Profiler::new());
+        let ctx = Context::default().with_profiler(profiler.clone());
+        for source in &["Hello {a}", "Bye {b}", "Third {c}"] {
+            let m = icu::parse(source).unwrap();
+            ctx.format(&m, &arg("a", 1));
+        }
+
+        let report = profiler.report();
+        let mut counts: Vec<(&str, u64, u64)> = report
+            .messages
+            .iter()
+            .map(|(source, stats)| (source.as_str(), stats.count, stats.argument_misses))
+            .collect();
+        counts.sort();
+        assert_eq!(counts, [("Bye {b}", 1, 1), ("Hello {a}", 1, 0), ("Third {c}", 1, 1)]);
+    }
+
+    #[test]
+    fn mean_time_keeps_the_remainder() {
+        let stats = MessageStats {
+            count: 3,
+            total_time: Duration::new(10, 1),
+            ..MessageStats::default()
+        };
+        assert_eq!(stats.mean_time(), Duration::new(3, 333_333_333));
+        let stats = MessageStats {
+            count: u64::from(u32::MAX) + 1,
+            total_time: Duration::from_secs(u64::from(u32::MAX) + 1),
+            ..MessageStats::default()
+        };
+        assert_eq!(stats.mean_time(), Duration::from_secs(1));
+    }
+}
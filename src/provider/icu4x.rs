@@ -0,0 +1,194 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use fixed_decimal::FixedDecimal;
+use icu_calendar::{DateTime as IcuDateTime, Gregorian as IcuGregorian};
+use icu_datetime::options::length;
+use icu_datetime::{TimeFormatter, TypedDateFormatter};
+use icu_decimal::FixedDecimalFormatter;
+use icu_locid::Locale;
+use icu_plurals::{PluralCategory as IcuPluralCategory, PluralOperands as IcuPluralOperands};
+use language_tags::LanguageTag;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use super::{DataProvider, DataRef};
+use datetime::{Calendar, DateTime, DateTimeData, DateTimeKind, DateTimeStyle};
+use number::NumberSymbols;
+use plural::{PluralOperands, PluralRuleSet};
+use PluralCategory;
+
+/// Locale data from the compiled data of the [icu4x] crates.
+///
+/// Plural rules come from `icu_plurals`, the digits, separators and
+/// grouping of numbers from `icu_decimal`, and dates and times in the
+/// Gregorian calendar from `icu_datetime`. Skeletons, other calendars
+/// and times with a zone, as well as percent, compact and unit
+/// patterns, still use the data compiled into this crate.
+///
+/// The icu4x formatters for a language are created when first needed
+/// and then kept.
+///
+/// [icu4x]: https://github.com/unicode-org/icu4x
+#[derive(Debug, Default)]
+pub struct Icu4xDataProvider {
+    plural_rules: Cache<Icu4xPluralRules>,
+    decimal_formatters: Cache<FixedDecimalFormatter>,
+    date_formatters: Cache<TypedDateFormatter<IcuGregorian>, (String, usize)>,
+    time_formatters: Cache<TimeFormatter, (String, usize)>,
+}
+
+// Values created for each language, or `None` for languages icu4x
+// has no data for.
+type Cache<T, K = String> = Mutex<HashMap<K, Option<Arc<T>>>>;
+
+fn cached<T, K, F>(cache: &Cache<T, K>, key: K, create: F) -> Option<Arc<T>>
+where
+    K: Eq + Hash,
+    F: FnOnce() -> Option<T>,
+{
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.entry(key).or_insert_with(|| create().map(Arc::new)).clone()
+}
+
+fn locale(language_tag: &LanguageTag) -> Option<Locale> {
+    language_tag.to_string().parse().ok()
+}
+
+#[derive(Debug)]
+struct Icu4xPluralRules(icu_plurals::PluralRules);
+
+impl PluralRuleSet for Icu4xPluralRules {
+    fn select(&self, operands: &PluralOperands) -> PluralCategory {
+        let mut number = operands.i.to_string();
+        if operands.v > 0 {
+            number = format!("{}.{:0width$}", number, operands.f, width = operands.v);
+        }
+        let operands: IcuPluralOperands = match number.parse() {
+            Ok(operands) => operands,
+            Err(_) => return PluralCategory::Other,
+        };
+        match self.0.category_for(operands) {
+            IcuPluralCategory::Zero => PluralCategory::Zero,
+            IcuPluralCategory::One => PluralCategory::One,
+            IcuPluralCategory::Two => PluralCategory::Two,
+            IcuPluralCategory::Few => PluralCategory::Few,
+            IcuPluralCategory::Many => PluralCategory::Many,
+            IcuPluralCategory::Other => PluralCategory::Other,
+        }
+    }
+}
+
+impl Icu4xDataProvider {
+    /// Construct a provider using the icu4x compiled data.
+    pub fn new() -> Self {
+        Icu4xDataProvider::default()
+    }
+}
+
+impl DataProvider for Icu4xDataProvider {
+    fn datetime_data(&self, _language_tag: &LanguageTag) -> Option<DataRef<DateTimeData>> {
+        None
+    }
+
+    fn number_symbols(&self, _language_tag: &LanguageTag) -> Option<DataRef<NumberSymbols>> {
+        None
+    }
+
+    fn plural_rules(&self, language_tag: &LanguageTag) -> Option<Arc<dyn PluralRuleSet>> {
+        let rules = cached(&self.plural_rules, language_tag.to_string(), || {
+            let rules = icu_plurals::PluralRules::try_new_cardinal(&(&locale(language_tag)?).into()).ok()?;
+            Some(Icu4xPluralRules(rules))
+        })?;
+        Some(rules)
+    }
+
+    fn write_digits(&self, language_tag: &LanguageTag, digits: &str, stream: &mut dyn fmt::Write) -> Option<fmt::Result> {
+        let formatter = cached(&self.decimal_formatters, language_tag.to_string(), || {
+            FixedDecimalFormatter::try_new(&(&locale(language_tag)?).into(), Default::default()).ok()
+        })?;
+        let value: FixedDecimal = digits.parse().ok()?;
+        Some(write!(stream, "{}", formatter.format(&value)))
+    }
+
+    fn format_datetime(
+        &self,
+        language_tag: &LanguageTag,
+        calendar: &dyn Calendar,
+        value: &DateTime,
+        kind: DateTimeKind,
+        style: &DateTimeStyle,
+        stream: &mut dyn fmt::Write,
+    ) -> Option<fmt::Result> {
+        if calendar.identifier() != "gregory" {
+            return None;
+        }
+        let value = IcuDateTime::try_new_gregorian_datetime(
+            value.year,
+            value.month,
+            value.day,
+            value.hour,
+            value.minute,
+            value.second,
+        )
+        .ok()?;
+        let key = language_tag.to_string();
+        match kind {
+            DateTimeKind::Date => {
+                let (index, length) = match *style {
+                    DateTimeStyle::Short => (0, length::Date::Short),
+                    DateTimeStyle::Medium => (1, length::Date::Medium),
+                    DateTimeStyle::Long => (2, length::Date::Long),
+                    DateTimeStyle::Full => (3, length::Date::Full),
+                    DateTimeStyle::Skeleton(_) | DateTimeStyle::Pattern(_) => return None,
+                };
+                let formatter = cached(&self.date_formatters, (key, index), || {
+                    TypedDateFormatter::try_new_with_length(&(&locale(language_tag)?).into(), length).ok()
+                })?;
+                Some(write!(stream, "{}", formatter.format(&value)))
+            }
+            DateTimeKind::Time => {
+                // The longer styles show the zone, which isn't supported.
+                let (index, length) = match *style {
+                    DateTimeStyle::Short => (0, length::Time::Short),
+                    DateTimeStyle::Medium => (1, length::Time::Medium),
+                    _ => return None,
+                };
+                let formatter = cached(&self.time_formatters, (key, index), || {
+                    TimeFormatter::try_new_with_length(&(&locale(language_tag)?).into(), length).ok()
+                })?;
+                Some(write!(stream, "{}", formatter.format(&value)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::parse;
+    use {arg, Context, DateTime};
+
+    #[test]
+    fn formats_with_icu4x() {
+        let ctx = Context::new("pl".parse().unwrap(), None);
+        let m = parse("{n, number} {n, plural, one {plik} few {pliki} many {plików} other {pliku}}").unwrap();
+        assert_eq!(ctx.format(&m, &arg("n", 3)), "3 pliki");
+        assert_eq!(ctx.format(&m, &arg("n", 25)), "25 plików");
+        assert_eq!(ctx.format(&m, &arg("n", 12345.5)), "12\u{a0}345,5 pliku");
+
+        let ctx = Context::new("en-US".parse().unwrap(), None);
+        let m = parse("{d, date, long} {d, time, short} {n, number}").unwrap();
+        let d = DateTime {
+            hour: 14,
+            minute: 5,
+            ..DateTime::new(2024, 5, 3)
+        };
+        let args = arg("d", d);
+        assert_eq!(ctx.format(&m, &args.arg("n", -1234.5)), "May 3, 2024 2:05\u{202f}PM -1,234.5");
+    }
+}
@@ -0,0 +1,353 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::{DataProvider, DataRef};
+use catalog::json::{parse_object, Value};
+use datetime::DateTimeData;
+use number::NumberSymbols;
+use plural::{PluralRuleSet, PluralRuleSyntax};
+use PluralCategory;
+
+/// An error from loading a locale data file.
+#[derive(Debug)]
+pub enum DataError {
+    /// The file couldn't be read.
+    Io(PathBuf, io::Error),
+    /// The file is malformed at a line, numbered from 1.
+    Syntax(PathBuf, usize),
+    /// A field is missing or has the wrong type, given by its path
+    /// such as `dates.months`.
+    InvalidField(PathBuf, String),
+}
+
+impl Error for DataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DataError::Io(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            DataError::Syntax(path, line) => write!(f, "{}:{}: malformed data file", path.display(), line),
+            DataError::InvalidField(path, field) => {
+                write!(f, "{}: missing or invalid field `{}`", path.display(), field)
+            }
+        }
+    }
+}
+
+/// Locale data loaded on demand from JSON files in a directory.
+///
+/// The data for a language is read from the file named after its
+/// language tag, such as `pt-BR.json`, or else after its language,
+/// such as `pt.json`. Each section is optional:
+///
+/// ```text
+/// {
+///   "numbers": {
+///     "decimal": ",", "group": ".", "minus": "-",
+///     "percentPattern": "# %", "permillePattern": "# ‰"
+///   },
+///   "plurals": { "one": "i = 1 and v = 0" },
+///   "dates": {
+///     "months": ["janeiro", ...], "monthsAbbr": ["jan.", ...],
+///     "weekdays": ["domingo", ...], "weekdaysAbbr": ["dom.", ...],
+///     "amPm": ["AM", "PM"],
+///     "datePatterns": ["dd/MM/y", "d 'de' MMM 'de' y", ...],
+///     "timePatterns": ["HH:mm", "HH:mm:ss", ...],
+///     "gmtFormat": "GMT{0}", "gmtZero": "GMT",
+///     "skeletons": { "yMd": "dd/MM/y" }
+///   }
+/// }
+/// ```
+///
+/// The currency pattern, where `¤` is the currency symbol, is `¤#` if
+/// not given. Plural rules use the [CLDR plural rule syntax]. Files are read when
+/// first needed, and their data is kept by the provider.
+/// Files that are missing or malformed provide no data; use
+/// [`load`] to see why.
+///
+/// [CLDR plural rule syntax]: ../plural/struct.PluralRuleSyntax.html
+/// [`load`]: #method.load
+#[derive(Debug)]
+pub struct JsonDataProvider {
+    dir: PathBuf,
+    // The data of each file, by file stem, or `None` if it failed to load.
+    loaded: Mutex<HashMap<String, Option<Arc<FileData>>>>,
+}
+
+#[derive(Debug, Default)]
+struct FileData {
+    datetime: Option<Arc<DateTimeData>>,
+    numbers: Option<Arc<NumberSymbols>>,
+    plurals: Option<Arc<PluralRuleSyntax>>,
+}
+
+impl JsonDataProvider {
+    /// Construct a provider for the files in `dir`.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        JsonDataProvider {
+            dir: dir.into(),
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load the file for a language now, reporting why it can't be
+    /// used. A missing file is an `Io` error.
+    pub fn load(&self, language_tag: &LanguageTag) -> Result<(), DataError> {
+        let stem = language_tag.to_string();
+        let result = load_file(&self.dir.join(format!("{}.json", stem)));
+        let data = result.as_ref().ok().cloned();
+        self.loaded.lock().unwrap_or_else(|e| e.into_inner()).insert(stem, data);
+        result.map(|_| ())
+    }
+
+    fn data(&self, language_tag: &LanguageTag) -> Option<Arc<FileData>> {
+        let mut stems = vec![language_tag.to_string()];
+        if let Some(ref language) = language_tag.language {
+            if *language != stems[0] {
+                stems.push(language.clone());
+            }
+        }
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        stems.into_iter().find_map(|stem| {
+            loaded
+                .entry(stem)
+                .or_insert_with_key(|stem| load_file(&self.dir.join(format!("{}.json", stem))).ok())
+                .clone()
+        })
+    }
+}
+
+impl DataProvider for JsonDataProvider {
+    fn datetime_data(&self, language_tag: &LanguageTag) -> Option<DataRef<DateTimeData>> {
+        let data = self.data(language_tag)?.datetime.clone()?;
+        Some(DataRef::Shared(data))
+    }
+
+    fn number_symbols(&self, language_tag: &LanguageTag) -> Option<DataRef<NumberSymbols>> {
+        let symbols = self.data(language_tag)?.numbers.clone()?;
+        Some(DataRef::Shared(symbols))
+    }
+
+    fn plural_rules(&self, language_tag: &LanguageTag) -> Option<Arc<dyn PluralRuleSet>> {
+        let rules = self.data(language_tag)?.plurals.clone()?;
+        Some(rules)
+    }
+}
+
+fn load_file(path: &Path) -> Result<Arc<FileData>, DataError> {
+    let source = fs::read_to_string(path).map_err(|e| DataError::Io(path.to_path_buf(), e))?;
+    let root = parse_object(&source).map_err(|line| DataError::Syntax(path.to_path_buf(), line))?;
+    let language = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+    let fields = Fields { path, prefix: "" };
+    let mut data = FileData::default();
+    if let Some(numbers) = fields.object(&root, "numbers")? {
+        data.numbers = Some(Arc::new(number_symbols(language, &fields.nested("numbers"), numbers)?));
+    }
+    if let Some(dates) = fields.object(&root, "dates")? {
+        data.datetime = Some(Arc::new(datetime_data(language, &fields.nested("dates"), dates)?));
+    }
+    if let Some(plurals) = fields.object(&root, "plurals")? {
+        data.plurals = Some(Arc::new(plural_rules(&fields.nested("plurals"), plurals)?));
+    }
+    Ok(Arc::new(data))
+}
+
+type Members = [(String, usize, Value)];
+
+// Reads fields of an object, naming them in errors by their path.
+struct Fields<'p> {
+    path: &'p Path,
+    prefix: &'p str,
+}
+
+impl<'p> Fields<'p> {
+    fn nested(&self, name: &'p str) -> Fields<'p> {
+        Fields {
+            path: self.path,
+            prefix: name,
+        }
+    }
+
+    fn invalid(&self, name: &str) -> DataError {
+        let field = if self.prefix.is_empty() || name.is_empty() {
+            format!("{}{}", self.prefix, name)
+        } else {
+            format!("{}.{}", self.prefix, name)
+        };
+        DataError::InvalidField(self.path.to_path_buf(), field)
+    }
+
+    fn get<'m>(&self, members: &'m Members, name: &str) -> Option<&'m Value> {
+        members.iter().find(|(n, _, _)| n == name).map(|(_, _, value)| value)
+    }
+
+    fn object<'m>(&self, members: &'m Members, name: &str) -> Result<Option<&'m Members>, DataError> {
+        match self.get(members, name) {
+            None => Ok(None),
+            Some(Value::Object(members)) => Ok(Some(members)),
+            Some(_) => Err(self.invalid(name)),
+        }
+    }
+
+    fn string(&self, members: &Members, name: &str) -> Result<Cow<'static, str>, DataError> {
+        match self.get(members, name) {
+            Some(Value::String(s)) => Ok(Cow::Owned(s.clone())),
+            _ => Err(self.invalid(name)),
+        }
+    }
+
+    fn strings<const N: usize>(&self, members: &Members, name: &str) -> Result<[Cow<'static, str>; N], DataError> {
+        let strings = match self.get(members, name) {
+            Some(Value::Array(values)) if values.len() == N => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(s) => Some(Cow::Owned(s.clone())),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        };
+        strings
+            .and_then(|strings| strings.try_into().ok())
+            .ok_or_else(|| self.invalid(name))
+    }
+}
+
+fn number_symbols(language: &str, fields: &Fields, members: &Members) -> Result<NumberSymbols, DataError> {
+    Ok(NumberSymbols {
+        language: Cow::Owned(language.to_string()),
+        decimal: fields.string(members, "decimal")?,
+        group: fields.string(members, "group")?,
+        minus: fields.string(members, "minus")?,
+        percent_pattern: fields.string(members, "percentPattern")?,
+        permille_pattern: fields.string(members, "permillePattern")?,
+        currency_pattern: match fields.get(members, "currencyPattern") {
+            Some(_) => fields.string(members, "currencyPattern")?,
+            None => Cow::Borrowed("¤#"),
+        },
+        compact_short: &[],
+        compact_long: &[],
+    })
+}
+
+fn datetime_data(language: &str, fields: &Fields, members: &Members) -> Result<DateTimeData, DataError> {
+    let skeletons = match fields.object(members, "skeletons")? {
+        Some(skeletons) => {
+            let fields = fields.nested("dates.skeletons");
+            let skeletons = skeletons
+                .iter()
+                .map(|(name, _, _)| Ok((Cow::Owned(name.clone()), fields.string(skeletons, name)?)))
+                .collect::<Result<Vec<_>, DataError>>()?;
+            Cow::Owned(skeletons)
+        }
+        None => Cow::Borrowed(&[][..]),
+    };
+    Ok(DateTimeData {
+        language: Cow::Owned(language.to_string()),
+        months: fields.strings(members, "months")?,
+        months_abbr: fields.strings(members, "monthsAbbr")?,
+        weekdays: fields.strings(members, "weekdays")?,
+        weekdays_abbr: fields.strings(members, "weekdaysAbbr")?,
+        am_pm: fields.strings(members, "amPm")?,
+        date_patterns: fields.strings(members, "datePatterns")?,
+        time_patterns: fields.strings(members, "timePatterns")?,
+        gmt_format: fields.string(members, "gmtFormat")?,
+        gmt_zero: fields.string(members, "gmtZero")?,
+        skeletons,
+    })
+}
+
+fn plural_rules(fields: &Fields, members: &Members) -> Result<PluralRuleSyntax, DataError> {
+    let mut rules = vec![];
+    for (name, _, _) in members {
+        let category = match name.as_str() {
+            "zero" => PluralCategory::Zero,
+            "one" => PluralCategory::One,
+            "two" => PluralCategory::Two,
+            "few" => PluralCategory::Few,
+            "many" => PluralCategory::Many,
+            _ => return Err(fields.invalid(name)),
+        };
+        rules.push((category, fields.string(members, name)?));
+    }
+    let rules: Vec<_> = rules.iter().map(|(category, rule)| (*category, &rule[..])).collect();
+    PluralRuleSyntax::parse(&rules).ok_or_else(|| fields.invalid(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataError, DataRef, JsonDataProvider};
+    use std::env;
+    use std::fs;
+    use std::sync::Arc;
+
+    use icu::parse;
+    use {arg, Context, DateTime};
+
+    const DATA: &str = r##"{
+  "numbers": {
+    "decimal": ",", "group": "'", "minus": "-",
+    "percentPattern": "#%", "permillePattern": "#‰"
+  },
+  "plurals": { "one": "n = 1", "two": "n = 2" },
+  "dates": {
+    "months": ["jar", "feb", "mar", "apr", "maj", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+    "monthsAbbr": ["ja", "fe", "mr", "ap", "mj", "jn", "jl", "au", "se", "ok", "no", "de"],
+    "weekdays": ["ne", "po", "ut", "st", "ct", "pa", "so"],
+    "weekdaysAbbr": ["n", "p", "u", "s", "c", "p", "s"],
+    "amPm": ["am", "pm"],
+    "datePatterns": ["d.M.y", "d. MMM y", "d. MMMM y", "EEEE d. MMMM y"],
+    "timePatterns": ["H:mm", "H:mm:ss", "H:mm:ss z", "H:mm:ss zzzz"],
+    "gmtFormat": "GMT{0}", "gmtZero": "GMT"
+  }
+}"##;
+
+    #[test]
+    fn data_is_loaded_on_demand() {
+        let dir = env::temp_dir().join(format!("message-format-provider-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("xx.json"), DATA).unwrap();
+        fs::write(dir.join("yy.json"), r#"{"numbers": {"decimal": ","}}"#).unwrap();
+
+        let provider = JsonDataProvider::new(&dir);
+        let mut ctx = Context::new("xx-YY".parse().unwrap(), None);
+        ctx.data_provider = Some(Arc::new(provider));
+        let m = parse("{d, date, long}: {n, number} {n, plural, one {one} two {two} other {other}}").unwrap();
+        let d = DateTime::new(2024, 5, 3);
+        let args = arg("d", d);
+        assert_eq!(ctx.format(&m, &args.arg("n", 1234.5)), "3. maj 2024: 1'234,5 other");
+        assert_eq!(ctx.format(&m, &args.arg("n", 2)), "3. maj 2024: 2 two");
+        let data = ctx.datetime_data();
+        assert!(matches!(data, DataRef::Shared(_)));
+        assert_eq!(data.months[4], "maj");
+
+        let provider = JsonDataProvider::new(&dir);
+        match provider.load(&"yy".parse().unwrap()) {
+            Err(DataError::InvalidField(_, field)) => assert_eq!(field, "numbers.group"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(provider.load(&"zz".parse().unwrap()), Err(DataError::Io(..))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
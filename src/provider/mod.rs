@@ -0,0 +1,174 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sources of locale data.
+//!
+//! The data for formatting dates, numbers and plurals comes from the
+//! tables compiled into this crate, which only cover English unless
+//! the `locale-data` feature is enabled. A [`DataProvider`] set as the
+//! [`Context::data_provider`] is asked first, so binaries can leave
+//! those tables out and load the locales they need at runtime with a
+//! [`JsonDataProvider`].
+//!
+//! With the `icu4x` feature, an [`Icu4xDataProvider`] is the default
+//! provider, so that applications already using the icu4x crates
+//! don't carry two copies of the data.
+//!
+//! Where neither has data for a language, English data is used. Use
+//! [`Context::missing_locale_data`] to find the languages for which
+//! that happens.
+//!
+//! [`DataProvider`]: trait.DataProvider.html
+//! [`Context::data_provider`]: ../struct.Context.html#structfield.data_provider
+//! [`JsonDataProvider`]: struct.JsonDataProvider.html
+//! [`Icu4xDataProvider`]: struct.Icu4xDataProvider.html
+//! [`Context::missing_locale_data`]: ../struct.Context.html#method.missing_locale_data
+
+use language_tags::LanguageTag;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[cfg(feature = "icu4x")]
+mod icu4x;
+mod json;
+
+#[cfg(feature = "icu4x")]
+pub use self::icu4x::Icu4xDataProvider;
+pub use self::json::{DataError, JsonDataProvider};
+
+use datetime::{Calendar, DateTime, DateTimeData, DateTimeKind, DateTimeStyle};
+use number::NumberSymbols;
+use plural::{PluralRuleSet, PluralRuleType, PluralRules};
+
+/// A source of locale data. Each method returns `None` when the
+/// provider has no data for a language, so that other data is used.
+pub trait DataProvider: Send + Sync {
+    /// The data for formatting dates and times.
+    fn datetime_data(&self, language_tag: &LanguageTag) -> Option<DataRef<DateTimeData>>;
+
+    /// The symbols for formatting numbers.
+    fn number_symbols(&self, language_tag: &LanguageTag) -> Option<DataRef<NumberSymbols>>;
+
+    /// The cardinal plural rules.
+    fn plural_rules(&self, language_tag: &LanguageTag) -> Option<Arc<dyn PluralRuleSet>>;
+
+    /// Write a number that has already been rounded, given as ASCII
+    /// digits with an optional `-` and `.`, with the locale's digits,
+    /// separators and grouping. Providers which return `None` leave
+    /// this to the `number_symbols`.
+    fn write_digits(
+        &self,
+        _language_tag: &LanguageTag,
+        _digits: &str,
+        _stream: &mut dyn fmt::Write,
+    ) -> Option<fmt::Result> {
+        None
+    }
+
+    /// Format a date or time in full, or return `None` to format it
+    /// with the `datetime_data`.
+    fn format_datetime(
+        &self,
+        _language_tag: &LanguageTag,
+        _calendar: &dyn Calendar,
+        _value: &DateTime,
+        _kind: DateTimeKind,
+        _style: &DateTimeStyle,
+        _stream: &mut dyn fmt::Write,
+    ) -> Option<fmt::Result> {
+        None
+    }
+}
+
+/// Locale data from a [`DataProvider`], either compiled into this
+/// crate or loaded at runtime and shared by the provider.
+///
+/// [`DataProvider`]: trait.DataProvider.html
+#[derive(Debug)]
+pub enum DataRef<T: 'static> {
+    /// Data compiled into this crate.
+    Static(&'static T),
+    /// Data loaded at runtime.
+    Shared(Arc<T>),
+}
+
+impl<T> Clone for DataRef<T> {
+    fn clone(&self) -> Self {
+        match self {
+            DataRef::Static(data) => DataRef::Static(data),
+            DataRef::Shared(data) => DataRef::Shared(data.clone()),
+        }
+    }
+}
+
+impl<T> Deref for DataRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            DataRef::Static(data) => data,
+            DataRef::Shared(data) => data,
+        }
+    }
+}
+
+/// A kind of locale data, for reporting the data that is missing for a
+/// language. See [`Context::missing_locale_data`].
+///
+/// [`Context::missing_locale_data`]: ../struct.Context.html#method.missing_locale_data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LocaleData {
+    /// The symbols for formatting numbers, such as the separators and
+    /// the percent sign.
+    NumberSymbols,
+    /// The names and patterns for formatting dates and times.
+    DateTime,
+    /// The patterns for formatting measure units, such as `5 km`.
+    Units,
+}
+
+impl fmt::Debug for dyn DataProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("DataProvider")
+    }
+}
+
+/// The data compiled into this crate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StaticDataProvider;
+
+impl DataProvider for StaticDataProvider {
+    fn datetime_data(&self, language_tag: &LanguageTag) -> Option<DataRef<DateTimeData>> {
+        DateTimeData::find(language_tag).map(DataRef::Static)
+    }
+
+    fn number_symbols(&self, language_tag: &LanguageTag) -> Option<DataRef<NumberSymbols>> {
+        NumberSymbols::find(language_tag).map(DataRef::Static)
+    }
+
+    fn plural_rules(&self, language_tag: &LanguageTag) -> Option<Arc<dyn PluralRuleSet>> {
+        let rules = PluralRules::find(language_tag, PluralRuleType::Cardinal)?;
+        Some(Arc::new(rules))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataProvider, StaticDataProvider};
+
+    #[test]
+    fn static_data_does_not_fall_back() {
+        let provider = StaticDataProvider;
+        let en = "en-GB".parse().unwrap();
+        assert_eq!(provider.datetime_data(&en).unwrap().language, "en");
+        assert!(provider.plural_rules(&en).is_some());
+        let unknown = "tlh".parse().unwrap();
+        assert!(provider.datetime_data(&unknown).is_none());
+        assert!(provider.number_symbols(&unknown).is_none());
+        assert!(provider.plural_rules(&unknown).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use {Args, Context, Message};
+
+/// A formatted message together with the data it was formatted from,
+/// for structured logging.
+///
+/// Logging only the localized text loses the machine-readable data
+/// behind it. A record keeps the message id and the arguments
+/// alongside the text, so that they can be emitted as separate fields
+/// by whichever logging framework is in use. With the `tracing`
+/// feature, [`trace`] emits them as the fields of a `tracing` event.
+///
+/// ```
+/// use message_format::{arg, icu, Context, MessageRecord};
+///
+/// let m = icu::parse("{user} uploaded {n, plural, one {a file} other {# files}}.").unwrap();
+/// let args = arg("user", "ann");
+/// let record = MessageRecord::new(&Context::default(), "upload.done", &m, &args.arg("n", 3));
+///
+/// assert_eq!(record.to_string(), "ann uploaded 3 files.");
+/// let fields: Vec<(&str, &str)> = record.fields().collect();
+/// assert_eq!(fields, [
+///     ("message_id", "upload.done"),
+///     ("message", "ann uploaded 3 files."),
+///     ("user", "ann"),
+///     ("n", "3"),
+/// ]);
+/// ```
+///
+/// [`trace`]: #method.trace
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageRecord {
+    /// The id of the message, such as its key in a bundle.
+    pub id: String,
+    /// The formatted message.
+    pub text: String,
+    /// The name and value of each argument used by the message, in the
+    /// order they appear. Missing arguments are left out.
+    pub args: Vec<(String, String)>,
+}
+
+impl MessageRecord {
+    /// Format `message` with `ctx` and record its arguments.
+    pub fn new<'f>(ctx: &Context, id: &str, message: &Message, args: &'f dyn Args<'f>) -> Self {
+        let recorded = message
+            .argument_names()
+            .into_iter()
+            .filter_map(|name| args.get(name).map(|value| (name.to_string(), value.to_string())))
+            .collect();
+        MessageRecord {
+            id: id.to_string(),
+            text: ctx.format(message, args),
+            args: recorded,
+        }
+    }
+
+    /// The record as `(name, value)` fields: `message_id`, `message`
+    /// and then the arguments.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        let head = vec![("message_id", self.id.as_str()), ("message", self.text.as_str())];
+        head.into_iter()
+            .chain(self.args.iter().map(|(name, value)| (name.as_str(), value.as_str())))
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl MessageRecord {
+    /// Format `message` in a `format_message` span and emit the record
+    /// as an event at `level`, with the feature `tracing`.
+    ///
+    /// The event's `message` is the formatted text, and it has the
+    /// `message_id` and the `args` as fields, so that subscribers which
+    /// write structured logs keep them apart.
+    ///
+    /// ```
+    /// use message_format::{arg, icu, Context, MessageRecord};
+    /// # extern crate message_format;
+    /// # extern crate tracing;
+    /// use tracing::Level;
+    ///
+    /// let m = icu::parse("{user} logged in.").unwrap();
+    /// let record = MessageRecord::trace(Level::INFO, &Context::default(), "login", &m, &arg("user", "ann"));
+    /// assert_eq!(record.text, "ann logged in.");
+    /// ```
+    pub fn trace<'f>(
+        level: tracing::Level,
+        ctx: &Context,
+        id: &str,
+        message: &Message,
+        args: &'f dyn Args<'f>,
+    ) -> Self {
+        let span = tracing::debug_span!("format_message", message_id = id);
+        let record = span.in_scope(|| MessageRecord::new(ctx, id, message, args));
+        record.emit(level);
+        record
+    }
+
+    /// Emit the record as a `tracing` event at `level`.
+    pub fn emit(&self, level: tracing::Level) {
+        // Event levels have to be constants.
+        macro_rules! emit {
+            ($($level:ident),*) => {
+                match level {
+                    $(tracing::Level::$level => tracing::event!(
+                        tracing::Level::$level,
+                        message_id = %self.id,
+                        args = ?RecordArgs(&self.args),
+                        "{}",
+                        self.text
+                    ),)*
+                }
+            };
+        }
+        emit!(TRACE, DEBUG, INFO, WARN, ERROR)
+    }
+}
+
+// The arguments of a record, written as a map.
+#[cfg(feature = "tracing")]
+struct RecordArgs<'a>(&'a [(String, String)]);
+
+#[cfg(feature = "tracing")]
+impl<'a> fmt::Debug for RecordArgs<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.0.iter().map(|(name, value)| (name, value))).finish()
+    }
+}
+
+impl fmt::Display for MessageRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    use super::MessageRecord;
+    use icu::parse;
+    use {arg, Context};
+
+    // Records the fields of spans and events as `name=value` lines.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes) -> Id {
+            let mut fields = Fields(vec![]);
+            span.record(&mut fields);
+            let line = format!("span {} {}", span.metadata().name(), fields.0.join(" "));
+            self.0.lock().unwrap().push(line);
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut fields = Fields(vec![]);
+            event.record(&mut fields);
+            let line = format!("{} {}", event.metadata().level(), fields.0.join(" "));
+            self.0.lock().unwrap().push(line);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn records_are_traced_with_fields() {
+        let recorder = Recorder::default();
+        let m = parse("{user} uploaded {n, number} files.").unwrap();
+        let args = arg("user", "ann");
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let ctx = Context::default();
+            MessageRecord::trace(Level::WARN, &ctx, "upload.done", &m, &args.arg("n", 3));
+        });
+        let lines = recorder.0.lock().unwrap();
+        assert_eq!(
+            *lines,
+            [
+                "span format_message message_id=upload.done",
+                "WARN message=ann uploaded 3 files. message_id=upload.done args={\"user\": \"ann\", \"n\": \"3\"}",
+            ]
+        );
+    }
+}
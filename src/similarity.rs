@@ -0,0 +1,108 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use icu::ast;
+use Message;
+
+// Stands for an argument in the text compared, so that an argument
+// counts as one character, whatever its name.
+const ARGUMENT: char = '\u{fffc}';
+
+// How much the text counts towards the similarity, with the rest
+// coming from the arguments.
+const TEXT_WEIGHT: f32 = 0.75;
+
+// The text of a message, including the text of every branch, with
+// each argument replaced by `ARGUMENT`.
+fn collect_text(message: &Message, text: &mut Vec<char>) {
+    message.walk(&mut |part| {
+        if let Some(plain) = part.as_any().downcast_ref::<ast::PlainText>() {
+            text.extend(plain.text.chars());
+        } else if part.children().is_empty() {
+            text.push(ARGUMENT);
+        }
+    });
+}
+
+// The number of characters to insert, delete or replace to turn `a`
+// into `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + (ca != cb) as usize;
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        ::std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// How similar two messages are, from `0.0` for nothing in common to
+/// `1.0` for the same text and arguments.
+///
+/// This combines the edit distance between the text of the messages,
+/// where each argument counts as one character, with how many of
+/// their arguments they share, so that tools can suggest reusing the
+/// translation of a similar message.
+///
+/// ```
+/// use message_format::{icu, similarity};
+///
+/// let a = icu::parse("You have {count} new messages").unwrap();
+/// let b = icu::parse("You have {count} new message").unwrap();
+/// let c = icu::parse("You have {n} new messages").unwrap();
+/// let d = icu::parse("Settings").unwrap();
+///
+/// assert_eq!(similarity(&a, &a), 1.0);
+/// assert!(similarity(&a, &b) > 0.95);
+/// assert!(similarity(&a, &c) < similarity(&a, &b));
+/// assert!(similarity(&a, &d) < 0.2);
+/// ```
+pub fn similarity(a: &Message, b: &Message) -> f32 {
+    let (mut text_a, mut text_b) = (vec![], vec![]);
+    collect_text(a, &mut text_a);
+    collect_text(b, &mut text_b);
+    let longest = text_a.len().max(text_b.len());
+    let text = match longest {
+        0 => 1.0,
+        _ => 1.0 - edit_distance(&text_a, &text_b) as f32 / longest as f32,
+    };
+
+    let names_a = a.argument_names();
+    let names_b = b.argument_names();
+    let shared = names_a.iter().filter(|name| names_b.contains(name)).count();
+    let all = names_a.len() + names_b.len() - shared;
+    let arguments = match all {
+        0 => 1.0,
+        _ => shared as f32 / all as f32,
+    };
+
+    TEXT_WEIGHT * text + (1.0 - TEXT_WEIGHT) * arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, similarity};
+    use icu;
+
+    #[test]
+    fn branches_and_arguments_count() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+
+        let a = icu::parse("{n, plural, one {# file} other {# files}}").unwrap();
+        let b = icu::parse("{n, plural, one {# file} other {# files}}").unwrap();
+        let c = icu::parse("{n, plural, one {# folder} other {# folders}}").unwrap();
+        let d = icu::parse("{k, plural, one {# file} other {# files}}").unwrap();
+        assert_eq!(similarity(&a, &b), 1.0);
+        assert!(similarity(&a, &c) > 0.5 && similarity(&a, &c) < 1.0);
+        assert_eq!(similarity(&a, &d), 0.75);
+    }
+}
@@ -0,0 +1,195 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Golden file tests of messages across languages.
+//!
+//! [`render_matrix`] formats a message in each of a list of languages
+//! with each of a list of arguments, and [`assert_snapshot`] compares
+//! the result with a file checked in alongside the tests. The
+//! [`assert_message_matrix!`] macro does both, so that a message is
+//! covered in every language with one line:
+//!
+//! ```
+//! # #[macro_use] extern crate message_format;
+//! # fn main() {
+//! use std::sync::Arc;
+//! use message_format::{arg, BundleSet, MessageBundle};
+//!
+//! let en = MessageBundle::parse_entries("en".parse().unwrap(), vec![
+//!     ("files", "{n, plural, one {# file} other {# files}}"),
+//! ]).unwrap();
+//! let bundles = Arc::new(BundleSet::new(en));
+//! # let path = std::env::temp_dir().join(format!("message-format-files-{}.snap", std::process::id()));
+//! # std::fs::write(&path, "[en]\nn=1: \"1 file\"\nn=2: \"2 files\"\n").unwrap();
+//!
+//! assert_message_matrix!(bundles, "files", [arg("n", 1), arg("n", 2)], ["en"], &path);
+//! # std::fs::remove_file(&path).unwrap();
+//! # }
+//! ```
+//!
+//! A golden file which doesn't exist yet is written rather than
+//! compared, and setting the `UPDATE_SNAPSHOTS` environment variable
+//! rewrites all of them, after which the changes can be reviewed with
+//! the rest of the code.
+//!
+//! [`render_matrix`]: fn.render_matrix.html
+//! [`assert_snapshot`]: fn.assert_snapshot.html
+//! [`assert_message_matrix!`]: ../macro.assert_message_matrix.html
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use {Args, BundleSet, Localizer};
+
+/// The environment variable which, when set, makes [`assert_snapshot`]
+/// rewrite golden files instead of comparing with them.
+///
+/// [`assert_snapshot`]: fn.assert_snapshot.html
+pub const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// Format the message `key` for each of `locales`, with each of
+/// `cases`, as text suitable for a golden file.
+///
+/// Each language is looked up as by a [`Localizer`], so a language
+/// without the message shows the one it falls back to, and a message
+/// found in no bundle, or failing to format, shows as its key in
+/// brackets. Each case is labelled with the values of the arguments
+/// the default bundle's message uses.
+///
+/// # Panics
+///
+/// If one of `locales` isn't a valid language tag.
+///
+/// [`Localizer`]: ../struct.Localizer.html
+pub fn render_matrix<'f, A: Args<'f>>(
+    bundles: &Arc<BundleSet>,
+    key: &str,
+    cases: &'f [A],
+    locales: &[&str],
+) -> String {
+    let names = match bundles.default_bundle().get(key) {
+        Some(message) => message.argument_names(),
+        None => vec![],
+    };
+    let mut output = String::new();
+    for locale in locales {
+        let language_tag = locale
+            .parse()
+            .unwrap_or_else(|_| panic!("{:?} is not a language tag", locale));
+        let localizer = Localizer::new(bundles.clone(), &[language_tag]).with_key_fallback(true);
+        let _ = writeln!(output, "[{}]", locale);
+        for (i, case) in cases.iter().enumerate() {
+            let label: Vec<String> = names
+                .iter()
+                .filter_map(|name| case.get(name).map(|value| format!("{}={}", name, value)))
+                .collect();
+            let label = if label.is_empty() {
+                format!("#{}", i)
+            } else {
+                label.join(" ")
+            };
+            let text = localizer.format(key, case).unwrap_or_default();
+            let _ = writeln!(output, "{}: {:?}", label, text);
+        }
+    }
+    output
+}
+
+/// Compare `actual` with the contents of the golden file at `path`.
+///
+/// The file is written instead when it doesn't exist, or when the
+/// [`UPDATE_VAR`] environment variable is set.
+///
+/// # Panics
+///
+/// If the file differs from `actual`, or can't be read or written.
+///
+/// [`UPDATE_VAR`]: constant.UPDATE_VAR.html
+pub fn assert_snapshot<P: AsRef<Path>>(path: P, actual: &str) {
+    let path = path.as_ref();
+    if env::var_os(UPDATE_VAR).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+        }
+        fs::write(path, actual).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        return;
+    }
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    if expected != actual {
+        let mut diff = String::new();
+        let (old, new): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+        for i in 0..old.len().max(new.len()) {
+            match (old.get(i), new.get(i)) {
+                (Some(a), Some(b)) if a == b => {}
+                (a, b) => {
+                    if let Some(a) = a {
+                        let _ = writeln!(diff, "-{}", a);
+                    }
+                    if let Some(b) = b {
+                        let _ = writeln!(diff, "+{}", b);
+                    }
+                }
+            }
+        }
+        panic!(
+            "{} doesn't match the output; set {} to update it\n{}",
+            path.display(),
+            UPDATE_VAR,
+            diff
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::panic;
+    use std::sync::Arc;
+
+    use super::{assert_snapshot, render_matrix};
+    use {arg, BundleSet, MessageBundle};
+
+    #[test]
+    fn matrix_falls_back() {
+        let en = MessageBundle::parse_entries(
+            "en".parse().unwrap(),
+            vec![("files", "{n, plural, one {# file} other {# files}}"), ("hi", "Hi")],
+        )
+        .unwrap();
+        let fr = MessageBundle::parse_entries(
+            "fr".parse().unwrap(),
+            vec![("files", "{n, plural, one {# fichier} other {# fichiers}}")],
+        )
+        .unwrap();
+        let mut bundles = BundleSet::new(en);
+        bundles.insert(fr);
+        let bundles = Arc::new(bundles);
+
+        let cases = [arg("n", 1), arg("n", 2)];
+        assert_eq!(
+            render_matrix(&bundles, "files", &cases, &["en", "fr"]),
+            "[en]\nn=1: \"1 file\"\nn=2: \"2 files\"\n[fr]\nn=1: \"1 fichier\"\nn=2: \"2 fichiers\"\n"
+        );
+        assert_eq!(render_matrix(&bundles, "hi", &cases, &["fr"]), "[fr]\n#0: \"Hi\"\n#1: \"Hi\"\n");
+        assert_eq!(render_matrix(&bundles, "bye", &cases[..1], &["en"]), "[en]\n#0: \"⟦bye⟧\"\n");
+    }
+
+    #[test]
+    fn snapshots_are_written_then_compared() {
+        let dir = ::std::env::temp_dir().join(format!("message-format-snapshot-{}", ::std::process::id()));
+        let path = dir.join("nested").join("a.snap");
+        assert_snapshot(&path, "one\ntwo\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+        assert_snapshot(&path, "one\ntwo\n");
+        let error = panic::catch_unwind(|| assert_snapshot(&path, "one\n2\n")).unwrap_err();
+        let message = error.downcast_ref::<String>().unwrap();
+        assert!(message.ends_with("-two\n+2\n"), "{}", message);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::ptr;
+
+use icu::ast;
+use {Args, Context, Message, MessagePart, PluralCategory};
+
+/// The expected shape of the arguments a message is formatted with:
+/// the key each `select` is expected to match and the category each
+/// `plural` is expected to fall in.
+///
+/// See [`Message::specialize`].
+///
+/// [`Message::specialize`]: struct.Message.html#method.specialize
+#[derive(Clone, Debug, Default)]
+pub struct ArgsShape {
+    selects: Vec<(String, String)>,
+    plurals: Vec<(String, PluralCategory)>,
+}
+
+impl ArgsShape {
+    /// Construct an empty `ArgsShape`, which resolves no branches.
+    pub fn new() -> Self {
+        ArgsShape::default()
+    }
+
+    /// Expect the `select` over `variable_name` to match `key`.
+    pub fn select(mut self, variable_name: &str, key: &str) -> Self {
+        self.selects.push((variable_name.to_string(), key.to_string()));
+        self
+    }
+
+    /// Expect the `plural` over `variable_name` to be in `category`.
+    pub fn plural(mut self, variable_name: &str, category: PluralCategory) -> Self {
+        self.plurals.push((variable_name.to_string(), category));
+        self
+    }
+
+    fn select_key(&self, variable_name: &str) -> Option<&str> {
+        self.selects
+            .iter()
+            .find(|&(name, _)| name == variable_name)
+            .map(|(_, key)| key.as_str())
+    }
+
+    fn plural_category(&self, variable_name: &str) -> Option<PluralCategory> {
+        self.plurals
+            .iter()
+            .find(|&(name, _)| name == variable_name)
+            .map(|&(_, category)| category)
+    }
+}
+
+/// A [`Message`] with the branches expected by an [`ArgsShape`]
+/// resolved ahead of time and adjacent text merged.
+///
+/// Each resolved branch is guarded: when the arguments don't match the
+/// shape, the original format is used, so the output is always the same
+/// as formatting the `Message` itself.
+///
+/// [`Message`]: struct.Message.html
+/// [`ArgsShape`]: struct.ArgsShape.html
+#[derive(Debug)]
+pub struct SpecializedMessage<'m> {
+    segments: Vec<Segment<'m>>,
+}
+
+#[derive(Debug)]
+enum Segment<'m> {
+    Text(String),
+    Part(&'m dyn MessagePart),
+    Select {
+        format: &'m ast::SelectFormat,
+        branch: &'m Message,
+        segments: Vec<Segment<'m>>,
+    },
+    Plural {
+        format: &'m ast::PluralFormat,
+        branch: &'m Message,
+        segments: Vec<Segment<'m>>,
+    },
+}
+
+fn specialize_parts<'m>(message: &'m Message, shape: &ArgsShape) -> Vec<Segment<'m>> {
+    let mut segments = vec![];
+    for part in &message.parts {
+        let any = part.as_any();
+        let segment = if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+            if let Some(Segment::Text(last)) = segments.last_mut() {
+                last.push_str(&text.text);
+                continue;
+            }
+            Segment::Text(text.text.clone())
+        } else if let Some(format) = any.downcast_ref::<ast::SelectFormat>() {
+            match shape.select_key(&format.variable_name) {
+                Some(key) => {
+                    let branch = format.lookup_message(key);
+                    Segment::Select {
+                        format,
+                        branch,
+                        segments: specialize_parts(branch, shape),
+                    }
+                }
+                None => Segment::Part(part.as_ref()),
+            }
+        } else if let Some(format) = any.downcast_ref::<ast::PluralFormat>() {
+            match shape.plural_category(&format.variable_name) {
+                Some(category) => {
+                    let branch = format.category_message(category);
+                    Segment::Plural {
+                        format,
+                        branch,
+                        segments: specialize_parts(branch, shape),
+                    }
+                }
+                None => Segment::Part(part.as_ref()),
+            }
+        } else {
+            Segment::Part(part.as_ref())
+        };
+        segments.push(segment);
+    }
+    segments
+}
+
+fn write_segments<'f>(
+    segments: &[Segment],
+    ctx: &Context,
+    stream: &mut dyn fmt::Write,
+    args: &'f dyn Args<'f>,
+) -> fmt::Result {
+    for segment in segments {
+        match *segment {
+            Segment::Text(ref text) => stream.write_str(text)?,
+            Segment::Part(part) => part.apply_format(ctx, stream, args)?,
+            Segment::Select {
+                format,
+                branch,
+                ref segments,
+            } => match args.get(&format.variable_name) {
+                Some(value) if ptr::eq(format.select_message(ctx, &value.select_key()), branch) => {
+                    write_segments(segments, ctx, stream, args)?
+                }
+                _ => format.apply_format(ctx, stream, args)?,
+            },
+            Segment::Plural {
+                format,
+                branch,
+                ref segments,
+            } => match args.get(&format.variable_name).and_then(|v| format.resolve(ctx, v)) {
+                Some((message, ctx)) if ptr::eq(message, branch) => {
+                    write_segments(segments, &ctx, stream, args)?
+                }
+                _ => format.apply_format(ctx, stream, args)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+impl<'m> SpecializedMessage<'m> {
+    pub(crate) fn new(message: &'m Message, shape: &ArgsShape) -> Self {
+        SpecializedMessage {
+            segments: specialize_parts(message, shape),
+        }
+    }
+
+    /// Format the message using `ctx`, returning a string.
+    pub fn format_with<'f>(&self, ctx: &Context, args: &'f dyn Args<'f>) -> String {
+        let mut output = String::new();
+        let _ = self.write_with(ctx, &mut output, args);
+        output
+    }
+
+    /// Write the message to a stream using `ctx`.
+    pub fn write_with<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        write_segments(&self.segments, ctx, stream, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArgsShape;
+    use {arg, icu, Context, PluralCategory};
+
+    #[test]
+    fn matches_unspecialized_output() {
+        let m = icu::parse(
+            "Log: {gender, select, female {she saw {count, plural, =0 {nothing} \
+             one {# file} other {# files}}} other {they saw {count, plural, \
+             one {# file} other {# files}}}} in {dir}.",
+        ).unwrap();
+        let shape = ArgsShape::new()
+            .select("gender", "female")
+            .plural("count", PluralCategory::Other);
+        let specialized = m.specialize(&shape);
+        let ctx = Context::default();
+
+        for &gender in &["female", "male"] {
+            for count in 0..4 {
+                let gender = arg("gender", gender);
+                let count = gender.arg("count", count);
+                let args = count.arg("dir", "/tmp");
+                assert_eq!(specialized.format_with(&ctx, &args), m.format_with(&ctx, &args));
+            }
+        }
+        assert_eq!(
+            specialized.format_with(&ctx, &arg("gender", "female").arg("count", 3).arg("dir", "/")),
+            "Log: she saw 3 files in /."
+        );
+    }
+
+    #[test]
+    fn merges_text() {
+        let m = icu::parse("{gender, select, other {a}}{gender, select, other {b}}c").unwrap();
+        let specialized = m.specialize(&ArgsShape::new());
+        assert_eq!(specialized.segments.len(), 3);
+
+        let specialized = m.specialize(&ArgsShape::new().select("gender", "x"));
+        assert_eq!(specialized.format_with(&Context::default(), &arg("gender", "x")), "abc");
+    }
+}
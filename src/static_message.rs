@@ -0,0 +1,99 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use {Args, Context, MessagePart};
+
+/// A part of a [`StaticMessage`].
+///
+/// [`StaticMessage`]: struct.StaticMessage.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StaticPart {
+    /// Text that is output as is.
+    Text(&'static str),
+    /// The value of the named argument.
+    Arg(&'static str),
+}
+
+/// A message consisting of text and arguments which can be defined
+/// as `static` data without any allocation.
+///
+/// These are usually created with the `static_message!` and `arg!`
+/// macros and can be formatted like any other message.
+///
+/// ```
+/// #[macro_use]
+/// extern crate message_format;
+///
+/// use message_format::{Context, StaticMessage};
+///
+/// static GREETING: StaticMessage = static_message!["Hello ", arg!(name), "!"];
+///
+/// fn main() {
+///     let ctx = Context::default();
+///     assert_eq!(format_message!(ctx, &GREETING, name => "Ann"), "Hello Ann!");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StaticMessage {
+    /// The parts of the message.
+    pub parts: &'static [StaticPart],
+}
+
+impl StaticMessage {
+    /// Construct a message from constituent parts.
+    pub const fn new(parts: &'static [StaticPart]) -> Self {
+        StaticMessage { parts }
+    }
+}
+
+impl MessagePart for StaticMessage {
+    fn apply_format<'f>(
+        &self,
+        ctx: &Context,
+        stream: &mut dyn fmt::Write,
+        args: &'f dyn Args<'f>,
+    ) -> fmt::Result {
+        for part in self.parts {
+            match *part {
+                StaticPart::Text(text) => stream.write_str(text)?,
+                StaticPart::Arg(name) => match args.get(name) {
+                    Some(value) => ctx.write_isolated(stream, |stream| value.format(ctx, stream))?,
+                    None => return Err(fmt::Error {}),
+                },
+            }
+        }
+        Ok(())
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn eq_part(&self, other: &dyn MessagePart) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StaticMessage, StaticPart};
+    use Context;
+
+    static EMPTY: StaticMessage = static_message![];
+    static TWO_ARGS: StaticMessage = static_message![arg!(a), " and ", arg!(b)];
+
+    #[test]
+    fn it_works() {
+        let ctx = Context::default();
+
+        assert_eq!(format_message!(ctx, &EMPTY), "");
+        assert_eq!(format_message!(ctx, &TWO_ARGS, a => 1, b => "two"), "1 and two");
+        assert_eq!(
+            TWO_ARGS.parts,
+            &[StaticPart::Arg("a"), StaticPart::Text(" and "), StaticPart::Arg("b")]
+        );
+    }
+}
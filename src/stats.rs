@@ -0,0 +1,174 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{BTreeSet, HashMap};
+
+use icu::ast;
+use {Message, MessagePart};
+
+// The number of messages listed in `BundleStats::longest`.
+pub const LONGEST_MESSAGES: usize = 10;
+
+/// The size and shape of the messages of a bundle, for planning
+/// translation and caching. See [`MessageBundle::stats`].
+///
+/// Variants of messages are only counted in `variants`.
+///
+/// [`MessageBundle::stats`]: struct.MessageBundle.html#method.stats
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BundleStats {
+    /// The number of messages.
+    pub messages: usize,
+    /// The number of variants of messages.
+    pub variants: usize,
+    /// The number of top-level parts of the messages, after merging
+    /// adjacent text.
+    pub parts: usize,
+    /// The number of messages with a `plural` format, at any depth.
+    pub plural_messages: usize,
+    /// The number of messages with a `select` format, at any depth.
+    pub select_messages: usize,
+    /// The names of the arguments used by any message, sorted.
+    pub argument_names: Vec<String>,
+    /// The keys of the messages with the most text, with the number of
+    /// characters of text in all of their branches and the messages
+    /// they reference, longest first. At most 10 messages are listed.
+    pub longest: Vec<(String, usize)>,
+}
+
+impl BundleStats {
+    /// The average number of top-level parts per message.
+    pub fn average_parts(&self) -> f64 {
+        match self.messages {
+            0 => 0.0,
+            messages => self.parts as f64 / messages as f64,
+        }
+    }
+}
+
+// What a walk over a message found.
+#[derive(Default)]
+struct Shape {
+    text_len: usize,
+    plural: bool,
+    select: bool,
+}
+
+pub(crate) fn stats<'a, I>(messages: I, variants: usize) -> BundleStats
+where
+    I: IntoIterator<Item = (&'a String, &'a Message)>,
+{
+    let mut stats = BundleStats {
+        variants,
+        ..Default::default()
+    };
+    let messages: Vec<(&String, &Message)> = messages.into_iter().collect();
+    let by_key: HashMap<&str, &Message> = messages.iter().map(|&(key, message)| (key.as_str(), message)).collect();
+    let mut names = BTreeSet::new();
+    let mut lengths = vec![];
+    for (key, message) in messages {
+        let mut shape = Shape::default();
+        walk(message, &by_key, &mut vec![key.as_str()], &mut shape);
+        stats.messages += 1;
+        stats.parts += message.parts.len();
+        stats.plural_messages += shape.plural as usize;
+        stats.select_messages += shape.select as usize;
+        names.extend(message.argument_names());
+        lengths.push((key.clone(), shape.text_len));
+    }
+    lengths.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    lengths.truncate(LONGEST_MESSAGES);
+    stats.argument_names = names.into_iter().map(String::from).collect();
+    stats.longest = lengths;
+    stats
+}
+
+// Walk `message`, and the messages it references that aren't in
+// `resolving` already, which would be a cycle.
+fn walk<'a>(
+    message: &'a Message,
+    by_key: &HashMap<&str, &'a Message>,
+    resolving: &mut Vec<&'a str>,
+    shape: &mut Shape,
+) {
+    let mut references = vec![];
+    message.walk(&mut |part: &'a dyn MessagePart| {
+        let any = part.as_any();
+        if let Some(text) = any.downcast_ref::<ast::PlainText>() {
+            shape.text_len += text.text.chars().count();
+        } else if any.is::<ast::SelectFormat>() {
+            shape.select = true;
+        } else if any.is::<ast::PluralFormat>() {
+            shape.plural = true;
+        } else if let Some(reference) = any.downcast_ref::<ast::MessageReference>() {
+            references.push(reference.key.as_str());
+        }
+    });
+    for key in references {
+        if let Some(referenced) = by_key.get(key) {
+            if !resolving.contains(&key) {
+                resolving.push(key);
+                walk(referenced, by_key, resolving, shape);
+                resolving.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use icu::{parse, parse_with, ParseOptions};
+    use MessageBundle;
+
+    #[test]
+    fn nested_formats_and_variants_are_counted() {
+        let mut bundle = MessageBundle::new("en".parse().unwrap());
+        assert_eq!(bundle.stats().average_parts(), 0.0);
+        for i in 0..12 {
+            bundle.insert(format!("k{:02}", i), "x".repeat(i % 3).as_str().into());
+        }
+        let nested = "{n, plural, other {{g, select, a {ab} other {c}}}}";
+        bundle.insert("nested", parse(nested).unwrap());
+        bundle.insert_variant("nested", "tone", "casual", parse("c").unwrap());
+
+        let stats = bundle.stats();
+        assert_eq!((stats.messages, stats.variants, stats.parts), (13, 1, 9));
+        assert_eq!((stats.plural_messages, stats.select_messages), (1, 1));
+        assert_eq!(stats.argument_names, ["g", "n"]);
+        let longest: Vec<(&str, usize)> = stats.longest.iter().map(|(k, n)| (k.as_str(), *n)).collect();
+        assert_eq!(
+            longest,
+            [
+                ("nested", 3),
+                ("k02", 2),
+                ("k05", 2),
+                ("k08", 2),
+                ("k11", 2),
+                ("k01", 1),
+                ("k04", 1),
+                ("k07", 1),
+                ("k10", 1),
+                ("k00", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn referenced_text_is_counted() {
+        let options = ParseOptions {
+            lenient: false,
+            extensions: true,
+        };
+        let mut bundle = MessageBundle::new("en".parse().unwrap());
+        bundle.insert("footer", parse_with("Bye{@loop}", options).unwrap());
+        bundle.insert("loop", parse_with("!{@footer}", options).unwrap());
+        bundle.insert("page", parse_with("Hi {n, plural, other {{@footer}}}", options).unwrap());
+
+        let stats = bundle.stats();
+        let longest: Vec<(&str, usize)> = stats.longest.iter().map(|(k, n)| (k.as_str(), *n)).collect();
+        assert_eq!(longest, [("page", 7), ("footer", 4), ("loop", 4)]);
+    }
+}
@@ -0,0 +1,121 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `write_message!`, which writes to either a `fmt::Write`
+//! or an `io::Write` stream.
+//!
+//! A type can't implement one trait for both kinds of stream, so the
+//! macro relies on method resolution: `ViaFmt` is implemented for
+//! `Target` itself and is preferred, while `ViaIo` is implemented for
+//! `&mut Target` and is used when the stream isn't a `fmt::Write`.
+
+use std::fmt;
+use std::io;
+
+use width::pad_end;
+use {Args, Context, MessagePart};
+
+/// A stream being written to by `write_message!`.
+pub struct Target<'a, W: ?Sized + 'a>(pub &'a mut W);
+
+fn write_padded<'f>(
+    ctx: &Context,
+    message: &dyn MessagePart,
+    stream: &mut dyn fmt::Write,
+    args: &'f dyn Args<'f>,
+    width: Option<usize>,
+) -> fmt::Result {
+    match width {
+        None => ctx.write(message, stream, args),
+        Some(width) => {
+            let mut output = String::new();
+            ctx.write(message, &mut output, args)?;
+            stream.write_str(&pad_end(&output, width))
+        }
+    }
+}
+
+/// Writes to a `fmt::Write` stream.
+pub trait ViaFmt {
+    /// Write `message`, padded to `width` columns if given.
+    fn write_message_to<'f>(
+        self,
+        ctx: &Context,
+        message: &dyn MessagePart,
+        args: &'f dyn Args<'f>,
+        width: Option<usize>,
+    ) -> fmt::Result;
+}
+
+impl<'a, W: fmt::Write + ?Sized> ViaFmt for Target<'a, W> {
+    fn write_message_to<'f>(
+        self,
+        ctx: &Context,
+        message: &dyn MessagePart,
+        args: &'f dyn Args<'f>,
+        width: Option<usize>,
+    ) -> fmt::Result {
+        let mut stream = FmtAdapter(self.0);
+        write_padded(ctx, message, &mut stream, args, width)
+    }
+}
+
+// Allows an unsized `fmt::Write` to be used as a trait object.
+struct FmtAdapter<'a, W: ?Sized + 'a>(&'a mut W);
+
+impl<'a, W: fmt::Write + ?Sized> fmt::Write for FmtAdapter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s)
+    }
+}
+
+/// Writes to an `io::Write` stream.
+pub trait ViaIo {
+    /// Write `message`, padded to `width` columns if given.
+    fn write_message_to<'f>(
+        self,
+        ctx: &Context,
+        message: &dyn MessagePart,
+        args: &'f dyn Args<'f>,
+        width: Option<usize>,
+    ) -> io::Result<()>;
+}
+
+impl<'a, 'b, W: io::Write + ?Sized> ViaIo for &'b mut Target<'a, W> {
+    fn write_message_to<'f>(
+        self,
+        ctx: &Context,
+        message: &dyn MessagePart,
+        args: &'f dyn Args<'f>,
+        width: Option<usize>,
+    ) -> io::Result<()> {
+        let mut stream = IoAdapter {
+            inner: &mut *self.0,
+            error: None,
+        };
+        match write_padded(ctx, message, &mut stream, args, width) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(stream
+                .error
+                .unwrap_or_else(|| io::Error::other("failed to format message"))),
+        }
+    }
+}
+
+// Writes formatted text to an `io::Write`, keeping the first error.
+struct IoAdapter<'a, W: ?Sized + 'a> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write + ?Sized> fmt::Write for IoAdapter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,151 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Styled output for terminals.
+//!
+//! Messages for command line tools can mark up their text with tags,
+//! such as `<b>`, `<dim>` and `<red>`, which [`format_styled`] turns
+//! into ANSI escape sequences. Translators can then move the styling
+//! along with the words it applies to:
+//!
+//! ```
+//! use message_format::term::{format_styled, ColorChoice};
+//! use message_format::{arg, icu, Context};
+//!
+//! let m = icu::parse("<red>error:</red> file <b>{path}</b> not found").unwrap();
+//! let mut ctx = Context::default();
+//! ctx.color = ColorChoice::Always;
+//! assert_eq!(
+//!     format_styled(&ctx, &m, &arg("path", "<b>.txt")),
+//!     "\u{1b}[31merror:\u{1b}[0m file \u{1b}[1m<b>.txt\u{1b}[0m not found",
+//! );
+//!
+//! ctx.color = ColorChoice::Never;
+//! assert_eq!(format_styled(&ctx, &m, &arg("path", "a.txt")), "error: file a.txt not found");
+//! ```
+//!
+//! Tags are only recognized in the text of the message, never in the
+//! values of arguments. Tags this module doesn't know are left as is.
+//!
+//! [`format_styled`]: fn.format_styled.html
+
+use parts::FormattedPart;
+use {Args, Context, Message, PartRole};
+
+pub use context::ColorChoice;
+
+// The tags known, with the SGR parameters they set.
+const STYLES: &[(&str, &str)] = &[
+    ("b", "1"),
+    ("bold", "1"),
+    ("dim", "2"),
+    ("i", "3"),
+    ("italic", "3"),
+    ("u", "4"),
+    ("underline", "4"),
+    ("black", "30"),
+    ("red", "31"),
+    ("green", "32"),
+    ("yellow", "33"),
+    ("blue", "34"),
+    ("magenta", "35"),
+    ("cyan", "36"),
+    ("white", "37"),
+];
+
+fn style(name: &str) -> Option<&'static str> {
+    STYLES.iter().find(|&&(tag, _)| tag == name).map(|&(_, sgr)| sgr)
+}
+
+// Writes the escape sequence for the styles still open.
+fn write_styles(open: &[&str], output: &mut String) {
+    output.push_str("\u{1b}[0m");
+    for sgr in open {
+        output.push_str("\u{1b}[");
+        output.push_str(sgr);
+        output.push('m');
+    }
+}
+
+fn render_literal(mut text: &str, enabled: bool, open: &mut Vec<&'static str>, output: &mut String) {
+    while let Some(start) = text.find('<') {
+        output.push_str(&text[..start]);
+        let rest = &text[start..];
+        let tag = rest.find('>').map(|end| &rest[1..end]);
+        let (closing, name) = match tag {
+            Some(tag) if tag.starts_with('/') => (true, &tag[1..]),
+            Some(tag) => (false, tag),
+            None => (false, ""),
+        };
+        match (tag, style(name)) {
+            (Some(tag), Some(sgr)) => {
+                if closing {
+                    if let Some(i) = open.iter().rposition(|&open| open == sgr) {
+                        open.remove(i);
+                    }
+                    if enabled {
+                        write_styles(open, output);
+                    }
+                } else {
+                    open.push(sgr);
+                    if enabled {
+                        output.push_str("\u{1b}[");
+                        output.push_str(sgr);
+                        output.push('m');
+                    }
+                }
+                text = &rest[tag.len() + 2..];
+            }
+            _ => {
+                output.push('<');
+                text = &rest[1..];
+            }
+        }
+    }
+    output.push_str(text);
+}
+
+/// Format a message using `ctx`, turning the tags in its text into
+/// ANSI escape sequences when `ctx.color` is enabled, and removing
+/// them otherwise.
+///
+/// Styles left open at the end of the message are reset, so they
+/// don't leak into the rest of the terminal output.
+pub fn format_styled<'f>(ctx: &Context, message: &Message, args: &'f dyn Args<'f>) -> String {
+    let parts: Vec<FormattedPart> = ctx.format_to_parts(message, args).unwrap_or_default();
+    let enabled = ctx.color.enabled();
+    let mut open = vec![];
+    let mut output = String::new();
+    for part in &parts {
+        match part.role {
+            PartRole::Literal => render_literal(&part.text, enabled, &mut open, &mut output),
+            _ => output.push_str(&part.text),
+        }
+    }
+    if enabled && !open.is_empty() {
+        write_styles(&[], &mut output);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_styled, ColorChoice};
+    use {arg, icu, Context};
+
+    #[test]
+    fn nested_and_unknown_tags() {
+        let ctx = Context {
+            color: ColorChoice::Always,
+            ..Context::default()
+        };
+        let m = icu::parse("<b>Total: <green>{n}</green> done</b> <x>ok</x> 1 < 2 <dim>").unwrap();
+        assert_eq!(
+            format_styled(&ctx, &m, &arg("n", 5)),
+            "\u{1b}[1mTotal: \u{1b}[32m5\u{1b}[0m\u{1b}[1m done\u{1b}[0m <x>ok</x> 1 < 2 \u{1b}[2m\u{1b}[0m"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+#[cfg(feature = "any-ascii")]
+use any_ascii::any_ascii;
+
+/// Rewrites text from one script into another, such as Serbian
+/// Cyrillic into Latin, or Japanese kana into romaji. See
+/// [`Context::with_transliterator`] and [`Context::transliterate_arg`].
+///
+/// Closures taking and returning text implement this trait.
+///
+/// [`Context::with_transliterator`]: struct.Context.html#method.with_transliterator
+/// [`Context::transliterate_arg`]: struct.Context.html#method.transliterate_arg
+pub trait Transliterator: Send + Sync {
+    /// The text `text` in the target script.
+    fn transliterate(&self, text: &str) -> String;
+}
+
+impl<F> Transliterator for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn transliterate(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+impl fmt::Debug for dyn Transliterator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Transliterator")
+    }
+}
+
+/// Transliterates text of any script into ASCII, for outputs which
+/// can't show anything else, such as SMS gateways or file names.
+///
+/// This requires the `any-ascii` feature.
+///
+/// ```
+/// use message_format::{arg, icu, AnyAscii, Context};
+/// use std::sync::Arc;
+///
+/// let ctx = Context::default().with_transliterator(Arc::new(AnyAscii));
+/// let m = icu::parse("{city}: {n} °C").unwrap();
+/// assert_eq!(ctx.format(&m, &arg("city", "Београд").arg("n", 21)), "Beograd: 21 degC");
+/// ```
+#[cfg(feature = "any-ascii")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AnyAscii;
+
+#[cfg(feature = "any-ascii")]
+impl Transliterator for AnyAscii {
+    fn transliterate(&self, text: &str) -> String {
+        any_ascii(text)
+    }
+}
@@ -0,0 +1,153 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use MessageBundle;
+
+/// Counts how often each message key is looked up, to find the
+/// messages of a catalog which are never used.
+///
+/// Attach a tracker to a localizer with
+/// [`Localizer::with_usage_tracker`]. Every key found by
+/// [`Localizer::lookup`] or [`Localizer::format`] is then counted, as
+/// are the messages referenced with `{@key}` by the messages
+/// formatted. Keys found in no bundle are not counted.
+///
+/// ```
+/// use std::sync::Arc;
+/// use message_format::{BundleSet, EmptyArgs, Localizer, MessageBundle, UsageTracker};
+///
+/// let en = MessageBundle::parse_entries(
+///     "en".parse().unwrap(),
+///     vec![("hello", "Hello!"), ("bye", "Goodbye!")],
+/// ).unwrap();
+/// let bundles = Arc::new(BundleSet::new(en));
+/// let tracker = Arc::new(UsageTracker::new());
+/// let localizer = Localizer::new(bundles.clone(), &[]).with_usage_tracker(tracker.clone());
+///
+/// localizer.format("hello", &EmptyArgs);
+/// localizer.format("hello", &EmptyArgs);
+///
+/// let report = tracker.report();
+/// assert_eq!(report.keys, [("hello".to_string(), 2)]);
+/// assert_eq!(report.unused(bundles.default_bundle()), ["bye"]);
+/// ```
+///
+/// [`Localizer::with_usage_tracker`]: struct.Localizer.html#method.with_usage_tracker
+/// [`Localizer::lookup`]: struct.Localizer.html#method.lookup
+/// [`Localizer::format`]: struct.Localizer.html#method.format
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    counts: Mutex<HashMap<String, u64>>,
+}
+
+impl UsageTracker {
+    /// Create a tracker with nothing recorded.
+    pub fn new() -> Self {
+        UsageTracker::default()
+    }
+
+    /// Record one use of `key`.
+    pub(crate) fn record(&self, key: &str) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        match counts.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(key.to_string(), 1);
+            }
+        }
+    }
+
+    /// The uses recorded so far.
+    pub fn report(&self) -> UsageReport {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        let mut keys: Vec<(String, u64)> = counts.iter().map(|(key, &count)| (key.clone(), count)).collect();
+        keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        UsageReport { keys }
+    }
+
+    /// Forget everything recorded so far.
+    pub fn reset(&self) {
+        self.counts.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// A snapshot of the uses recorded by a [`UsageTracker`].
+///
+/// Displaying a report writes one line per key.
+///
+/// [`UsageTracker`]: struct.UsageTracker.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UsageReport {
+    /// Each key used, with the number of times it was, ordered by
+    /// that number, most first.
+    pub keys: Vec<(String, u64)>,
+}
+
+impl UsageReport {
+    /// The number of times `key` was used.
+    pub fn count(&self, key: &str) -> u64 {
+        self.keys.iter().find(|(k, _)| k == key).map_or(0, |&(_, count)| count)
+    }
+
+    /// The keys of `bundle` which were never used, in order.
+    pub fn unused<'b>(&self, bundle: &'b MessageBundle) -> Vec<&'b str> {
+        bundle
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .filter(|key| self.count(key) == 0)
+            .collect()
+    }
+}
+
+impl fmt::Display for UsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, count) in &self.keys {
+            writeln!(f, "{}: {}", key, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::UsageTracker;
+    use icu::ParseOptions;
+    use {BundleSet, EmptyArgs, Localizer, MessageBundle};
+
+    #[test]
+    fn references_and_fallbacks_are_counted() {
+        let options = ParseOptions {
+            extensions: true,
+            ..ParseOptions::default()
+        };
+        let en = MessageBundle::parse_entries_with(
+            "en".parse().unwrap(),
+            vec![("product", "Frob"), ("welcome", "Welcome to {@product}!"), ("old", "Old")],
+            options,
+        )
+        .unwrap();
+        let fr = MessageBundle::parse_entries("fr".parse().unwrap(), vec![("product", "Frob FR")]).unwrap();
+        let mut bundles = BundleSet::new(en);
+        bundles.insert(fr);
+        let tracker = Arc::new(UsageTracker::new());
+        let localizer = Localizer::new(Arc::new(bundles), &["fr".parse().unwrap()]).with_usage_tracker(tracker.clone());
+
+        assert_eq!(localizer.format("welcome", &EmptyArgs).unwrap(), "Welcome to Frob FR!");
+        assert_eq!(localizer.format("missing", &EmptyArgs), None);
+
+        let report = tracker.report();
+        assert_eq!(report.keys, [("product".to_string(), 1), ("welcome".to_string(), 1)]);
+        assert_eq!(report.to_string(), "product: 1\nwelcome: 1\n");
+        tracker.reset();
+        assert_eq!(tracker.report().count("welcome"), 0);
+    }
+}
@@ -4,51 +4,380 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt;
 
+use icu::ast;
+use number::{Number, NumberOptions};
+use {Context, DateTime, Decimal};
+
+/// A user defined type that can be used as a [`Value`].
+///
+/// This allows values to be passed to a message without having
+/// to format them ahead of time, so that they can be formatted
+/// using the [`Context`] of the message.
+///
+/// ```
+/// # #[macro_use] extern crate message_format;
+/// # fn main() {
+/// use std::fmt;
+/// use message_format::{icu, Context, Formattable};
+///
+/// #[derive(Debug)]
+/// struct Apples(u32);
+///
+/// impl Formattable for Apples {
+///     fn format(&self, _ctx: &Context, stream: &mut dyn fmt::Write) -> fmt::Result {
+///         write!(stream, "{} apples", self.0)
+///     }
+///
+///     fn plural_value(&self) -> Option<i64> {
+///         Some(i64::from(self.0))
+///     }
+/// }
+///
+/// let ctx = Context::default();
+/// let m = icu::parse("{n, plural, one {An apple} other {{n}}}").unwrap();
+/// assert_eq!(format_message!(ctx, &m, n => &Apples(1)), "An apple");
+/// assert_eq!(format_message!(ctx, &m, n => &Apples(3)), "3 apples");
+/// # }
+/// ```
+///
+/// [`Value`]: enum.Value.html
+/// [`Context`]: struct.Context.html
+pub trait Formattable: fmt::Debug {
+    /// Write the value as it should appear in a simple format such
+    /// as `{name}`.
+    fn format(&self, ctx: &Context, stream: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// The number to use when selecting a plural form, if this value
+    /// can be used with a `plural` format.
+    fn plural_value(&self) -> Option<i64> {
+        None
+    }
+
+    /// The key to match against the branches of a `select` format.
+    /// When this is `None`, the formatted value is used.
+    fn select_key(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// A type, usually an enum, whose values choose a branch of a
+/// `select` format.
+///
+/// Implementing this trait also implements [`Formattable`], so
+/// a reference to the value can be passed as an argument.
+///
+/// ```
+/// # #[macro_use] extern crate message_format;
+/// # fn main() {
+/// use message_format::{icu, Context, SelectKey};
+///
+/// #[derive(Debug)]
+/// enum Role { Admin, Guest }
+///
+/// impl SelectKey for Role {
+///     fn select_key(&self) -> &str {
+///         match *self {
+///             Role::Admin => "admin",
+///             Role::Guest => "guest",
+///         }
+///     }
+/// }
+///
+/// let ctx = Context::default();
+/// let m = icu::parse("{role, select, admin {Welcome back!} other {Hello!}}").unwrap();
+/// assert_eq!(format_message!(ctx, &m, role => &Role::Admin), "Welcome back!");
+/// assert_eq!(format_message!(ctx, &m, role => &Role::Guest), "Hello!");
+/// # }
+/// ```
+///
+/// [`Formattable`]: trait.Formattable.html
+pub trait SelectKey: fmt::Debug {
+    /// The key to match against the branches of a `select` format.
+    fn select_key(&self) -> &str;
+}
+
+impl<T: SelectKey> Formattable for T {
+    fn format(&self, _ctx: &Context, stream: &mut dyn fmt::Write) -> fmt::Result {
+        stream.write_str(SelectKey::select_key(self))
+    }
+
+    fn select_key(&self) -> Option<&str> {
+        Some(SelectKey::select_key(self))
+    }
+}
+
 /// A wrapper around a value, used with [`Args`] so that a [`MessagePart`]
 /// can access the original value when necessary.
 ///
 /// These are not typically created directly, but are created automatically
 /// via the standard library's `From` trait when creating [`Args`].
 ///
+/// Every integer type converts without an error. The integers that
+/// don't fit in an `i64`, which only `u64`, `usize`, `isize`, `i128`
+/// and `u128` can hold, become a `Value::Decimal` rather than a
+/// `Value::Number`. They are still formatted with all their digits,
+/// but [`as_number`] returns `None` for them, and `plural` formats
+/// choose a branch for them from the nearest `f64`. Convert with
+/// `i64::try_from` first where an out of range value is a mistake.
+/// An `f32` becomes the `f64` it is exactly equal to, which may have
+/// more digits than it was written with.
+///
+/// ```
+/// use message_format::Value;
+///
+/// assert_eq!(Value::from(3u64), Value::Number(3));
+/// let large = Value::from(u64::MAX);
+/// assert_eq!(large.to_string(), "18446744073709551615");
+/// assert_eq!(large.as_number(), None);
+/// assert_eq!(Value::from(0.1f32), Value::Float(0.10000000149011612));
+/// ```
+///
 /// [`Args`]: struct.Args.html
 /// [`MessagePart`]: trait.MessagePart.html
-#[derive(Debug, PartialEq)]
+/// [`as_number`]: #method.as_number
+#[derive(Debug)]
 pub enum Value<'a> {
     /// Wrap an `i64`.
     Number(i64),
+    /// Wrap an `f64`.
+    Float(f64),
+    /// Wrap a `bool`.
+    Bool(bool),
     /// Wrap an `&str`.
     Str(&'a str),
+    /// Wrap an owned `String`.
+    String(String),
+    /// Wrap a [`Decimal`], which keeps its visible fraction digits.
+    ///
+    /// [`Decimal`]: struct.Decimal.html
+    Decimal(Decimal),
+    /// Wrap a [`DateTime`].
+    ///
+    /// [`DateTime`]: struct.DateTime.html
+    Date(DateTime),
+    /// Wrap a user defined type implementing [`Formattable`].
+    ///
+    /// [`Formattable`]: trait.Formattable.html
+    Dynamic(&'a dyn Formattable),
 }
 
-impl<'a> From<i32> for Value<'a> {
-    fn from(value: i32) -> Value<'a> {
-        Value::Number(i64::from(value))
+/// The kind of a [`Value`], naming its variant.
+///
+/// [`Value`]: enum.Value.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A `Value::Number`.
+    Number,
+    /// A `Value::Float`.
+    Float,
+    /// A `Value::Bool`.
+    Bool,
+    /// A `Value::Str`.
+    Str,
+    /// A `Value::String`.
+    String,
+    /// A `Value::Decimal`.
+    Decimal,
+    /// A `Value::Date`.
+    Date,
+    /// A `Value::Dynamic`.
+    Dynamic,
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ValueKind::Number => "a number",
+            ValueKind::Float => "a float",
+            ValueKind::Bool => "a bool",
+            ValueKind::Str | ValueKind::String => "a string",
+            ValueKind::Decimal => "a decimal",
+            ValueKind::Date => "a date",
+            ValueKind::Dynamic => "a user defined value",
+        })
+    }
+}
+
+impl<'a> Value<'a> {
+    /// The kind of this value.
+    pub fn kind(&self) -> ValueKind {
+        match *self {
+            Value::Number(_) => ValueKind::Number,
+            Value::Float(_) => ValueKind::Float,
+            Value::Bool(_) => ValueKind::Bool,
+            Value::Str(_) => ValueKind::Str,
+            Value::String(_) => ValueKind::String,
+            Value::Decimal(_) => ValueKind::Decimal,
+            Value::Date(_) => ValueKind::Date,
+            Value::Dynamic(_) => ValueKind::Dynamic,
+        }
+    }
+
+    /// The numeric value, if there is one. Floats are only
+    /// converted when they have no fractional part.
+    pub fn as_number(&self) -> Option<i64> {
+        match *self {
+            Value::Number(n) => Some(n),
+            Value::Float(f) if f.fract() == 0.0 && f.abs() < 9.2e18 => Some(f as i64),
+            Value::Decimal(ref d) if d.fraction_digits() == 0 && d.value().abs() < 9.2e18 => {
+                Some(d.value() as i64)
+            }
+            Value::Dynamic(d) => d.plural_value(),
+            _ => None,
+        }
+    }
+
+    /// The value as a floating point number, if it is numeric.
+    pub fn as_float(&self) -> Option<f64> {
+        match *self {
+            Value::Number(n) => Some(n as f64),
+            Value::Float(f) => Some(f),
+            Value::Decimal(ref d) => Some(d.value()),
+            Value::Dynamic(d) => d.plural_value().map(|n| n as f64),
+            _ => None,
+        }
+    }
+
+    // The value as a number to format, keeping all the digits of an
+    // integer.
+    pub(crate) fn number(&self) -> Option<Number> {
+        match *self {
+            Value::Number(n) => Some(Number::integer(i128::from(n))),
+            Value::Decimal(ref d) if d.fraction_digits() == 0 => {
+                let text = d.to_string();
+                let (negative, digits) = match text.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, &text[..]),
+                };
+                match digits.parse() {
+                    Ok(magnitude) => Some(Number::Integer { negative, magnitude }),
+                    Err(_) => Some(Number::Float(d.value())),
+                }
+            }
+            _ => self.as_float().map(Number::Float),
+        }
+    }
+
+    /// The number of visible fraction digits, for a [`Decimal`].
+    ///
+    /// [`Decimal`]: struct.Decimal.html
+    pub fn fraction_digits(&self) -> Option<usize> {
+        match *self {
+            Value::Decimal(ref d) => Some(d.fraction_digits()),
+            _ => None,
+        }
+    }
+
+    /// The key used to choose a branch of a `select` format. Values
+    /// other than strings are matched using their formatted text, so
+    /// `true` matches a `true` branch and `3` matches a `3` branch.
+    pub fn select_key(&self) -> Cow<'_, str> {
+        match *self {
+            Value::Str(s) => Cow::Borrowed(s),
+            Value::String(ref s) => Cow::Borrowed(s),
+            Value::Dynamic(d) => match d.select_key() {
+                Some(key) => Cow::Borrowed(key),
+                None => Cow::Owned(self.to_string()),
+            },
+            _ => Cow::Owned(self.to_string()),
+        }
+    }
+
+    /// Write the value as it should appear in a simple format.
+    ///
+    /// Numbers are written for the locale of `ctx`, as by a `number`
+    /// format and by `#`, with its separators and numbering system.
+    /// Integers keep all their digits, even those past 2^53.
+    pub fn format(&self, ctx: &Context, stream: &mut dyn fmt::Write) -> fmt::Result {
+        self.format_with(ctx, None, stream)
+    }
+
+    // Like `format`, with the number options given with the argument.
+    pub(crate) fn format_with(
+        &self,
+        ctx: &Context,
+        overrides: Option<&NumberOptions>,
+        stream: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        match *self {
+            Value::Number(_) | Value::Float(_) | Value::Decimal(_) => {
+                let number = self.number().ok_or(fmt::Error {})?;
+                ast::write_placeholder(ctx, number, self.fraction_digits(), overrides, stream)
+            }
+            Value::Dynamic(d) => d.format(ctx, stream),
+            _ => write!(stream, "{}", self),
+        }
     }
 }
 
-impl<'a> From<u32> for Value<'a> {
-    fn from(value: u32) -> Value<'a> {
-        Value::Number(i64::from(value))
+impl<'a> PartialEq for Value<'a> {
+    /// Dynamic values are only equal if they refer to the same object.
+    fn eq(&self, other: &Value<'a>) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (Value::Date(a), Value::Date(b)) => a == b,
+            (Value::Dynamic(a), Value::Dynamic(b)) => ::std::ptr::addr_eq(*a, *b),
+            _ => false,
+        }
     }
 }
 
-impl<'a> From<i64> for Value<'a> {
-    fn from(value: i64) -> Value<'a> {
-        Value::Number(value)
+macro_rules! from_lossless {
+    ($($t:ty),*) => {$(
+        impl<'a> From<$t> for Value<'a> {
+            fn from(value: $t) -> Value<'a> {
+                Value::Number(i64::from(value))
+            }
+        }
+    )*};
+}
+
+from_lossless!(i8, i16, i32, i64, u8, u16, u32);
+
+// These types may hold values which don't fit in an `i64`. Those
+// values are kept exactly as a `Value::Decimal` rather than wrapping,
+// though they lose precision where they are used as an `f64`.
+macro_rules! from_checked {
+    ($($t:ty),*) => {$(
+        impl<'a> From<$t> for Value<'a> {
+            fn from(value: $t) -> Value<'a> {
+                match i64::try_from(value) {
+                    Ok(n) => Value::Number(n),
+                    Err(_) => Value::Decimal(
+                        value.to_string().parse().expect("integers are valid decimals"),
+                    ),
+                }
+            }
+        }
+    )*};
+}
+
+from_checked!(isize, usize, u64, i128, u128);
+
+impl<'a> From<f32> for Value<'a> {
+    fn from(value: f32) -> Value<'a> {
+        Value::Float(f64::from(value))
     }
 }
 
-impl<'a> From<u64> for Value<'a> {
-    fn from(value: u64) -> Value<'a> {
-        Value::Number(value as i64)
+impl<'a> From<f64> for Value<'a> {
+    fn from(value: f64) -> Value<'a> {
+        Value::Float(value)
     }
 }
 
-impl<'a> From<usize> for Value<'a> {
-    fn from(value: usize) -> Value<'a> {
-        Value::Number(value as i64)
+impl<'a> From<bool> for Value<'a> {
+    fn from(value: bool) -> Value<'a> {
+        Value::Bool(value)
     }
 }
 
@@ -58,12 +387,96 @@ impl<'a> From<&'a str> for Value<'a> {
     }
 }
 
+impl<'a> From<&'a String> for Value<'a> {
+    fn from(value: &'a String) -> Value<'a> {
+        Value::Str(value.as_str())
+    }
+}
+
+impl<'a> From<String> for Value<'a> {
+    fn from(value: String) -> Value<'a> {
+        Value::String(value)
+    }
+}
+
+impl<'a> From<Decimal> for Value<'a> {
+    fn from(value: Decimal) -> Value<'a> {
+        Value::Decimal(value)
+    }
+}
+
+impl<'a> From<DateTime> for Value<'a> {
+    fn from(value: DateTime) -> Value<'a> {
+        Value::Date(value)
+    }
+}
+
+impl<'a, T: Formattable> From<&'a T> for Value<'a> {
+    fn from(value: &'a T) -> Value<'a> {
+        Value::Dynamic(value)
+    }
+}
+
+impl<'a> From<&'a dyn Formattable> for Value<'a> {
+    fn from(value: &'a dyn Formattable) -> Value<'a> {
+        Value::Dynamic(value)
+    }
+}
+
 impl<'a> fmt::Display for Value<'a> {
-    /// Forward `fmt::Display` to the underlying value.
+    /// Forward `fmt::Display` to the underlying value, so numbers are
+    /// written as Rust writes them, whatever the locale. Dynamic values
+    /// are formatted using [`Context::current`]. Messages use [`format`]
+    /// instead.
+    ///
+    /// [`Context::current`]: struct.Context.html#method.current
+    /// [`format`]: enum.Value.html#method.format
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Value::Number(i) => i.fmt(f),
+            Value::Float(n) => n.fmt(f),
+            Value::Bool(b) => b.fmt(f),
             Value::Str(s) => s.fmt(f),
+            Value::String(ref s) => s.fmt(f),
+            Value::Decimal(ref d) => d.fmt(f),
+            Value::Date(ref d) => d.fmt(f),
+            Value::Dynamic(d) => d.format(&Context::current(), f),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+    use Context;
+
+    #[test]
+    fn integers_convert() {
+        assert_eq!(Value::from(3u8), Value::Number(3));
+        assert_eq!(Value::from(-3i16), Value::Number(-3));
+        assert_eq!(Value::from(3u64), Value::Number(3));
+        assert_eq!(Value::from(3isize), Value::Number(3));
+        assert_eq!(Value::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Value::from(i128::MIN).as_number(), None);
+    }
+
+    #[test]
+    fn large_integers_keep_their_digits() {
+        let ctx = Context::default();
+        let mut s = String::new();
+        Value::from(u128::MAX).format(&ctx, &mut s).unwrap();
+        assert_eq!(s, "340,282,366,920,938,463,463,374,607,431,768,211,455");
+        assert_eq!(Value::from(u64::MAX - 1).fraction_digits(), Some(0));
+    }
+
+    #[test]
+    fn other_types_convert() {
+        assert_eq!(Value::from(2.5f32), Value::Float(2.5));
+        assert_eq!(Value::from(2.0f64).as_number(), Some(2));
+        assert_eq!(Value::from(true), Value::Bool(true));
+        let s = String::from("John");
+        assert_eq!(Value::from(&s), Value::Str("John"));
+        assert_eq!(Value::from(s.clone()), Value::String(s));
+        assert_eq!(format!("{}", Value::from(false)), "false");
+    }
+}
@@ -0,0 +1,226 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+
+use icu::ast;
+use plural::PluralRules;
+use {Message, MessagePart, PluralCategory, Value};
+
+/// The branch of a `plural` or `select` taken by a [`Variant`].
+///
+/// [`Variant`]: struct.Variant.html
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    /// A key of a `select`, or `other`.
+    Key(String),
+    /// An exact value of a `plural`, as in `=0`.
+    Exact(f64),
+    /// A plural category, taken when the value less the offset of the
+    /// `plural` is in it.
+    Category {
+        /// The category.
+        category: PluralCategory,
+        /// The offset of the `plural`.
+        offset: i64,
+        /// The exact values of the `plural`, which take their own
+        /// branches instead.
+        exact: Vec<f64>,
+    },
+}
+
+/// One combination of the branches of a message, found by
+/// [`Message::enumerate_variants`].
+///
+/// [`Message::enumerate_variants`]: struct.Message.html#method.enumerate_variants
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    /// The branch taken for each argument selected on, in the order
+    /// the message selects on them.
+    pub selectors: Vec<(String, Selector)>,
+}
+
+// The numbers tried to find one in a plural category.
+fn category_candidates() -> impl Iterator<Item = f64> {
+    (0..=1000).map(f64::from).chain(vec![0.5, 1.5])
+}
+
+impl Variant {
+    /// Values for the arguments selected on which take the branches
+    /// of this variant, with plural categories chosen by `rules`.
+    ///
+    /// This is `None` when a category isn't used by `rules`, such as
+    /// `zero` in English, as no value takes its branch. Other
+    /// arguments must be added before formatting.
+    pub fn args(&self, rules: &PluralRules) -> Option<HashMap<&str, Value<'static>>> {
+        let mut args = HashMap::new();
+        for (name, selector) in &self.selectors {
+            let value = match *selector {
+                Selector::Key(ref key) => Value::String(key.clone()),
+                Selector::Exact(n) => number_value(n),
+                Selector::Category {
+                    category,
+                    offset,
+                    ref exact,
+                } => {
+                    let n = category_candidates()
+                        .map(|n| n + offset as f64)
+                        .find(|&n| rules.select(n - offset as f64) == category && !exact.contains(&n))?;
+                    number_value(n)
+                }
+            };
+            args.insert(name.as_str(), value);
+        }
+        Some(args)
+    }
+}
+
+fn number_value(n: f64) -> Value<'static> {
+    if n.fract() == 0.0 {
+        Value::Number(n as i64)
+    } else {
+        Value::Float(n)
+    }
+}
+
+type Path = Vec<(String, Selector)>;
+
+// Prefix each of the variants of `message` with a selector.
+fn branch(name: &str, selector: Selector, message: &Message, paths: &mut Vec<Path>) {
+    for mut path in variants(message) {
+        path.insert(0, (name.to_string(), selector.clone()));
+        paths.push(path);
+    }
+}
+
+// The variants of one part of a message, or `None` if it doesn't
+// select. The messages nested in parts other than `select` and
+// `plural` are all formatted.
+fn part_variants(part: &dyn MessagePart) -> Option<Vec<Path>> {
+    let mut paths = vec![];
+    let any = part.as_any();
+    if let Some(fmt) = any.downcast_ref::<ast::SelectFormat>() {
+        for mapping in &fmt.mappings {
+            branch(&fmt.variable_name, Selector::Key(mapping.value.clone()), &mapping.message, &mut paths);
+        }
+        branch(&fmt.variable_name, Selector::Key("other".to_string()), &fmt.default, &mut paths);
+    } else if let Some(fmt) = any.downcast_ref::<ast::PluralFormat>() {
+        for mapping in &fmt.literals {
+            branch(&fmt.variable_name, Selector::Exact(mapping.value), &mapping.message, &mut paths);
+        }
+        let categories = [
+            (PluralCategory::Zero, fmt.zero.as_ref()),
+            (PluralCategory::One, fmt.one.as_ref()),
+            (PluralCategory::Two, fmt.two.as_ref()),
+            (PluralCategory::Few, fmt.few.as_ref()),
+            (PluralCategory::Many, fmt.many.as_ref()),
+            (PluralCategory::Other, Some(&fmt.other)),
+        ];
+        let exact: Vec<f64> = fmt.literals.iter().map(|mapping| mapping.value).collect();
+        for &(category, message) in &categories {
+            if let Some(message) = message {
+                let selector = Selector::Category {
+                    category,
+                    offset: fmt.offset,
+                    exact: exact.clone(),
+                };
+                branch(&fmt.variable_name, selector, message, &mut paths);
+            }
+        }
+    } else {
+        let children = part.children();
+        if children.is_empty() {
+            return None;
+        }
+        return Some(variants_of(&children));
+    }
+    Some(paths)
+}
+
+// Whether two paths can be taken together, which they can't when
+// they take different branches for the same argument.
+fn compatible(a: &Path, b: &Path) -> bool {
+    b.iter().all(|(name, selector)| {
+        a.iter().all(|(other_name, other)| other_name != name || other == selector)
+    })
+}
+
+fn variants(message: &Message) -> Vec<Path> {
+    variants_of(&[message])
+}
+
+// The variants of messages formatted one after the other.
+fn variants_of(messages: &[&Message]) -> Vec<Path> {
+    let mut paths: Vec<Path> = vec![vec![]];
+    for part in messages.iter().flat_map(|message| &message.parts) {
+        let branches = match part_variants(part.as_ref()) {
+            Some(branches) => branches,
+            None => continue,
+        };
+        let mut product = vec![];
+        for path in &paths {
+            for branch in branches.iter().filter(|branch| compatible(path, branch)) {
+                let mut combined = path.clone();
+                for selector in branch {
+                    if !combined.contains(selector) {
+                        combined.push(selector.clone());
+                    }
+                }
+                product.push(combined);
+            }
+        }
+        paths = product;
+    }
+    paths
+}
+
+pub(crate) fn enumerate(message: &Message) -> Vec<Variant> {
+    variants(message).into_iter().map(|selectors| Variant { selectors }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selector;
+    use icu;
+    use plural::PluralRules;
+    use {Context, PluralCategory};
+
+    #[test]
+    fn nested_and_repeated_selectors() {
+        let m = icu::parse(
+            "{g, select, female {{n, plural, one {She has # cat} other {She has # cats}}} other {They}} \
+             {g, select, female {herself} other {themselves}}",
+        )
+        .unwrap();
+        let variants = m.enumerate_variants();
+        // `g` takes the same branch in both selects.
+        assert_eq!(variants.len(), 3);
+        assert_eq!(
+            variants[1].selectors,
+            [
+                ("g".to_string(), Selector::Key("female".to_string())),
+                (
+                    "n".to_string(),
+                    Selector::Category {
+                        category: PluralCategory::Other,
+                        offset: 0,
+                        exact: vec![],
+                    }
+                ),
+            ]
+        );
+
+        let rules = PluralRules::cardinal(&"en".parse().unwrap());
+        let outputs: Vec<String> = variants
+            .iter()
+            .map(|variant| Context::default().format(&m, &variant.args(&rules).unwrap()))
+            .collect();
+        assert_eq!(outputs, ["She has 1 cat herself", "She has 0 cats herself", "They themselves"]);
+
+        let m = icu::parse("{n, plural, zero {none} other {#}}").unwrap();
+        assert_eq!(m.enumerate_variants()[0].args(&rules), None);
+    }
+}
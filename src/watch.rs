@@ -0,0 +1,220 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use language_tags::LanguageTag;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use catalog::{self, CatalogError};
+use {BundleSet, Localizer};
+
+/// A [`BundleSet`] loaded from a directory of catalog files that is
+/// reloaded when the files change, for seeing copy edits during
+/// development without restarting.
+///
+/// The directory is watched with the [`notify`] crate. When catalog
+/// files are added, removed or modified, the whole directory is
+/// reloaded once the changes have stopped for the `debounce` time
+/// and, if it loads without errors, swapped in at once, so readers
+/// never see a mix of old and new bundles. Otherwise the previous
+/// bundles stay in use. Either way, the subscriber is told the
+/// outcome, as it is when the directory can't be watched.
+///
+/// Watching stops when the `WatchingBundleSet` is dropped.
+///
+/// [`BundleSet`]: struct.BundleSet.html
+/// [`notify`]: https://docs.rs/notify
+#[derive(Debug)]
+pub struct WatchingBundleSet {
+    shared: Arc<Shared>,
+    watcher: RecommendedWatcher,
+    stop: Sender<Change>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    dir: PathBuf,
+    default: LanguageTag,
+    current: RwLock<Arc<BundleSet>>,
+}
+
+// What the reloading thread is told.
+enum Change {
+    Event(notify::Result<Event>),
+    Stop,
+}
+
+impl WatchingBundleSet {
+    /// Load the catalogs in `dir` and watch them for changes, calling
+    /// `subscriber` after each reload attempt.
+    ///
+    /// Fails if the initial load fails or the directory can't be
+    /// watched.
+    pub fn new<F>(
+        dir: &Path,
+        default: LanguageTag,
+        debounce: Duration,
+        subscriber: F,
+    ) -> Result<Self, CatalogError>
+    where
+        F: Fn(Result<&BundleSet, &CatalogError>) + Send + 'static,
+    {
+        let bundles = catalog::load_dir(dir, &default)?;
+        let watch_error = |e: notify::Error| CatalogError::Io(dir.to_path_buf(), io::Error::other(e));
+        let (sender, receiver) = mpsc::channel();
+        let events = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = events.send(Change::Event(event));
+        })
+        .map_err(watch_error)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive).map_err(watch_error)?;
+        let shared = Arc::new(Shared {
+            dir: dir.to_path_buf(),
+            default,
+            current: RwLock::new(Arc::new(bundles)),
+        });
+        let thread = {
+            let shared = shared.clone();
+            thread::spawn(move || shared.watch(&receiver, debounce, subscriber))
+        };
+        Ok(WatchingBundleSet {
+            shared,
+            watcher,
+            stop: sender,
+            thread: Some(thread),
+        })
+    }
+
+    /// The bundles as of the last successful load.
+    pub fn current(&self) -> Arc<BundleSet> {
+        self.shared.current.read().unwrap().clone()
+    }
+
+    /// Construct a [`Localizer`] from the current bundles.
+    ///
+    /// [`Localizer`]: struct.Localizer.html
+    pub fn localizer(&self, requested: &[LanguageTag]) -> Localizer {
+        Localizer::new(self.current(), requested)
+    }
+
+    /// Reload the catalogs now, without waiting for a change.
+    pub fn reload(&self) -> Result<(), CatalogError> {
+        self.shared.reload()
+    }
+}
+
+impl Drop for WatchingBundleSet {
+    fn drop(&mut self) {
+        let _ = self.watcher.unwatch(&self.shared.dir);
+        let _ = self.stop.send(Change::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Shared {
+    fn reload(&self) -> Result<(), CatalogError> {
+        let bundles = catalog::load_dir(&self.dir, &self.default)?;
+        *self.current.write().unwrap() = Arc::new(bundles);
+        Ok(())
+    }
+
+    fn watch<F>(&self, receiver: &Receiver<Change>, debounce: Duration, subscriber: F)
+    where
+        F: Fn(Result<&BundleSet, &CatalogError>),
+    {
+        while let Ok(Change::Event(event)) = receiver.recv() {
+            let mut changes = vec![event];
+            // Editors often write a file in several steps.
+            loop {
+                match receiver.recv_timeout(debounce) {
+                    Ok(Change::Event(event)) => changes.push(event),
+                    Ok(Change::Stop) | Err(RecvTimeoutError::Disconnected) => return,
+                    Err(RecvTimeoutError::Timeout) => break,
+                }
+            }
+            let mut changed = false;
+            for event in changes {
+                match event {
+                    Ok(event) => changed |= changes_catalogs(&event),
+                    Err(error) => {
+                        let error = CatalogError::Io(self.dir.clone(), io::Error::other(error));
+                        subscriber(Err(&error));
+                    }
+                }
+            }
+            if !changed {
+                continue;
+            }
+            match self.reload() {
+                Ok(()) => {
+                    let current = self.current.read().unwrap().clone();
+                    subscriber(Ok(&current));
+                }
+                Err(error) => subscriber(Err(&error)),
+            }
+        }
+    }
+}
+
+// Whether an event adds, removes or modifies catalog files.
+fn changes_catalogs(event: &Event) -> bool {
+    let kind = matches!(
+        event.kind,
+        EventKind::Any | EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    );
+    kind && event.paths.iter().any(|path| catalog::is_catalog_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchingBundleSet;
+    use std::env;
+    use std::fs;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use EmptyArgs;
+
+    #[test]
+    fn changes_are_reloaded() {
+        let dir = env::temp_dir().join(format!("message-format-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("en.messages"), "title = Old\n").unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let watching = WatchingBundleSet::new(
+            &dir,
+            "en".parse().unwrap(),
+            Duration::from_millis(50),
+            move |result| sender.send(result.map(|_| ()).map_err(|e| e.to_string())).unwrap(),
+        )
+        .unwrap();
+        let title = |w: &WatchingBundleSet| w.localizer(&[]).format("title", &EmptyArgs {});
+        assert_eq!(title(&watching).unwrap(), "Old");
+
+        fs::write(dir.join("en.messages"), "title = New\n").unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(title(&watching).unwrap(), "New");
+
+        // Other files are ignored.
+        fs::write(dir.join("notes.txt"), "title = Notes\n").unwrap();
+        assert!(receiver.recv_timeout(Duration::from_millis(300)).is_err());
+
+        fs::write(dir.join("en.messages"), "title = {bad name}\n").unwrap();
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).unwrap().is_err());
+        assert_eq!(title(&watching).unwrap(), "New");
+
+        drop(watching);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
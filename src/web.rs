@@ -0,0 +1,205 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers for choosing the language of a web request.
+//!
+//! With the `axum` or `actix-web` feature, a [`Localizer`] can be taken
+//! as an argument of a handler. It is negotiated from the request's
+//! `Accept-Language` header against a [`BundleSet`] kept in the
+//! application: as state that an `Arc<BundleSet>` can be taken from
+//! with axum, or as `web::Data<BundleSet>` app data with actix-web.
+//!
+//! ```text
+//! async fn greet(localizer: Localizer) -> String {
+//!     localizer.format("greeting", &arg("name", "Ann")).unwrap_or_default()
+//! }
+//!
+//! let app = Router::new().route("/", get(greet)).with_state(Arc::new(bundles));
+//! ```
+//!
+//! Other frameworks only need to read the header and call
+//! [`localizer_for_request`].
+//!
+//! [`Localizer`]: ../struct.Localizer.html
+//! [`localizer_for_request`]: fn.localizer_for_request.html
+//! [`BundleSet`]: ../struct.BundleSet.html
+
+use language_tags::LanguageTag;
+use std::cmp::Reverse;
+#[cfg(feature = "axum")]
+use std::convert::Infallible;
+#[cfg(any(feature = "axum", feature = "actix-web"))]
+use std::future;
+#[cfg(feature = "axum")]
+use std::future::Future;
+use std::sync::Arc;
+
+use {BundleSet, Localizer};
+
+/// Parse the value of an `Accept-Language` header into language tags,
+/// most preferred first.
+///
+/// Entries are ordered by their quality value, keeping the header's
+/// order for equal values. The `*` wildcard, entries with a quality
+/// of zero and malformed entries are left out.
+///
+/// ```
+/// use message_format::web::parse_accept_language;
+///
+/// let tags = parse_accept_language("de;q=0.5, fr-CH, fr;q=0.9, *;q=0.1, en;q=0");
+/// let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+/// assert_eq!(tags, ["fr-CH", "fr", "de"]);
+/// ```
+pub fn parse_accept_language(header: &str) -> Vec<LanguageTag> {
+    let mut entries: Vec<(u16, LanguageTag)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut fields = entry.split(';').map(str::trim);
+            let tag = fields.next()?;
+            let mut quality = 1000;
+            for field in fields {
+                if let Some(q) = field.strip_prefix("q=").or_else(|| field.strip_prefix("Q=")) {
+                    let q: f32 = q.trim().parse().ok()?;
+                    quality = (q.clamp(0.0, 1.0) * 1000.0).round() as u16;
+                }
+            }
+            if tag == "*" || quality == 0 {
+                return None;
+            }
+            tag.parse().ok().map(|tag| (quality, tag))
+        })
+        .collect();
+    // A stable sort keeps the header's order for equal qualities.
+    entries.sort_by_key(|&(quality, _)| Reverse(quality));
+    entries.into_iter().map(|(_, tag)| tag).collect()
+}
+
+/// Construct a [`Localizer`] for a request from its `Accept-Language`
+/// header, falling back to the default bundle when it is missing.
+///
+/// [`Localizer`]: ../struct.Localizer.html
+pub fn localizer_for_request(bundles: &Arc<BundleSet>, accept_language: Option<&str>) -> Localizer {
+    let requested = accept_language.map(parse_accept_language).unwrap_or_default();
+    Localizer::new(bundles.clone(), &requested)
+}
+
+#[cfg(feature = "axum")]
+impl<S> axum::extract::FromRequestParts<S> for Localizer
+where
+    Arc<BundleSet>: axum::extract::FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> impl Future<Output = Result<Self, Infallible>> + Send {
+        let bundles: Arc<BundleSet> = axum::extract::FromRef::from_ref(state);
+        let header = parts.headers.get(axum::http::header::ACCEPT_LANGUAGE);
+        future::ready(Ok(localizer_for_request(&bundles, header.and_then(|v| v.to_str().ok()))))
+    }
+}
+
+#[cfg(feature = "actix-web")]
+impl actix_web::FromRequest for Localizer {
+    type Error = actix_web::Error;
+    type Future = future::Ready<Result<Self, actix_web::Error>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let result = match req.app_data::<actix_web::web::Data<BundleSet>>() {
+            Some(bundles) => {
+                let header = req.headers().get(actix_web::http::header::ACCEPT_LANGUAGE);
+                let header = header.and_then(|v| v.to_str().ok());
+                Ok(localizer_for_request(&bundles.clone().into_inner(), header))
+            }
+            None => Err(actix_web::error::ErrorInternalServerError(
+                "the app data has no `web::Data<BundleSet>`",
+            )),
+        };
+        future::ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::localizer_for_request;
+    #[cfg(any(feature = "axum", feature = "actix-web"))]
+    use std::future::Future;
+    use std::sync::Arc;
+    use {arg, BundleSet, MessageBundle};
+
+    #[test]
+    fn negotiates_from_header() {
+        let bundle = |tag: &str, text: &str| {
+            MessageBundle::parse_entries(tag.parse().unwrap(), vec![("hi", text)]).unwrap()
+        };
+        let mut bundles = BundleSet::new(bundle("en", "Hi {n}"));
+        bundles.insert(bundle("de", "Hallo {n}"));
+        bundles.insert(bundle("sv", "Hej {n}"));
+        let bundles = Arc::new(bundles);
+
+        let args = arg("n", 1);
+        let format = |header| localizer_for_request(&bundles, header).format("hi", &args);
+        assert_eq!(format(Some("sv-FI, de;q=0.8")).unwrap(), "Hej 1");
+        assert_eq!(format(Some("ja, de-AT;q=0.8")).unwrap(), "Hallo 1");
+        assert_eq!(format(Some("ja")).unwrap(), "Hi 1");
+        assert_eq!(format(None).unwrap(), "Hi 1");
+    }
+
+    // The value of an extractor's future, which is always ready.
+    #[cfg(any(feature = "axum", feature = "actix-web"))]
+    fn ready<F: Future>(future: F) -> F::Output {
+        use std::pin::pin;
+        use std::task::{Context, Poll, Waker};
+
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the extractor is pending"),
+        }
+    }
+
+    #[cfg(any(feature = "axum", feature = "actix-web"))]
+    fn bundles() -> BundleSet {
+        let bundle = |tag: &str, text: &str| {
+            MessageBundle::parse_entries(tag.parse().unwrap(), vec![("hi", text)]).unwrap()
+        };
+        let mut bundles = BundleSet::new(bundle("en", "Hi"));
+        bundles.insert(bundle("de", "Hallo"));
+        bundles
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn axum_extracts_a_localizer() {
+        use axum::extract::FromRequestParts;
+        use axum::http::header::ACCEPT_LANGUAGE;
+        use axum::http::Request;
+        use Localizer;
+
+        let bundles = Arc::new(bundles());
+        let request = Request::builder().header(ACCEPT_LANGUAGE, "de-CH, en;q=0.5").body(()).unwrap();
+        let (mut parts, _) = request.into_parts();
+        let localizer = ready(Localizer::from_request_parts(&mut parts, &bundles)).unwrap();
+        assert_eq!(localizer.format("hi", &arg("n", 1)).unwrap(), "Hallo");
+    }
+
+    #[cfg(feature = "actix-web")]
+    #[test]
+    fn actix_extracts_a_localizer() {
+        use actix_web::http::header::ACCEPT_LANGUAGE;
+        use actix_web::test::TestRequest;
+        use actix_web::web::Data;
+        use actix_web::FromRequest;
+        use Localizer;
+
+        let request = TestRequest::default().insert_header((ACCEPT_LANGUAGE, "de"));
+        let request = request.app_data(Data::new(bundles())).to_http_request();
+        let localizer = ready(Localizer::extract(&request)).unwrap();
+        assert_eq!(localizer.format("hi", &arg("n", 1)).unwrap(), "Hallo");
+        assert!(ready(Localizer::extract(&TestRequest::default().to_http_request())).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Measuring and Padding Text
+//!
+//! Formatted messages are often aligned in columns, such as in
+//! terminal UIs. Counting `char`s gets this wrong for text with
+//! combining marks, emoji sequences and East Asian characters, so
+//! these helpers work with grapheme clusters, the characters a reader
+//! sees, and with the number of terminal columns each one takes.
+//!
+//! Grapheme clusters are found with a simplification of the Unicode
+//! rules that handles combining marks, variation selectors, emoji
+//! modifiers and `ZWJ` sequences, flags, Hangul jamo and `CRLF`.
+//! Widths follow the East Asian Width property, with ambiguous
+//! characters taking one column.
+//!
+//! ```
+//! use message_format::width;
+//!
+//! assert_eq!(width::grapheme_count("cafe\u{301}"), 4);
+//! assert_eq!(width::display_width("日本語"), 6);
+//! assert_eq!(width::pad_end("日本", 6), "日本  ");
+//! assert_eq!(width::truncate_to_width("日本語", 5), "日本");
+//! ```
+
+use std::borrow::Cow;
+
+// Characters joining the cluster before them, such as combining
+// marks, variation selectors and emoji modifiers.
+const EXTENDING: &[(char, char)] = &[
+    ('\u{300}', '\u{36f}'),
+    ('\u{483}', '\u{489}'),
+    ('\u{591}', '\u{5bd}'),
+    ('\u{5bf}', '\u{5c7}'),
+    ('\u{610}', '\u{61a}'),
+    ('\u{64b}', '\u{65f}'),
+    ('\u{670}', '\u{670}'),
+    ('\u{6d6}', '\u{6dc}'),
+    ('\u{6df}', '\u{6e4}'),
+    ('\u{6e7}', '\u{6e8}'),
+    ('\u{6ea}', '\u{6ed}'),
+    ('\u{900}', '\u{903}'),
+    ('\u{93a}', '\u{94f}'),
+    ('\u{951}', '\u{957}'),
+    ('\u{962}', '\u{963}'),
+    ('\u{981}', '\u{983}'),
+    ('\u{9bc}', '\u{9d7}'),
+    ('\u{e31}', '\u{e31}'),
+    ('\u{e34}', '\u{e3a}'),
+    ('\u{e47}', '\u{e4e}'),
+    ('\u{1160}', '\u{11ff}'),
+    ('\u{1ab0}', '\u{1aff}'),
+    ('\u{1dc0}', '\u{1dff}'),
+    ('\u{200c}', '\u{200d}'),
+    ('\u{20d0}', '\u{20ff}'),
+    ('\u{302a}', '\u{302f}'),
+    ('\u{3099}', '\u{309a}'),
+    ('\u{fe00}', '\u{fe0f}'),
+    ('\u{fe20}', '\u{fe2f}'),
+    ('\u{1f3fb}', '\u{1f3ff}'),
+    ('\u{e0020}', '\u{e007f}'),
+    ('\u{e0100}', '\u{e01ef}'),
+];
+
+// Characters taking no columns of their own, beyond the extending
+// characters: controls, zero width spaces and bidi formatting.
+const ZERO_WIDTH: &[(char, char)] = &[
+    ('\u{0}', '\u{1f}'),
+    ('\u{7f}', '\u{9f}'),
+    ('\u{ad}', '\u{ad}'),
+    ('\u{200b}', '\u{200f}'),
+    ('\u{2028}', '\u{202e}'),
+    ('\u{2060}', '\u{206f}'),
+    ('\u{feff}', '\u{feff}'),
+];
+
+// Characters with an East Asian Width of wide or fullwidth, and
+// emoji shown as pictures by default.
+const WIDE: &[(char, char)] = &[
+    ('\u{1100}', '\u{115f}'),
+    ('\u{231a}', '\u{231b}'),
+    ('\u{2329}', '\u{232a}'),
+    ('\u{23e9}', '\u{23ec}'),
+    ('\u{23f0}', '\u{23f0}'),
+    ('\u{23f3}', '\u{23f3}'),
+    ('\u{25fd}', '\u{25fe}'),
+    ('\u{2614}', '\u{2615}'),
+    ('\u{2648}', '\u{2653}'),
+    ('\u{26a1}', '\u{26a1}'),
+    ('\u{26aa}', '\u{26ab}'),
+    ('\u{26bd}', '\u{26be}'),
+    ('\u{26c4}', '\u{26c5}'),
+    ('\u{26d4}', '\u{26d4}'),
+    ('\u{26ea}', '\u{26ea}'),
+    ('\u{26f2}', '\u{26f5}'),
+    ('\u{26fa}', '\u{26fd}'),
+    ('\u{2705}', '\u{2705}'),
+    ('\u{270a}', '\u{270b}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{274c}', '\u{274c}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27b0}', '\u{27b0}'),
+    ('\u{27bf}', '\u{27bf}'),
+    ('\u{2b1b}', '\u{2b1c}'),
+    ('\u{2b50}', '\u{2b50}'),
+    ('\u{2b55}', '\u{2b55}'),
+    ('\u{2e80}', '\u{303e}'),
+    ('\u{3041}', '\u{33ff}'),
+    ('\u{3400}', '\u{4dbf}'),
+    ('\u{4e00}', '\u{9fff}'),
+    ('\u{a000}', '\u{a4cf}'),
+    ('\u{a960}', '\u{a97f}'),
+    ('\u{ac00}', '\u{d7a3}'),
+    ('\u{f900}', '\u{faff}'),
+    ('\u{fe10}', '\u{fe19}'),
+    ('\u{fe30}', '\u{fe6f}'),
+    ('\u{ff00}', '\u{ff60}'),
+    ('\u{ffe0}', '\u{ffe6}'),
+    ('\u{16fe0}', '\u{16fe4}'),
+    ('\u{17000}', '\u{18cff}'),
+    ('\u{1b000}', '\u{1b2ff}'),
+    ('\u{1f004}', '\u{1f004}'),
+    ('\u{1f0cf}', '\u{1f0cf}'),
+    ('\u{1f18e}', '\u{1f18e}'),
+    ('\u{1f191}', '\u{1f19a}'),
+    ('\u{1f200}', '\u{1f251}'),
+    ('\u{1f300}', '\u{1f320}'),
+    ('\u{1f32d}', '\u{1f335}'),
+    ('\u{1f337}', '\u{1f37c}'),
+    ('\u{1f37e}', '\u{1f393}'),
+    ('\u{1f3a0}', '\u{1f3ca}'),
+    ('\u{1f3cf}', '\u{1f3d3}'),
+    ('\u{1f3e0}', '\u{1f3f0}'),
+    ('\u{1f3f4}', '\u{1f3f4}'),
+    ('\u{1f3f8}', '\u{1f43e}'),
+    ('\u{1f440}', '\u{1f440}'),
+    ('\u{1f442}', '\u{1f4fc}'),
+    ('\u{1f4ff}', '\u{1f53d}'),
+    ('\u{1f54b}', '\u{1f54e}'),
+    ('\u{1f550}', '\u{1f567}'),
+    ('\u{1f57a}', '\u{1f57a}'),
+    ('\u{1f595}', '\u{1f596}'),
+    ('\u{1f5a4}', '\u{1f5a4}'),
+    ('\u{1f5fb}', '\u{1f64f}'),
+    ('\u{1f680}', '\u{1f6c5}'),
+    ('\u{1f6cc}', '\u{1f6cc}'),
+    ('\u{1f6d0}', '\u{1f6d2}'),
+    ('\u{1f6d5}', '\u{1f6d7}'),
+    ('\u{1f6dc}', '\u{1f6df}'),
+    ('\u{1f6eb}', '\u{1f6ec}'),
+    ('\u{1f6f4}', '\u{1f6fc}'),
+    ('\u{1f7e0}', '\u{1f7eb}'),
+    ('\u{1f7f0}', '\u{1f7f0}'),
+    ('\u{1f90c}', '\u{1f93a}'),
+    ('\u{1f93c}', '\u{1f945}'),
+    ('\u{1f947}', '\u{1f9ff}'),
+    ('\u{1fa70}', '\u{1faff}'),
+    ('\u{20000}', '\u{2fffd}'),
+    ('\u{30000}', '\u{3fffd}'),
+];
+
+const ZWJ: char = '\u{200d}';
+const EMOJI_PRESENTATION: char = '\u{fe0f}';
+
+fn in_table(table: &[(char, char)], c: char) -> bool {
+    table
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                ::std::cmp::Ordering::Less
+            } else if start > c {
+                ::std::cmp::Ordering::Greater
+            } else {
+                ::std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+/// An iterator over the grapheme clusters of a string, created by
+/// [`graphemes`].
+///
+/// [`graphemes`]: fn.graphemes.html
+#[derive(Clone, Debug)]
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut previous = first;
+        let mut flags = is_regional_indicator(first) as usize;
+        let mut end = self.rest.len();
+        for (i, c) in chars {
+            let joins = match (previous, c) {
+                ('\r', '\n') => true,
+                (ZWJ, _) => true,
+                (_, c) if in_table(EXTENDING, c) => true,
+                (_, c) if is_regional_indicator(c) && flags == 1 => {
+                    flags += 1;
+                    true
+                }
+                _ => false,
+            };
+            if !joins {
+                end = i;
+                break;
+            }
+            previous = c;
+        }
+        let (cluster, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(cluster)
+    }
+}
+
+/// The grapheme clusters of `text`, the characters a reader sees.
+///
+/// ```
+/// use message_format::width::graphemes;
+///
+/// let clusters: Vec<&str> = graphemes("ne\u{301}e 👍🏽 🇫🇷").collect();
+/// assert_eq!(clusters, ["n", "e\u{301}", "e", " ", "👍🏽", " ", "🇫🇷"]);
+/// ```
+pub fn graphemes(text: &str) -> Graphemes<'_> {
+    Graphemes { rest: text }
+}
+
+/// The number of grapheme clusters in `text`.
+pub fn grapheme_count(text: &str) -> usize {
+    graphemes(text).count()
+}
+
+// The number of columns taken by one grapheme cluster.
+fn cluster_width(cluster: &str) -> usize {
+    let first = match cluster.chars().next() {
+        Some(first) => first,
+        None => return 0,
+    };
+    if is_regional_indicator(first) || in_table(WIDE, first) {
+        2
+    } else if in_table(ZERO_WIDTH, first) || in_table(EXTENDING, first) {
+        0
+    } else if cluster.contains(EMOJI_PRESENTATION) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The number of terminal columns taken by `text`.
+///
+/// Wide characters, such as CJK ideographs and most emoji, take two
+/// columns, and controls and formatting characters, such as the bidi
+/// isolation marks written around arguments, take none.
+pub fn display_width(text: &str) -> usize {
+    graphemes(text).map(cluster_width).sum()
+}
+
+/// The longest start of `text` taking at most `width` columns,
+/// without splitting a grapheme cluster.
+pub fn truncate_to_width(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    let mut end = 0;
+    for cluster in graphemes(text) {
+        used += cluster_width(cluster);
+        if used > width {
+            break;
+        }
+        end += cluster.len();
+    }
+    &text[..end]
+}
+
+/// Pad `text` with spaces after it to take at least `width` columns.
+pub fn pad_end(text: &str, width: usize) -> Cow<'_, str> {
+    match width.checked_sub(display_width(text)) {
+        Some(padding) if padding > 0 => Cow::Owned(format!("{}{:2$}", text, "", padding)),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+/// Pad `text` with spaces before it to take at least `width` columns.
+pub fn pad_start(text: &str, width: usize) -> Cow<'_, str> {
+    match width.checked_sub(display_width(text)) {
+        Some(padding) if padding > 0 => Cow::Owned(format!("{:1$}{2}", "", padding, text)),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{display_width, grapheme_count, graphemes, pad_start, truncate_to_width};
+
+    #[test]
+    fn clusters_and_widths() {
+        let family = "👩\u{200d}👩\u{200d}👧";
+        assert_eq!(grapheme_count(family), 1);
+        assert_eq!(display_width(family), 2);
+        assert_eq!(graphemes("🇫🇷🇩🇪").count(), 2);
+        assert_eq!(graphemes("a\r\nb").collect::<Vec<_>>(), ["a", "\r\n", "b"]);
+        assert_eq!(grapheme_count("한국어"), 3);
+        assert_eq!(grapheme_count("\u{1112}\u{1161}\u{11ab}"), 1);
+
+        assert_eq!(display_width("\u{2068}Kim\u{2069}"), 3);
+        assert_eq!(display_width("ｈｉ!"), 5);
+        assert_eq!(display_width("❤\u{fe0f}"), 2);
+
+        assert_eq!(truncate_to_width("ae\u{301}b", 2), "ae\u{301}");
+        assert_eq!(truncate_to_width("日本", 1), "");
+        assert_eq!(pad_start("日本", 5), " 日本");
+        assert_eq!(pad_start("日本語", 5), "日本語");
+    }
+}